@@ -11,8 +11,11 @@ import argparse
 import json
 import os
 import shutil
+import re
 import subprocess
 import sys
+import threading
+import time
 import venv
 from pathlib import Path
 
@@ -25,22 +28,50 @@ PACKAGES = [
     "huggingface-hub>=0.24",
 ]
 
+# Bump whenever PACKAGES change in a way an existing environment would not
+# satisfy. Each version is built side by side in `python-env-v<N>`, and
+# `python-env` (a symlink, which the worker runs from) is switched to it only
+# once it passes the smoke test.
+ENV_VERSION = 2
+ACTIVE_ENV = "python-env"
+
 MODEL_REPOS = {
     "large-v3-turbo": "mlx-community/whisper-large-v3-turbo",
     "large-v3": "mlx-community/whisper-large-v3-mlx",
+    "medium": "mlx-community/whisper-medium-mlx",
+    "small": "mlx-community/whisper-small-mlx",
+    "base": "mlx-community/whisper-base-mlx",
+    "tiny": "mlx-community/whisper-tiny-mlx",
 }
 
 MODEL_FOLDERS = {
     "large-v3-turbo": "whisper-large-v3-turbo",
     "large-v3": "whisper-large-v3",
+    "medium": "whisper-medium",
+    "small": "whisper-small",
+    "base": "whisper-base",
+    "tiny": "whisper-tiny",
 }
 
+# What each model file should be (size, SHA-256 of the weights), from the
+# Hugging Face metadata; the app verifies the download against it.
+MANIFEST_FILE = "whisperbar-manifest.json"
+
+# Tries per model file before the install fails; each one resumes the partial
+# file, backing off 1, 2, 4... seconds.
+DOWNLOAD_ATTEMPTS = 5
+
 
 def emit(event_type: str, **fields: object) -> None:
     payload = {"type": event_type, **fields}
     print(json.dumps(payload), flush=True)
 
 
+def emit_phase(phase: str, status: str, progress: float, **fields: object) -> None:
+    """Per-phase progress: phase is venv, packages or model; status is active, done or skipped."""
+    emit("phase", phase=phase, status=status, progress=round(max(0.0, min(1.0, progress)), 4), **fields)
+
+
 def run(command: list[str], env: dict[str, str] | None = None) -> subprocess.CompletedProcess[str]:
     return subprocess.run(
         command,
@@ -59,11 +90,13 @@ def check_python_ready(venv_python: Path) -> tuple[bool, str]:
     if not venv_python.exists():
         return False, "python virtual environment missing"
 
+    # Smoke test: imports alone pass with a broken Metal backend, so run one
+    # tiny MLX computation too.
     probe = run(
         [
             str(venv_python),
             "-c",
-            "import mlx_whisper; import numpy; import huggingface_hub",
+            "import mlx_whisper, numpy, huggingface_hub; import mlx.core as mx; mx.eval(mx.array([1.0]) + 1)",
         ]
     )
     if probe.returncode != 0:
@@ -83,46 +116,211 @@ def check_model_ready(model_path: Path) -> tuple[bool, str]:
     return True, "ready"
 
 
+def built_on_this_runtime(venv_dir: Path) -> bool:
+    """Whether venv_dir was created from the interpreter running this script.
+    A venv only links to its base interpreter, so one built before the app
+    replaced the Python runtime (`python_runtime.rs`) may no longer start."""
+    try:
+        config = (venv_dir / "pyvenv.cfg").read_text()
+    except OSError:
+        return False
+    values = {}
+    for line in config.splitlines():
+        key, _, value = line.partition("=")
+        values[key.strip()] = value.strip()
+    home = values.get("home")
+    return (
+        home is not None
+        and values.get("version") == "%d.%d.%d" % sys.version_info[:3]
+        and Path(home).resolve() == Path(sys.executable).parent.resolve()
+    )
+
+
+def env_dir_for(app_data_dir: Path, version: int) -> Path:
+    return app_data_dir / f"{ACTIVE_ENV}-v{version}"
+
+
+def activate_env(app_data_dir: Path, env_dir: Path) -> None:
+    """Points `python-env` at env_dir with a rename, which is atomic, so a
+    crash leaves either the old or the new environment active. The one that
+    was active stays on disk as a rollback unless it was built on a replaced
+    runtime; any older one is removed."""
+    active = app_data_dir / ACTIVE_ENV
+    previous: Path | None = None
+    if active.is_symlink():
+        previous = active.resolve()
+    elif active.exists():
+        # A pre-versioned environment is a real directory. Moving it keeps it
+        # usable: bin/python links to the runtime by absolute path.
+        previous = env_dir_for(app_data_dir, 1)
+        if previous.exists():
+            shutil.rmtree(previous)
+        active.rename(previous)
+
+    staged = app_data_dir / f"{ACTIVE_ENV}.next"
+    if staged.is_symlink() or staged.exists():
+        staged.unlink()
+    staged.symlink_to(env_dir.name)
+    os.replace(staged, active)
+    remove_stale_envs(app_data_dir, env_dir, previous)
+
+
+def remove_stale_envs(app_data_dir: Path, env_dir: Path, rollback: Path | None) -> None:
+    """Removes every versioned environment but env_dir and the rollback, and
+    the rollback too when it was built on a runtime that has been replaced."""
+    keep = {env_dir.name}
+    if rollback is not None and built_on_this_runtime(rollback):
+        keep.add(rollback.name)
+    for stale in app_data_dir.glob(f"{ACTIVE_ENV}-v*"):
+        if stale.name not in keep and stale.is_dir():
+            shutil.rmtree(stale, ignore_errors=True)
+
+
 def install_venv(venv_dir: Path) -> None:
     emit("progress", progress=0.12, message="Creating Python environment")
-    builder = venv.EnvBuilder(with_pip=True, clear=False, upgrade=False)
+    emit_phase("venv", "active", 0.0)
+    # Clears what an interrupted earlier build left behind.
+    builder = venv.EnvBuilder(with_pip=True, clear=True, upgrade=False)
     builder.create(venv_dir)
+    emit_phase("venv", "done", 1.0)
+
+
+def package_name(requirement: str) -> str:
+    return re.split(r"[<>=!~\[ ]", requirement, maxsplit=1)[0].lower().replace("_", "-")
 
 
 def install_packages(venv_python: Path) -> None:
     emit("progress", progress=0.3, message="Installing Python packages")
+    emit_phase("packages", "active", 0.0)
     command = [str(venv_python), "-m", "pip", "install", "--upgrade", *PACKAGES]
-    result = run(command)
 
-    if result.returncode != 0:
-        raise RuntimeError(
-            "pip install failed: "
-            + (result.stderr.strip() or result.stdout.strip() or "unknown error")
-        )
+    # pip prints one "Collecting <name>" / "Requirement already satisfied: <name>"
+    # line per requested package; count ours to estimate progress.
+    wanted = {package_name(requirement) for requirement in PACKAGES}
+    seen: set[str] = set()
+    output: list[str] = []
+    process = subprocess.Popen(command, stdout=subprocess.PIPE, stderr=subprocess.STDOUT, text=True)
+    assert process.stdout is not None
+    for line in process.stdout:
+        output.append(line)
+        match = re.match(r"(?:Collecting|Requirement already satisfied:)\s+([A-Za-z0-9_.\-]+)", line.strip())
+        if not match:
+            continue
+        name = package_name(match.group(1))
+        if name in wanted and name not in seen:
+            seen.add(name)
+            emit_phase("packages", "active", len(seen) / (len(wanted) + 1), file=name)
+
+    if process.wait() != 0:
+        raise RuntimeError("pip install failed: " + ("".join(output[-20:]).strip() or "unknown error"))
+    emit_phase("packages", "done", 1.0)
+
+
+def directory_size(path: Path) -> int:
+    total = 0
+    for item in path.rglob("*"):
+        try:
+            if item.is_file():
+                total += item.stat().st_size
+        except OSError:
+            continue
+    return total
 
 
 def download_model(venv_python: Path, model_repo: str, model_path: Path, hf_home: Path) -> None:
     emit("progress", progress=0.62, message=f"Downloading model {model_path.name}")
+    emit_phase("model", "active", 0.0)
 
+    # Download file by file so the current file can be reported; incomplete
+    # downloads land under local_dir, so its size tracks transferred bytes.
+    # Files finished by an earlier run are skipped and a partial `.incomplete`
+    # file is continued with a range request, so an interrupted install picks
+    # up where it stopped. A dropped connection is retried the same way.
     script = f"""
-from huggingface_hub import snapshot_download
-snapshot_download(
-    repo_id={model_repo!r},
-    local_dir={str(model_path)!r},
-)
-print("ok")
+import json, time
+from pathlib import Path
+from huggingface_hub import HfApi, hf_hub_download
+info = HfApi().model_info({model_repo!r}, files_metadata=True)
+files = [(s.rfilename, s.size or 0) for s in info.siblings]
+manifest = {{}}
+for s in info.siblings:
+    if s.size is None:
+        continue
+    lfs = s.lfs
+    sha256 = lfs.get("sha256") if isinstance(lfs, dict) else getattr(lfs, "sha256", None)
+    manifest[s.rfilename] = {{"size": s.size, "sha256": sha256}}
+Path({str(model_path)!r}).mkdir(parents=True, exist_ok=True)
+Path({str(model_path)!r}, {MANIFEST_FILE!r}).write_text(json.dumps(manifest, indent=2))
+print(json.dumps({{"total": sum(size for _, size in files)}}), flush=True)
+for name, size in files:
+    print(json.dumps({{"file": name, "size": size}}), flush=True)
+    for attempt in range({DOWNLOAD_ATTEMPTS}):
+        try:
+            hf_hub_download(repo_id={model_repo!r}, filename=name, local_dir={str(model_path)!r})
+            break
+        except OSError:
+            if attempt == {DOWNLOAD_ATTEMPTS} - 1:
+                raise
+            time.sleep(2 ** attempt)
+print(json.dumps({{"done": True}}), flush=True)
 """
 
     env = dict(os.environ)
     env["HF_HOME"] = str(hf_home)
-
-    result = run([str(venv_python), "-c", script], env=env)
-    if result.returncode != 0:
-        raise RuntimeError(
-            "model download failed: "
-            + (result.stderr.strip() or result.stdout.strip() or "unknown error")
+    env["HF_HUB_DISABLE_PROGRESS_BARS"] = "1"
+
+    # Bytes an earlier, interrupted run left behind count as done.
+    resumed = directory_size(model_path) if model_path.exists() else 0
+    process = subprocess.Popen(
+        [str(venv_python), "-c", script],
+        stdout=subprocess.PIPE,
+        stderr=subprocess.PIPE,
+        text=True,
+        env=env,
+    )
+    progress_state: dict[str, object] = {"total": 0, "file": None}
+    stderr_lines: list[str] = []
+
+    def read_stdout() -> None:
+        assert process.stdout is not None
+        for line in process.stdout:
+            try:
+                progress_state.update(json.loads(line))
+            except json.JSONDecodeError:
+                continue
+
+    def read_stderr() -> None:
+        assert process.stderr is not None
+        stderr_lines.extend(process.stderr)
+
+    readers = [threading.Thread(target=read_stdout, daemon=True), threading.Thread(target=read_stderr, daemon=True)]
+    for reader in readers:
+        reader.start()
+
+    while process.poll() is None:
+        time.sleep(0.5)
+        total = int(progress_state.get("total") or 0)
+        if total <= 0:
+            continue
+        done = min(directory_size(model_path), total)
+        emit("progress", progress=round(0.62 + 0.36 * done / total, 4), message=f"Downloading model {model_path.name}")
+        emit_phase(
+            "model",
+            "active",
+            done / total,
+            file=progress_state.get("file"),
+            bytes_done=done,
+            bytes_total=total,
+            bytes_resumed=min(resumed, total) or None,
         )
 
+    for reader in readers:
+        reader.join(timeout=2)
+
+    if process.returncode != 0:
+        raise RuntimeError("model download failed: " + ("".join(stderr_lines[-20:]).strip() or "unknown error"))
+    emit_phase("model", "done", 1.0)
+
 
 def parse_args() -> argparse.Namespace:
     parser = argparse.ArgumentParser()
@@ -143,22 +341,28 @@ def main() -> int:
         return 1
 
     app_data_dir = Path(args.app_data_dir).expanduser().resolve()
-    venv_dir = app_data_dir / "python-env"
+    env_dir = env_dir_for(app_data_dir, ENV_VERSION)
+    env_python = venv_python_path(env_dir)
+    active_dir = app_data_dir / ACTIVE_ENV
     model_path = app_data_dir / "models" / model_folder
     hf_home = app_data_dir / "hf-cache"
-    venv_python = venv_python_path(venv_dir)
+    venv_python = venv_python_path(active_dir)
 
     try:
         emit("progress", progress=0.02, message="Checking dependencies")
 
         if args.reset:
             emit("progress", progress=0.05, message="Resetting existing dependencies")
-            if venv_dir.exists():
-                shutil.rmtree(venv_dir)
+            if env_dir.exists():
+                shutil.rmtree(env_dir)
             if model_path.exists():
                 shutil.rmtree(model_path)
 
-        python_ready, python_reason = check_python_ready(venv_python)
+        env_active = active_dir.is_symlink() and active_dir.resolve() == env_dir.resolve()
+        if env_active:
+            python_ready, python_reason = check_python_ready(venv_python)
+        else:
+            python_ready, python_reason = False, f"Python environment v{ENV_VERSION} not built yet"
         model_ready, _ = check_model_ready(model_path)
 
         if python_ready and model_ready:
@@ -178,16 +382,36 @@ def main() -> int:
         model_path.parent.mkdir(parents=True, exist_ok=True)
         hf_home.mkdir(parents=True, exist_ok=True)
 
-        if not venv_python.exists():
-            install_venv(venv_dir)
+        # Everything below builds env_dir only; the active environment keeps
+        # working until the new one has passed the smoke test. An environment
+        # from a replaced runtime is rebuilt from scratch.
+        rebuilt = not env_python.exists() or not built_on_this_runtime(env_dir)
+        if rebuilt:
+            install_venv(env_dir)
+        else:
+            emit_phase("venv", "skipped", 1.0)
 
-        python_ready, _ = check_python_ready(venv_python)
+        python_ready, _ = check_python_ready(env_python)
         if not python_ready:
-            install_packages(venv_python)
+            install_packages(env_python)
+        else:
+            emit_phase("packages", "skipped", 1.0)
+
+        python_ready, python_reason = check_python_ready(env_python)
+        if not python_ready:
+            raise RuntimeError(
+                f"new Python environment failed its smoke test, keeping the current one: {python_reason}"
+            )
+        if not env_active:
+            activate_env(app_data_dir, env_dir)
+        elif rebuilt:
+            remove_stale_envs(app_data_dir, env_dir, None)
 
         model_ready, _ = check_model_ready(model_path)
         if not model_ready:
             download_model(venv_python, model_repo, model_path, hf_home)
+        else:
+            emit_phase("model", "skipped", 1.0)
 
         python_ready, python_reason = check_python_ready(venv_python)
         model_ready, model_reason = check_model_ready(model_path)
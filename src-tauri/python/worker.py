@@ -8,16 +8,20 @@ chunks as JSON lines to stdout.
 from __future__ import annotations
 
 import argparse
+import base64
 import json
+import math
 import os
 import re
 import signal
 import subprocess
 import sys
 import threading
+import time
 import traceback
+import wave
 from pathlib import Path
-from queue import SimpleQueue
+from queue import Empty, SimpleQueue
 
 import mlx_whisper
 import numpy as np
@@ -34,6 +38,50 @@ MICROPHONE_KEYWORDS = (
 PREFERRED_BUILTIN_MIC_KEYWORDS = ("macbook", "built-in", "internal")
 DEPRIORITIZED_MOBILE_MIC_KEYWORDS = ("iphone", "continuity", "desk view")
 APP_VIRTUAL_AUDIO_KEYWORDS = ("teams audio", "zoomaudio", "discord", "slack")
+LEVEL_UPDATES_PER_SECOND = 4
+# Desktop audio that tracks the mic this closely is the mic looped back
+# (monitoring, a virtual device routing the mic to the output).
+LOOPBACK_CORRELATION = 0.6
+LOOPBACK_MAX_LAG_SECONDS = 0.5
+LOOPBACK_CONFIRM_CHUNKS = 3
+LOOPBACK_MIN_RMS = 0.003
+# A live microphone always has a noise floor; exact zeros for this many meter
+# blocks mean it is muted (hardware mute key or the system input mute).
+MIC_MUTE_BLOCKS = 3
+# Mic-only sessions wait this long for the app to name a replacement after
+# the microphone fails or disappears, before giving up.
+MIC_SWITCH_GRACE_SECONDS = 3.0
+# Chunks quieter than this are skipped instead of transcribed. A calibrated
+# microphone profile replaces it for mic-only sessions.
+SILENCE_RMS = 0.0006
+# Automatic gain control, mirroring the ScreenCaptureKit helper so the
+# microphone and desktop audio reach the mix at comparable levels.
+AGC_TARGET_RMS = 0.1
+AGC_MAX_GAIN = 10.0
+AGC_MIN_GAIN = 0.316
+AGC_GATE_RMS = 0.002
+AGC_BLOCK_SAMPLES = 160
+AGC_ATTACK_SECONDS = 0.02
+AGC_RELEASE_SECONDS = 1.5
+# Voice activity detection through the helper's `--vad-filter` (WebRTC VAD),
+# which answers each 30 ms frame with `1` (speech) or `0`.
+VAD_FRAME_SAMPLES = 480
+VAD_FRAME_SECONDS = 0.03
+# A chunk needs this much detected speech to be transcribed; anything less is
+# breathing, clicks or noise the model would turn into hallucinated text.
+VAD_MIN_SPEECH_SECONDS = 0.3
+# A pause this long between detected speech starts a new paragraph.
+PARAGRAPH_PAUSE_SECONDS = 2.0
+# Acoustic scene gating (`--skip-music`). Speech keeps dipping between words,
+# even over a soundtrack; music holds its level. A desktop chunk counts as
+# music when almost none of its 30 ms frames fall below half its mean level
+# and their loudness barely varies.
+MUSIC_MIN_RMS = 0.003
+MUSIC_MAX_LOW_ENERGY_RATIO = 0.1
+MUSIC_MAX_SPREAD_DB = 6.0
+# Chunks in a row that must sound like music before transcription pauses
+# (about 8 s); the first chunk that does not resumes it.
+MUSIC_CONFIRM_CHUNKS = 3
 
 
 def emit(event_type: str, **fields: object) -> None:
@@ -41,14 +89,49 @@ def emit(event_type: str, **fields: object) -> None:
     print(json.dumps(payload), flush=True)
 
 
-def parse_args() -> argparse.Namespace:
+def parse_args(argv: list[str] | None = None) -> argparse.Namespace:
     parser = argparse.ArgumentParser()
     parser.add_argument("--language", default="en")
     parser.add_argument("--model-path", required=True)
     parser.add_argument("--mic-device", default="")
     parser.add_argument("--sck-helper-path", default="")
     parser.add_argument("--chunk-seconds", type=float, default=2.8)
-    return parser.parse_args()
+    parser.add_argument("--diarize", action="store_true")
+    # Transcribe the mic and desktop audio separately and tag each segment with
+    # its source, for `Me:` / `Others:` transcripts. Replaces diarization.
+    parser.add_argument("--separate-sources", action="store_true")
+    # Skip the ScreenCaptureKit helper and transcribe the microphone alone.
+    parser.add_argument("--no-system-audio", action="store_true")
+    parser.add_argument("--device-profiles", default="{}")
+    # Passed through to the ScreenCaptureKit helper: `high` (sinc) or `fast` (linear).
+    parser.add_argument("--resample-quality", choices=("high", "fast"), default="high")
+    # Pipe the microphone through the helper's RNNoise filter (`--denoise-filter`).
+    parser.add_argument("--denoise", action="store_true")
+    # Gate transcription on the helper's voice detector (`--vad-filter`) and
+    # report speech and paragraph breaks from it instead of from model output.
+    parser.add_argument("--vad", action="store_true")
+    # Pause transcription of desktop audio while it is music with no speech.
+    parser.add_argument("--skip-music", action="store_true")
+    # Time every word and attach `words` to each partial.
+    parser.add_argument("--word-timestamps", action="store_true")
+    # The app sends buffered mic audio as a `preroll <base64 s16le>` stdin line
+    # once capture starts; it is prepended to the first chunk.
+    parser.add_argument("--preroll", action="store_true")
+    # When set, every captured PCM chunk (pre-roll included) is also written here as WAV.
+    parser.add_argument("--audio-path", default="")
+    # Attendee names and title keywords of the linked calendar event.
+    parser.add_argument("--initial-prompt", default="")
+    # Stay alive between sessions with the model loaded: each session starts
+    # with a `start <JSON array of arguments>` line and ends with `idle`.
+    parser.add_argument("--persistent", action="store_true")
+    # Seconds a persistent worker waits for the next session before exiting.
+    # It waits for the first session indefinitely, so a preloaded worker
+    # stays ready until it is used.
+    parser.add_argument("--idle-timeout", type=float, default=600.0)
+    # Transcribe this kept recording (16 kHz mono WAV) instead of capturing,
+    # then exit. Capture arguments are ignored.
+    parser.add_argument("--transcribe-file", default="")
+    return parser.parse_args(argv)
 
 
 def normalize_language(language: str) -> str:
@@ -150,7 +233,45 @@ def pick_microphone_device(devices: list[tuple[str, str]], preferred: str) -> tu
     return f":{idx}", name
 
 
-def spawn_ffmpeg_mic_only(mic_input: str) -> subprocess.Popen[bytes]:
+def load_device_profile(raw_profiles: str, device_name: str | None) -> dict:
+    if not device_name:
+        return {}
+    try:
+        profiles = json.loads(raw_profiles)
+    except json.JSONDecodeError:
+        return {}
+    profile = profiles.get(device_name) if isinstance(profiles, dict) else None
+    return profile if isinstance(profile, dict) else {}
+
+
+def mic_filters(profile: dict) -> list[str]:
+    filters: list[str] = []
+
+    channel = profile.get("channel")
+    if isinstance(channel, int) and channel >= 1:
+        # Pick one input channel instead of downmixing all of them.
+        filters.append(f"pan=mono|c0=c{channel - 1}")
+
+    if profile.get("noiseSuppression"):
+        filters.append("afftdn")
+
+    gain_db = profile.get("gainDb")
+    if isinstance(gain_db, (int, float)) and gain_db != 0:
+        filters.append(f"volume={gain_db}dB")
+
+    return filters
+
+
+def silence_threshold(profile: dict) -> float:
+    threshold = profile.get("silenceThreshold")
+    if isinstance(threshold, (int, float)) and 0 < threshold <= 1:
+        return float(threshold)
+    return SILENCE_RMS
+
+
+def spawn_ffmpeg_mic_only(
+    mic_input: str, profile: dict, sample_rate: int = 16000
+) -> subprocess.Popen[bytes]:
     ffmpeg_bin = resolve_ffmpeg_binary()
     command = [
         ffmpeg_bin,
@@ -163,10 +284,15 @@ def spawn_ffmpeg_mic_only(mic_input: str) -> subprocess.Popen[bytes]:
         "avfoundation",
         "-i",
         mic_input,
+    ]
+    filters = mic_filters(profile)
+    if filters:
+        command += ["-af", ",".join(filters)]
+    command += [
         "-ac",
         "1",
         "-ar",
-        "16000",
+        str(sample_rate),
         "-f",
         "s16le",
         "-",
@@ -174,8 +300,57 @@ def spawn_ffmpeg_mic_only(mic_input: str) -> subprocess.Popen[bytes]:
     return subprocess.Popen(command, stdout=subprocess.PIPE, stderr=subprocess.PIPE)
 
 
-def spawn_screencapturekit_helper(helper_path: str) -> subprocess.Popen[bytes]:
-    command = [helper_path, "--sck-audio-helper"]
+def start_mic_capture(
+    mic_input: str, profile: dict, denoise_helper: str | None = None
+) -> tuple[subprocess.Popen[bytes], SimpleQueue[str]]:
+    """Returns the process whose stdout carries 16 kHz mono PCM: ffmpeg itself,
+    or the RNNoise filter ffmpeg feeds at 48 kHz when `denoise_helper` is set."""
+    process = spawn_ffmpeg_mic_only(mic_input, profile, 48000 if denoise_helper else 16000)
+    if process.stdout is None:
+        raise RuntimeError("microphone ffmpeg stdout unavailable")
+    if process.stderr is None:
+        raise RuntimeError("microphone ffmpeg stderr unavailable")
+    stderr_queue: SimpleQueue[str] = SimpleQueue()
+    start_stderr_reader(process, stderr_queue)
+    if not denoise_helper:
+        return process, stderr_queue
+
+    # Stopping the filter ends ffmpeg too: its next write fails with EPIPE.
+    # Likewise ffmpeg exiting closes the filter's stdin, so a failed or
+    # unplugged mic still shows up as the returned process exiting.
+    try:
+        denoiser = subprocess.Popen(
+            [denoise_helper, "--denoise-filter"],
+            stdin=process.stdout,
+            stdout=subprocess.PIPE,
+            stderr=subprocess.PIPE,
+        )
+    except OSError:
+        stop_process(process)
+        raise
+    process.stdout.close()
+    start_stderr_reader(denoiser, stderr_queue)
+    return denoiser, stderr_queue
+
+
+def stop_process(proc: subprocess.Popen[bytes] | None) -> None:
+    if proc is None or proc.poll() is not None:
+        return
+    try:
+        if proc.stdin is not None:
+            proc.stdin.write(b"stop\n")
+            proc.stdin.flush()
+    except Exception:  # noqa: BLE001
+        pass
+    proc.terminate()
+    try:
+        proc.wait(timeout=2)
+    except subprocess.TimeoutExpired:
+        proc.kill()
+
+
+def spawn_screencapturekit_helper(helper_path: str, resample_quality: str) -> subprocess.Popen[bytes]:
+    command = [helper_path, "--sck-audio-helper", "--resample-quality", resample_quality]
     return subprocess.Popen(
         command,
         stdin=subprocess.PIPE,
@@ -208,19 +383,13 @@ def mix_pcm_streams(primary: bytes, secondary: bytes | None) -> bytes:
     if first.size == 0 and second.size == 0:
         return b""
 
+    # Both streams are already levelled by AGC, so they are summed as is.
     target_len = int(max(first.size, second.size))
     mixed = np.zeros(target_len, dtype=np.float32)
     if first.size:
-        mixed[: first.size] += first * 0.8
+        mixed[: first.size] += first
     if second.size:
-        second_gain = 1.35
-        if first.size:
-            first_rms = rms_level(first / 32768.0)
-            second_rms = rms_level(second / 32768.0)
-            if first_rms > 1e-4 and second_rms > 1e-4:
-                # Keep mic intelligible when desktop audio is louder.
-                second_gain *= float(np.clip(first_rms / second_rms, 0.8, 2.2))
-        mixed[: second.size] += second * second_gain
+        mixed[: second.size] += second
 
     np.clip(mixed, -32768.0, 32767.0, out=mixed)
     return mixed.astype(np.int16).tobytes()
@@ -243,6 +412,52 @@ def start_stderr_reader(process: subprocess.Popen[bytes], queue: SimpleQueue[str
     return thread
 
 
+def pcm_level(pcm_bytes: bytes) -> dict[str, float]:
+    audio = np.frombuffer(pcm_bytes[: len(pcm_bytes) - len(pcm_bytes) % 2], dtype=np.int16)
+    if audio.size == 0:
+        return {"rms": 0.0, "peak": 0.0}
+    scaled = audio.astype(np.float32) / 32768.0
+    return {
+        "rms": round(rms_level(scaled), 4),
+        "peak": round(float(np.max(np.abs(scaled))), 4),
+    }
+
+
+def emit_audio_level(desktop: bytes | None, mic: bytes | None) -> None:
+    # Linear 0..1 values; None for a source that is not being captured.
+    emit(
+        "level",
+        desktop=pcm_level(desktop) if desktop is not None else None,
+        mic=pcm_level(mic) if mic is not None else None,
+    )
+
+
+def loopback_correlation(desktop: bytes, mic: bytes, sample_rate: int) -> float:
+    """Peak normalized cross-correlation of the two sources within
+    LOOPBACK_MAX_LAG_SECONDS; 0.0 when either side is near silent."""
+    size = min(len(desktop), len(mic)) // 2
+    if size < sample_rate // 2:
+        return 0.0
+    first = np.frombuffer(desktop[: size * 2], dtype=np.int16).astype(np.float32) / 32768.0
+    second = np.frombuffer(mic[: size * 2], dtype=np.int16).astype(np.float32) / 32768.0
+    if rms_level(first) < LOOPBACK_MIN_RMS or rms_level(second) < LOOPBACK_MIN_RMS:
+        return 0.0
+    first = first - float(np.mean(first))
+    second = second - float(np.mean(second))
+    norm = float(np.linalg.norm(first) * np.linalg.norm(second))
+    if norm <= 0.0:
+        return 0.0
+
+    fft_size = 1 << int(np.ceil(np.log2(size * 2)))
+    correlation = np.fft.irfft(
+        np.fft.rfft(first, fft_size) * np.conj(np.fft.rfft(second, fft_size)),
+        fft_size,
+    )
+    max_lag = min(int(sample_rate * LOOPBACK_MAX_LAG_SECONDS), size - 1)
+    window = np.concatenate((correlation[: max_lag + 1], correlation[-max_lag:]))
+    return float(np.max(np.abs(window)) / norm)
+
+
 def drain_queue(queue: SimpleQueue[str]) -> str:
     lines: list[str] = []
     while not queue.empty():
@@ -250,48 +465,470 @@ def drain_queue(queue: SimpleQueue[str]) -> str:
     return "\n".join(lines)
 
 
+def pcm_from_bytes(pcm_bytes: bytes) -> np.ndarray:
+    return np.frombuffer(pcm_bytes, dtype=np.int16).astype(np.float32) / 32768.0
+
+
 def rms_level(audio: np.ndarray) -> float:
     if audio.size == 0:
         return 0.0
     return float(np.sqrt(np.mean(np.square(audio), dtype=np.float64)))
 
 
+def spectral_embedding(audio: np.ndarray, sample_rate: int = 16000) -> np.ndarray | None:
+    frame = 512
+    hop = 256
+    if audio.size < frame * 4:
+        return None
+
+    frames = np.lib.stride_tricks.sliding_window_view(audio, frame)[::hop]
+    energies = np.sqrt(np.mean(np.square(frames), axis=1))
+    voiced = frames[energies > max(float(energies.mean()) * 0.5, 1e-4)]
+    if voiced.shape[0] < 4:
+        return None
+
+    spectrum = np.abs(np.fft.rfft(voiced * np.hanning(frame), axis=1)) ** 2
+    freqs = np.fft.rfftfreq(frame, 1.0 / sample_rate)
+    edges = np.geomspace(150.0, 7600.0, 17)
+    bands = np.stack(
+        [spectrum[:, (freqs >= low) & (freqs < high)].sum(axis=1) for low, high in zip(edges[:-1], edges[1:])],
+        axis=1,
+    )
+    log_bands = np.log(bands + 1e-10)
+    # Drop per-frame loudness so the embedding tracks timbre, not volume.
+    log_bands -= log_bands.mean(axis=1, keepdims=True)
+    return np.concatenate([log_bands.mean(axis=0), log_bands.std(axis=0)])
+
+
+def is_music(audio: np.ndarray) -> bool:
+    """Whether a desktop chunk sounds like music with no speech in it."""
+    frame_count = audio.size // VAD_FRAME_SAMPLES
+    if frame_count < 8 or rms_level(audio) < MUSIC_MIN_RMS:
+        return False
+    frames = audio[: frame_count * VAD_FRAME_SAMPLES].reshape(frame_count, VAD_FRAME_SAMPLES)
+    energies = np.sqrt(np.mean(np.square(frames), axis=1))
+    low_energy_ratio = float(np.mean(energies < 0.5 * float(energies.mean())))
+    spread_db = float(np.std(20.0 * np.log10(energies + 1e-6)))
+    return low_energy_ratio <= MUSIC_MAX_LOW_ENERGY_RATIO and spread_db <= MUSIC_MAX_SPREAD_DB
+
+
+class MusicGate:
+    """Pauses transcription while desktop audio is music with no speech, so
+    the model neither burns battery on it nor writes out hallucinated lyrics.
+    Each change is reported as a `music` event."""
+
+    def __init__(self) -> None:
+        self.music_chunks = 0
+        self.active = False
+
+    def update(self, desktop: np.ndarray, at: float) -> bool:
+        self.music_chunks = self.music_chunks + 1 if is_music(desktop) else 0
+        active = self.music_chunks >= MUSIC_CONFIRM_CHUNKS
+        if active != self.active:
+            self.active = active
+            emit("music", active=active, at=round(at, 2))
+        return active
+
+
+class AutoGain:
+    """Moves one gain towards AGC_TARGET_RMS per block: quickly down, slowly up,
+    held through pauses, and limited so no block clips."""
+
+    def __init__(self, sample_rate: int = 16000) -> None:
+        block_seconds = AGC_BLOCK_SAMPLES / sample_rate
+        self.gain = 1.0
+        self.attack = 1.0 - math.exp(-block_seconds / AGC_ATTACK_SECONDS)
+        self.release = 1.0 - math.exp(-block_seconds / AGC_RELEASE_SECONDS)
+
+    def process(self, pcm_bytes: bytes) -> bytes:
+        audio = pcm_from_bytes(pcm_bytes[: len(pcm_bytes) - len(pcm_bytes) % 2])
+        for start in range(0, audio.size, AGC_BLOCK_SAMPLES):
+            block = audio[start : start + AGC_BLOCK_SAMPLES]
+            rms = rms_level(block)
+            if rms > AGC_GATE_RMS:
+                desired = min(max(AGC_TARGET_RMS / rms, AGC_MIN_GAIN), AGC_MAX_GAIN)
+                rate = self.attack if desired < self.gain else self.release
+                self.gain += (desired - self.gain) * rate
+            peak = float(np.max(np.abs(block)))
+            block *= min(self.gain, 0.99 / peak) if peak > 0 else self.gain
+        return (audio * 32767.0).round().astype(np.int16).tobytes()
+
+
+class VoiceDetector:
+    """Client of the helper's `--vad-filter` process. Tracks the pause before
+    the next detected speech separately for each source."""
+
+    def __init__(self, helper_path: str) -> None:
+        self.process = subprocess.Popen(
+            [helper_path, "--vad-filter"],
+            stdin=subprocess.PIPE,
+            stdout=subprocess.PIPE,
+            stderr=subprocess.DEVNULL,
+        )
+        self.pauses: dict[str | None, float] = {}
+
+    def speech_frames(self, audio: np.ndarray) -> np.ndarray:
+        pcm = (np.clip(audio, -1.0, 1.0) * 32767.0).round().astype(np.int16)
+        pcm = np.pad(pcm, (0, -pcm.size % VAD_FRAME_SAMPLES))
+        frame_count = pcm.size // VAD_FRAME_SAMPLES
+        if self.process.stdin is None or self.process.stdout is None:
+            raise RuntimeError("voice detector pipes unavailable")
+        try:
+            self.process.stdin.write(pcm.tobytes())
+            self.process.stdin.flush()
+            flags = self.process.stdout.read(frame_count)
+        except (BrokenPipeError, OSError):
+            flags = b""
+        if len(flags) != frame_count:
+            raise RuntimeError("voice detector stopped unexpectedly")
+        return np.frombuffer(flags, dtype=np.uint8) == ord("1")
+
+    def detect(self, source: str | None, audio: np.ndarray) -> tuple[bool, bool]:
+        """Returns whether `audio` holds enough speech to transcribe, and
+        whether that speech follows a pause long enough for a new paragraph."""
+        frames = self.speech_frames(audio)
+        speech = np.flatnonzero(frames)
+        pause = self.pauses.get(source, 0.0)
+        if speech.size * VAD_FRAME_SECONDS < VAD_MIN_SPEECH_SECONDS:
+            self.pauses[source] = pause + frames.size * VAD_FRAME_SECONDS
+            return False, False
+        paragraph = pause + speech[0] * VAD_FRAME_SECONDS >= PARAGRAPH_PAUSE_SECONDS
+        self.pauses[source] = (frames.size - 1 - speech[-1]) * VAD_FRAME_SECONDS
+        return True, paragraph
+
+    def close(self) -> None:
+        if self.process.stdin is not None:
+            self.process.stdin.close()
+        try:
+            self.process.wait(timeout=2)
+        except subprocess.TimeoutExpired:
+            self.process.kill()
+
+
+class SpeakerTracker:
+    """Online speaker clustering over coarse spectral embeddings.
+
+    Separates clearly different voices (e.g. two people on a call); it is not a
+    substitute for a trained diarization model.
+    """
+
+    def __init__(self, max_speakers: int = 4, threshold: float = 0.86) -> None:
+        self.max_speakers = max_speakers
+        self.threshold = threshold
+        self.centroids: list[np.ndarray] = []
+        self.counts: list[int] = []
+        self.last_speaker: int | None = None
+
+    def assign(self, audio: np.ndarray) -> int | None:
+        embedding = spectral_embedding(audio)
+        if embedding is None:
+            return self.last_speaker
+
+        best_index = -1
+        best_score = -1.0
+        for index, centroid in enumerate(self.centroids):
+            denom = float(np.linalg.norm(centroid) * np.linalg.norm(embedding)) + 1e-9
+            score = float(np.dot(centroid, embedding)) / denom
+            if score > best_score:
+                best_index, best_score = index, score
+
+        if best_index >= 0 and (best_score >= self.threshold or len(self.centroids) >= self.max_speakers):
+            count = self.counts[best_index]
+            self.centroids[best_index] = (self.centroids[best_index] * count + embedding) / (count + 1)
+            self.counts[best_index] = min(count + 1, 50)
+            self.last_speaker = best_index + 1
+        else:
+            self.centroids.append(embedding)
+            self.counts.append(1)
+            self.last_speaker = len(self.centroids)
+
+        return self.last_speaker
+
+
+def chunk_bounds(result: dict, chunk_start: float, chunk_duration: float) -> tuple[float, float]:
+    segments = result.get("segments", []) or []
+    if not segments:
+        return round(chunk_start, 2), round(chunk_start + chunk_duration, 2)
+
+    first = float(segments[0].get("start", 0.0))
+    last = float(segments[-1].get("end", chunk_duration))
+    return round(chunk_start + first, 2), round(chunk_start + min(last, chunk_duration), 2)
+
+
+def chunk_words(segments: list[dict], chunk_start: float) -> list[dict]:
+    words: list[dict] = []
+    for segment in segments:
+        for word in segment.get("words", []) or []:
+            text = str(word.get("word", "")).strip()
+            if not text:
+                continue
+            words.append(
+                {
+                    "word": text,
+                    "start": round(chunk_start + float(word.get("start", 0.0)), 2),
+                    "end": round(chunk_start + float(word.get("end", 0.0)), 2),
+                }
+            )
+    return words
+
+
+def sent_timings(timings: dict) -> dict:
+    """Stage times for a partial (`captureMs`, `inferenceMs`), stamped with
+    the wall-clock time the line is written so the app can time transport."""
+    return {**timings, "sentAt": round(time.time(), 4)}
+
+
+def emit_diarized_segments(
+    result: dict,
+    pcm: np.ndarray,
+    tracker: SpeakerTracker,
+    sample_rate: int,
+    chunk_start: float,
+    timings: dict,
+) -> list[str]:
+    emitted: list[str] = []
+    pending_text: list[str] = []
+    pending_words: list[dict] = []
+    pending_speaker: int | None = None
+    pending_start = 0.0
+    pending_end = 0.0
+
+    def flush() -> None:
+        if pending_text:
+            text = " ".join(pending_text)
+            extra = {"words": pending_words} if pending_words else {}
+            emit(
+                "partial",
+                text=text,
+                speaker=pending_speaker,
+                start=round(chunk_start + pending_start, 2),
+                end=round(chunk_start + pending_end, 2),
+                timings=sent_timings(timings),
+                **extra,
+            )
+            emitted.append(text)
+
+    for segment in result.get("segments", []) or []:
+        text = str(segment.get("text", "")).strip()
+        if not text:
+            continue
+        segment_start = float(segment.get("start", 0.0))
+        segment_end = float(segment.get("end", segment_start))
+        start = max(0, int(segment_start * sample_rate))
+        end = min(pcm.size, int(segment_end * sample_rate))
+        speaker = tracker.assign(pcm[start:end] if end > start else pcm)
+
+        if pending_text and speaker != pending_speaker:
+            flush()
+            pending_text = []
+            pending_words = []
+        if not pending_text:
+            pending_start = segment_start
+        pending_speaker = speaker
+        pending_end = segment_end
+        pending_text.append(text)
+        pending_words.extend(chunk_words([segment], chunk_start))
+
+    flush()
+    return emitted
+
+
+class SessionCommands:
+    """What the app sends on stdin while a session runs."""
+
+    def __init__(self) -> None:
+        self.stop_event = threading.Event()
+        self.preroll_queue: SimpleQueue[bytes] = SimpleQueue()
+        # `mute mic` / `mute desktop` requests, applied by the capture loop.
+        self.mute_queue: SimpleQueue[str] = SimpleQueue()
+        # `mic <capture name>` requests, sent by the app when devices change.
+        self.mic_queue: SimpleQueue[str] = SimpleQueue()
+
+    def handle(self, command: str) -> None:
+        if command.lower() == "stop":
+            self.stop_event.set()
+        elif command.startswith("mute "):
+            self.mute_queue.put(command[len("mute ") :].strip())
+        elif command.startswith("mic "):
+            self.mic_queue.put(command[len("mic ") :].strip())
+        elif command.startswith("preroll"):
+            payload = command[len("preroll") :].strip()
+            try:
+                self.preroll_queue.put(base64.b64decode(payload) if payload else b"")
+            except ValueError:
+                self.preroll_queue.put(b"")
+
+
 def main() -> int:
     args = parse_args()
-    language = normalize_language(args.language)
-    model_path = Path(args.model_path).expanduser().resolve()
+    if args.persistent:
+        return serve(args)
+    if args.transcribe_file:
+        return transcribe_file(args)
 
-    if resolve_ffmpeg_binary(raise_if_missing=False) is None:
-        emit(
-            "error",
-            message=(
-                "ffmpeg is required for microphone capture. "
-                "Install it with Homebrew (`brew install ffmpeg`) or set WHISPERBAR_FFMPEG_PATH."
-            ),
+    commands = SessionCommands()
+
+    def read_commands() -> None:
+        for line in sys.stdin:
+            commands.handle(line.strip())
+            if commands.stop_event.is_set():
+                break
+
+    def stop_from_signal(_signum: int, _frame: object) -> None:
+        commands.stop_event.set()
+
+    signal.signal(signal.SIGINT, stop_from_signal)
+    signal.signal(signal.SIGTERM, stop_from_signal)
+
+    stdin_thread = threading.Thread(target=read_commands, daemon=True)
+    stdin_thread.start()
+    return run_session(args, commands)
+
+
+def transcribe_file(args: argparse.Namespace) -> int:
+    """`--transcribe-file`: one `partial` per recognized segment of a kept
+    recording (per speaker turn with `--diarize`), with times from the start
+    of the file, then `final`."""
+    try:
+        emit("status", message="Transcribing recording")
+        with wave.open(args.transcribe_file, "rb") as recording:
+            if (
+                recording.getnchannels() != 1
+                or recording.getsampwidth() != 2
+                or recording.getframerate() != 16000
+            ):
+                emit("error", message="The recording is not 16 kHz mono 16-bit WAV.")
+                return 1
+            audio = pcm_from_bytes(recording.readframes(recording.getnframes()))
+        result = mlx_whisper.transcribe(
+            audio,
+            path_or_hf_repo=str(Path(args.model_path).expanduser().resolve()),
+            language=normalize_language(args.language),
+            no_speech_threshold=0.45,
+            temperature=0.0,
+            condition_on_previous_text=True,
+            initial_prompt=args.initial_prompt or None,
+            word_timestamps=args.word_timestamps,
         )
+    except Exception as exc:  # noqa: BLE001
+        emit("error", message=f"{exc}\n{traceback.format_exc()}")
         return 1
 
-    stop_event = threading.Event()
+    if args.diarize:
+        emit_diarized_segments(result, audio, SpeakerTracker(), 16000, 0.0, {})
+        emit("final")
+        return 0
 
-    def stop_from_stdin() -> None:
+    for segment in result.get("segments", []):
+        text = str(segment.get("text", "")).strip()
+        if not text:
+            continue
+        fields: dict[str, object] = {
+            "text": text,
+            "start": round(float(segment.get("start", 0.0)), 2),
+            "end": round(float(segment.get("end", 0.0)), 2),
+        }
+        if args.word_timestamps:
+            fields["words"] = chunk_words([segment], 0.0)
+        emit("partial", **fields)
+    emit("final")
+    return 0
+
+
+def serve(args: argparse.Namespace) -> int:
+    """Persistent mode: load the model once and report `ready`, then run one
+    session per `start` line. `stop` ends the current session, after which the
+    worker reports `idle`. Exits on `shutdown`, stdin closing, a failed session, or
+    no new session for `--idle-timeout` seconds after the last one."""
+    sessions: SimpleQueue[tuple[argparse.Namespace, SessionCommands] | None] = SimpleQueue()
+    current = [SessionCommands()]
+
+    def read_commands() -> None:
         for line in sys.stdin:
-            if line.strip().lower() == "stop":
-                stop_event.set()
+            command = line.strip()
+            if command.startswith("start "):
+                try:
+                    session_args = parse_args(json.loads(command[len("start ") :]))
+                except (ValueError, SystemExit) as exc:
+                    emit("error", message=f"Invalid session arguments: {exc}")
+                    continue
+                # Later lines (preroll, mute) belong to the new session.
+                current[0] = SessionCommands()
+                sessions.put((session_args, current[0]))
+            elif command.lower() == "shutdown":
                 break
+            else:
+                current[0].handle(command)
+        current[0].stop_event.set()
+        sessions.put(None)
 
     def stop_from_signal(_signum: int, _frame: object) -> None:
-        stop_event.set()
+        current[0].stop_event.set()
+        sessions.put(None)
 
     signal.signal(signal.SIGINT, stop_from_signal)
     signal.signal(signal.SIGTERM, stop_from_signal)
 
-    stdin_thread = threading.Thread(target=stop_from_stdin, daemon=True)
+    stdin_thread = threading.Thread(target=read_commands, daemon=True)
     stdin_thread.start()
 
+    try:
+        emit("status", message="Loading model")
+        # The first transcription loads the weights; later ones with the same
+        # path reuse them.
+        mlx_whisper.transcribe(
+            np.zeros(16000, dtype=np.float32),
+            path_or_hf_repo=str(Path(args.model_path).expanduser().resolve()),
+            language=normalize_language(args.language),
+            temperature=0.0,
+        )
+    except Exception as exc:  # noqa: BLE001
+        emit("error", message=f"{exc}\n{traceback.format_exc()}")
+        return 1
+
+    emit("ready")
+    idle_timeout: float | None = None
+    while True:
+        try:
+            session = sessions.get(timeout=idle_timeout)
+        except Empty:
+            emit("status", message="Worker idle too long, exiting")
+            return 0
+        if session is None:
+            return 0
+        session_args, commands = session
+        idle_timeout = args.idle_timeout
+        code = run_session(session_args, commands)
+        if code != 0:
+            return code
+        emit("idle")
+
+
+def run_session(args: argparse.Namespace, commands: SessionCommands) -> int:
+    language = normalize_language(args.language)
+    model_path = Path(args.model_path).expanduser().resolve()
+
+    if resolve_ffmpeg_binary(raise_if_missing=False) is None:
+        emit(
+            "error",
+            message=(
+                "ffmpeg is required for microphone capture. "
+                "Install it with Homebrew (`brew install ffmpeg`) or set WHISPERBAR_FFMPEG_PATH."
+            ),
+        )
+        return 1
+
+    stop_event = commands.stop_event
+    preroll_queue = commands.preroll_queue
+    mute_queue = commands.mute_queue
+    mic_queue = commands.mic_queue
+
     desktop_proc: subprocess.Popen[bytes] | None = None
     mic_proc: subprocess.Popen[bytes] | None = None
     desktop_stderr_queue: SimpleQueue[str] | None = None
     mic_stderr_queue: SimpleQueue[str] | None = None
+    audio_writer: wave.Wave_write | None = None
+    voice_detector: VoiceDetector | None = None
 
     try:
         emit("status", message="Loading model")
@@ -300,47 +937,184 @@ def main() -> int:
         bytes_per_second = sample_rate * 2
         # Slightly shorter chunks reduce missed transitions in conversational speech.
         chunk_bytes = max(int(bytes_per_second * args.chunk_seconds), int(bytes_per_second * 1.2))
+        level_bytes = bytes_per_second // LEVEL_UPDATES_PER_SECOND
 
+        system_audio = not args.no_system_audio
         helper_path = args.sck_helper_path.strip()
-        if not helper_path:
+        if system_audio and not helper_path:
             raise RuntimeError("ScreenCaptureKit helper path is missing")
-        if not Path(helper_path).exists():
+        if system_audio and not Path(helper_path).exists():
             raise RuntimeError(f"ScreenCaptureKit helper binary not found: {helper_path}")
 
         mic_input, mic_name = choose_optional_mic(args)
-        if mic_input and mic_name:
+        if not system_audio and not mic_input:
+            raise RuntimeError(
+                "System audio is off and no microphone is available. "
+                "Select a microphone or turn system audio back on."
+            )
+        if system_audio and mic_input and mic_name:
             emit(
                 "status",
                 message=f"Listening desktop (ScreenCaptureKit) + mic: {mic_name}",
             )
-        else:
+        elif system_audio:
             emit("status", message="Listening desktop (ScreenCaptureKit)")
-
-        desktop_proc = spawn_screencapturekit_helper(helper_path)
-        if desktop_proc.stdout is None:
-            raise RuntimeError("ScreenCaptureKit helper stdout unavailable")
-        if desktop_proc.stderr is None:
-            raise RuntimeError("ScreenCaptureKit helper stderr unavailable")
-        desktop_stderr_queue = SimpleQueue()
-        start_stderr_reader(desktop_proc, desktop_stderr_queue)
-
+        else:
+            emit("status", message=f"Listening mic: {mic_name}")
+
+        if system_audio:
+            desktop_proc = spawn_screencapturekit_helper(helper_path, args.resample_quality)
+            if desktop_proc.stdout is None:
+                raise RuntimeError("ScreenCaptureKit helper stdout unavailable")
+            if desktop_proc.stderr is None:
+                raise RuntimeError("ScreenCaptureKit helper stderr unavailable")
+            desktop_stderr_queue = SimpleQueue()
+            start_stderr_reader(desktop_proc, desktop_stderr_queue)
+
+        denoise_helper = helper_path if args.denoise and helper_path else None
+        if args.vad and helper_path:
+            voice_detector = VoiceDetector(helper_path)
+        mic_profile = load_device_profile(args.device_profiles, mic_name) if mic_input else {}
         if mic_input:
-            mic_proc = spawn_ffmpeg_mic_only(mic_input)
-            if mic_proc.stdout is None:
-                raise RuntimeError("microphone ffmpeg stdout unavailable")
-            if mic_proc.stderr is None:
-                raise RuntimeError("microphone ffmpeg stderr unavailable")
-            mic_stderr_queue = SimpleQueue()
-            start_stderr_reader(mic_proc, mic_stderr_queue)
+            mic_proc, mic_stderr_queue = start_mic_capture(mic_input, mic_profile, denoise_helper)
+
+        if args.audio_path:
+            audio_writer = wave.open(args.audio_path, "wb")
+            audio_writer.setnchannels(1)
+            audio_writer.setsampwidth(2)
+            audio_writer.setframerate(sample_rate)
 
         collected: list[str] = []
+        speaker_tracker = SpeakerTracker() if args.diarize else None
+        mic_gain = AutoGain(sample_rate)
+        music_gate = MusicGate() if args.skip_music and system_audio else None
+        session_seconds = 0.0
+        awaiting_preroll = args.preroll
+        muted: set[str] = set()
+        loopback_chunks = 0
+        loopback_reported = False
+        silent_mic_blocks = 0
+        mic_muted = False
 
         while not stop_event.is_set():
-            if desktop_proc is None or desktop_proc.stdout is None:
+            while not mute_queue.empty():
+                source = mute_queue.get()
+                if source not in ("mic", "desktop") or source in muted:
+                    continue
+                has_other = mic_proc is not None if source == "desktop" else system_audio
+                if not has_other or muted:
+                    emit("status", message=f"Can't drop {source}: it is the only source left")
+                    continue
+                muted.add(source)
+                emit("muted", source=source)
+
+            while not mic_queue.empty():
+                requested = resolve_device(mic_queue.get(), list_audio_devices())
+                if requested is None:
+                    continue
+                if mic_proc is not None and mic_proc.poll() is None and requested[1] == mic_name:
+                    continue
+                # After a failure the app already explained the switch.
+                recovering = mic_proc is None
+                stop_process(mic_proc)
+                mic_input, mic_name = requested
+                mic_profile = load_device_profile(args.device_profiles, mic_name)
+                mic_proc, mic_stderr_queue = start_mic_capture(mic_input, mic_profile, denoise_helper)
+                silent_mic_blocks = 0
+                if not recovering:
+                    emit("status", message=f"Switched microphone to {mic_name}")
+
+            if not system_audio and mic_proc is None:
+                raise RuntimeError("Microphone disconnected and no other microphone is available.")
+
+            if system_audio and (desktop_proc is None or desktop_proc.stdout is None):
                 raise RuntimeError("desktop capture process is not running")
 
-            desktop_bytes = desktop_proc.stdout.read(chunk_bytes)
-            if not desktop_bytes:
+            # Read in short blocks so the level meter updates while a chunk fills.
+            # Desktop audio paces the loop; without it the microphone does.
+            desktop_buffer = bytearray()
+            mic_buffer = bytearray()
+            while max(len(desktop_buffer), len(mic_buffer)) < chunk_bytes:
+                block_size = min(level_bytes, chunk_bytes - max(len(desktop_buffer), len(mic_buffer)))
+                desktop_block = b""
+                if desktop_proc is not None and desktop_proc.stdout is not None:
+                    desktop_block = desktop_proc.stdout.read(block_size)
+                    if not desktop_block:
+                        break
+                    desktop_buffer += desktop_block
+
+                mic_block = b""
+                if mic_proc is not None and mic_proc.stdout is not None:
+                    mic_block = mic_proc.stdout.read(block_size)
+                    if mic_block:
+                        mic_buffer += mic_block
+                    elif mic_proc.poll() is not None:
+                        mic_error = (
+                            drain_queue(mic_stderr_queue)
+                            if mic_stderr_queue is not None
+                            else ""
+                        )
+                        # Failed to open or unplugged: the app picks a replacement.
+                        emit("mic_failed", device=mic_name, message=mic_error or None)
+                        if not system_audio:
+                            try:
+                                mic_queue.put(mic_queue.get(timeout=MIC_SWITCH_GRACE_SECONDS))
+                            except Empty:
+                                raise RuntimeError(
+                                    "Microphone capture stopped. "
+                                    + (mic_error or "Check macOS Microphone permission.")
+                                ) from None
+                            mic_proc = None
+                            mic_stderr_queue = None
+                            break
+                        mic_proc = None
+                        mic_stderr_queue = None
+
+                if mic_block:
+                    # Session time where this block starts.
+                    block_start = session_seconds + (len(mic_buffer) - len(mic_block)) / bytes_per_second
+                    if not np.any(np.frombuffer(mic_block[: len(mic_block) - len(mic_block) % 2], dtype=np.int16)):
+                        silent_mic_blocks += 1
+                        if not mic_muted and silent_mic_blocks >= MIC_MUTE_BLOCKS:
+                            mic_muted = True
+                            muted_at = block_start - (silent_mic_blocks - 1) * len(mic_block) / bytes_per_second
+                            emit("mic_muted", muted=True, at=round(max(muted_at, 0.0), 2))
+                    else:
+                        silent_mic_blocks = 0
+                        if mic_muted:
+                            mic_muted = False
+                            emit("mic_muted", muted=False, at=round(block_start, 2))
+
+                if not system_audio and not mic_block:
+                    break
+
+                emit_audio_level(
+                    desktop_block if system_audio else None,
+                    mic_block if mic_proc is not None else None,
+                )
+                if stop_event.is_set():
+                    break
+
+            # The chunk is complete; what follows until inference is queueing.
+            captured_at = time.monotonic()
+            desktop_bytes = bytes(desktop_buffer)
+            mic_bytes = bytes(mic_buffer)
+
+            if not loopback_reported and not muted and desktop_bytes and mic_bytes:
+                if loopback_correlation(desktop_bytes, mic_bytes, sample_rate) >= LOOPBACK_CORRELATION:
+                    loopback_chunks += 1
+                else:
+                    loopback_chunks = 0
+                if loopback_chunks >= LOOPBACK_CONFIRM_CHUNKS:
+                    loopback_reported = True
+                    emit(
+                        "loopback",
+                        message=(
+                            "Desktop audio is echoing your microphone (mic monitoring or a loopback device), "
+                            "so speech may be transcribed twice. Drop one source for this session."
+                        ),
+                    )
+            if system_audio and not desktop_bytes and desktop_proc is not None:
                 if desktop_proc.poll() is not None:
                     stderr_text = (
                         drain_queue(desktop_stderr_queue)
@@ -353,31 +1127,33 @@ def main() -> int:
                     )
                 continue
 
-            mic_bytes = b""
-            if mic_proc is not None and mic_proc.stdout is not None:
-                mic_chunk = mic_proc.stdout.read(chunk_bytes)
-                if mic_chunk:
-                    mic_bytes = mic_chunk
-                elif mic_proc.poll() is not None:
-                    mic_error = (
-                        drain_queue(mic_stderr_queue)
-                        if mic_stderr_queue is not None
-                        else ""
-                    )
-                    emit(
-                        "status",
-                        message=(
-                            "Microphone capture stopped; continuing with desktop only. "
-                            + (mic_error or "Check macOS Microphone permission.")
-                        ),
-                    )
-                    mic_proc = None
-                    mic_stderr_queue = None
-
-            pcm_bytes = mix_pcm_streams(desktop_bytes, mic_bytes)
+            if mic_bytes:
+                mic_bytes = mic_gain.process(mic_bytes)
+
+            # Muted sources are still read so their pipes never fill up.
+            if "desktop" in muted:
+                pcm_bytes = mix_pcm_streams(mic_bytes, None)
+            else:
+                pcm_bytes = mix_pcm_streams(desktop_bytes, None if "mic" in muted else mic_bytes)
+
+            # Both sources must be in the mix to be told apart.
+            separate = args.separate_sources and bool(mic_bytes) and bool(desktop_bytes) and not muted
+
+            if awaiting_preroll:
+                awaiting_preroll = False
+                try:
+                    preroll_bytes = preroll_queue.get(timeout=1.0)
+                except Empty:
+                    preroll_bytes = b""
+                # Keep sample alignment even if the buffer was cut mid-sample.
+                preroll_bytes = preroll_bytes[: len(preroll_bytes) - len(preroll_bytes) % 2]
+                pcm_bytes = preroll_bytes + pcm_bytes
+                # Pre-roll is mic audio; pad the desktop so both stay aligned.
+                mic_bytes = preroll_bytes + mic_bytes
+                desktop_bytes = bytes(len(preroll_bytes)) + desktop_bytes
 
             if not pcm_bytes:
-                if desktop_proc.poll() is not None:
+                if desktop_proc is not None and desktop_proc.poll() is not None:
                     stderr_text = (
                         drain_queue(desktop_stderr_queue)
                         if desktop_stderr_queue is not None
@@ -389,26 +1165,100 @@ def main() -> int:
                     )
                 continue
 
-            pcm = np.frombuffer(pcm_bytes, dtype=np.int16).astype(np.float32) / 32768.0
+            if audio_writer is not None:
+                audio_writer.writeframes(pcm_bytes)
+
+            pcm = pcm_from_bytes(pcm_bytes)
+            chunk_start = session_seconds
+            session_seconds += pcm.size / sample_rate
             if pcm.size < int(sample_rate * 0.8):
                 continue
-            if rms_level(pcm) < 0.0006:
-                continue
-
-            result = mlx_whisper.transcribe(
-                pcm,
-                path_or_hf_repo=str(model_path),
-                language=language,
-                # Lower no_speech_threshold keeps short Portuguese/English fragments.
-                no_speech_threshold=0.45,
-                temperature=0.0,
-                condition_on_previous_text=True,
-                word_timestamps=False,
+            # The calibrated threshold was measured before AGC; follow its gain.
+            mic_threshold = silence_threshold(mic_profile) * mic_gain.gain
+            music_only = music_gate is not None and music_gate.update(
+                pcm_from_bytes(b"" if "desktop" in muted else desktop_bytes), chunk_start
             )
-            chunk_text = str(result.get("text", "")).strip()
-            if chunk_text:
-                collected.append(chunk_text)
-                emit("partial", text=chunk_text)
+            if separate:
+                streams = [
+                    ("mic", pcm_from_bytes(mic_bytes), mic_threshold),
+                    ("desktop", pcm_from_bytes(desktop_bytes), SILENCE_RMS),
+                ]
+                if music_only:
+                    streams = streams[:1]
+            elif music_only:
+                # Keep transcribing the microphone on its own.
+                has_mic = bool(mic_bytes) and "mic" not in muted
+                streams = [(None, pcm_from_bytes(mic_bytes), mic_threshold)] if has_mic else []
+            else:
+                # Desktop audio has its own floor, so the mic's calibrated
+                # threshold only applies when the mic is all that is mixed.
+                mic_only = not system_audio or "desktop" in muted
+                threshold = mic_threshold if mic_only else SILENCE_RMS
+                streams = [(None, pcm, threshold)]
+
+            # Both sources cover the same span; emit in spoken order.
+            chunk_partials: list[dict] = []
+            for source, audio, threshold in streams:
+                paragraph = False
+                if voice_detector is not None:
+                    # The detector replaces the level threshold entirely.
+                    has_speech, paragraph = voice_detector.detect(source, audio)
+                    if not has_speech:
+                        continue
+                    emit("speech")
+                elif rms_level(audio) < threshold:
+                    continue
+
+                inference_started = time.monotonic()
+                result = mlx_whisper.transcribe(
+                    audio,
+                    path_or_hf_repo=str(model_path),
+                    language=language,
+                    # Lower no_speech_threshold keeps short Portuguese/English fragments.
+                    no_speech_threshold=0.45,
+                    temperature=0.0,
+                    condition_on_previous_text=True,
+                    initial_prompt=args.initial_prompt or None,
+                    word_timestamps=args.word_timestamps,
+                )
+                timings = {
+                    "captureMs": round((inference_started - captured_at) * 1000, 1),
+                    "inferenceMs": round((time.monotonic() - inference_started) * 1000, 1),
+                }
+                if speaker_tracker is not None and source is None:
+                    diarized = emit_diarized_segments(
+                        result, audio, speaker_tracker, sample_rate, chunk_start, timings
+                    )
+                    if diarized and voice_detector is None:
+                        # Lets the app's silence watchdog know someone is still talking.
+                        emit("speech")
+                    collected.extend(diarized)
+                    continue
+
+                chunk_text = str(result.get("text", "")).strip()
+                if chunk_text:
+                    start, end = chunk_bounds(result, chunk_start, audio.size / sample_rate)
+                    partial = {
+                        "text": chunk_text,
+                        "start": start,
+                        "end": end,
+                        "source": source,
+                        "timings": timings,
+                    }
+                    if paragraph:
+                        partial["paragraph"] = True
+                    words = chunk_words(result.get("segments", []) or [], chunk_start)
+                    if words:
+                        partial["words"] = words
+                    chunk_partials.append(partial)
+
+            chunk_partials.sort(key=lambda partial: partial["start"])
+            for partial in chunk_partials:
+                if voice_detector is None:
+                    emit("speech")
+                collected.append(partial["text"])
+                partial["timings"] = sent_timings(partial["timings"])
+                emit("partial", **partial)
 
         final_text = "\n".join(collected).strip()
         emit("final", text=final_text)
@@ -418,22 +1268,12 @@ def main() -> int:
         emit("error", message=f"{exc}\n{traceback.format_exc()}")
         return 1
     finally:
+        if audio_writer is not None:
+            audio_writer.close()
         for proc in [desktop_proc, mic_proc]:
-            if proc is None:
-                continue
-            if proc.poll() is not None:
-                continue
-            try:
-                if proc.stdin is not None:
-                    proc.stdin.write(b"stop\n")
-                    proc.stdin.flush()
-            except Exception:  # noqa: BLE001
-                pass
-            proc.terminate()
-            try:
-                proc.wait(timeout=2)
-            except subprocess.TimeoutExpired:
-                proc.kill()
+            stop_process(proc)
+        if voice_detector is not None:
+            voice_detector.close()
 
 
 def shutil_which(binary: str) -> str | None:
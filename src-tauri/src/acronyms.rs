@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail, Context};
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+
+use crate::transcript_file;
+
+/// Entries per profile; the whole map is compiled into one pattern.
+pub const MAX_ACRONYMS: usize = 500;
+
+/// Separates the two sides of an entry in the text format.
+const SEPARATOR: &str = "=>";
+
+/// How the model writes a term (`sequel`, `K eight S`) and how it should
+/// read in the transcript (`SQL`, `K8s`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Acronym {
+    pub spoken: String,
+    pub written: String,
+}
+
+/// An acronym map compiled for a session. Spoken forms match whole words,
+/// ignoring case and with any run of spaces or hyphens between their words;
+/// the longest one wins where several overlap.
+#[derive(Debug, Clone, Default)]
+pub struct Expander {
+    pattern: Option<Regex>,
+    /// Written form by `key` of the spoken form.
+    written: HashMap<String, String>,
+}
+
+impl Expander {
+    pub fn new(entries: &[Acronym]) -> Self {
+        let mut entries = entries
+            .iter()
+            .filter(|entry| !words(&entry.spoken).is_empty())
+            .collect::<Vec<_>>();
+        if entries.is_empty() {
+            return Self::default();
+        }
+        // The regex alternation takes the first branch that matches, so
+        // `K eight S` has to come before `K`.
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.spoken.trim().len()));
+
+        let alternatives = entries
+            .iter()
+            .map(|entry| spoken_pattern(&entry.spoken))
+            .collect::<Vec<_>>()
+            .join("|");
+        let pattern =
+            Regex::new(&format!("(?i){alternatives}")).expect("escaped acronym pattern is valid");
+        let written = entries
+            .iter()
+            .map(|entry| (key(&entry.spoken), entry.written.trim().to_string()))
+            .collect();
+
+        Self {
+            pattern: Some(pattern),
+            written,
+        }
+    }
+
+    pub fn apply(&self, text: &str) -> String {
+        let Some(pattern) = &self.pattern else {
+            return text.to_string();
+        };
+        pattern
+            .replace_all(text, |caps: &Captures| {
+                let matched = &caps[0];
+                self.written
+                    .get(&key(matched))
+                    .cloned()
+                    .unwrap_or_else(|| matched.to_string())
+            })
+            .into_owned()
+    }
+}
+
+fn words(spoken: &str) -> Vec<&str> {
+    spoken
+        .split(|c: char| c.is_whitespace() || c == '-')
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+fn key(spoken: &str) -> String {
+    words(spoken).join(" ").to_lowercase()
+}
+
+/// `K eight S` -> `\bK[\s-]+eight[\s-]+S\b`. The `\b` is left off an edge
+/// that is not a word character (`C++`), where it could never match.
+fn spoken_pattern(spoken: &str) -> String {
+    let words = words(spoken);
+    let body = words
+        .iter()
+        .map(|word| regex::escape(word))
+        .collect::<Vec<_>>()
+        .join(r"[\s-]+");
+    let word_char = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+    let start = if word_char(words[0].chars().next()) {
+        r"\b"
+    } else {
+        ""
+    };
+    let end = if word_char(words[words.len() - 1].chars().last()) {
+        r"\b"
+    } else {
+        ""
+    };
+    format!("(?:{start}{body}{end})")
+}
+
+pub fn validate(entries: &[Acronym]) -> anyhow::Result<()> {
+    if entries.len() > MAX_ACRONYMS {
+        bail!("an acronym profile holds at most {MAX_ACRONYMS} entries");
+    }
+    for entry in entries {
+        if words(&entry.spoken).is_empty() || entry.written.trim().is_empty() {
+            bail!("every acronym needs both a spoken and a written form");
+        }
+    }
+    Ok(())
+}
+
+/// Parses the import/export format: one `spoken => written` entry per line.
+/// Blank lines and lines starting with `#` are ignored; a later entry for the
+/// same spoken form replaces an earlier one.
+pub fn parse(text: &str) -> anyhow::Result<Vec<Acronym>> {
+    let mut entries: Vec<Acronym> = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (spoken, written) = line.split_once(SEPARATOR).ok_or_else(|| {
+            anyhow!(
+                "line {}: expected `spoken {SEPARATOR} written`, got `{line}`",
+                index + 1
+            )
+        })?;
+        let entry = Acronym {
+            spoken: spoken.trim().to_string(),
+            written: written.trim().to_string(),
+        };
+        validate(std::slice::from_ref(&entry))
+            .map_err(|error| anyhow!("line {}: {error}", index + 1))?;
+
+        let spoken_key = key(&entry.spoken);
+        entries.retain(|existing| key(&existing.spoken) != spoken_key);
+        entries.push(entry);
+    }
+    validate(&entries)?;
+    Ok(entries)
+}
+
+/// Writes `entries` in the format `parse` reads.
+pub fn to_text(name: &str, entries: &[Acronym]) -> String {
+    let mut text = format!("# WhisperBar acronyms: {name}\n# spoken {SEPARATOR} written\n");
+    for entry in entries {
+        text.push_str(&format!(
+            "{} {SEPARATOR} {}\n",
+            entry.spoken.trim(),
+            entry.written.trim()
+        ));
+    }
+    text
+}
+
+/// Writes an exported profile to `~/Downloads/<name>.acronyms.txt` (`-2`,
+/// `-3`, ... when that name is taken) and reveals it in Finder.
+pub async fn export(name: &str, text: &str) -> anyhow::Result<String> {
+    let downloads_dir =
+        dirs::download_dir().ok_or_else(|| anyhow!("unable to locate Downloads directory"))?;
+    let file_name = name
+        .chars()
+        .map(|c| if matches!(c, '/' | ':') { '-' } else { c })
+        .collect::<String>();
+    let (destination, _) =
+        transcript_file::create_unique(&downloads_dir, &file_name, "acronyms.txt").await?;
+    tokio::fs::write(&destination, text)
+        .await
+        .with_context(|| format!("failed writing {}", destination.display()))?;
+
+    let _ = std::process::Command::new("open")
+        .arg("-R")
+        .arg(&destination)
+        .spawn();
+
+    Ok(destination.display().to_string())
+}
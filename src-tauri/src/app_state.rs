@@ -1,10 +1,58 @@
-use std::{fs, path::PathBuf, sync::Arc};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
 
-use crate::models;
+use crate::{
+    acronyms::{self, Acronym},
+    audio::{AudioSource, DeviceProfile},
+    bootstrap::{DownloadNetwork, DownloadNetworkSummary},
+    dictation::DictationOutput,
+    engine::{Engine, WarmWorker},
+    errors::{ErrorKind, ErrorSeverity},
+    glossary::MeetingEndAction,
+    history::HistoryIndex,
+    install_status::InstallStatus,
+    links::AutoLinkSink,
+    llm::{Llm, LlmSummary},
+    maintenance,
+    managed::ManagedPreferences,
+    models::{self, CustomModel, ModelBackend},
+    partials,
+    postprocess::{Postprocess, PostprocessSummary},
+    preroll::{self, PrerollState},
+    recording_audio,
+    replacements::{self, Replacement},
+    sck_audio_helper::ResampleQuality,
+    search::SearchIndex,
+    snapshot_log::SnapshotLog,
+    transcript_edit::EditHistory,
+    transcript_file::{self, FileNaming, RenderOptions, Title},
+    tray,
+    ui::FloatingPlacement,
+};
+use chrono::{DateTime, Local, Utc};
 use serde::{Deserialize, Serialize};
 use tauri::async_runtime::JoinHandle;
 use tauri::{AppHandle, Emitter};
-use tokio::{process::Child, process::ChildStdin, sync::Mutex};
+use tokio::sync::{Mutex, Notify};
+
+/// Settings changes within this window are written to disk together.
+const SETTINGS_WRITE_DEBOUNCE: Duration = Duration::from_millis(500);
+
+pub const MAX_AUTO_STOP_SILENCE_MINUTES: u32 = 120;
+pub const DEFAULT_WARM_WORKER_MINUTES: u32 = 10;
+pub const MAX_WARM_WORKER_MINUTES: u32 = 120;
+pub const DEFAULT_MAX_RECORDING_MINUTES: u32 = 180;
+pub const DEFAULT_MEETING_END_GRACE_MINUTES: u32 = 5;
+pub const MAX_MEETING_END_GRACE_MINUTES: u32 = 120;
+pub const MAX_RECORDING_MINUTES_LIMIT: u32 = 24 * 60;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum AppStatus {
@@ -12,48 +60,401 @@ pub enum AppStatus {
     Installing,
     Ready,
     Recording,
+    /// A kept recording is being transcribed again (`history::retranscribe`).
+    Transcribing,
     Error,
 }
 
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AppSnapshot {
+    /// Position in the emitted event stream (see `SnapshotLog`); 0 until emitted.
+    pub seq: u64,
     pub status: AppStatus,
     pub status_message: String,
     pub language: String,
     pub selected_model_id: String,
     pub selected_model_installed: bool,
     pub selected_mic_device: Option<String>,
+    pub system_audio_enabled: bool,
+    pub muted_source: Option<AudioSource>,
+    pub mic_muted: bool,
+    /// Desktop audio is music with no speech, so it is not being transcribed.
+    pub music_gated: bool,
     pub transcript: String,
+    pub session_notes: Vec<SessionNote>,
     pub last_saved_path: Option<String>,
+    pub last_audio_path: Option<String>,
     pub install_progress: Option<f32>,
+    pub install_status: Option<InstallStatus>,
     pub error_message: Option<String>,
+    pub error_kind: Option<ErrorKind>,
+    pub error_severity: Option<ErrorSeverity>,
+    pub suppressed_errors: Vec<ErrorKind>,
+    pub auto_link_sinks: Vec<AutoLinkSink>,
+    pub download_network: DownloadNetworkSummary,
+    pub device_profiles: BTreeMap<String, DeviceProfile>,
+    pub acronym_profiles: BTreeMap<String, Vec<Acronym>>,
+    pub acronym_profile: Option<String>,
+    /// The replacement dictionary in the format `replacements::parse` reads.
+    pub replacements: String,
+    pub preroll_seconds: u32,
+    pub preroll_active: bool,
+    pub keep_audio: bool,
+    pub silence_trim_padding: Option<u32>,
+    pub hallucination_filter: bool,
+    pub remove_fillers: bool,
+    pub resample_quality: ResampleQuality,
+    pub noise_suppression: bool,
+    pub voice_activity_detection: bool,
+    pub skip_music: bool,
+    pub word_timestamps: bool,
+    pub auto_stop_silence_minutes: u32,
+    pub warm_worker_minutes: u32,
+    pub preload_model: bool,
+    pub max_recording_minutes: u32,
+    pub meeting_end_action: MeetingEndAction,
+    pub meeting_end_grace_minutes: u32,
+    pub floating_placement: Option<FloatingPlacement>,
+    pub record_button: bool,
+    pub maintenance_hour: u32,
+    pub retention_days: u32,
+    pub partial_updates_per_second: u32,
+    pub diarization_enabled: bool,
+    /// False while a whisper.cpp model is selected; see
+    /// `SettingsState::diarization_available`.
+    pub diarization_available: bool,
+    pub separate_sources: bool,
+    pub include_timestamps: bool,
+    pub name_template: String,
+    pub title_llm: Option<LlmSummary>,
+    pub postprocess: Option<PostprocessSummary>,
+    pub spell_check_enabled: bool,
+    pub transcript_dirty: bool,
+    pub transcript_can_undo: bool,
+    pub transcript_can_redo: bool,
+    pub recording_countdown: Option<u32>,
+    /// Whole seconds since the current recording started; refreshed every
+    /// second while recording.
+    pub recording_elapsed_seconds: Option<u64>,
+    /// The app data directory is not writable; see `AppPaths::read_only`.
+    pub read_only: bool,
+    /// Where aggregate usage counters go, when a configuration profile
+    /// turned them on; see `usage`.
+    pub usage_report_endpoint: Option<String>,
+    /// Fields of this snapshot a configuration profile decides, which the
+    /// panel shows locked; see `ManagedPreferences::locked_settings`.
+    pub locked_settings: Vec<String>,
+    /// The models a configuration profile allows; `None` allows all.
+    pub allowed_models: Option<Vec<String>>,
+    /// A configuration profile limits LLM servers to this Mac.
+    pub local_only: bool,
+    /// Where transcripts are saved.
+    pub output_dir: Option<String>,
+    /// Command availability; see `Availability`.
+    pub can_start: bool,
+    pub can_stop: bool,
+    pub can_install: bool,
 }
 
-pub struct WorkerProcess {
-    pub child: Child,
-    pub stdin: Option<ChildStdin>,
-    pub stdout_task: Option<JoinHandle<()>>,
-    pub stderr_task: Option<JoinHandle<()>>,
+/// Which recording and install commands can run right now. The panel, the
+/// tray menu and the hotkeys all go by these instead of reading the status
+/// themselves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Availability {
+    pub can_start: bool,
+    /// `stop_recording` and `cancel_recording`.
+    pub can_stop: bool,
+    /// `install_selected_model` and `retry_bootstrap`.
+    pub can_install: bool,
 }
 
-pub struct StateInner {
-    pub status: AppStatus,
-    pub status_message: String,
-    pub language: String,
-    pub selected_model_id: String,
-    pub selected_mic_device: Option<String>,
-    pub transcript: String,
-    pub last_saved_path: Option<String>,
-    pub install_progress: Option<f32>,
-    pub error_message: Option<String>,
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptSegment {
+    pub text: String,
+    pub speaker: Option<u32>,
+    /// Input the segment was transcribed from, when sources are transcribed
+    /// separately (`separate_sources`).
+    pub source: Option<AudioSource>,
+    /// Starts a new paragraph: the voice detector heard a long pause before it.
+    pub paragraph: bool,
+    /// Seconds from the start of the session.
+    pub start: Option<f64>,
+    pub end: Option<f64>,
+    /// Per-word timing, when the worker runs with `word_timestamps`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub words: Vec<WordTiming>,
+}
+
+/// One word of a segment, in seconds from the start of the session.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WordTiming {
+    pub word: String,
+    pub start: f64,
+    pub end: f64,
+}
+
+/// A note typed while recording, at session time `at` (seconds), or a
+/// screenshot pinned with `capture_moment`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionNote {
+    pub at: f64,
+    pub text: String,
+    /// Screenshot path, relative to the output directory.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+}
+
+/// Session time (seconds) during which the microphone delivered digital
+/// silence. `end` is `None` while the mute is still ongoing.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MuteInterval {
+    pub start: f64,
+    pub end: Option<f64>,
+}
+
+/// The engine transcribing the current session and the task applying its
+/// events.
+pub struct RunningEngine {
+    pub engine: Box<dyn Engine>,
+    pub events_task: Option<JoinHandle<()>>,
+}
+
+/// Locations under the app data directory. Fixed for the lifetime of the app,
+/// so they live outside the locked partitions.
+pub struct AppPaths {
     pub app_data_dir: PathBuf,
     pub scripts_dir: PathBuf,
     pub bootstrap_script: PathBuf,
     pub worker_script: PathBuf,
-    pub venv_python: PathBuf,
+    /// The app data directory could not be written at startup (another user
+    /// account's install, a backup restored with the wrong owner). History
+    /// and search still work; anything that writes there is refused up front.
+    pub read_only: bool,
+}
+
+/// Returned by actions that need to write to the app data directory while
+/// the app is read-only.
+#[derive(Debug)]
+pub struct ReadOnly(pub PathBuf);
+
+impl std::fmt::Display for ReadOnly {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "WhisperBar is read-only: {} is not writable by this user",
+            self.0.display()
+        )
+    }
+}
+
+impl std::error::Error for ReadOnly {}
+
+impl AppPaths {
+    pub fn ensure_writable(&self) -> Result<(), ReadOnly> {
+        if self.read_only {
+            return Err(ReadOnly(self.app_data_dir.clone()));
+        }
+        Ok(())
+    }
+}
+
+/// User preferences persisted to `settings.json`.
+pub struct SettingsState {
+    pub language: String,
+    pub selected_model_id: String,
+    /// Model folders registered with `add_custom_model`.
+    pub custom_models: Vec<CustomModel>,
+    pub selected_mic_device: Option<String>,
+    /// Capture desktop audio through the ScreenCaptureKit helper.
+    pub system_audio_enabled: bool,
+    pub partial_updates_per_second: u32,
+    pub diarization_enabled: bool,
+    /// Transcribe the microphone and desktop audio separately and label the
+    /// transcript `Me` / `Others`. Takes precedence over diarization while
+    /// both sources are captured.
+    pub separate_sources: bool,
+    pub include_timestamps: bool,
+    /// What saved transcripts are named after; see `transcript_file::FileNaming`.
+    pub name_template: String,
+    /// Titles each transcript after stop when set; see `titles::generate`.
+    pub title_llm: Option<Llm>,
+    /// Sections appended to each transcript after stop when set; see
+    /// `postprocess::spawn`.
+    pub postprocess: Option<Postprocess>,
+    /// Check the reviewed transcript against the hunspell dictionary for
+    /// `language` when a session ends.
+    pub spell_check_enabled: bool,
+    /// Where spoken URLs and email addresses are written out and linked.
+    pub auto_link_sinks: Vec<AutoLinkSink>,
+    /// Proxy and Hugging Face mirror used by model installs.
+    pub download_network: DownloadNetwork,
+    /// Warning kinds the user chose not to see again.
+    pub suppressed_errors: Vec<ErrorKind>,
+    /// Capture tuning per microphone, keyed by device name.
+    pub device_profiles: BTreeMap<String, DeviceProfile>,
+    /// Acronym maps for transcript text, keyed by profile name.
+    pub acronym_profiles: BTreeMap<String, Vec<Acronym>>,
+    /// The acronym profile applied to new sessions; `None` keeps the text as
+    /// the model wrote it.
+    pub acronym_profile: Option<String>,
+    /// Find/replace entries applied to every segment after acronyms.
+    pub replacements: Vec<Replacement>,
+    /// Seconds of microphone audio kept before recording starts; 0 disables.
+    pub preroll_seconds: u32,
+    /// Keep a WAV of each session next to its transcript.
+    pub keep_audio: bool,
+    /// Seconds of silence kept around the speech when the kept WAV is
+    /// trimmed on stop; `None` keeps the whole recording.
+    pub silence_trim_padding: Option<u32>,
+    /// Drop segments that look like Whisper hallucinations instead of adding
+    /// them to the transcript; see `hallucinations::Filter`.
+    pub hallucination_filter: bool,
+    /// Strip filler words, repeats and stutters from segments; see
+    /// `text_cleanup::remove_fillers`.
+    pub remove_fillers: bool,
+    /// Resampler the ScreenCaptureKit helper converts desktop audio with.
+    pub resample_quality: ResampleQuality,
+    /// Run the microphone through the RNNoise filter (`denoise`) before
+    /// transcription.
+    pub noise_suppression: bool,
+    /// Only transcribe chunks the WebRTC voice detector (`vad`) hears speech
+    /// in; also drives silence auto-stop and paragraph breaks.
+    pub voice_activity_detection: bool,
+    /// Pause transcription while desktop audio is music with no speech.
+    pub skip_music: bool,
+    /// Have the worker time every word, for live word-by-word highlighting.
+    pub word_timestamps: bool,
+    /// Stop recording after this many minutes without speech; 0 disables.
+    pub auto_stop_silence_minutes: u32,
+    /// Keep the MLX worker and its model loaded for this many minutes after
+    /// a recording, so the next one starts without the load; 0 disables.
+    pub warm_worker_minutes: u32,
+    /// Start the warm worker and load the model when the app launches.
+    pub preload_model: bool,
+    /// Hard cap on a session's length, after which it is stopped and saved; 0 disables.
+    pub max_recording_minutes: u32,
+    /// Applied when a session linked to a calendar event runs past the
+    /// event's end by `meeting_end_grace_minutes`.
+    pub meeting_end_action: MeetingEndAction,
+    pub meeting_end_grace_minutes: u32,
+    /// Corner the live window snaps to when it opens; `None` leaves it where
+    /// the window manager puts it.
+    pub floating_placement: Option<FloatingPlacement>,
+    /// A second menu bar item that starts and stops recording in one click;
+    /// see `tray::set_record_button`.
+    pub record_button: bool,
+    /// Local hour after which the daily maintenance run (`maintenance`) is due.
+    pub maintenance_hour: u32,
+    /// Saved sessions older than this many days are deleted by maintenance; 0
+    /// keeps them forever.
+    pub retention_days: u32,
     pub model_path: PathBuf,
-    pub worker: Option<WorkerProcess>,
+}
+
+/// Status machine and the running engine.
+pub struct SessionState {
+    pub status: AppStatus,
+    pub status_message: String,
+    pub error_message: Option<String>,
+    pub error_kind: Option<ErrorKind>,
+    /// Bumped on every `set_error` so a pending auto-dismiss can tell whether
+    /// the error it was scheduled for is still the one shown.
+    pub error_serial: u64,
+    pub last_saved_path: Option<String>,
+    pub last_audio_path: Option<String>,
+    pub worker: Option<RunningEngine>,
+    /// Where the worker is writing this session's audio, when `keep_audio` is on.
+    pub audio_path: Option<PathBuf>,
+    /// This session's transcript autosave (`recovery::spawn_autosave`).
+    pub autosave_path: Option<PathBuf>,
+    /// Pending delayed start from `worker::start_recording_after`.
+    pub countdown: Option<JoinHandle<()>>,
+    pub countdown_seconds: Option<u32>,
+    /// Paste or copy the transcript when this session stops.
+    pub dictation: DictationOutput,
+    /// Set while recording; also identifies the session to its watchdogs.
+    pub recording_started_at: Option<Instant>,
+    /// Wall-clock time the session started, for names derived from it.
+    pub recording_started_local: Option<DateTime<Local>>,
+    /// Last time the worker reported a chunk with speech.
+    pub last_speech_at: Option<Instant>,
+    /// Input the worker dropped from the mix for this session.
+    pub muted_source: Option<AudioSource>,
+    /// The microphone is delivering digital silence (hardware or system mute).
+    pub mic_muted: bool,
+    /// The worker paused transcription of desktop audio that is only music.
+    pub music_gated: bool,
+    /// Scheduled end of the calendar event this session was started for.
+    pub meeting_end: Option<DateTime<Utc>>,
+    /// Running microphone calibration from `calibration::start`.
+    pub calibration: Option<JoinHandle<()>>,
+    /// Capture names of microphones that failed this session; the fallback
+    /// never picks them again.
+    pub failed_mics: Vec<String>,
+}
+
+impl SessionState {
+    pub fn set_error(&mut self, kind: ErrorKind, message: String) {
+        self.error_message = Some(message);
+        self.error_kind = Some(kind);
+        self.error_serial += 1;
+    }
+
+    pub fn reset_error(&mut self) {
+        self.error_message = None;
+        self.error_kind = None;
+    }
+
+    pub fn availability(&self, model_installed: bool, read_only: bool) -> Availability {
+        Availability {
+            can_start: matches!(self.status, AppStatus::Ready | AppStatus::Idle)
+                && model_installed
+                && !read_only,
+            // Stopping takes the worker out first, so a stop already under
+            // way is not offered again.
+            can_stop: self.status == AppStatus::Recording && self.worker.is_some(),
+            can_install: !matches!(
+                self.status,
+                AppStatus::Installing | AppStatus::Recording | AppStatus::Transcribing
+            ) && !read_only,
+        }
+    }
+}
+
+/// Live transcript segments. Written by the worker event loop on every partial
+/// flush, so it is kept apart from everything the command handlers touch.
+pub struct TranscriptState {
+    pub segments: Vec<TranscriptSegment>,
+    pub partial_metrics: partials::PartialMetrics,
+    /// Set when the reviewed transcript was edited after it was last saved.
+    pub dirty: bool,
+    /// Noted in the saved transcript so a gap reads as a mute, not silence.
+    pub mute_intervals: Vec<MuteInterval>,
+    /// Notes added with `append_session_note`, in the order they were typed.
+    pub notes: Vec<SessionNote>,
+    /// Undo/redo stacks for review edits of this session's transcript.
+    pub edits: EditHistory,
+}
+
+impl TranscriptState {
+    pub fn has_text(&self) -> bool {
+        self.segments
+            .iter()
+            .any(|segment| !segment.text.trim().is_empty())
+    }
+}
+
+/// Dependency/model installation progress.
+pub struct InstallState {
+    pub progress: Option<f32>,
+    /// Per-phase breakdown of the current (or last) bootstrap run.
+    pub status: Option<InstallStatus>,
+    pub venv_python: PathBuf,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -61,83 +462,299 @@ pub struct StateInner {
 pub struct PersistedSettings {
     pub language: Option<String>,
     pub selected_model_id: Option<String>,
+    pub custom_models: Option<Vec<CustomModel>>,
     pub selected_mic_device: Option<String>,
+    pub system_audio_enabled: Option<bool>,
+    pub partial_updates_per_second: Option<u32>,
+    pub diarization_enabled: Option<bool>,
+    pub separate_sources: Option<bool>,
+    pub include_timestamps: Option<bool>,
+    pub name_template: Option<String>,
+    pub title_llm: Option<Llm>,
+    pub postprocess: Option<Postprocess>,
+    pub spell_check_enabled: Option<bool>,
+    pub auto_link_sinks: Option<Vec<AutoLinkSink>>,
+    pub download_network: Option<DownloadNetwork>,
+    pub suppressed_errors: Option<Vec<ErrorKind>>,
+    pub device_profiles: Option<BTreeMap<String, DeviceProfile>>,
+    pub acronym_profiles: Option<BTreeMap<String, Vec<Acronym>>>,
+    pub acronym_profile: Option<String>,
+    pub replacements: Option<Vec<Replacement>>,
+    pub preroll_seconds: Option<u32>,
+    pub keep_audio: Option<bool>,
+    pub silence_trim_padding: Option<u32>,
+    pub hallucination_filter: Option<bool>,
+    pub remove_fillers: Option<bool>,
+    pub resample_quality: Option<ResampleQuality>,
+    pub noise_suppression: Option<bool>,
+    pub voice_activity_detection: Option<bool>,
+    pub skip_music: Option<bool>,
+    pub word_timestamps: Option<bool>,
+    pub auto_stop_silence_minutes: Option<u32>,
+    pub warm_worker_minutes: Option<u32>,
+    pub preload_model: Option<bool>,
+    pub max_recording_minutes: Option<u32>,
+    pub meeting_end_action: Option<MeetingEndAction>,
+    pub meeting_end_grace_minutes: Option<u32>,
+    pub floating_placement: Option<FloatingPlacement>,
+    pub record_button: Option<bool>,
+    pub maintenance_hour: Option<u32>,
+    pub retention_days: Option<u32>,
 }
 
-impl StateInner {
-    pub fn new(app_data_dir: PathBuf) -> Self {
-        let scripts_dir = app_data_dir.join("python");
-        let venv_python = app_data_dir.join("python-env").join("bin").join("python");
+impl SettingsState {
+    fn load(app_data_dir: &Path) -> Self {
         let selected_model_id = models::default_model_id().to_string();
-        let model_path = models::model_path(&app_data_dir, &selected_model_id)
+        let model_path = models::model_path(app_data_dir, &selected_model_id)
             .unwrap_or_else(|| app_data_dir.join("models").join("whisper-large-v3-turbo"));
 
-        let mut state = Self {
-            status: AppStatus::Ready,
-            status_message: "Ready".to_string(),
+        let mut settings = Self {
             language: "en".to_string(),
             selected_model_id,
+            custom_models: Vec::new(),
             selected_mic_device: None,
-            transcript: String::new(),
-            last_saved_path: None,
-            install_progress: None,
-            error_message: None,
-            bootstrap_script: scripts_dir.join("bootstrap.py"),
-            worker_script: scripts_dir.join("worker.py"),
-            app_data_dir,
-            scripts_dir,
-            venv_python,
+            system_audio_enabled: true,
+            partial_updates_per_second: partials::DEFAULT_UPDATES_PER_SECOND,
+            diarization_enabled: false,
+            separate_sources: false,
+            include_timestamps: false,
+            name_template: transcript_file::DEFAULT_NAME_TEMPLATE.to_string(),
+            title_llm: None,
+            postprocess: None,
+            spell_check_enabled: false,
+            auto_link_sinks: Vec::new(),
+            download_network: DownloadNetwork::default(),
+            suppressed_errors: Vec::new(),
+            device_profiles: BTreeMap::new(),
+            acronym_profiles: BTreeMap::new(),
+            acronym_profile: None,
+            replacements: Vec::new(),
+            preroll_seconds: 0,
+            keep_audio: false,
+            silence_trim_padding: None,
+            hallucination_filter: true,
+            remove_fillers: false,
+            resample_quality: ResampleQuality::default(),
+            noise_suppression: false,
+            voice_activity_detection: false,
+            skip_music: false,
+            word_timestamps: false,
+            auto_stop_silence_minutes: 0,
+            warm_worker_minutes: DEFAULT_WARM_WORKER_MINUTES,
+            preload_model: false,
+            max_recording_minutes: DEFAULT_MAX_RECORDING_MINUTES,
+            meeting_end_action: MeetingEndAction::Off,
+            meeting_end_grace_minutes: DEFAULT_MEETING_END_GRACE_MINUTES,
+            floating_placement: None,
+            record_button: false,
+            maintenance_hour: maintenance::DEFAULT_MAINTENANCE_HOUR,
+            retention_days: 0,
             model_path,
-            worker: None,
         };
 
-        if let Ok(settings) = load_settings(&state.app_data_dir) {
-            if let Some(language) = settings.language {
+        if let Ok(persisted) = load_settings(app_data_dir) {
+            if let Some(language) = persisted.language {
                 if language == "en" || language == "pt-BR" {
-                    state.language = language;
+                    settings.language = language;
                 }
             }
 
-            if let Some(model_id) = settings.selected_model_id {
-                if models::find_model(&model_id).is_some() {
-                    state.selected_model_id = model_id.clone();
-                    state.model_path = models::model_path(&state.app_data_dir, &model_id)
-                        .unwrap_or_else(|| {
-                            state.app_data_dir.join("models").join("whisper-large-v3-turbo")
-                        });
+            settings.custom_models = persisted.custom_models.unwrap_or_default();
+            if let Some(model_id) = persisted.selected_model_id {
+                if let Some(model_path) =
+                    models::resolve_path(app_data_dir, &settings.custom_models, &model_id)
+                {
+                    settings.model_path = model_path;
+                    settings.selected_model_id = model_id;
                 }
             }
 
-            state.selected_mic_device = settings.selected_mic_device;
-        }
+            settings.selected_mic_device = persisted.selected_mic_device;
+            settings.system_audio_enabled = persisted.system_audio_enabled.unwrap_or(true);
 
-        if !is_model_installed(&state.model_path) {
-            state.status_message = "Model not installed. Select a model and click Install Model.".to_string();
+            if let Some(rate) = persisted.partial_updates_per_second {
+                if partials::is_valid_rate(rate) {
+                    settings.partial_updates_per_second = rate;
+                }
+            }
+
+            settings.diarization_enabled = persisted.diarization_enabled.unwrap_or(false);
+            settings.separate_sources = persisted.separate_sources.unwrap_or(false);
+            settings.include_timestamps = persisted.include_timestamps.unwrap_or(false);
+            if let Some(template) = persisted
+                .name_template
+                .filter(|template| transcript_file::validate_name_template(template).is_ok())
+            {
+                settings.name_template = template;
+            }
+            settings.title_llm = persisted.title_llm;
+            settings.postprocess = persisted.postprocess;
+            settings.spell_check_enabled = persisted.spell_check_enabled.unwrap_or(false);
+            settings.auto_link_sinks = persisted.auto_link_sinks.unwrap_or_default();
+            settings.download_network = persisted.download_network.unwrap_or_default();
+            settings.suppressed_errors = persisted.suppressed_errors.unwrap_or_default();
+            settings.device_profiles = persisted.device_profiles.unwrap_or_default();
+            settings.acronym_profiles = persisted.acronym_profiles.unwrap_or_default();
+            settings.acronym_profile = persisted
+                .acronym_profile
+                .filter(|name| settings.acronym_profiles.contains_key(name));
+            settings.replacements = persisted
+                .replacements
+                .filter(|entries| replacements::validate(entries).is_ok())
+                .unwrap_or_default();
+            settings.preroll_seconds = persisted
+                .preroll_seconds
+                .unwrap_or(0)
+                .min(preroll::MAX_PREROLL_SECONDS);
+            settings.keep_audio = persisted.keep_audio.unwrap_or(false);
+            settings.silence_trim_padding = persisted
+                .silence_trim_padding
+                .map(|padding| padding.min(recording_audio::MAX_SILENCE_TRIM_PADDING));
+            settings.hallucination_filter = persisted.hallucination_filter.unwrap_or(true);
+            settings.remove_fillers = persisted.remove_fillers.unwrap_or(false);
+            settings.resample_quality = persisted.resample_quality.unwrap_or_default();
+            settings.noise_suppression = persisted.noise_suppression.unwrap_or(false);
+            settings.voice_activity_detection = persisted.voice_activity_detection.unwrap_or(false);
+            settings.skip_music = persisted.skip_music.unwrap_or(false);
+            settings.word_timestamps = persisted.word_timestamps.unwrap_or(false);
+            settings.auto_stop_silence_minutes = persisted
+                .auto_stop_silence_minutes
+                .unwrap_or(0)
+                .min(MAX_AUTO_STOP_SILENCE_MINUTES);
+            settings.warm_worker_minutes = persisted
+                .warm_worker_minutes
+                .unwrap_or(DEFAULT_WARM_WORKER_MINUTES)
+                .min(MAX_WARM_WORKER_MINUTES);
+            settings.preload_model = persisted.preload_model.unwrap_or(false);
+            settings.max_recording_minutes = persisted
+                .max_recording_minutes
+                .unwrap_or(DEFAULT_MAX_RECORDING_MINUTES)
+                .min(MAX_RECORDING_MINUTES_LIMIT);
+            settings.meeting_end_action = persisted.meeting_end_action.unwrap_or_default();
+            settings.meeting_end_grace_minutes = persisted
+                .meeting_end_grace_minutes
+                .unwrap_or(DEFAULT_MEETING_END_GRACE_MINUTES)
+                .min(MAX_MEETING_END_GRACE_MINUTES);
+            settings.floating_placement = persisted.floating_placement;
+            settings.record_button = persisted.record_button.unwrap_or(false);
+            if let Some(hour) = persisted.maintenance_hour.filter(|hour| *hour < 24) {
+                settings.maintenance_hour = hour;
+            }
+            settings.retention_days = persisted
+                .retention_days
+                .unwrap_or(0)
+                .min(maintenance::MAX_RETENTION_DAYS);
         }
 
-        state
+        settings
     }
 
-    pub fn snapshot(&self) -> AppSnapshot {
-        AppSnapshot {
-            status: self.status,
-            status_message: self.status_message.clone(),
+    pub fn render_options(&self) -> RenderOptions {
+        RenderOptions {
+            include_timestamps: self.include_timestamps,
+            link_addresses: self.auto_links(AutoLinkSink::Transcript),
+        }
+    }
+
+    pub fn file_naming(&self) -> FileNaming {
+        FileNaming {
+            template: self.name_template.clone(),
+            model_id: self.selected_model_id.clone(),
             language: self.language.clone(),
-            selected_model_id: self.selected_model_id.clone(),
-            selected_model_installed: is_model_installed(&self.model_path),
+            title: Title::FirstWords,
+        }
+    }
+
+    pub fn auto_links(&self, sink: AutoLinkSink) -> bool {
+        self.auto_link_sinks.contains(&sink)
+    }
+
+    /// Speaker labels come from the Python worker; whisper.cpp models have
+    /// none, and `diarization_enabled` is kept but ignored while one is
+    /// selected.
+    pub fn diarization_available(&self) -> bool {
+        models::backend(&self.custom_models, &self.selected_model_id)
+            != Some(ModelBackend::WhisperCpp)
+    }
+
+    /// The selected acronym profile, compiled for a session.
+    pub fn acronym_expander(&self) -> acronyms::Expander {
+        self.acronym_profile
+            .as_ref()
+            .and_then(|name| self.acronym_profiles.get(name))
+            .map(|entries| acronyms::Expander::new(entries))
+            .unwrap_or_default()
+    }
+
+    /// The replacement list, compiled for a session.
+    pub fn replacer(&self) -> replacements::Replacer {
+        replacements::Replacer::new(&self.replacements)
+    }
+
+    fn to_persisted(&self) -> PersistedSettings {
+        PersistedSettings {
+            language: Some(self.language.clone()),
+            selected_model_id: Some(self.selected_model_id.clone()),
+            custom_models: Some(self.custom_models.clone()),
             selected_mic_device: self.selected_mic_device.clone(),
-            transcript: self.transcript.clone(),
-            last_saved_path: self.last_saved_path.clone(),
-            install_progress: self.install_progress,
-            error_message: self.error_message.clone(),
+            system_audio_enabled: Some(self.system_audio_enabled),
+            partial_updates_per_second: Some(self.partial_updates_per_second),
+            diarization_enabled: Some(self.diarization_enabled),
+            separate_sources: Some(self.separate_sources),
+            include_timestamps: Some(self.include_timestamps),
+            name_template: Some(self.name_template.clone()),
+            title_llm: self.title_llm.clone(),
+            postprocess: self.postprocess.clone(),
+            spell_check_enabled: Some(self.spell_check_enabled),
+            auto_link_sinks: Some(self.auto_link_sinks.clone()),
+            download_network: Some(self.download_network.clone()),
+            suppressed_errors: Some(self.suppressed_errors.clone()),
+            device_profiles: Some(self.device_profiles.clone()),
+            acronym_profiles: Some(self.acronym_profiles.clone()),
+            acronym_profile: self.acronym_profile.clone(),
+            replacements: Some(self.replacements.clone()),
+            preroll_seconds: Some(self.preroll_seconds),
+            keep_audio: Some(self.keep_audio),
+            silence_trim_padding: self.silence_trim_padding,
+            hallucination_filter: Some(self.hallucination_filter),
+            remove_fillers: Some(self.remove_fillers),
+            resample_quality: Some(self.resample_quality),
+            noise_suppression: Some(self.noise_suppression),
+            voice_activity_detection: Some(self.voice_activity_detection),
+            skip_music: Some(self.skip_music),
+            word_timestamps: Some(self.word_timestamps),
+            auto_stop_silence_minutes: Some(self.auto_stop_silence_minutes),
+            warm_worker_minutes: Some(self.warm_worker_minutes),
+            preload_model: Some(self.preload_model),
+            max_recording_minutes: Some(self.max_recording_minutes),
+            meeting_end_action: Some(self.meeting_end_action),
+            meeting_end_grace_minutes: Some(self.meeting_end_grace_minutes),
+            floating_placement: self.floating_placement,
+            record_button: Some(self.record_button),
+            maintenance_hour: Some(self.maintenance_hour),
+            retention_days: Some(self.retention_days),
         }
     }
 }
 
-fn is_model_installed(model_path: &std::path::Path) -> bool {
+/// Creates the directory if needed and writes (then removes) a probe file,
+/// which also catches ownership problems that permission bits alone miss.
+fn is_writable(dir: &Path) -> bool {
+    let probe = dir.join(".write-probe");
+    let writable = std::fs::create_dir_all(dir)
+        .and_then(|()| std::fs::write(&probe, b""))
+        .is_ok();
+    let _ = std::fs::remove_file(&probe);
+    writable
+}
+
+pub fn is_model_installed(model_path: &Path) -> bool {
     if !model_path.exists() {
         return false;
     }
+    if model_path.join(models::GGML_MODEL_FILE).is_file() {
+        return true;
+    }
 
     let config_exists = model_path.join("config.json").exists();
     let has_weights = std::fs::read_dir(model_path)
@@ -150,64 +767,571 @@ fn is_model_installed(model_path: &std::path::Path) -> bool {
     config_exists && has_weights
 }
 
-pub fn save_settings(inner: &StateInner) -> anyhow::Result<()> {
-    let settings = PersistedSettings {
-        language: Some(inner.language.clone()),
-        selected_model_id: Some(inner.selected_model_id.clone()),
-        selected_mic_device: inner.selected_mic_device.clone(),
-    };
-    save_settings_to_dir(&inner.app_data_dir, &settings)
-}
-
-fn settings_path(app_data_dir: &std::path::Path) -> PathBuf {
+fn settings_path(app_data_dir: &Path) -> PathBuf {
     app_data_dir.join("settings.json")
 }
 
-fn load_settings(app_data_dir: &std::path::Path) -> anyhow::Result<PersistedSettings> {
+fn load_settings(app_data_dir: &Path) -> anyhow::Result<PersistedSettings> {
     let path = settings_path(app_data_dir);
     let raw = fs::read_to_string(&path)?;
     let parsed = serde_json::from_str::<PersistedSettings>(&raw)?;
     Ok(parsed)
 }
 
-fn save_settings_to_dir(
-    app_data_dir: &std::path::Path,
+async fn save_settings_to_dir(
+    app_data_dir: &Path,
     settings: &PersistedSettings,
 ) -> anyhow::Result<()> {
-    fs::create_dir_all(app_data_dir)?;
+    tokio::fs::create_dir_all(app_data_dir).await?;
     let path = settings_path(app_data_dir);
     let json = serde_json::to_string_pretty(settings)?;
-    fs::write(path, json)?;
+    tokio::fs::write(path, json).await?;
     Ok(())
 }
 
+/// Coalesces settings saves: commands only mark the settings dirty, and the
+/// task started by `spawn_settings_writer` writes them once things go quiet.
+#[derive(Default)]
+pub struct SettingsWriter {
+    dirty: AtomicBool,
+    changed: Notify,
+    /// Serializes the debounced write and `flush_settings`.
+    io: Mutex<()>,
+}
+
+/// Shared application state, split into independently locked partitions.
+///
+/// Never hold two partition locks at once: take one, copy out what you need,
+/// release it, then take the next. Snapshots are assembled the same way, so a
+/// UI refresh never blocks the worker event loop for longer than a single
+/// partition clone.
 #[derive(Clone)]
-pub struct SharedState(pub Arc<Mutex<StateInner>>);
+pub struct SharedState {
+    pub paths: Arc<AppPaths>,
+    pub settings: Arc<Mutex<SettingsState>>,
+    pub session: Arc<Mutex<SessionState>>,
+    pub transcript: Arc<Mutex<TranscriptState>>,
+    pub install: Arc<Mutex<InstallState>>,
+    pub history: Arc<Mutex<HistoryIndex>>,
+    pub search: Arc<Mutex<SearchIndex>>,
+    pub preroll: Arc<Mutex<PrerollState>>,
+    pub settings_writer: Arc<SettingsWriter>,
+    pub snapshots: Arc<Mutex<SnapshotLog>>,
+    /// The MLX worker kept loaded between recordings (`warm_worker_minutes`).
+    pub warm_worker: Arc<Mutex<Option<WarmWorker>>>,
+    /// Transcript shown by each open viewer window, by window label.
+    pub viewers: Arc<Mutex<BTreeMap<String, PathBuf>>>,
+    /// What a configuration profile set, as read at startup.
+    pub managed: Arc<ManagedPreferences>,
+}
 
 impl SharedState {
     pub fn new(app_data_dir: PathBuf) -> Self {
-        Self(Arc::new(Mutex::new(StateInner::new(app_data_dir))))
+        let scripts_dir = app_data_dir.join("python");
+        let venv_python = app_data_dir.join("python-env").join("bin").join("python");
+        let managed = ManagedPreferences::load();
+        let mut settings = SettingsState::load(&app_data_dir);
+        managed.apply(&mut settings, &app_data_dir);
+        if let Some(dir) = &managed.output_dir {
+            transcript_file::set_managed_output_dir(dir.clone());
+        }
+        let history = HistoryIndex::load(&app_data_dir);
+        let search = SearchIndex::open(&app_data_dir);
+        let read_only = !is_writable(&app_data_dir);
+
+        let status_message = if read_only {
+            "Read-only: history and search only".to_string()
+        } else if is_model_installed(&settings.model_path) {
+            "Ready".to_string()
+        } else {
+            "Model not installed. Select a model and click Install Model.".to_string()
+        };
+
+        let paths = AppPaths {
+            bootstrap_script: scripts_dir.join("bootstrap.py"),
+            worker_script: scripts_dir.join("worker.py"),
+            app_data_dir,
+            scripts_dir,
+            read_only,
+        };
+
+        Self {
+            paths: Arc::new(paths),
+            settings: Arc::new(Mutex::new(settings)),
+            session: Arc::new(Mutex::new(SessionState {
+                status: AppStatus::Ready,
+                status_message,
+                error_message: None,
+                error_kind: None,
+                error_serial: 0,
+                last_saved_path: None,
+                last_audio_path: None,
+                worker: None,
+                audio_path: None,
+                autosave_path: None,
+                countdown: None,
+                countdown_seconds: None,
+                dictation: DictationOutput::Off,
+                recording_started_at: None,
+                recording_started_local: None,
+                last_speech_at: None,
+                muted_source: None,
+                mic_muted: false,
+                music_gated: false,
+                meeting_end: None,
+                calibration: None,
+                failed_mics: Vec::new(),
+            })),
+            transcript: Arc::new(Mutex::new(TranscriptState {
+                segments: Vec::new(),
+                partial_metrics: partials::PartialMetrics::default(),
+                dirty: false,
+                mute_intervals: Vec::new(),
+                notes: Vec::new(),
+                edits: EditHistory::default(),
+            })),
+            install: Arc::new(Mutex::new(InstallState {
+                progress: None,
+                status: None,
+                venv_python,
+            })),
+            history: Arc::new(Mutex::new(history)),
+            search: Arc::new(Mutex::new(search)),
+            preroll: Arc::new(Mutex::new(PrerollState::default())),
+            settings_writer: Arc::new(SettingsWriter::default()),
+            snapshots: Arc::new(Mutex::new(SnapshotLog::default())),
+            warm_worker: Arc::new(Mutex::new(None)),
+            viewers: Arc::new(Mutex::new(BTreeMap::new())),
+            managed: Arc::new(managed),
+        }
+    }
+
+    pub async fn status(&self) -> AppStatus {
+        self.session.lock().await.status
+    }
+
+    pub async fn availability(&self) -> Availability {
+        let model_installed = is_model_installed(&self.settings.lock().await.model_path);
+        self.session
+            .lock()
+            .await
+            .availability(model_installed, self.paths.read_only)
+    }
+
+    /// Queues a write of the current settings to `settings.json`. Changes
+    /// made while read-only apply until the app quits.
+    pub fn schedule_settings_save(&self) {
+        if self.paths.read_only {
+            return;
+        }
+        self.settings_writer.dirty.store(true, Ordering::SeqCst);
+        self.settings_writer.changed.notify_one();
+    }
+
+    /// Writes pending settings now. Called on shutdown so a change made just
+    /// before quitting is not lost.
+    pub async fn flush_settings(&self) -> anyhow::Result<()> {
+        let _io = self.settings_writer.io.lock().await;
+        if !self.settings_writer.dirty.swap(false, Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        // Copy out under the lock, write after releasing it.
+        let persisted = self.settings.lock().await.to_persisted();
+        let result = save_settings_to_dir(&self.paths.app_data_dir, &persisted).await;
+        if result.is_err() {
+            self.settings_writer.dirty.store(true, Ordering::SeqCst);
+        }
+        result
+    }
+
+    /// Starts the background task that performs scheduled settings writes.
+    pub fn spawn_settings_writer(&self) {
+        let state = self.clone();
+        tauri::async_runtime::spawn(async move {
+            loop {
+                state.settings_writer.changed.notified().await;
+                tokio::time::sleep(SETTINGS_WRITE_DEBOUNCE).await;
+                let _ = state.flush_settings().await;
+            }
+        });
     }
 }
 
 pub async fn snapshot(state: &SharedState) -> AppSnapshot {
-    let guard = state.0.lock().await;
-    guard.snapshot()
+    let (
+        language,
+        selected_model_id,
+        selected_mic_device,
+        system_audio_enabled,
+        partial_updates_per_second,
+        diarization_enabled,
+        diarization_available,
+        separate_sources,
+        include_timestamps,
+        name_template,
+        title_llm,
+        postprocess,
+        spell_check_enabled,
+        suppressed_errors,
+        auto_link_sinks,
+        download_network,
+        device_profiles,
+        acronym_profiles,
+        acronym_profile,
+        replacements,
+        preroll_seconds,
+        keep_audio,
+        silence_trim_padding,
+        hallucination_filter,
+        remove_fillers,
+        resample_quality,
+        noise_suppression,
+        voice_activity_detection,
+        skip_music,
+        word_timestamps,
+        auto_stop_silence_minutes,
+        warm_worker_minutes,
+        preload_model,
+        max_recording_minutes,
+        meeting_end_action,
+        meeting_end_grace_minutes,
+        floating_placement,
+        record_button,
+        maintenance_hour,
+        retention_days,
+        model_path,
+    ) = {
+        let settings = state.settings.lock().await;
+        (
+            settings.language.clone(),
+            settings.selected_model_id.clone(),
+            settings.selected_mic_device.clone(),
+            settings.system_audio_enabled,
+            settings.partial_updates_per_second,
+            settings.diarization_enabled && settings.diarization_available(),
+            settings.diarization_available(),
+            settings.separate_sources,
+            settings.include_timestamps,
+            settings.name_template.clone(),
+            settings.title_llm.as_ref().map(Llm::summary),
+            settings.postprocess.as_ref().map(Postprocess::summary),
+            settings.spell_check_enabled,
+            settings.suppressed_errors.clone(),
+            settings.auto_link_sinks.clone(),
+            settings.download_network.summary(),
+            settings.device_profiles.clone(),
+            settings.acronym_profiles.clone(),
+            settings.acronym_profile.clone(),
+            replacements::to_text(&settings.replacements),
+            settings.preroll_seconds,
+            settings.keep_audio,
+            settings.silence_trim_padding,
+            settings.hallucination_filter,
+            settings.remove_fillers,
+            settings.resample_quality,
+            settings.noise_suppression,
+            settings.voice_activity_detection,
+            settings.skip_music,
+            settings.word_timestamps,
+            settings.auto_stop_silence_minutes,
+            settings.warm_worker_minutes,
+            settings.preload_model,
+            settings.max_recording_minutes,
+            settings.meeting_end_action,
+            settings.meeting_end_grace_minutes,
+            settings.floating_placement,
+            settings.record_button,
+            settings.maintenance_hour,
+            settings.retention_days,
+            settings.model_path.clone(),
+        )
+    };
+
+    let (
+        status,
+        status_message,
+        error_message,
+        error_kind,
+        last_saved_path,
+        last_audio_path,
+        recording_countdown,
+        recording_elapsed_seconds,
+        muted_source,
+        mic_muted,
+        music_gated,
+        availability,
+    ) = {
+        let session = state.session.lock().await;
+        (
+            session.status,
+            session.status_message.clone(),
+            session.error_message.clone(),
+            session.error_kind,
+            session.last_saved_path.clone(),
+            session.last_audio_path.clone(),
+            session.countdown_seconds,
+            session
+                .recording_started_at
+                .map(|started_at| started_at.elapsed().as_secs()),
+            session.muted_source,
+            session.mic_muted,
+            session.music_gated,
+            session.availability(is_model_installed(&model_path), state.paths.read_only),
+        )
+    };
+
+    let (segments, session_notes, transcript_dirty, transcript_can_undo, transcript_can_redo) = {
+        let transcript = state.transcript.lock().await;
+        (
+            transcript.segments.clone(),
+            transcript.notes.clone(),
+            transcript.dirty,
+            transcript.edits.can_undo(),
+            transcript.edits.can_redo(),
+        )
+    };
+    let transcript = transcript_file::render_plain(&segments);
+    let (install_progress, install_status) = {
+        let install = state.install.lock().await;
+        (install.progress, install.status.clone())
+    };
+    let preroll_active = preroll::is_active(state).await;
+
+    AppSnapshot {
+        seq: 0,
+        status,
+        status_message,
+        language,
+        selected_model_id,
+        selected_model_installed: is_model_installed(&model_path),
+        selected_mic_device,
+        system_audio_enabled,
+        muted_source,
+        mic_muted,
+        music_gated,
+        transcript,
+        session_notes,
+        last_saved_path,
+        last_audio_path,
+        install_progress,
+        install_status,
+        error_message,
+        error_kind,
+        error_severity: error_kind.map(ErrorKind::severity),
+        suppressed_errors,
+        auto_link_sinks,
+        download_network,
+        device_profiles,
+        acronym_profiles,
+        acronym_profile,
+        replacements,
+        preroll_seconds,
+        preroll_active,
+        keep_audio,
+        silence_trim_padding,
+        hallucination_filter,
+        remove_fillers,
+        resample_quality,
+        noise_suppression,
+        voice_activity_detection,
+        skip_music,
+        word_timestamps,
+        auto_stop_silence_minutes,
+        warm_worker_minutes,
+        preload_model,
+        max_recording_minutes,
+        meeting_end_action,
+        meeting_end_grace_minutes,
+        floating_placement,
+        record_button,
+        maintenance_hour,
+        retention_days,
+        partial_updates_per_second,
+        diarization_enabled,
+        diarization_available,
+        separate_sources,
+        include_timestamps,
+        name_template,
+        title_llm,
+        postprocess,
+        spell_check_enabled,
+        transcript_dirty,
+        transcript_can_undo,
+        transcript_can_redo,
+        recording_countdown,
+        recording_elapsed_seconds,
+        read_only: state.paths.read_only,
+        usage_report_endpoint: state
+            .managed
+            .usage_reporting
+            .as_ref()
+            .map(|reporting| reporting.endpoint.clone()),
+        locked_settings: state.managed.locked_settings(),
+        allowed_models: state.managed.allowed_models.clone(),
+        local_only: state.managed.local_only,
+        output_dir: transcript_file::output_dir()
+            .ok()
+            .map(|dir| dir.display().to_string()),
+        can_start: availability.can_start,
+        can_stop: availability.can_stop,
+        can_install: availability.can_install,
+    }
 }
 
-pub async fn emit_state(app: &AppHandle, state: &SharedState) {
-    let snapshot = snapshot(state).await;
+pub fn emit_snapshot(app: &AppHandle, snapshot: AppSnapshot) {
     let _ = app.emit("whisperbar://state", snapshot);
 }
 
-pub async fn update_state<F>(app: &AppHandle, state: &SharedState, updater: F)
+pub async fn emit_state(app: &AppHandle, state: &SharedState) {
+    // Build and emit under the log lock: a snapshot built before another one
+    // must not take a later sequence number and replace the newer state.
+    let mut log = state.snapshots.lock().await;
+    let snapshot = log.record(snapshot(state).await);
+    tray::set_availability(app, &snapshot);
+    emit_snapshot(app, snapshot);
+}
+
+/// The last emitted snapshot, so a new window starts from a numbered state.
+/// Emits one first if nothing was emitted yet.
+pub async fn latest_snapshot(app: &AppHandle, state: &SharedState) -> AppSnapshot {
+    if let Some(latest) = state.snapshots.lock().await.latest() {
+        return latest.clone();
+    }
+    emit_state(app, state).await;
+    state
+        .snapshots
+        .lock()
+        .await
+        .latest()
+        .cloned()
+        .expect("snapshot was just recorded")
+}
+
+pub async fn update_settings<F>(app: &AppHandle, state: &SharedState, updater: F)
 where
-    F: FnOnce(&mut StateInner),
+    F: FnOnce(&mut SettingsState),
 {
-    let snapshot = {
-        let mut guard = state.0.lock().await;
+    {
+        let mut guard = state.settings.lock().await;
         updater(&mut guard);
-        guard.snapshot()
-    };
-    let _ = app.emit("whisperbar://state", snapshot);
+    }
+    emit_state(app, state).await;
+}
+
+pub async fn update_session<F>(app: &AppHandle, state: &SharedState, updater: F)
+where
+    F: FnOnce(&mut SessionState),
+{
+    {
+        let mut guard = state.session.lock().await;
+        updater(&mut guard);
+    }
+    emit_state(app, state).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEGMENTS: usize = 2_000;
+
+    fn temp_state(name: &str) -> (SharedState, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("whisperbar-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).expect("create temp app data dir");
+        (SharedState::new(dir.clone()), dir)
+    }
+
+    fn segment(index: usize) -> TranscriptSegment {
+        TranscriptSegment {
+            text: format!("seg{index}"),
+            speaker: None,
+            source: None,
+            paragraph: false,
+            start: Some(index as f64),
+            end: Some(index as f64 + 1.0),
+            words: Vec::new(),
+        }
+    }
+
+    fn segment_count(snapshot: &AppSnapshot) -> usize {
+        snapshot.transcript.matches("seg").count()
+    }
+
+    /// The worker event loop appends a segment per partial while the UI keeps
+    /// taking snapshots; neither may starve the other, and every snapshot
+    /// sees a consistent, growing transcript.
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn snapshots_keep_up_with_transcript_updates() {
+        let (state, dir) = temp_state("snapshot-contention");
+
+        let writer = {
+            let state = state.clone();
+            tokio::spawn(async move {
+                for index in 0..SEGMENTS {
+                    state.transcript.lock().await.segments.push(segment(index));
+                    tokio::task::yield_now().await;
+                }
+            })
+        };
+        let reader = {
+            let state = state.clone();
+            tokio::spawn(async move {
+                let mut counts = Vec::new();
+                let mut slowest = Duration::ZERO;
+                loop {
+                    let started = Instant::now();
+                    let count = segment_count(&snapshot(&state).await);
+                    slowest = slowest.max(started.elapsed());
+                    counts.push(count);
+                    if count == SEGMENTS {
+                        return (counts, slowest);
+                    }
+                }
+            })
+        };
+
+        let (writer, reader) = tokio::time::timeout(Duration::from_secs(30), async {
+            tokio::join!(writer, reader)
+        })
+        .await
+        .expect("snapshots and transcript updates deadlocked");
+        writer.expect("writer task");
+        let (counts, slowest) = reader.expect("reader task");
+
+        assert!(counts.windows(2).all(|pair| pair[0] <= pair[1]));
+        // Snapshots were taken while the transcript was still growing.
+        assert!(counts.iter().any(|count| (1..SEGMENTS).contains(count)));
+        assert!(
+            slowest < Duration::from_secs(1),
+            "slowest snapshot {slowest:?}"
+        );
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    /// A held transcript lock (a long update) blocks only the transcript:
+    /// status and settings reads go through.
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn a_transcript_update_does_not_block_other_partitions() {
+        let (state, dir) = temp_state("partition-independence");
+        let transcript = state.transcript.lock().await;
+
+        let wait = Duration::from_millis(500);
+        tokio::time::timeout(wait, state.status())
+            .await
+            .expect("status blocked by the transcript lock");
+        tokio::time::timeout(wait, state.settings.lock())
+            .await
+            .expect("settings blocked by the transcript lock");
+        tokio::time::timeout(wait, state.install.lock())
+            .await
+            .expect("install blocked by the transcript lock");
+
+        drop(transcript);
+        tokio::time::timeout(wait, snapshot(&state))
+            .await
+            .expect("snapshot blocked after the update finished");
+        let _ = fs::remove_dir_all(dir);
+    }
 }
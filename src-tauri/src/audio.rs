@@ -1,15 +1,90 @@
 use std::process::Stdio;
 
 use regex::Regex;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use tokio::process::Command;
 
+use crate::core_audio;
+
 #[derive(Clone, Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AudioDeviceOption {
     pub id: String,
     pub name: String,
     pub is_microphone_like: bool,
+    pub input_channels: u32,
+}
+
+/// Per-device capture tuning, applied by the worker as ffmpeg filters.
+///
+/// Profiles are keyed by device name, which is what the worker's ffmpeg
+/// capture identifies the device by.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceProfile {
+    /// Applied after noise suppression, in decibels.
+    pub gain_db: f32,
+    pub noise_suppression: bool,
+    /// 1-based input channel to record; `None` downmixes every channel.
+    pub channel: Option<u32>,
+    /// Linear RMS below which a mic-only chunk is skipped as silence, as
+    /// measured by calibration; `None` uses the worker's default.
+    pub silence_threshold: Option<f32>,
+}
+
+pub const MAX_GAIN_DB: f32 = 24.0;
+
+pub const AUDIO_LEVEL_EVENT: &str = "whisperbar://audio-level";
+
+/// Linear 0..1 levels over one meter block, as measured by the worker.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct LevelReading {
+    pub rms: f32,
+    pub peak: f32,
+}
+
+/// Payload of `AUDIO_LEVEL_EVENT`, sent a few times per second while
+/// recording. `mic` is `None` when no microphone is being captured.
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct AudioLevel {
+    pub mic: Option<LevelReading>,
+    pub desktop: Option<LevelReading>,
+}
+
+/// One of the two inputs the worker mixes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AudioSource {
+    Mic,
+    Desktop,
+}
+
+impl AudioSource {
+    /// Name used in worker stdin commands and events.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AudioSource::Mic => "mic",
+            AudioSource::Desktop => "desktop",
+        }
+    }
+}
+
+impl DeviceProfile {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if !self.gain_db.is_finite() || self.gain_db.abs() > MAX_GAIN_DB {
+            anyhow::bail!("gain must be between -{MAX_GAIN_DB} and {MAX_GAIN_DB} dB");
+        }
+        if self.channel == Some(0) {
+            anyhow::bail!("channel numbers start at 1");
+        }
+        if self
+            .silence_threshold
+            .is_some_and(|threshold| !(threshold > 0.0 && threshold <= 1.0))
+        {
+            anyhow::bail!("silence threshold must be between 0 and 1");
+        }
+        Ok(())
+    }
 }
 
 const MICROPHONE_KEYWORDS: [&str; 7] = [
@@ -25,69 +100,75 @@ const PREFERRED_BUILTIN_MIC_KEYWORDS: [&str; 3] = ["macbook", "built-in", "inter
 const DEPRIORITIZED_MOBILE_MIC_KEYWORDS: [&str; 3] = ["iphone", "continuity", "desk view"];
 const APP_VIRTUAL_AUDIO_KEYWORDS: [&str; 4] = ["teams audio", "zoomaudio", "discord", "slack"];
 
-pub async fn list_audio_devices() -> anyhow::Result<Vec<AudioDeviceOption>> {
-    let output = Command::new("ffmpeg")
-        .arg("-f")
-        .arg("avfoundation")
-        .arg("-list_devices")
-        .arg("true")
-        .arg("-i")
-        .arg("")
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .output()
-        .await?;
-
-    let text = format!(
-        "{}\n{}",
-        String::from_utf8_lossy(&output.stdout),
-        String::from_utf8_lossy(&output.stderr)
-    );
-
-    let re = Regex::new(r"\[(\d+)\]\s+(.+)$")?;
-    let mut in_audio_section = false;
-    let mut devices = Vec::new();
-
-    for raw_line in text.lines() {
-        let line = raw_line.trim();
+/// Input devices from CoreAudio. Ids are device UIDs, which stay stable when
+/// devices are plugged in or removed; capture still goes through ffmpeg,
+/// which is handed the device name (see `capture_name`).
+pub fn list_audio_devices() -> anyhow::Result<Vec<AudioDeviceOption>> {
+    let devices = core_audio::input_devices()?
+        .into_iter()
+        .map(|device| {
+            let lowered = device.name.to_lowercase();
+            AudioDeviceOption {
+                id: device.uid,
+                is_microphone_like: contains_any(&lowered, &MICROPHONE_KEYWORDS),
+                name: device.name,
+                input_channels: device.input_channels,
+            }
+        })
+        .collect();
 
-        if line.contains("AVFoundation audio devices") {
-            in_audio_section = true;
-            continue;
-        }
+    Ok(devices)
+}
 
-        if line.contains("AVFoundation video devices") {
-            in_audio_section = false;
-            continue;
-        }
+/// Whether `id` is an avfoundation index (`2`, `:2`), which is how the
+/// selected microphone was saved before device UIDs.
+pub fn is_legacy_device_id(id: &str) -> bool {
+    let index = id.strip_prefix(':').unwrap_or(id);
+    !index.is_empty() && index.chars().all(|c| c.is_ascii_digit())
+}
 
-        if !in_audio_section {
-            continue;
-        }
+/// Maps a legacy avfoundation index to the UID of the device ffmpeg lists
+/// under it, matched by name. `None` when ffmpeg is unavailable or the index
+/// no longer exists.
+pub async fn migrate_legacy_device_id(id: &str, devices: &[AudioDeviceOption]) -> Option<String> {
+    let index = id.strip_prefix(':').unwrap_or(id);
+    let output = Command::new("ffmpeg")
+        .args([
+            "-hide_banner",
+            "-f",
+            "avfoundation",
+            "-list_devices",
+            "true",
+            "-i",
+            "",
+        ])
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
+        .await
+        .ok()?;
+
+    let listing = String::from_utf8_lossy(&output.stderr);
+    let pattern = Regex::new(r"\[(\d+)\]\s+(.+)$").ok()?;
+    let name = listing
+        .lines()
+        .skip_while(|line| !line.contains("AVFoundation audio devices"))
+        .filter_map(|line| pattern.captures(line.trim()))
+        .find(|caps| &caps[1] == index)
+        .map(|caps| caps[2].trim().to_string())?;
 
-        let Some(caps) = re.captures(line) else {
-            continue;
-        };
-
-        let Some(id_match) = caps.get(1) else {
-            continue;
-        };
-        let Some(name_match) = caps.get(2) else {
-            continue;
-        };
-
-        let id = id_match.as_str().to_string();
-        let name = name_match.as_str().trim().to_string();
-        let lowered = name.to_lowercase();
-
-        devices.push(AudioDeviceOption {
-            id,
-            name,
-            is_microphone_like: contains_any(&lowered, &MICROPHONE_KEYWORDS),
-        });
-    }
+    devices
+        .iter()
+        .find(|device| device.name == name)
+        .map(|device| device.id.clone())
+}
 
-    Ok(devices)
+/// The name ffmpeg's avfoundation input knows the device `id` by.
+pub fn capture_name(devices: &[AudioDeviceOption], id: &str) -> Option<String> {
+    devices
+        .iter()
+        .find(|device| device.id == id)
+        .map(|device| device.name.clone())
 }
 
 pub fn choose_default_mic(devices: &[AudioDeviceOption]) -> Option<String> {
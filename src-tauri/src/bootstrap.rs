@@ -1,16 +1,24 @@
-use std::process::Stdio;
+use std::{
+    path::{Path, PathBuf},
+    process::Stdio,
+    time::Duration,
+};
 
 use anyhow::{anyhow, Context};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use tauri::AppHandle;
 use tokio::{
-    io::{AsyncBufReadExt, BufReader},
+    io::{AsyncBufReadExt, AsyncReadExt, BufReader},
     process::Command,
 };
 
 use crate::{
-    app_state::{emit_state, save_settings, update_state, AppStatus, SharedState},
-    models, runtime_scripts,
+    app_state::{self, emit_state, AppStatus, SharedState},
+    errors::{self, ErrorKind},
+    install_status::{InstallPhaseId, InstallPhaseStatus, InstallStatus, PhaseUpdate},
+    integrity::{self, Manifest, ManifestEntry},
+    models::{self, ModelBackend, ModelSpec},
+    python_runtime, runtime_scripts, storage,
 };
 
 #[derive(Debug, Deserialize)]
@@ -22,13 +30,141 @@ struct BootstrapEvent {
     venv_python: Option<String>,
     model_path: Option<String>,
     model_id: Option<String>,
+    phase: Option<InstallPhaseId>,
+    status: Option<InstallPhaseStatus>,
+    file: Option<String>,
+    bytes_done: Option<u64>,
+    bytes_total: Option<u64>,
+    bytes_resumed: Option<u64>,
 }
 
-pub async fn run_bootstrap(app: &AppHandle, state: &SharedState) -> anyhow::Result<()> {
-    let selected_model_id = {
-        let guard = state.0.lock().await;
-        guard.selected_model_id.clone()
+/// Tries at a GGML download before the install fails. Each one resumes the
+/// partial file, backing off 1, 2, 4... seconds.
+const DOWNLOAD_ATTEMPTS: u32 = 5;
+
+/// Host every model URL (and `huggingface_hub`'s default endpoint) points at.
+const HF_ENDPOINT: &str = "https://huggingface.co";
+
+/// How installs reach the network, for users behind a proxy or a Hugging
+/// Face mirror (`set_download_network`). Passed to the bootstrap script and
+/// curl through the environment, so pip and `huggingface_hub` pick it up too.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadNetwork {
+    /// `http://host:port`, optionally with credentials; also `socks5://`.
+    pub proxy: Option<String>,
+    /// Replaces `https://huggingface.co` for model files (`HF_ENDPOINT`).
+    pub hf_endpoint: Option<String>,
+}
+
+/// What snapshots show of a `DownloadNetwork`: the proxy's credentials stay
+/// in `settings.json`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadNetworkSummary {
+    /// The proxy without its `user:password@`.
+    pub proxy: Option<String>,
+    pub has_credentials: bool,
+    pub hf_endpoint: Option<String>,
+}
+
+impl DownloadNetwork {
+    /// Trims both values, treats blanks as unset and rejects anything that
+    /// is not a URL.
+    pub fn new(proxy: Option<String>, hf_endpoint: Option<String>) -> anyhow::Result<Self> {
+        let clean = |value: Option<String>, schemes: &[&str], what: &str| {
+            let Some(value) = value
+                .map(|value| value.trim().trim_end_matches('/').to_string())
+                .filter(|value| !value.is_empty())
+            else {
+                return Ok(None);
+            };
+            if schemes
+                .iter()
+                .any(|scheme| value.starts_with(&format!("{scheme}://")))
+            {
+                Ok(Some(value))
+            } else {
+                Err(anyhow!(
+                    "{what} must start with {}",
+                    schemes
+                        .iter()
+                        .map(|scheme| format!("{scheme}://"))
+                        .collect::<Vec<_>>()
+                        .join(" or ")
+                ))
+            }
+        };
+
+        Ok(Self {
+            proxy: clean(proxy, &["http", "https", "socks5", "socks5h"], "proxy")?,
+            hf_endpoint: clean(hf_endpoint, &["http", "https"], "mirror endpoint")?,
+        })
+    }
+
+    pub fn summary(&self) -> DownloadNetworkSummary {
+        let proxy = self.proxy.as_deref().map(without_credentials);
+        DownloadNetworkSummary {
+            has_credentials: proxy.as_ref().is_some_and(|(_, stripped)| *stripped),
+            proxy: proxy.map(|(proxy, _)| proxy),
+            hf_endpoint: self.hf_endpoint.clone(),
+        }
+    }
+
+    /// `proxy` as `set_download_network` received it. The settings window
+    /// only knows the summary, so a proxy equal to the summary's keeps the
+    /// saved credentials instead of dropping them.
+    pub fn keep_credentials(mut self, current: &DownloadNetwork) -> Self {
+        if self.proxy.is_some() && self.proxy == current.summary().proxy {
+            self.proxy = current.proxy.clone();
+        }
+        self
+    }
+
+    pub fn apply(&self, command: &mut Command) {
+        if let Some(proxy) = &self.proxy {
+            // curl only reads the lowercase `http_proxy`; Python reads either.
+            for name in ["HTTPS_PROXY", "https_proxy", "HTTP_PROXY", "http_proxy"] {
+                command.env(name, proxy);
+            }
+        }
+        if let Some(endpoint) = &self.hf_endpoint {
+            command.env("HF_ENDPOINT", endpoint);
+        }
+    }
+
+    /// `url` on the mirror, when one is set and `url` is on Hugging Face.
+    fn url(&self, url: &str) -> String {
+        match (&self.hf_endpoint, url.strip_prefix(HF_ENDPOINT)) {
+            (Some(endpoint), Some(path)) => format!("{endpoint}{path}"),
+            _ => url.to_string(),
+        }
+    }
+}
+
+/// `url` without the `user:password@` of its authority, and whether it had one.
+fn without_credentials(url: &str) -> (String, bool) {
+    let Some((scheme, rest)) = url.split_once("://") else {
+        return (url.to_string(), false);
     };
+    let authority = &rest[..rest.find('/').unwrap_or(rest.len())];
+    match authority.rfind('@') {
+        Some(at) => (format!("{scheme}://{}", &rest[at + 1..]), true),
+        None => (url.to_string(), false),
+    }
+}
+
+/// Where a GGML download stands, for the model phase.
+#[derive(Clone, Copy)]
+struct DownloadBytes {
+    done: u64,
+    total: Option<u64>,
+    /// Already on disk from an interrupted earlier attempt.
+    resumed: Option<u64>,
+}
+
+pub async fn run_bootstrap(app: &AppHandle, state: &SharedState) -> anyhow::Result<()> {
+    let selected_model_id = state.settings.lock().await.selected_model_id.clone();
 
     run_bootstrap_for_model(app, state, &selected_model_id).await
 }
@@ -38,26 +174,101 @@ pub async fn run_bootstrap_for_model(
     state: &SharedState,
     model_id: &str,
 ) -> anyhow::Result<()> {
+    state.paths.ensure_writable()?;
+    state.managed.check_model(model_id)?;
     runtime_scripts::ensure_scripts(state).await?;
 
-    let model =
-        models::find_model(model_id).ok_or_else(|| anyhow!("unsupported model id: {model_id}"))?;
+    let Some(model) = models::find_model(model_id) else {
+        let custom = state
+            .settings
+            .lock()
+            .await
+            .custom_models
+            .iter()
+            .any(|model| model.id == model_id);
+        return Err(if custom {
+            anyhow!("custom models are used in place; check that the folder still has its weights")
+        } else {
+            anyhow!("unsupported model id: {model_id}")
+        });
+    };
 
-    update_state(app, state, |inner| {
-        inner.status = AppStatus::Installing;
-        inner.status_message = format!("Preparing model: {}", model.name);
-        inner.install_progress = Some(0.05);
-        inner.error_message = None;
-        inner.selected_model_id = model.id.to_string();
-    })
+    match state.status().await {
+        AppStatus::Installing => return Err(anyhow!("an installation is already running")),
+        AppStatus::Recording => {
+            return Err(anyhow!("stop the recording before installing a model"))
+        }
+        AppStatus::Transcribing => {
+            return Err(anyhow!(
+                "wait for the re-transcription to finish before installing a model"
+            ))
+        }
+        _ => {}
+    }
+
+    state.settings.lock().await.selected_model_id = model.id.to_string();
+    state.install.lock().await.status = Some(InstallStatus::default());
+    set_installing(
+        app,
+        state,
+        format!("Preparing model: {}", model.name),
+        Some(0.05),
+    )
     .await;
 
-    let (script_path, app_data_dir) = {
-        let guard = state.0.lock().await;
-        (guard.bootstrap_script.clone(), guard.app_data_dir.clone())
+    // A model that failed the pre-session size check is fetched again
+    // rather than counted as installed.
+    if let Some(model_path) = models::model_path(&state.paths.app_data_dir, model.id) {
+        integrity::remove_wrongly_sized(&model_path);
+    }
+
+    // Linked in before the preflight so it counts as already downloaded, and
+    // before the Python bootstrap so it skips the download. Any failure just
+    // falls back to downloading.
+    let _ = reuse_hf_cache(app, state, &model).await;
+
+    if let Err(error) = storage::install_preflight(&state.paths.app_data_dir, &model) {
+        state.install.lock().await.progress = None;
+        let message = error.to_string();
+        errors::report(app, state, ErrorKind::LowDiskSpace, message, |session| {
+            session.status = AppStatus::Error;
+            session.status_message = "Not enough disk space".to_string();
+        })
+        .await;
+        return Err(error.into());
+    }
+
+    if model.backend == ModelBackend::WhisperCpp {
+        return install_ggml_model(app, state, model).await;
+    }
+
+    let script_path = state.paths.bootstrap_script.clone();
+    let app_data_dir = state.paths.app_data_dir.clone();
+    let network = state.settings.lock().await.download_network.clone();
+
+    set_installing(
+        app,
+        state,
+        "Preparing Python runtime".to_string(),
+        Some(0.05),
+    )
+    .await;
+    let python = match python_runtime::ensure(&app_data_dir, &network).await {
+        Ok(python) => python,
+        Err(error) => {
+            set_install_failed(
+                app,
+                state,
+                format!("Python runtime install failed: {error}"),
+            )
+            .await;
+            return Err(error);
+        }
     };
 
-    let mut child = Command::new("python3")
+    let mut command = Command::new(python);
+    network.apply(&mut command);
+    let mut child = command
         .arg(script_path)
         .arg("--app-data-dir")
         .arg(&app_data_dir)
@@ -92,6 +303,7 @@ pub async fn run_bootstrap_for_model(
     });
 
     let mut lines = BufReader::new(stdout).lines();
+    let mut downloaded = false;
 
     while let Some(line) = lines
         .next_line()
@@ -104,52 +316,69 @@ pub async fn run_bootstrap_for_model(
                     let message = event
                         .message
                         .unwrap_or_else(|| format!("Preparing model: {}", model.name));
-                    update_state(app, state, move |inner| {
-                        inner.status = AppStatus::Installing;
-                        inner.status_message = message;
-                        inner.install_progress = event.progress;
-                        inner.error_message = None;
-                    })
-                    .await;
+                    set_installing(app, state, message, event.progress).await;
+                }
+                "phase" => {
+                    let (Some(phase), Some(status)) = (event.phase, event.status) else {
+                        continue;
+                    };
+                    downloaded |=
+                        phase == InstallPhaseId::Model && status == InstallPhaseStatus::Done;
+                    let update = PhaseUpdate {
+                        phase,
+                        status,
+                        progress: event.progress.unwrap_or(0.0),
+                        file: event.file,
+                        bytes_done: event.bytes_done,
+                        bytes_total: event.bytes_total,
+                        bytes_resumed: event.bytes_resumed,
+                    };
+                    state
+                        .install
+                        .lock()
+                        .await
+                        .status
+                        .get_or_insert_with(InstallStatus::default)
+                        .apply(update);
+                    emit_state(app, state).await;
                 }
                 "ready" => {
-                    let model_id_from_event = event.model_id.clone();
-                    update_state(app, state, |inner| {
-                        if let Some(path) = event.venv_python {
-                            inner.venv_python = path.into();
+                    if downloaded {
+                        let model_path = event
+                            .model_path
+                            .as_deref()
+                            .map(PathBuf::from)
+                            .or_else(|| models::model_path(&app_data_dir, model.id));
+                        if let Some(model_path) = model_path {
+                            if let Err(error) = verify_download(app, state, &model_path).await {
+                                let _ = child.wait().await;
+                                return Err(error);
+                            }
                         }
+                    }
+                    {
+                        let mut settings = state.settings.lock().await;
                         if let Some(path) = event.model_path {
-                            inner.model_path = path.into();
-                        }
-                        if let Some(id) = model_id_from_event {
-                            inner.selected_model_id = id;
-                        } else {
-                            inner.selected_model_id = model.id.to_string();
+                            settings.model_path = path.into();
                         }
-                        inner.status = AppStatus::Ready;
-                        inner.status_message = "Ready".to_string();
-                        inner.install_progress = Some(1.0);
-                        inner.error_message = None;
-                    })
-                    .await;
-
+                        settings.selected_model_id =
+                            event.model_id.unwrap_or_else(|| model.id.to_string());
+                    }
                     {
-                        let guard = state.0.lock().await;
-                        let _ = save_settings(&guard);
+                        let mut install = state.install.lock().await;
+                        if let Some(path) = event.venv_python {
+                            install.venv_python = path.into();
+                        }
                     }
+                    set_ready(app, state).await;
+
+                    state.schedule_settings_save();
                 }
                 "error" => {
                     let message = event
                         .message
                         .unwrap_or_else(|| "Dependency/model installation failed".to_string());
-                    let message_for_state = message.clone();
-                    update_state(app, state, move |inner| {
-                        inner.status = AppStatus::Error;
-                        inner.status_message = "Dependency/model installation failed".to_string();
-                        inner.error_message = Some(message_for_state.clone());
-                        inner.install_progress = None;
-                    })
-                    .await;
+                    set_install_failed(app, state, message.clone()).await;
                     let _ = child.kill().await;
                     let _ = child.wait().await;
                     return Err(anyhow!(message));
@@ -172,33 +401,398 @@ pub async fn run_bootstrap_for_model(
         } else {
             format!("Dependency/model installation failed: {stderr_output}")
         };
-        let message_for_state = message.clone();
+        set_install_failed(app, state, message.clone()).await;
 
-        update_state(app, state, move |inner| {
-            inner.status = AppStatus::Error;
-            inner.status_message = "Dependency/model installation failed".to_string();
-            inner.error_message = Some(message_for_state.clone());
-            inner.install_progress = None;
-        })
+        return Err(anyhow!(message));
+    }
+
+    if state.status().await != AppStatus::Ready {
+        set_ready(app, state).await;
+    }
+
+    emit_state(app, state).await;
+    Ok(())
+}
+
+/// whisper.cpp models are a single GGML file and run in the app itself, so
+/// there is no Python environment to prepare: just download the file with
+/// curl, reporting progress from the size of the partial download.
+async fn install_ggml_model(
+    app: &AppHandle,
+    state: &SharedState,
+    model: ModelSpec,
+) -> anyhow::Result<()> {
+    let result = download_ggml_model(app, state, model).await;
+    if let Err(error) = &result {
+        set_install_failed(app, state, format!("Model download failed: {error}")).await;
+    }
+    result
+}
+
+async fn download_ggml_model(
+    app: &AppHandle,
+    state: &SharedState,
+    model: ModelSpec,
+) -> anyhow::Result<()> {
+    let network = state.settings.lock().await.download_network.clone();
+    let url = model
+        .ggml_url
+        .map(|url| network.url(url))
+        .ok_or_else(|| anyhow!("{} has no download URL", model.name))?;
+    let url = url.as_str();
+    let model_path = models::model_path(&state.paths.app_data_dir, model.id)
+        .ok_or_else(|| anyhow!("unsupported model id: {}", model.id))?;
+
+    for phase in [InstallPhaseId::Venv, InstallPhaseId::Packages] {
+        apply_phase(app, state, phase, InstallPhaseStatus::Skipped, 1.0, None).await;
+    }
+
+    let model_file = model_path.join(models::GGML_MODEL_FILE);
+    if model_file.is_file() {
+        apply_phase(
+            app,
+            state,
+            InstallPhaseId::Model,
+            InstallPhaseStatus::Skipped,
+            1.0,
+            None,
+        )
         .await;
+    } else {
+        tokio::fs::create_dir_all(&model_path)
+            .await
+            .with_context(|| format!("failed creating {}", model_path.display()))?;
+        set_installing(app, state, format!("Downloading {}", model.name), Some(0.1)).await;
 
-        return Err(anyhow!(message));
+        let remote = remote_file(&network, url).await;
+        let bytes_total = remote.size;
+        let part_file = model_path.join(format!("{}.part", models::GGML_MODEL_FILE));
+        let resumed = Some(file_size(&part_file).await).filter(|size| *size > 0);
+
+        let mut attempt = 1;
+        loop {
+            // A previous attempt may have got every byte before it was cut
+            // off; asking for more would fail with 416.
+            let complete = match bytes_total {
+                Some(total) if total > 0 => file_size(&part_file).await >= total,
+                _ => false,
+            };
+            if complete {
+                break;
+            }
+
+            match curl_download(app, state, &network, url, &part_file, bytes_total, resumed).await {
+                Ok(()) => break,
+                Err(error) if attempt < DOWNLOAD_ATTEMPTS => {
+                    let progress = state.install.lock().await.progress;
+                    set_installing(
+                        app,
+                        state,
+                        format!(
+                            "Download interrupted, resuming ({attempt}/{}): {error}",
+                            DOWNLOAD_ATTEMPTS - 1
+                        ),
+                        progress,
+                    )
+                    .await;
+                    tokio::time::sleep(Duration::from_secs(1 << (attempt - 1))).await;
+                    attempt += 1;
+                }
+                Err(error) => return Err(error),
+            }
+        }
+
+        tokio::fs::rename(&part_file, &model_file)
+            .await
+            .with_context(|| format!("failed moving {}", part_file.display()))?;
+        if let Some(size) = bytes_total {
+            let manifest = [(
+                models::GGML_MODEL_FILE.to_string(),
+                ManifestEntry {
+                    size,
+                    sha256: remote.sha256,
+                },
+            )]
+            .into();
+            integrity::write_manifest(&model_path, &manifest).await?;
+            verify_download(app, state, &model_path).await?;
+        }
+        apply_phase(
+            app,
+            state,
+            InstallPhaseId::Model,
+            InstallPhaseStatus::Done,
+            1.0,
+            None,
+        )
+        .await;
     }
 
     {
-        let guard = state.0.lock().await;
-        if guard.status != AppStatus::Ready {
-            drop(guard);
-            update_state(app, state, |inner| {
-                inner.status = AppStatus::Ready;
-                inner.status_message = "Ready".to_string();
-                inner.install_progress = Some(1.0);
-                inner.error_message = None;
-            })
-            .await;
+        let mut settings = state.settings.lock().await;
+        settings.model_path = model_path;
+        settings.selected_model_id = model.id.to_string();
+    }
+    set_ready(app, state).await;
+    state.schedule_settings_save();
+    Ok(())
+}
+
+/// One curl run into `part_file`, reporting its size until curl exits.
+/// `-C -` continues from whatever the partial file already holds.
+async fn curl_download(
+    app: &AppHandle,
+    state: &SharedState,
+    network: &DownloadNetwork,
+    url: &str,
+    part_file: &Path,
+    total: Option<u64>,
+    resumed: Option<u64>,
+) -> anyhow::Result<()> {
+    let mut command = Command::new("curl");
+    network.apply(&mut command);
+    let mut child = command
+        .args(["-L", "--fail", "--silent", "--show-error", "-C", "-", "-o"])
+        .arg(part_file)
+        .arg(url)
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("failed to start curl")?;
+
+    let status = loop {
+        if let Some(status) = child.try_wait().context("failed waiting for curl")? {
+            break status;
         }
+        let done = file_size(part_file).await;
+        let progress = total
+            .filter(|total| *total > 0)
+            .map_or(0.0, |total| done as f32 / total as f32);
+        apply_phase(
+            app,
+            state,
+            InstallPhaseId::Model,
+            InstallPhaseStatus::Active,
+            progress,
+            Some(DownloadBytes {
+                done,
+                total,
+                resumed,
+            }),
+        )
+        .await;
+        state.install.lock().await.progress = Some(0.1 + progress * 0.9);
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    };
+
+    if status.success() {
+        return Ok(());
+    }
+    let mut stderr = String::new();
+    if let Some(mut pipe) = child.stderr.take() {
+        let _ = pipe.read_to_string(&mut stderr).await;
     }
+    Err(anyhow!(
+        "curl exited with {status}{}",
+        if stderr.trim().is_empty() {
+            String::new()
+        } else {
+            format!(": {}", stderr.trim())
+        }
+    ))
+}
 
+async fn apply_phase(
+    app: &AppHandle,
+    state: &SharedState,
+    phase: InstallPhaseId,
+    status: InstallPhaseStatus,
+    progress: f32,
+    bytes: Option<DownloadBytes>,
+) {
+    let update = PhaseUpdate {
+        phase,
+        status,
+        progress,
+        file: bytes.map(|_| models::GGML_MODEL_FILE.to_string()),
+        bytes_done: bytes.map(|bytes| bytes.done),
+        bytes_total: bytes.and_then(|bytes| bytes.total),
+        bytes_resumed: bytes.and_then(|bytes| bytes.resumed),
+    };
+    state
+        .install
+        .lock()
+        .await
+        .status
+        .get_or_insert_with(InstallStatus::default)
+        .apply(update);
     emit_state(app, state).await;
-    Ok(())
+}
+
+/// What the server says about the file behind `url`, from curl's headers.
+#[derive(Default)]
+struct RemoteFile {
+    /// The last `content-length`, after redirects.
+    size: Option<u64>,
+    /// Hugging Face's `x-linked-etag`, the SHA-256 of a Git LFS file.
+    sha256: Option<String>,
+}
+
+async fn remote_file(network: &DownloadNetwork, url: &str) -> RemoteFile {
+    let mut command = Command::new("curl");
+    network.apply(&mut command);
+    let Ok(output) = command.args(["-sIL", url]).output().await else {
+        return RemoteFile::default();
+    };
+    let mut remote = RemoteFile::default();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let (name, value) = (name.trim(), value.trim().trim_matches('"'));
+        if name.eq_ignore_ascii_case("content-length") {
+            if let Ok(size) = value.parse() {
+                remote.size = Some(size);
+            }
+        } else if name.eq_ignore_ascii_case("x-linked-etag")
+            && value.len() == 64
+            && value.chars().all(|c| c.is_ascii_hexdigit())
+        {
+            remote.sha256 = Some(value.to_string());
+        }
+    }
+    remote
+}
+
+/// Brings a copy of `model` from the user's own Hugging Face cache into the
+/// app's models folder instead of downloading it again. Files are hard
+/// linked, which takes no space and survives the cache being cleared, or
+/// copied when the cache is on another volume. Cached LFS blobs are named by
+/// their SHA-256, which goes into the manifest once the files are checked
+/// against it. Returns whether it did.
+async fn reuse_hf_cache(
+    app: &AppHandle,
+    state: &SharedState,
+    model: &ModelSpec,
+) -> anyhow::Result<bool> {
+    let model_path = models::model_path(&state.paths.app_data_dir, model.id)
+        .ok_or_else(|| anyhow!("unsupported model id: {}", model.id))?;
+    if app_state::is_model_installed(&model_path) {
+        return Ok(false);
+    }
+    let Some(files) = models::find_in_hf_cache(model) else {
+        return Ok(false);
+    };
+
+    set_installing(
+        app,
+        state,
+        format!("Reusing {} from the Hugging Face cache", model.name),
+        Some(0.1),
+    )
+    .await;
+    tokio::fs::create_dir_all(&model_path)
+        .await
+        .with_context(|| format!("failed creating {}", model_path.display()))?;
+
+    let mut manifest = Manifest::new();
+    for (name, cached) in files {
+        let blob = tokio::fs::canonicalize(&cached)
+            .await
+            .with_context(|| format!("failed resolving {}", cached.display()))?;
+        let target = model_path.join(&name);
+        let _ = tokio::fs::remove_file(&target).await;
+        if tokio::fs::hard_link(&blob, &target).await.is_err() {
+            tokio::fs::copy(&blob, &target)
+                .await
+                .with_context(|| format!("failed copying {}", blob.display()))?;
+        }
+
+        let sha256 = blob
+            .file_name()
+            .map(|blob_name| blob_name.to_string_lossy().to_string())
+            .filter(|digest| digest.len() == 64 && digest.chars().all(|c| c.is_ascii_hexdigit()));
+        manifest.insert(
+            name,
+            ManifestEntry {
+                size: file_size(&target).await,
+                sha256,
+            },
+        );
+    }
+
+    // A cached blob whose bytes do not match its digest name is removed
+    // here and downloaded instead.
+    set_installing(app, state, "Verifying model files".to_string(), Some(0.15)).await;
+    integrity::verify_against(&model_path, &manifest).await?;
+    integrity::write_manifest(&model_path, &manifest).await?;
+    Ok(true)
+}
+
+/// Checks a model downloaded by this install against its manifest. On a
+/// mismatch the bad files are already gone, so `Retry Install` fetches them
+/// again.
+async fn verify_download(
+    app: &AppHandle,
+    state: &SharedState,
+    model_path: &Path,
+) -> anyhow::Result<()> {
+    set_installing(app, state, "Verifying model files".to_string(), Some(0.99)).await;
+    let result = integrity::verify(model_path).await;
+    if let Err(error) = &result {
+        state.install.lock().await.progress = None;
+        let kind = if error.is::<integrity::ModelCorrupted>() {
+            ErrorKind::ModelCorrupted
+        } else {
+            ErrorKind::Install
+        };
+        errors::report(app, state, kind, error.to_string(), |session| {
+            session.status = AppStatus::Error;
+            session.status_message = "Model verification failed".to_string();
+        })
+        .await;
+    }
+    result
+}
+
+async fn file_size(path: &Path) -> u64 {
+    tokio::fs::metadata(path)
+        .await
+        .map_or(0, |metadata| metadata.len())
+}
+
+async fn set_installing(
+    app: &AppHandle,
+    state: &SharedState,
+    message: String,
+    progress: Option<f32>,
+) {
+    {
+        let mut session = state.session.lock().await;
+        session.status = AppStatus::Installing;
+        session.status_message = message;
+        session.reset_error();
+    }
+    state.install.lock().await.progress = progress;
+    emit_state(app, state).await;
+}
+
+async fn set_ready(app: &AppHandle, state: &SharedState) {
+    {
+        let mut session = state.session.lock().await;
+        session.status = AppStatus::Ready;
+        session.status_message = "Ready".to_string();
+        session.reset_error();
+    }
+    state.install.lock().await.progress = Some(1.0);
+    emit_state(app, state).await;
+}
+
+async fn set_install_failed(app: &AppHandle, state: &SharedState, message: String) {
+    state.install.lock().await.progress = None;
+    errors::report(app, state, ErrorKind::Install, message, |session| {
+        session.status = AppStatus::Error;
+        session.status_message = "Dependency/model installation failed".to_string();
+    })
+    .await;
 }
@@ -0,0 +1,364 @@
+use std::{
+    os::unix::fs::MetadataExt,
+    path::{Component, Path, PathBuf},
+    sync::OnceLock,
+};
+
+use anyhow::{anyhow, bail, Context};
+use chrono::Local;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use tokio::process::Command;
+
+use crate::{
+    app_state::SharedState,
+    history::{self, HistoryEntry},
+    transcript_file,
+};
+
+pub const EXTENSION: &str = "whisperbar";
+
+const MANIFEST: &str = "manifest.json";
+
+const VERSION: u32 = 1;
+
+/// `manifest.json` at the root of a bundle. File names are relative to the
+/// bundle root.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Manifest {
+    version: u32,
+    /// RFC 3339 time the session was saved on the sender's Mac.
+    created_at: String,
+    model_id: String,
+    language: String,
+    diarization_enabled: bool,
+    duration_seconds: Option<f64>,
+    word_count: usize,
+    transcript: String,
+    /// Screenshots the transcript links to, relative to its folder.
+    #[serde(default)]
+    attachments: Vec<String>,
+    audio: Option<String>,
+}
+
+/// Packs a saved session into `~/Downloads/<transcript name>.whisperbar`
+/// (`-2`, `-3`, ... when that name is taken) and reveals it in Finder: the
+/// Markdown transcript (with its notes, title and post-processing sections),
+/// the screenshots it links to, the session's details and, with
+/// `include_audio`, the kept recording. The rating stays behind.
+pub async fn export(state: &SharedState, id: &str, include_audio: bool) -> anyhow::Result<String> {
+    let entry = history::find(state, id).await?;
+    let transcript = PathBuf::from(&entry.path);
+    let (dir, name) = split_path(&transcript)?;
+    let text = tokio::fs::read_to_string(&transcript)
+        .await
+        .with_context(|| format!("failed reading {}", transcript.display()))?;
+
+    let attachments = image_links(&text)
+        .into_iter()
+        .filter(|link| safe_relative(link) && dir.join(link).is_file())
+        .collect::<Vec<_>>();
+    let audio = match entry.audio_path.as_deref().map(Path::new) {
+        Some(audio) if include_audio && audio.is_file() => Some(split_path(audio)?),
+        Some(_) if include_audio => bail!("the recording of this session is missing"),
+        None if include_audio => bail!("this session has no kept recording"),
+        _ => None,
+    };
+
+    let manifest = Manifest {
+        version: VERSION,
+        created_at: entry.created_at.clone(),
+        model_id: entry.model_id.clone(),
+        language: entry.language.clone(),
+        diarization_enabled: entry.diarization_enabled,
+        duration_seconds: entry.duration_seconds,
+        word_count: entry.word_count,
+        transcript: name.clone(),
+        attachments: attachments.clone(),
+        audio: audio.as_ref().map(|(_, name)| name.clone()),
+    };
+
+    let downloads_dir =
+        dirs::download_dir().ok_or_else(|| anyhow!("unable to locate Downloads directory"))?;
+    let stem = transcript
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "Transcript".to_string());
+    // Reserves the name; tar then writes into the empty file.
+    let (destination, _) = transcript_file::create_unique(&downloads_dir, &stem, EXTENSION).await?;
+
+    let staging = staging_dir("export");
+    let packed = async {
+        tokio::fs::create_dir_all(&staging)
+            .await
+            .with_context(|| format!("failed creating {}", staging.display()))?;
+        tokio::fs::write(
+            staging.join(MANIFEST),
+            serde_json::to_string_pretty(&manifest)?,
+        )
+        .await?;
+
+        // Each `-C` applies to the names after it, so every file is stored
+        // under its bundle name without copying it first.
+        let mut command = Command::new("tar");
+        command.arg("-czf").arg(&destination);
+        command.arg("-C").arg(&staging).arg(MANIFEST);
+        command.arg("-C").arg(&dir).arg(&name).args(&attachments);
+        if let Some((audio_dir, audio_name)) = &audio {
+            command.arg("-C").arg(audio_dir).arg(audio_name);
+        }
+        let output = command.output().await.context("failed starting tar")?;
+        if !output.status.success() {
+            bail!(
+                "failed packing the session: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        anyhow::Ok(())
+    }
+    .await;
+    let _ = tokio::fs::remove_dir_all(&staging).await;
+    if packed.is_err() {
+        let _ = tokio::fs::remove_file(&destination).await;
+    }
+    packed?;
+
+    let _ = std::process::Command::new("open")
+        .arg("-R")
+        .arg(&destination)
+        .spawn();
+
+    Ok(destination.display().to_string())
+}
+
+/// Unpacks a bundle into the output directory and adds it to the history
+/// with the sender's session details. The transcript gets a `-2` suffix when
+/// its name is taken; screenshots that already exist are left alone.
+/// Returns the imported transcript's path.
+pub async fn import(app: &AppHandle, state: &SharedState, path: &Path) -> anyhow::Result<String> {
+    state.paths.ensure_writable()?;
+    if !path.is_file() {
+        bail!("{} does not exist", path.display());
+    }
+    check_entry_types(path).await?;
+
+    let staging = staging_dir("import");
+    tokio::fs::create_dir_all(&staging)
+        .await
+        .with_context(|| format!("failed creating {}", staging.display()))?;
+    let imported = unpack(app, state, path, &staging).await;
+    let _ = tokio::fs::remove_dir_all(&staging).await;
+    imported
+}
+
+async fn unpack(
+    app: &AppHandle,
+    state: &SharedState,
+    path: &Path,
+    staging: &Path,
+) -> anyhow::Result<String> {
+    let output = Command::new("tar")
+        .arg("-xzf")
+        .arg(path)
+        .arg("-C")
+        .arg(staging)
+        .output()
+        .await
+        .context("failed starting tar")?;
+    if !output.status.success() {
+        bail!(
+            "{} is not a WhisperBar bundle: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    let manifest = staged_file(staging, MANIFEST)
+        .await
+        .with_context(|| format!("{} is not a WhisperBar bundle", path.display()))?;
+    let manifest = tokio::fs::read_to_string(manifest)
+        .await
+        .with_context(|| format!("{} is not a WhisperBar bundle", path.display()))?;
+    let manifest: Manifest =
+        serde_json::from_str(&manifest).context("the bundle manifest is invalid")?;
+    if manifest.version > VERSION {
+        bail!("the bundle was made by a newer WhisperBar; update to import it");
+    }
+    let names = std::iter::once(&manifest.transcript)
+        .chain(&manifest.attachments)
+        .chain(&manifest.audio);
+    for name in names {
+        if !safe_relative(name) {
+            bail!("the bundle lists an unsafe path: {name}");
+        }
+    }
+    if manifest.transcript.contains('/') || !manifest.transcript.ends_with(".md") {
+        bail!("the bundle transcript must be a Markdown file at its root");
+    }
+
+    let text = tokio::fs::read_to_string(staged_file(staging, &manifest.transcript).await?)
+        .await
+        .context("the bundle has no transcript")?;
+    let stem = manifest.transcript.trim_end_matches(".md");
+    let transcript = transcript_file::save_imported(stem, &text).await?;
+    let dir = transcript
+        .parent()
+        .ok_or_else(|| anyhow!("invalid transcript path {}", transcript.display()))?
+        .to_path_buf();
+
+    if !manifest.attachments.is_empty() {
+        // Screenshots go into a folder of this transcript's own, so they never
+        // land on (or are mistaken for) another session's files of the same name.
+        let folder = attachments_folder(&transcript);
+        for attachment in &manifest.attachments {
+            let destination = dir.join(&folder).join(attachment);
+            if let Some(parent) = destination.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            tokio::fs::copy(staged_file(staging, attachment).await?, &destination)
+                .await
+                .with_context(|| format!("failed copying {attachment}"))?;
+        }
+        let text = relink(&text, &manifest.attachments, &folder);
+        tokio::fs::write(&transcript, &text)
+            .await
+            .with_context(|| format!("failed writing {}", transcript.display()))?;
+    }
+
+    let audio = match &manifest.audio {
+        Some(name) => {
+            let destination = transcript.with_extension("wav");
+            tokio::fs::copy(staged_file(staging, name).await?, &destination)
+                .await
+                .context("failed copying the recording")?;
+            Some(destination.display().to_string())
+        }
+        None => None,
+    };
+
+    let entry = HistoryEntry {
+        id: format!("bundle-{}", Local::now().format("%Y%m%d-%H%M%S%3f")),
+        path: transcript.display().to_string(),
+        created_at: manifest.created_at,
+        model_id: manifest.model_id,
+        language: manifest.language,
+        mic_device: None,
+        diarization_enabled: manifest.diarization_enabled,
+        duration_seconds: manifest.duration_seconds,
+        word_count: manifest.word_count,
+        rating: None,
+        audio_path: audio,
+    };
+    history::add_imported(app, state, entry, &text).await?;
+
+    Ok(transcript.display().to_string())
+}
+
+/// Refuses a bundle holding anything but plain files and folders before it
+/// is unpacked: a symlink or hard link could make the import read a file from
+/// outside the bundle.
+async fn check_entry_types(path: &Path) -> anyhow::Result<()> {
+    let output = Command::new("tar")
+        .arg("-tvzf")
+        .arg(path)
+        .output()
+        .await
+        .context("failed starting tar")?;
+    if !output.status.success() {
+        bail!(
+            "{} is not a WhisperBar bundle: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    // `-v` lists entries like `ls -l`: `-` for a file, `d` for a folder,
+    // `l` for a symlink and `h` for a hard link.
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        if !line.starts_with(['-', 'd']) {
+            bail!("the bundle contains a link or special file: {line}");
+        }
+    }
+    Ok(())
+}
+
+/// `name` in the unpacked bundle, checked again now that it is on disk: a
+/// regular file with no other links, whose real path is inside `staging`.
+async fn staged_file(staging: &Path, name: &str) -> anyhow::Result<PathBuf> {
+    let path = staging.join(name);
+    let meta = tokio::fs::symlink_metadata(&path)
+        .await
+        .with_context(|| format!("the bundle has no {name}"))?;
+    if !meta.file_type().is_file() || meta.nlink() > 1 {
+        bail!("the bundle entry {name} is not a regular file");
+    }
+    let root = tokio::fs::canonicalize(staging)
+        .await
+        .with_context(|| format!("failed resolving {}", staging.display()))?;
+    let resolved = tokio::fs::canonicalize(&path)
+        .await
+        .with_context(|| format!("failed resolving {name}"))?;
+    if !resolved.starts_with(&root) {
+        bail!("the bundle lists an unsafe path: {name}");
+    }
+    Ok(resolved)
+}
+
+fn staging_dir(purpose: &str) -> PathBuf {
+    std::env::temp_dir().join(format!(
+        "whisperbar-{purpose}-{}",
+        Local::now().format("%Y%m%d-%H%M%S%3f")
+    ))
+}
+
+fn split_path(path: &Path) -> anyhow::Result<(PathBuf, String)> {
+    match (path.parent(), path.file_name()) {
+        (Some(dir), Some(name)) => Ok((dir.to_path_buf(), name.to_string_lossy().into_owned())),
+        _ => Err(anyhow!("invalid path {}", path.display())),
+    }
+}
+
+/// Targets of `![alt](target)` image links, as written.
+fn image_links(text: &str) -> Vec<String> {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    let mut links = PATTERN
+        .get_or_init(|| Regex::new(r"!\[[^\]]*\]\(([^)\s]+)\)").expect("invalid image pattern"))
+        .captures_iter(text)
+        .map(|caps| caps[1].to_string())
+        .collect::<Vec<_>>();
+    links.sort();
+    links.dedup();
+    links
+}
+
+/// `<transcript name>-attachments`, with whitespace replaced so the links
+/// stay readable by `image_links`.
+fn attachments_folder(transcript: &Path) -> String {
+    let stem = transcript
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "Transcript".to_string());
+    format!("{}-attachments", stem.replace(char::is_whitespace, "-"))
+}
+
+/// Points the image links to `attachments` at their copies under `folder`.
+fn relink(text: &str, attachments: &[String], folder: &str) -> String {
+    attachments
+        .iter()
+        .fold(text.to_string(), |text, attachment| {
+            text.replace(
+                &format!("]({attachment})"),
+                &format!("]({folder}/{attachment})"),
+            )
+        })
+}
+
+/// A relative path that stays inside the folder it is resolved against.
+fn safe_relative(path: &str) -> bool {
+    let path = Path::new(path);
+    path.components().next().is_some()
+        && path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+}
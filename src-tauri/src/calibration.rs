@@ -0,0 +1,272 @@
+use std::process::Stdio;
+
+use anyhow::{anyhow, Context};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+use tokio::{
+    io::AsyncReadExt,
+    process::{ChildStdout, Command},
+};
+
+use crate::{
+    app_state::{update_settings, AppStatus, SharedState},
+    audio::{self, DeviceProfile, LevelReading, MAX_GAIN_DB},
+};
+
+pub const CALIBRATION_EVENT: &str = "whisperbar://calibration";
+
+const QUIET_SECONDS: u32 = 3;
+const SPEAK_SECONDS: u32 = 6;
+const BLOCKS_PER_SECOND: u32 = 4;
+/// 16 kHz mono s16le, the format the worker transcribes.
+const BLOCK_BYTES: usize = 16_000 * 2 / BLOCKS_PER_SECOND as usize;
+
+/// Loudness speech is brought to by the recommended gain. Leaves headroom
+/// for laughs and raised voices before clipping.
+const TARGET_SPEECH_DBFS: f32 = -20.0;
+/// Recommended gain stays well inside `MAX_GAIN_DB` so a bad measurement
+/// can't make the profile unusable.
+const MAX_RECOMMENDED_GAIN_DB: f32 = MAX_GAIN_DB / 2.0;
+/// Below this speech-to-noise ratio the voice can't be told apart from the
+/// room and the measurement is rejected.
+const MIN_SNR_DB: f32 = 6.0;
+/// Below this ratio noise suppression is recommended.
+const NOISY_SNR_DB: f32 = 20.0;
+
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CalibrationPhase {
+    Quiet,
+    Speak,
+    Done,
+    Failed,
+}
+
+/// Payload of `CALIBRATION_EVENT`, sent a few times per second while the
+/// wizard runs and once when it finishes.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CalibrationProgress {
+    pub phase: CalibrationPhase,
+    pub remaining_seconds: u32,
+    pub level: Option<LevelReading>,
+    pub result: Option<CalibrationResult>,
+    pub message: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CalibrationResult {
+    pub device_name: String,
+    pub noise_floor_db: f32,
+    pub speech_db: f32,
+    /// The profile that was saved for `device_name`.
+    pub profile: DeviceProfile,
+}
+
+/// Measures the selected (or default) microphone: a few seconds of room
+/// noise, then a few seconds of speech. The recommended gain and silence
+/// threshold are saved as the device's profile, keeping its channel choice.
+/// Progress is reported through `CALIBRATION_EVENT`.
+pub async fn start(app: &AppHandle, state: &SharedState) -> anyhow::Result<()> {
+    let selected = state.settings.lock().await.selected_mic_device.clone();
+    let devices = audio::list_audio_devices()?;
+    let device_name = selected
+        .or_else(|| audio::choose_default_mic(&devices))
+        .and_then(|id| audio::capture_name(&devices, &id))
+        .ok_or_else(|| anyhow!("Connect a microphone to calibrate"))?;
+
+    let mut session = state.session.lock().await;
+    if session.status == AppStatus::Recording {
+        return Err(anyhow!("calibration can't run while recording"));
+    }
+    if session.calibration.is_some() {
+        return Err(anyhow!("calibration is already running"));
+    }
+
+    let app_task = app.clone();
+    let state_task = state.clone();
+    session.calibration = Some(tauri::async_runtime::spawn(async move {
+        if let Err(error) = run(&app_task, &state_task, device_name).await {
+            let _ = app_task.emit(
+                CALIBRATION_EVENT,
+                CalibrationProgress {
+                    phase: CalibrationPhase::Failed,
+                    remaining_seconds: 0,
+                    level: None,
+                    result: None,
+                    message: Some(error.to_string()),
+                },
+            );
+        }
+        state_task.session.lock().await.calibration = None;
+    }));
+
+    Ok(())
+}
+
+/// Aborts a running calibration. Returns `false` when none was running.
+pub async fn cancel(state: &SharedState) -> bool {
+    let Some(calibration) = state.session.lock().await.calibration.take() else {
+        return false;
+    };
+    calibration.abort();
+    true
+}
+
+async fn run(app: &AppHandle, state: &SharedState, device_name: String) -> anyhow::Result<()> {
+    let existing = state
+        .settings
+        .lock()
+        .await
+        .device_profiles
+        .get(&device_name)
+        .cloned()
+        .unwrap_or_default();
+
+    let mut command = Command::new("ffmpeg");
+    command
+        .args(["-hide_banner", "-loglevel", "error", "-f", "avfoundation"])
+        .arg("-i")
+        .arg(format!(":{device_name}"));
+    // Measure the same channel the worker will record.
+    if let Some(channel) = existing.channel {
+        command
+            .arg("-af")
+            .arg(format!("pan=mono|c0=c{}", channel - 1));
+    }
+    let mut child = command
+        .args(["-ac", "1", "-ar", "16000", "-f", "s16le", "-"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .context("failed starting ffmpeg for calibration")?;
+    let mut stdout = child
+        .stdout
+        .take()
+        .context("unable to capture calibration audio")?;
+
+    let noise = measure(app, &mut stdout, CalibrationPhase::Quiet, QUIET_SECONDS).await?;
+    let speech = measure(app, &mut stdout, CalibrationPhase::Speak, SPEAK_SECONDS).await?;
+    let _ = child.kill().await;
+
+    let result = recommend(device_name, existing, &noise, &speech)?;
+    let profile = result.profile.clone();
+    let key = result.device_name.clone();
+    update_settings(app, state, |settings| {
+        settings.device_profiles.insert(key, profile);
+    })
+    .await;
+    state.schedule_settings_save();
+
+    let _ = app.emit(
+        CALIBRATION_EVENT,
+        CalibrationProgress {
+            phase: CalibrationPhase::Done,
+            remaining_seconds: 0,
+            level: None,
+            result: Some(result),
+            message: None,
+        },
+    );
+    Ok(())
+}
+
+/// Reads `seconds` of audio and returns the RMS of each block, emitting the
+/// live level as it goes.
+async fn measure(
+    app: &AppHandle,
+    stdout: &mut ChildStdout,
+    phase: CalibrationPhase,
+    seconds: u32,
+) -> anyhow::Result<Vec<f32>> {
+    let blocks = seconds * BLOCKS_PER_SECOND;
+    let mut levels = Vec::with_capacity(blocks as usize);
+    let mut block = vec![0u8; BLOCK_BYTES];
+
+    for index in 0..blocks {
+        stdout.read_exact(&mut block).await.map_err(|_| {
+            anyhow!("The microphone stopped delivering audio. Check microphone permissions.")
+        })?;
+        let reading = level(&block);
+        levels.push(reading.rms);
+
+        let _ = app.emit(
+            CALIBRATION_EVENT,
+            CalibrationProgress {
+                phase,
+                remaining_seconds: (blocks - index).div_ceil(BLOCKS_PER_SECOND),
+                level: Some(reading),
+                result: None,
+                message: None,
+            },
+        );
+    }
+
+    Ok(levels)
+}
+
+fn level(block: &[u8]) -> LevelReading {
+    let samples = block
+        .chunks_exact(2)
+        .map(|pair| f32::from(i16::from_le_bytes([pair[0], pair[1]])) / 32768.0);
+    let (sum, peak, count) = samples.fold((0.0f64, 0.0f32, 0usize), |(sum, peak, count), x| {
+        (sum + f64::from(x * x), peak.max(x.abs()), count + 1)
+    });
+    LevelReading {
+        rms: (sum / count.max(1) as f64).sqrt() as f32,
+        peak,
+    }
+}
+
+/// The noise floor is the median quiet block, so a cough doesn't skew it;
+/// speech is the louder half of the speaking blocks, since pauses between
+/// words are part of that phase too.
+fn recommend(
+    device_name: String,
+    existing: DeviceProfile,
+    noise: &[f32],
+    speech: &[f32],
+) -> anyhow::Result<CalibrationResult> {
+    let mut noise = noise.to_vec();
+    noise.sort_by(f32::total_cmp);
+    let noise_rms = noise.get(noise.len() / 2).copied().unwrap_or_default();
+
+    let mut speech = speech.to_vec();
+    speech.sort_by(f32::total_cmp);
+    let louder = &speech[speech.len() / 2..];
+    let speech_rms = louder.iter().sum::<f32>() / louder.len().max(1) as f32;
+
+    let noise_floor_db = to_dbfs(noise_rms);
+    let speech_db = to_dbfs(speech_rms);
+    let snr_db = speech_db - noise_floor_db;
+    if snr_db < MIN_SNR_DB {
+        return Err(anyhow!(
+            "Couldn't tell your voice apart from the background noise. Move closer to the microphone and try again."
+        ));
+    }
+
+    let gain_db = (TARGET_SPEECH_DBFS - speech_db)
+        .round()
+        .clamp(-MAX_RECOMMENDED_GAIN_DB, MAX_RECOMMENDED_GAIN_DB);
+    // Halfway between noise and speech on a log scale, after the new gain.
+    let silence_threshold = (noise_rms * speech_rms).sqrt() * 10f32.powf(gain_db / 20.0);
+
+    Ok(CalibrationResult {
+        device_name,
+        noise_floor_db,
+        speech_db,
+        profile: DeviceProfile {
+            gain_db,
+            noise_suppression: snr_db < NOISY_SNR_DB,
+            channel: existing.channel,
+            silence_threshold: Some(silence_threshold.min(1.0)),
+        },
+    })
+}
+
+fn to_dbfs(rms: f32) -> f32 {
+    20.0 * rms.max(1e-6).log10()
+}
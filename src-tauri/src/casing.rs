@@ -0,0 +1,178 @@
+use std::sync::OnceLock;
+
+use regex::{Captures, Regex};
+
+/// Cased letters the first segment needs before the session is judged to
+/// have (or lack) case; shorter lines are left as they are meanwhile.
+const MIN_CASED_LETTERS: usize = 20;
+
+/// Names written with capitals in both languages.
+const NAMES: [&str; 9] = [
+    "GitHub",
+    "Google",
+    "iPhone",
+    "LinkedIn",
+    "macOS",
+    "Microsoft",
+    "WhatsApp",
+    "YouTube",
+    "WhisperBar",
+];
+
+/// `May` and `March` are left out: as words they are usually not months.
+const ENGLISH_NAMES: [&str; 26] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+    "January",
+    "February",
+    "April",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+    "English",
+    "Portuguese",
+    "Spanish",
+    "French",
+    "German",
+    "Brazil",
+    "America",
+    "Europe",
+    "London",
+];
+
+/// Weekdays and months are lowercase in Portuguese.
+const PORTUGUESE_NAMES: [&str; 7] = [
+    "Brasil",
+    "Portugal",
+    "São Paulo",
+    "Rio de Janeiro",
+    "Europa",
+    "Estados Unidos",
+    "Lisboa",
+];
+
+/// Restores sentence casing for models that write everything in lowercase
+/// (or uppercase). Whether a session needs it is decided once, from its
+/// first segment with enough letters; sessions whose text is already cased
+/// pass through untouched.
+#[derive(Debug)]
+pub struct Restorer {
+    language: String,
+    caseless: Option<bool>,
+    /// The last restored segment ended mid-sentence (`,`, `;`, `:`).
+    continues: bool,
+}
+
+impl Restorer {
+    pub fn new(language: &str) -> Self {
+        Self {
+            language: language.to_string(),
+            caseless: None,
+            continues: false,
+        }
+    }
+
+    pub fn apply(&mut self, text: &str) -> String {
+        if self.caseless.is_none() {
+            self.caseless = detect(text);
+        }
+        if self.caseless != Some(true) {
+            return text.to_string();
+        }
+
+        let restored = sentence_case(&text.to_lowercase(), !self.continues);
+        let restored = names(&restored, &self.language);
+        let restored = match self.language.as_str() {
+            "en" => pronoun_i(&restored),
+            _ => restored,
+        };
+        self.continues = restored.trim_end().ends_with([',', ';', ':']);
+        restored
+    }
+}
+
+/// `Some(true)` when every cased letter has the same case, `None` when there
+/// are too few to tell.
+fn detect(text: &str) -> Option<bool> {
+    let cased = text
+        .chars()
+        .filter(|c| c.is_lowercase() || c.is_uppercase())
+        .collect::<Vec<_>>();
+    if cased.len() < MIN_CASED_LETTERS {
+        return None;
+    }
+    Some(cased.iter().all(|c| c.is_lowercase()) || cased.iter().all(|c| c.is_uppercase()))
+}
+
+/// Capitalizes the first letter after `.`, `?`, `!` or `…`, and the first
+/// one of the text when `starts_sentence`. A digit ends the wait, so `3.5`
+/// stays one number.
+fn sentence_case(text: &str, starts_sentence: bool) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut capitalize = starts_sentence;
+    for c in text.chars() {
+        if capitalize && c.is_alphabetic() {
+            output.extend(c.to_uppercase());
+            capitalize = false;
+            continue;
+        }
+        if c.is_numeric() {
+            capitalize = false;
+        }
+        if matches!(c, '.' | '?' | '!' | '…') {
+            capitalize = true;
+        }
+        output.push(c);
+    }
+    output
+}
+
+fn regex(cell: &'static OnceLock<Regex>, names: &[&str]) -> &'static Regex {
+    cell.get_or_init(|| {
+        let alternatives = names
+            .iter()
+            .map(|name| regex::escape(name))
+            .collect::<Vec<_>>()
+            .join("|");
+        Regex::new(&format!(r"(?i)\b(?:{alternatives})\b")).expect("invalid name pattern")
+    })
+}
+
+/// Writes known names the way they are spelled.
+fn names(text: &str, language: &str) -> String {
+    static ENGLISH: OnceLock<Regex> = OnceLock::new();
+    static PORTUGUESE: OnceLock<Regex> = OnceLock::new();
+
+    let (cell, local) = match language {
+        "pt-BR" => (&PORTUGUESE, &PORTUGUESE_NAMES[..]),
+        _ => (&ENGLISH, &ENGLISH_NAMES[..]),
+    };
+    let all = NAMES.iter().chain(local).copied().collect::<Vec<_>>();
+    regex(cell, &all)
+        .replace_all(text, |caps: &Captures| {
+            let matched = &caps[0];
+            all.iter()
+                .find(|name| name.to_lowercase() == matched.to_lowercase())
+                .map(|name| name.to_string())
+                .unwrap_or_else(|| matched.to_string())
+        })
+        .into_owned()
+}
+
+/// `i`, `i'm`, `i'll` -> `I`, `I'm`, `I'll`.
+fn pronoun_i(text: &str) -> String {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN
+        .get_or_init(|| Regex::new(r"\bi\b").expect("invalid pronoun pattern"))
+        .replace_all(text, "I")
+        .into_owned()
+}
@@ -0,0 +1,110 @@
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Context};
+
+use crate::{
+    app_state::{AppStatus, MuteInterval, SessionNote, SharedState, TranscriptSegment},
+    transcript_file,
+};
+
+/// Length of one chapter of session time.
+pub const CHAPTER_SECONDS: f64 = 30.0 * 60.0;
+
+/// Writes every chapter the worker has transcribed past to
+/// `~/Documents/WhisperBar/Transcript-<started>-chapters/Chapter-NN.md` while
+/// the session keeps recording. A chapter counts as completed once a segment
+/// starts after its end, so lagging chunks do not leave it short. Chapters
+/// exported earlier are rewritten with the same name, which picks up notes
+/// added since. Returns the chapter files, oldest first.
+pub async fn export_completed(state: &SharedState) -> anyhow::Result<Vec<PathBuf>> {
+    let started = {
+        let session = state.session.lock().await;
+        match (session.status, session.recording_started_local) {
+            (AppStatus::Recording, Some(started)) => started,
+            _ => return Err(anyhow!("recording is not active")),
+        }
+    };
+    let (segments, mutes, notes) = {
+        let transcript = state.transcript.lock().await;
+        (
+            transcript.segments.clone(),
+            transcript.mute_intervals.clone(),
+            transcript.notes.clone(),
+        )
+    };
+    let options = state.settings.lock().await.render_options();
+
+    let chapters = split(&segments);
+    let Some(completed) = chapters.len().checked_sub(1).filter(|count| *count > 0) else {
+        return Err(anyhow!(
+            "no chapter completed yet; the first one ends at {} minutes",
+            CHAPTER_SECONDS as u64 / 60
+        ));
+    };
+
+    let dir = transcript_file::output_dir()?.join(format!(
+        "Transcript-{}-chapters",
+        started.format("%Y-%m-%d-%H-%M")
+    ));
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .with_context(|| format!("failed creating {}", dir.display()))?;
+
+    let mut written = Vec::with_capacity(completed);
+    for (index, chapter) in chapters.iter().take(completed).enumerate() {
+        let (from, to) = (
+            index as f64 * CHAPTER_SECONDS,
+            (index + 1) as f64 * CHAPTER_SECONDS,
+        );
+        let within = |at: f64| at >= from && at < to;
+        let mutes = mutes
+            .iter()
+            .filter(|mute| within(mute.start))
+            .cloned()
+            .collect::<Vec<MuteInterval>>();
+        // Chapter files sit one folder below the screenshots' base.
+        let notes = notes
+            .iter()
+            .filter(|note| within(note.at))
+            .map(|note| SessionNote {
+                image: note.image.as_ref().map(|image| format!("../{image}")),
+                ..note.clone()
+            })
+            .collect::<Vec<SessionNote>>();
+
+        let body = transcript_file::render_markdown(chapter, &mutes, &notes, options);
+        let path = dir.join(format!("Chapter-{:02}.md", index + 1));
+        tokio::fs::write(
+            &path,
+            format!(
+                "# Chapter {} ({}–{})\n\n{body}\n",
+                index + 1,
+                transcript_file::format_timestamp(from),
+                transcript_file::format_timestamp(to)
+            ),
+        )
+        .await
+        .with_context(|| format!("failed writing {}", path.display()))?;
+        written.push(path);
+    }
+
+    Ok(written)
+}
+
+/// Groups segments by the chapter their start falls in. A segment without
+/// times joins the chapter of the one before it. The last group is the
+/// chapter still being recorded.
+fn split(segments: &[TranscriptSegment]) -> Vec<Vec<TranscriptSegment>> {
+    let mut chapters: Vec<Vec<TranscriptSegment>> = vec![Vec::new()];
+    let mut index = 0;
+    for segment in segments {
+        if let Some(start) = segment.start {
+            index = (start / CHAPTER_SECONDS).floor().max(0.0) as usize;
+        }
+        if index >= chapters.len() {
+            chapters.resize_with(index + 1, Vec::new);
+        }
+        chapters[index].push(segment.clone());
+    }
+    chapters
+}
@@ -0,0 +1,142 @@
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Context};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWrite, AsyncWriteExt, BufReader},
+    process::ChildStdin,
+};
+
+use crate::{
+    app_state::{SharedState, TranscriptSegment},
+    runtime_scripts,
+    worker::{self, SegmentCleanup, WorkerEvent},
+};
+
+/// Matches `identifier` in `tauri.conf.json`, so the CLI shares the app's
+/// settings, Python environment and models.
+const APP_IDENTIFIER: &str = "com.whisperbar";
+
+const USAGE: &str = "usage: whisperbar record [--output -|PATH]";
+
+/// `whisperbar record [--output -|PATH]`: records with the saved settings and
+/// writes each transcribed segment as one JSON line as soon as it is final.
+/// `-` (the default) streams to stdout; status goes to stderr so pipelines
+/// only see segments. Stops on Ctrl-C or SIGTERM. Nothing is saved to the
+/// transcript folder or the history.
+pub fn record(args: &[String]) -> anyhow::Result<()> {
+    let output = parse_output(args)?;
+    let app_data_dir = dirs::data_dir()
+        .ok_or_else(|| anyhow!("unable to locate the application data directory"))?
+        .join(APP_IDENTIFIER);
+
+    tokio::runtime::Runtime::new()?.block_on(run(SharedState::new(app_data_dir), output))
+}
+
+fn parse_output(args: &[String]) -> anyhow::Result<Option<PathBuf>> {
+    let mut output = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--output" | "-o" => {
+                let value = args.next().ok_or_else(|| anyhow!(USAGE))?;
+                output = (value != "-").then(|| PathBuf::from(value));
+            }
+            _ => return Err(anyhow!("unknown argument `{arg}`\n{USAGE}")),
+        }
+    }
+    Ok(output)
+}
+
+async fn run(state: SharedState, output: Option<PathBuf>) -> anyhow::Result<()> {
+    runtime_scripts::ensure_scripts(&state).await?;
+    let mut cleanup = SegmentCleanup::new(&*state.settings.lock().await);
+
+    let mut writer: Box<dyn AsyncWrite + Unpin + Send> = match &output {
+        Some(path) => Box::new(
+            tokio::fs::File::create(path)
+                .await
+                .with_context(|| format!("failed creating {}", path.display()))?,
+        ),
+        None => Box::new(tokio::io::stdout()),
+    };
+
+    let mut command = worker::worker_command(&state).await?;
+    command.stderr(std::process::Stdio::inherit());
+    let mut child = command.spawn().context("failed starting worker")?;
+    let stdout = child
+        .stdout
+        .take()
+        .context("unable to capture worker stdout")?;
+    let mut stdin = child.stdin.take();
+    let mut lines = BufReader::new(stdout).lines();
+
+    let mut terminate = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())?;
+    let mut stopping = false;
+    let mut failure = None;
+
+    loop {
+        tokio::select! {
+            line = lines.next_line() => {
+                let Some(line) = line? else { break };
+                let Ok(event) = serde_json::from_str::<WorkerEvent>(&line) else {
+                    continue;
+                };
+                match event.event_type.as_str() {
+                    "partial" => {
+                        let Some(text) = event.text else { continue };
+                        let segment = TranscriptSegment {
+                            text: cleanup.text(&text),
+                            speaker: event.speaker,
+                            source: event.source,
+                            paragraph: event.paragraph.unwrap_or(false),
+                            start: event.start,
+                            end: event.end,
+                            words: event.words.unwrap_or_default(),
+                        };
+                        // Nothing left once fillers are removed.
+                        if segment.text.is_empty() {
+                            continue;
+                        }
+                        if let Some(reason) = cleanup.hallucination(&segment) {
+                            eprintln!("dropped `{}`: {}", segment.text, reason.describe());
+                            continue;
+                        }
+                        let mut json = serde_json::to_vec(&segment)?;
+                        json.push(b'\n');
+                        writer.write_all(&json).await?;
+                        writer.flush().await?;
+                    }
+                    "status" => {
+                        if let Some(message) = event.message {
+                            eprintln!("{message}");
+                        }
+                    }
+                    "error" => {
+                        failure = event.message;
+                    }
+                    _ => {}
+                }
+            }
+            _ = tokio::signal::ctrl_c(), if !stopping => {
+                stopping = true;
+                signal_stop(&mut stdin).await;
+            }
+            _ = terminate.recv(), if !stopping => {
+                stopping = true;
+                signal_stop(&mut stdin).await;
+            }
+        }
+    }
+
+    let _ = child.wait().await;
+    match failure {
+        Some(message) => Err(anyhow!(message)),
+        None => Ok(()),
+    }
+}
+
+async fn signal_stop(stdin: &mut Option<ChildStdin>) {
+    if let Some(stdin) = stdin.as_mut() {
+        let _ = stdin.write_all(b"stop\n").await;
+    }
+}
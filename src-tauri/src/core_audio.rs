@@ -0,0 +1,295 @@
+use std::{
+    ffi::{c_char, c_void},
+    mem, ptr,
+};
+
+use anyhow::anyhow;
+
+type AudioObjectId = u32;
+type OsStatus = i32;
+type PropertyListener = extern "C" fn(
+    object: AudioObjectId,
+    address_count: u32,
+    addresses: *const PropertyAddress,
+    client_data: *mut c_void,
+) -> OsStatus;
+
+const fn four_cc(code: &[u8; 4]) -> u32 {
+    u32::from_be_bytes(*code)
+}
+
+/// `kAudioObjectSystemObject`.
+const SYSTEM_OBJECT: AudioObjectId = 1;
+/// `kAudioHardwarePropertyDevices`.
+const PROPERTY_DEVICES: u32 = four_cc(b"dev#");
+/// `kAudioDevicePropertyDeviceUID`.
+const PROPERTY_DEVICE_UID: u32 = four_cc(b"uid ");
+/// `kAudioObjectPropertyName`.
+const PROPERTY_NAME: u32 = four_cc(b"lnam");
+/// `kAudioDevicePropertyStreamConfiguration`.
+const PROPERTY_STREAM_CONFIGURATION: u32 = four_cc(b"slay");
+/// `kAudioObjectPropertyScopeGlobal`.
+const SCOPE_GLOBAL: u32 = four_cc(b"glob");
+/// `kAudioObjectPropertyScopeInput`.
+const SCOPE_INPUT: u32 = four_cc(b"inpt");
+/// `kAudioObjectPropertyElementMain`.
+const ELEMENT_MAIN: u32 = 0;
+/// `kCFStringEncodingUTF8`.
+const STRING_ENCODING_UTF8: u32 = 0x0800_0100;
+
+#[repr(C)]
+struct PropertyAddress {
+    selector: u32,
+    scope: u32,
+    element: u32,
+}
+
+/// `AudioBuffer`; only the channel count is read.
+#[repr(C)]
+struct AudioBuffer {
+    number_channels: u32,
+    _data_byte_size: u32,
+    _data: *mut c_void,
+}
+
+/// Header of a variable-length `AudioBufferList`.
+#[repr(C)]
+struct AudioBufferList {
+    number_buffers: u32,
+    buffers: [AudioBuffer; 1],
+}
+
+#[link(name = "CoreAudio", kind = "framework")]
+extern "C" {
+    fn AudioObjectGetPropertyDataSize(
+        object: AudioObjectId,
+        address: *const PropertyAddress,
+        qualifier_size: u32,
+        qualifier: *const c_void,
+        data_size: *mut u32,
+    ) -> OsStatus;
+    fn AudioObjectGetPropertyData(
+        object: AudioObjectId,
+        address: *const PropertyAddress,
+        qualifier_size: u32,
+        qualifier: *const c_void,
+        data_size: *mut u32,
+        data: *mut c_void,
+    ) -> OsStatus;
+    fn AudioObjectAddPropertyListener(
+        object: AudioObjectId,
+        address: *const PropertyAddress,
+        listener: PropertyListener,
+        client_data: *mut c_void,
+    ) -> OsStatus;
+}
+
+#[link(name = "CoreFoundation", kind = "framework")]
+extern "C" {
+    fn CFStringGetLength(string: *const c_void) -> isize;
+    fn CFStringGetMaximumSizeForEncoding(length: isize, encoding: u32) -> isize;
+    fn CFStringGetCString(
+        string: *const c_void,
+        buffer: *mut c_char,
+        buffer_size: isize,
+        encoding: u32,
+    ) -> bool;
+    fn CFRelease(cf: *const c_void);
+}
+
+/// A CoreAudio device that can record.
+pub struct InputDevice {
+    /// `kAudioDevicePropertyDeviceUID`: survives reboots and re-plugging,
+    /// unlike the avfoundation index.
+    pub uid: String,
+    pub name: String,
+    pub input_channels: u32,
+}
+
+/// Every device with at least one input channel, in CoreAudio's order.
+pub fn input_devices() -> anyhow::Result<Vec<InputDevice>> {
+    let ids = read_array::<AudioObjectId>(SYSTEM_OBJECT, &address(PROPERTY_DEVICES, SCOPE_GLOBAL))?;
+
+    let mut devices = Vec::new();
+    for id in ids {
+        // A device can vanish between the listing and these reads; skip it.
+        let Ok(input_channels) = input_channel_count(id) else {
+            continue;
+        };
+        if input_channels == 0 {
+            continue;
+        }
+        let (Ok(uid), Ok(name)) = (
+            read_string(id, &address(PROPERTY_DEVICE_UID, SCOPE_GLOBAL)),
+            read_string(id, &address(PROPERTY_NAME, SCOPE_GLOBAL)),
+        ) else {
+            continue;
+        };
+        devices.push(InputDevice {
+            uid,
+            name,
+            input_channels,
+        });
+    }
+
+    Ok(devices)
+}
+
+/// Calls `on_change` whenever a device is added or removed. CoreAudio invokes
+/// it on its own notification thread, often several times per change, so it
+/// should only hand off work. The listener stays registered for the rest of
+/// the process.
+pub fn watch_devices<F>(on_change: F) -> anyhow::Result<()>
+where
+    F: Fn() + Send + Sync + 'static,
+{
+    extern "C" fn notify(
+        _object: AudioObjectId,
+        _address_count: u32,
+        _addresses: *const PropertyAddress,
+        client_data: *mut c_void,
+    ) -> OsStatus {
+        let on_change = unsafe { &*(client_data as *const Box<dyn Fn() + Send + Sync>) };
+        on_change();
+        0
+    }
+
+    let callback: Box<Box<dyn Fn() + Send + Sync>> = Box::new(Box::new(on_change));
+    let client_data = Box::into_raw(callback);
+    let status = unsafe {
+        AudioObjectAddPropertyListener(
+            SYSTEM_OBJECT,
+            &address(PROPERTY_DEVICES, SCOPE_GLOBAL),
+            notify,
+            client_data.cast(),
+        )
+    };
+    if status != 0 {
+        drop(unsafe { Box::from_raw(client_data) });
+    }
+    check(status, "device listener registration")
+}
+
+fn address(selector: u32, scope: u32) -> PropertyAddress {
+    PropertyAddress {
+        selector,
+        scope,
+        element: ELEMENT_MAIN,
+    }
+}
+
+fn check(status: OsStatus, what: &str) -> anyhow::Result<()> {
+    if status == 0 {
+        Ok(())
+    } else {
+        Err(anyhow!("CoreAudio {what} failed with status {status}"))
+    }
+}
+
+fn data_size(object: AudioObjectId, address: &PropertyAddress) -> anyhow::Result<u32> {
+    let mut size = 0u32;
+    let status =
+        unsafe { AudioObjectGetPropertyDataSize(object, address, 0, ptr::null(), &mut size) };
+    check(status, "property size query")?;
+    Ok(size)
+}
+
+fn read_array<T: Copy + Default>(
+    object: AudioObjectId,
+    address: &PropertyAddress,
+) -> anyhow::Result<Vec<T>> {
+    let mut size = data_size(object, address)?;
+    let mut values = vec![T::default(); size as usize / mem::size_of::<T>()];
+    let status = unsafe {
+        AudioObjectGetPropertyData(
+            object,
+            address,
+            0,
+            ptr::null(),
+            &mut size,
+            values.as_mut_ptr().cast(),
+        )
+    };
+    check(status, "property read")?;
+    values.truncate(size as usize / mem::size_of::<T>());
+    Ok(values)
+}
+
+/// Reads a `CFStringRef` property. CoreAudio hands out a retained copy, so
+/// it is released here.
+fn read_string(object: AudioObjectId, address: &PropertyAddress) -> anyhow::Result<String> {
+    let mut string: *const c_void = ptr::null();
+    let mut size = mem::size_of::<*const c_void>() as u32;
+    let status = unsafe {
+        AudioObjectGetPropertyData(
+            object,
+            address,
+            0,
+            ptr::null(),
+            &mut size,
+            (&mut string as *mut *const c_void).cast(),
+        )
+    };
+    check(status, "string property read")?;
+    if string.is_null() {
+        return Err(anyhow!("CoreAudio returned no string"));
+    }
+
+    let converted = unsafe {
+        let capacity =
+            CFStringGetMaximumSizeForEncoding(CFStringGetLength(string), STRING_ENCODING_UTF8) + 1;
+        let mut buffer = vec![0u8; capacity.max(1) as usize];
+        let ok = CFStringGetCString(
+            string,
+            buffer.as_mut_ptr().cast(),
+            buffer.len() as isize,
+            STRING_ENCODING_UTF8,
+        );
+        CFRelease(string);
+        ok.then(|| {
+            let end = buffer
+                .iter()
+                .position(|byte| *byte == 0)
+                .unwrap_or(buffer.len());
+            String::from_utf8_lossy(&buffer[..end]).into_owned()
+        })
+    };
+
+    converted.ok_or_else(|| anyhow!("CoreAudio string is not valid UTF-8"))
+}
+
+/// Sums the channels of every input stream of `device`.
+fn input_channel_count(device: AudioObjectId) -> anyhow::Result<u32> {
+    let address = address(PROPERTY_STREAM_CONFIGURATION, SCOPE_INPUT);
+    let mut size = data_size(device, &address)?;
+    if (size as usize) < mem::size_of::<u32>() {
+        return Ok(0);
+    }
+
+    // u64 storage keeps the list 8-byte aligned for the pointers it holds.
+    let mut storage = vec![0u64; (size as usize).div_ceil(mem::size_of::<u64>())];
+    let status = unsafe {
+        AudioObjectGetPropertyData(
+            device,
+            &address,
+            0,
+            ptr::null(),
+            &mut size,
+            storage.as_mut_ptr().cast(),
+        )
+    };
+    check(status, "stream configuration read")?;
+
+    let list = storage.as_ptr().cast::<AudioBufferList>();
+    let channels = unsafe {
+        let count = (*list).number_buffers as usize;
+        let buffers = ptr::addr_of!((*list).buffers).cast::<AudioBuffer>();
+        let available = (size as usize).saturating_sub(mem::offset_of!(AudioBufferList, buffers))
+            / mem::size_of::<AudioBuffer>();
+        (0..count.min(available))
+            .map(|index| (*buffers.add(index)).number_channels)
+            .sum()
+    };
+
+    Ok(channels)
+}
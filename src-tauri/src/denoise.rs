@@ -0,0 +1,71 @@
+use std::io::{self, Read, Write};
+
+use anyhow::Context;
+use nnnoiseless::DenoiseState;
+use rubato::{
+    Resampler, SincFixedIn, SincInterpolationParameters, SincInterpolationType, WindowFunction,
+};
+
+/// RNNoise works on 48 kHz audio; the worker's ffmpeg delivers the mic at
+/// this rate when noise suppression is on.
+const INPUT_SAMPLE_RATE: u32 = 48_000;
+const OUTPUT_SAMPLE_RATE: u32 = 16_000;
+
+/// `whisperbar --denoise-filter`: reads 48 kHz mono s16le on stdin, removes
+/// steady background noise (fans, keyboards, hum) with RNNoise and writes
+/// 16 kHz mono s16le, the worker's format, on stdout. Runs until stdin closes.
+pub fn run() -> anyhow::Result<()> {
+    let mut denoiser = DenoiseState::new();
+    let mut resampler = SincFixedIn::<f32>::new(
+        f64::from(OUTPUT_SAMPLE_RATE) / f64::from(INPUT_SAMPLE_RATE),
+        1.0,
+        SincInterpolationParameters {
+            sinc_len: 128,
+            f_cutoff: 0.95,
+            interpolation: SincInterpolationType::Linear,
+            oversampling_factor: 128,
+            window: WindowFunction::BlackmanHarris2,
+        },
+        DenoiseState::FRAME_SIZE,
+        1,
+    )?;
+
+    let mut stdin = io::stdin().lock();
+    let mut stdout = io::stdout().lock();
+    let mut bytes = vec![0u8; DenoiseState::FRAME_SIZE * 2];
+    let mut frame = vec![0.0f32; DenoiseState::FRAME_SIZE];
+    let mut denoised = vec![0.0f32; DenoiseState::FRAME_SIZE];
+    let mut resampled = vec![vec![0.0f32; resampler.output_frames_max()]];
+    let mut pcm = Vec::with_capacity(resampler.output_frames_max() * 2);
+
+    loop {
+        match stdin.read_exact(&mut bytes) {
+            Ok(()) => {}
+            Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+            Err(error) => return Err(error).context("failed reading microphone audio"),
+        }
+
+        // RNNoise expects samples on the i16 scale, not -1..1.
+        for (sample, pair) in frame.iter_mut().zip(bytes.chunks_exact(2)) {
+            *sample = f32::from(i16::from_le_bytes([pair[0], pair[1]]));
+        }
+        denoiser.process_frame(&mut denoised, &frame);
+
+        let (_, written) = resampler.process_into_buffer(&[&denoised], &mut resampled, None)?;
+        pcm.clear();
+        for sample in &resampled[0][..written] {
+            let value = sample
+                .clamp(f32::from(i16::MIN), f32::from(i16::MAX))
+                .round() as i16;
+            pcm.extend_from_slice(&value.to_le_bytes());
+        }
+        if stdout
+            .write_all(&pcm)
+            .and_then(|()| stdout.flush())
+            .is_err()
+        {
+            // The worker stopped reading: the capture is over.
+            return Ok(());
+        }
+    }
+}
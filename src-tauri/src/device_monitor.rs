@@ -0,0 +1,65 @@
+use std::time::Duration;
+
+use tauri::{AppHandle, Emitter};
+use tokio::sync::mpsc;
+
+use crate::{
+    app_state::{AppStatus, SharedState},
+    audio::{self, AudioDeviceOption},
+    core_audio, preroll, worker,
+};
+
+/// Emitted with the new `AudioDeviceOption` list after a device is plugged
+/// in or removed.
+pub const DEVICES_CHANGED_EVENT: &str = "whisperbar://devices-changed";
+
+/// CoreAudio reports a single change as a burst of notifications.
+const SETTLE_DELAY: Duration = Duration::from_millis(300);
+
+/// Watches for device changes for the lifetime of the app. The saved
+/// microphone choice is left alone, so a device that is unplugged and plugged
+/// back in is picked up again.
+pub fn start(app: &AppHandle, state: &SharedState) -> anyhow::Result<()> {
+    let (changes, mut receiver) = mpsc::unbounded_channel();
+    core_audio::watch_devices(move || {
+        let _ = changes.send(());
+    })?;
+
+    let app = app.clone();
+    let state = state.clone();
+    tauri::async_runtime::spawn(async move {
+        while receiver.recv().await.is_some() {
+            tokio::time::sleep(SETTLE_DELAY).await;
+            while receiver.try_recv().is_ok() {}
+
+            let Ok(devices) = audio::list_audio_devices() else {
+                continue;
+            };
+            let _ = app.emit(DEVICES_CHANGED_EVENT, &devices);
+
+            if state.status().await == AppStatus::Recording {
+                follow_microphone(&state, &devices).await;
+            } else {
+                preroll::sync(&state).await;
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// Keeps a running session on the selected microphone, or on the default one
+/// while the selected microphone is disconnected.
+async fn follow_microphone(state: &SharedState, devices: &[AudioDeviceOption]) {
+    let selected = state.settings.lock().await.selected_mic_device.clone();
+    let capture_name = selected
+        .as_deref()
+        .and_then(|id| audio::capture_name(devices, id))
+        .or_else(|| {
+            audio::choose_default_mic(devices).and_then(|id| audio::capture_name(devices, &id))
+        });
+
+    if let Some(capture_name) = capture_name {
+        let _ = worker::switch_microphone(state, &capture_name).await;
+    }
+}
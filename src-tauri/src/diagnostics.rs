@@ -0,0 +1,43 @@
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::{
+    app_state::{is_model_installed, SharedState},
+    maintenance::{self, MaintenanceReport},
+};
+
+/// Installation health at a glance, for support requests and the settings
+/// panel.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Diagnostics {
+    pub app_version: String,
+    pub app_data_dir: String,
+    pub selected_model_id: String,
+    pub selected_model_installed: bool,
+    pub history_sessions: usize,
+    pub read_only: bool,
+    /// `None` until the first maintenance run has finished.
+    pub last_maintenance: Option<MaintenanceReport>,
+}
+
+pub async fn collect(app: &AppHandle, state: &SharedState) -> Diagnostics {
+    let (selected_model_id, model_path) = {
+        let settings = state.settings.lock().await;
+        (
+            settings.selected_model_id.clone(),
+            settings.model_path.clone(),
+        )
+    };
+    let history_sessions = state.history.lock().await.entries.len();
+
+    Diagnostics {
+        app_version: app.package_info().version.to_string(),
+        app_data_dir: state.paths.app_data_dir.display().to_string(),
+        selected_model_id,
+        selected_model_installed: is_model_installed(&model_path),
+        history_sessions,
+        read_only: state.paths.read_only,
+        last_maintenance: maintenance::last_report(&state.paths),
+    }
+}
@@ -0,0 +1,171 @@
+use std::{ffi::c_void, process::Stdio, time::Duration};
+
+use anyhow::{anyhow, Context};
+use tokio::{io::AsyncWriteExt, process::Command};
+
+pub const PERMISSION_MESSAGE: &str =
+    "Dictation needs Accessibility permission. Enable WhisperBar in System Settings > Privacy & Security > Accessibility.";
+
+/// `kVK_ANSI_V` from `HIToolbox/Events.h`.
+const KEY_CODE_V: u16 = 9;
+/// `kCGEventFlagMaskCommand`.
+const EVENT_FLAG_COMMAND: u64 = 1 << 20;
+/// `kCGHIDEventTap`.
+const HID_EVENT_TAP: u32 = 0;
+
+/// `clipboard info` names for plain text. Anything else on the pasteboard
+/// (images, files, rich text) cannot be put back through `pbcopy`.
+const PLAIN_TEXT_TYPES: [&str; 4] = ["«class utf8»", "«class ut16»", "string", "Unicode text"];
+
+#[link(name = "ApplicationServices", kind = "framework")]
+extern "C" {
+    fn AXIsProcessTrusted() -> bool;
+    fn CGEventCreateKeyboardEvent(
+        source: *const c_void,
+        virtual_key: u16,
+        key_down: bool,
+    ) -> *mut c_void;
+    fn CGEventSetFlags(event: *mut c_void, flags: u64);
+    fn CGEventPost(tap: u32, event: *mut c_void);
+}
+
+#[link(name = "CoreFoundation", kind = "framework")]
+extern "C" {
+    fn CFRelease(cf: *const c_void);
+}
+
+/// Where a dictation's transcript goes when the session stops, besides the
+/// saved file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DictationOutput {
+    /// A regular session: the transcript is only saved.
+    #[default]
+    Off,
+    /// Pasted into the frontmost app (`start_recording` with `dictation`).
+    Paste,
+    /// Left on the clipboard (`quick_dictation`).
+    Copy,
+}
+
+/// Whether macOS allows this process to post synthetic key events.
+pub fn accessibility_trusted() -> bool {
+    unsafe { AXIsProcessTrusted() }
+}
+
+/// Pastes `text` into the frontmost application via the pasteboard and a
+/// synthetic Cmd+V, then restores the previous pasteboard contents when they
+/// were plain text. Anything richer is left replaced by `text` rather than
+/// put back as a lossy text copy.
+pub async fn paste_text(text: &str) -> anyhow::Result<()> {
+    if !accessibility_trusted() {
+        return Err(anyhow!(PERMISSION_MESSAGE));
+    }
+
+    let previous = if pasteboard_is_plain_text().await {
+        read_pasteboard().await.ok()
+    } else {
+        None
+    };
+    write_pasteboard(text).await?;
+    post_command_v()?;
+
+    // The target app reads the pasteboard asynchronously after the key event.
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    if let Some(previous) = previous {
+        let _ = write_pasteboard(&previous).await;
+    }
+
+    Ok(())
+}
+
+/// Puts `text` on the pasteboard and leaves it there. Needs no permission.
+pub async fn copy_text(text: &str) -> anyhow::Result<()> {
+    write_pasteboard(text).await
+}
+
+async fn pasteboard_is_plain_text() -> bool {
+    let Ok(output) = Command::new("osascript")
+        .args(["-e", "clipboard info"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .output()
+        .await
+    else {
+        return false;
+    };
+    output.status.success() && only_plain_text(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// `clipboard info` prints `type, size, type, size, ...`.
+fn only_plain_text(info: &str) -> bool {
+    info.trim()
+        .split(", ")
+        .step_by(2)
+        .filter(|kind| !kind.is_empty())
+        .all(|kind| PLAIN_TEXT_TYPES.contains(&kind))
+}
+
+async fn read_pasteboard() -> anyhow::Result<String> {
+    let output = Command::new("pbpaste")
+        .stdout(Stdio::piped())
+        .output()
+        .await
+        .context("failed reading pasteboard")?;
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+pub async fn write_pasteboard(text: &str) -> anyhow::Result<()> {
+    let mut child = Command::new("pbcopy")
+        .stdin(Stdio::piped())
+        .spawn()
+        .context("failed starting pbcopy")?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(text.as_bytes())
+            .await
+            .context("failed writing pasteboard")?;
+    }
+
+    let status = child.wait().await.context("failed waiting for pbcopy")?;
+    if !status.success() {
+        return Err(anyhow!("pbcopy exited with {status}"));
+    }
+    Ok(())
+}
+
+fn post_command_v() -> anyhow::Result<()> {
+    for key_down in [true, false] {
+        unsafe {
+            let event = CGEventCreateKeyboardEvent(std::ptr::null(), KEY_CODE_V, key_down);
+            if event.is_null() {
+                return Err(anyhow!("failed creating keyboard event"));
+            }
+            CGEventSetFlags(event, EVENT_FLAG_COMMAND);
+            CGEventPost(HID_EVENT_TAP, event);
+            CFRelease(event);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_text_pasteboard_is_restorable() {
+        assert!(only_plain_text(
+            "«class utf8», 5, «class ut16», 12, string, 5, Unicode text, 10\n"
+        ));
+        assert!(only_plain_text(""));
+    }
+
+    #[test]
+    fn images_and_rich_text_are_not_restorable() {
+        assert!(!only_plain_text("«class PNGf», 20443, «class 8BPS», 90210"));
+        assert!(!only_plain_text(
+            "«class RTF », 412, «class utf8», 5, string, 5, Unicode text, 10"
+        ));
+    }
+}
@@ -0,0 +1,503 @@
+use std::{
+    ffi::OsString,
+    future::Future,
+    path::PathBuf,
+    pin::Pin,
+    process::Stdio,
+    sync::{Arc, Mutex as StdMutex},
+    time::Duration,
+};
+
+use anyhow::{anyhow, Context};
+use base64::Engine as _;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    process::{Child, ChildStderr, ChildStdin, Command},
+    sync::{
+        mpsc::{self, UnboundedReceiver, UnboundedSender},
+        oneshot, Mutex, MutexGuard,
+    },
+    time::timeout,
+};
+
+use crate::{
+    app_state::SharedState,
+    audio::AudioSource,
+    models::{self, ModelBackend},
+    pipeline_timings,
+    worker::{self, WorkerEvent},
+};
+
+pub type EngineFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// What a session adds to the saved settings when it starts an engine.
+#[derive(Debug, Default)]
+pub struct SessionOptions {
+    /// Words the model should expect, from the linked meeting.
+    pub initial_prompt: Option<String>,
+    /// Where to write the session's 16 kHz audio.
+    pub audio_path: Option<PathBuf>,
+    /// Wait for `EngineInput::Preroll` before the first chunk.
+    pub preroll: bool,
+}
+
+/// Sent to a running engine while it records.
+#[derive(Debug)]
+pub enum EngineInput {
+    /// Leave a source out of the mix for the rest of the session.
+    Mute(AudioSource),
+    /// Capture the microphone with this ffmpeg capture name instead.
+    Mic(String),
+    /// Raw 16 kHz s16le audio buffered before the session started.
+    Preroll(Vec<u8>),
+}
+
+/// Received from a running engine, in the order it produced them.
+#[derive(Debug)]
+pub enum EngineEvent {
+    Event(WorkerEvent),
+    /// Free-form diagnostics (a worker's stderr), shown in the status line.
+    Log(String),
+}
+
+/// A transcription backend for one recording session. Session handling in
+/// `worker` only talks to this, so a backend that does not run as a worker
+/// process can be added here without touching it or the command handlers.
+///
+/// Events arrive on the receiver returned by `start`, which closes once the
+/// engine has nothing more to say.
+pub trait Engine: Send {
+    fn feed(&mut self, input: EngineInput) -> EngineFuture<'_, anyhow::Result<()>>;
+
+    /// Asks the engine to transcribe what it still holds and finish, forcing
+    /// it after `grace`. Resolves to `true` when it had to be forced.
+    fn stop(&mut self, grace: Duration) -> EngineFuture<'_, anyhow::Result<bool>>;
+}
+
+/// Starts the engine for the selected model.
+pub async fn start(
+    state: &SharedState,
+    options: SessionOptions,
+) -> anyhow::Result<(Box<dyn Engine>, UnboundedReceiver<EngineEvent>)> {
+    // Both backends (the Python MLX worker and `--native-worker`) are worker
+    // processes; `worker_command` picks the right one.
+    let mut command = worker::worker_command(state).await?;
+    if let Some(prompt) = &options.initial_prompt {
+        command.arg("--initial-prompt").arg(prompt);
+    }
+    if let Some(path) = &options.audio_path {
+        command.arg("--audio-path").arg(path);
+    }
+    if options.preroll {
+        command.arg("--preroll");
+    }
+
+    if let Some(idle) = warm_idle_timeout(state).await {
+        let (engine, events) = WarmWorkerEngine::start(state, command, idle).await?;
+        return Ok((Box::new(engine), events));
+    }
+
+    let (engine, events) = WorkerProcessEngine::spawn(command)?;
+    Ok((Box::new(engine), events))
+}
+
+/// How long a kept-warm worker waits between sessions, or `None` when
+/// sessions get a worker of their own. Only the Python worker is worth
+/// keeping: its interpreter, MLX import and model load are what delay the
+/// first partial.
+async fn warm_idle_timeout(state: &SharedState) -> Option<Duration> {
+    let settings = state.settings.lock().await;
+    let backend = models::backend(&settings.custom_models, &settings.selected_model_id);
+    (settings.warm_worker_minutes > 0 && backend == Some(ModelBackend::Mlx))
+        .then(|| Duration::from_secs(u64::from(settings.warm_worker_minutes) * 60))
+}
+
+/// Starts the kept-warm worker for the selected model ahead of the first
+/// recording (`preload_model`). It loads the model and then waits, with no
+/// idle timeout until its first session. Does nothing for whisper.cpp
+/// models or with `warm_worker_minutes` at 0.
+pub async fn preload(state: &SharedState) -> anyhow::Result<()> {
+    let Some(idle) = warm_idle_timeout(state).await else {
+        return Ok(());
+    };
+    let command = worker::worker_command(state).await?;
+    let (program, script, _) = split_worker_command(&command)?;
+    warm_worker_for(state, program, script, idle).await?;
+    Ok(())
+}
+
+/// Stops the kept-warm worker, if one is running, e.g. when the setting is
+/// turned off.
+pub async fn shutdown_warm_worker(state: &SharedState) {
+    let Some(mut worker) = state.warm_worker.lock().await.take() else {
+        return;
+    };
+    let _ = worker.stdin.write_all(b"shutdown\n").await;
+    if timeout(Duration::from_secs(2), worker.child.wait())
+        .await
+        .is_err()
+    {
+        let _ = worker.child.kill().await;
+    }
+}
+
+/// A worker process speaking the JSON-line protocol: events on stdout,
+/// diagnostics on stderr, commands on stdin.
+struct WorkerProcessEngine {
+    child: Child,
+    stdin: Option<ChildStdin>,
+}
+
+impl WorkerProcessEngine {
+    fn spawn(mut command: Command) -> anyhow::Result<(Self, UnboundedReceiver<EngineEvent>)> {
+        command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        let mut child = command.spawn().with_context(|| {
+            format!(
+                "failed starting worker with {}",
+                command.as_std().get_program().to_string_lossy()
+            )
+        })?;
+
+        let stdout = child
+            .stdout
+            .take()
+            .context("unable to capture worker stdout")?;
+        let stderr = child
+            .stderr
+            .take()
+            .context("unable to capture worker stderr")?;
+        let stdin = child.stdin.take();
+
+        let (events_tx, events) = mpsc::unbounded_channel();
+        let stdout_events = events_tx.clone();
+        tauri::async_runtime::spawn(async move {
+            let mut lines = BufReader::new(stdout).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                // Anything that is not an event (stray prints) is ignored.
+                let Ok(event) = serde_json::from_str::<WorkerEvent>(&line) else {
+                    continue;
+                };
+                if let Some(timings) = &event.timings {
+                    pipeline_timings::record_worker(timings);
+                }
+                if stdout_events.send(EngineEvent::Event(event)).is_err() {
+                    break;
+                }
+            }
+        });
+        forward_stderr(stderr, events_tx);
+
+        Ok((Self { child, stdin }, events))
+    }
+
+    async fn send_line(&mut self, line: &str) -> anyhow::Result<()> {
+        let stdin = self
+            .stdin
+            .as_mut()
+            .ok_or_else(|| anyhow!("missing worker process"))?;
+        stdin
+            .write_all(format!("{line}\n").as_bytes())
+            .await
+            .context("failed signaling worker")
+    }
+}
+
+impl Engine for WorkerProcessEngine {
+    fn feed(&mut self, input: EngineInput) -> EngineFuture<'_, anyhow::Result<()>> {
+        Box::pin(async move { self.send_line(&input_line(input)).await })
+    }
+
+    fn stop(&mut self, grace: Duration) -> EngineFuture<'_, anyhow::Result<bool>> {
+        Box::pin(async move {
+            let signaled = self.send_line("stop").await;
+            if timeout(grace, self.child.wait()).await.is_ok() {
+                return signaled.map(|()| false);
+            }
+            self.child.kill().await.context("failed killing worker")?;
+            let _ = self.child.wait().await;
+            Ok(true)
+        })
+    }
+}
+
+/// Which worker a kept-warm process is; a session that needs another one
+/// replaces it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct WarmKey {
+    program: OsString,
+    script: OsString,
+    model_path: PathBuf,
+}
+
+/// Where the kept-warm worker's output goes while a session runs.
+struct WarmSession {
+    events: UnboundedSender<EngineEvent>,
+    /// Fired by the worker's `idle` event once the session has finished.
+    done: oneshot::Sender<()>,
+}
+
+/// A Python worker started with `--persistent`: it keeps the model loaded
+/// between recordings and runs one session per `start` line, exiting by
+/// itself after `--idle-timeout`. Held in `SharedState::warm_worker`.
+pub struct WarmWorker {
+    key: WarmKey,
+    child: Child,
+    stdin: ChildStdin,
+    session: Arc<StdMutex<Option<WarmSession>>>,
+}
+
+impl WarmWorker {
+    fn spawn(key: WarmKey, language: &str, idle: Duration) -> anyhow::Result<Self> {
+        let mut command = Command::new(&key.program);
+        command
+            .arg(&key.script)
+            .arg("--persistent")
+            .arg("--idle-timeout")
+            .arg(idle.as_secs().to_string())
+            .arg("--language")
+            .arg(language)
+            .arg("--model-path")
+            .arg(&key.model_path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        let mut child = command.spawn().with_context(|| {
+            format!(
+                "failed starting worker with {}",
+                key.program.to_string_lossy()
+            )
+        })?;
+
+        let stdout = child
+            .stdout
+            .take()
+            .context("unable to capture worker stdout")?;
+        let stderr = child
+            .stderr
+            .take()
+            .context("unable to capture worker stderr")?;
+        let stdin = child.stdin.take().context("unable to open worker stdin")?;
+        let session: Arc<StdMutex<Option<WarmSession>>> = Arc::default();
+
+        let stdout_session = session.clone();
+        tauri::async_runtime::spawn(async move {
+            let mut lines = BufReader::new(stdout).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                let Ok(event) = serde_json::from_str::<WorkerEvent>(&line) else {
+                    continue;
+                };
+                if let Some(timings) = &event.timings {
+                    pipeline_timings::record_worker(timings);
+                }
+                let mut current = lock(&stdout_session);
+                match event.event_type.as_str() {
+                    // Between sessions; nobody is listening.
+                    "ready" => {}
+                    // Dropping the sender ends the session's event stream.
+                    "idle" => {
+                        if let Some(finished) = current.take() {
+                            let _ = finished.done.send(());
+                        }
+                    }
+                    _ => {
+                        if let Some(active) = current.as_ref() {
+                            let _ = active.events.send(EngineEvent::Event(event));
+                        }
+                    }
+                }
+            }
+            // Exited (idle timeout, crash): close whatever session was open.
+            lock(&stdout_session).take();
+        });
+
+        let stderr_session = session.clone();
+        tauri::async_runtime::spawn(async move {
+            let mut lines = BufReader::new(stderr).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                if let Some(active) = lock(&stderr_session).as_ref() {
+                    let _ = active.events.send(EngineEvent::Log(line));
+                }
+            }
+        });
+
+        Ok(Self {
+            key,
+            child,
+            stdin,
+            session,
+        })
+    }
+
+    fn is_alive(&mut self) -> bool {
+        matches!(self.child.try_wait(), Ok(None))
+    }
+
+    async fn send_line(&mut self, line: &str) -> anyhow::Result<()> {
+        self.stdin
+            .write_all(format!("{line}\n").as_bytes())
+            .await
+            .context("failed signaling worker")
+    }
+}
+
+/// Program, script and session arguments of a Python worker command.
+fn split_worker_command(command: &Command) -> anyhow::Result<(OsString, OsString, Vec<String>)> {
+    let command = command.as_std();
+    let mut args = command.get_args().map(OsString::from);
+    let script = args
+        .next()
+        .ok_or_else(|| anyhow!("worker command has no script"))?;
+    let session_args = args
+        .map(|arg| {
+            arg.into_string()
+                .map_err(|arg| anyhow!("worker argument {arg:?} is not UTF-8"))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok((command.get_program().to_owned(), script, session_args))
+}
+
+/// The kept-warm worker slot, holding a live worker for the selected model:
+/// the running one, or a new one if there is none or it runs another model.
+async fn warm_worker_for(
+    state: &SharedState,
+    program: OsString,
+    script: OsString,
+    idle: Duration,
+) -> anyhow::Result<MutexGuard<'_, Option<WarmWorker>>> {
+    let (model_path, language) = {
+        let settings = state.settings.lock().await;
+        (settings.model_path.clone(), settings.language.clone())
+    };
+    let key = WarmKey {
+        program,
+        script,
+        model_path,
+    };
+
+    let mut slot = state.warm_worker.lock().await;
+    let reusable = slot
+        .as_mut()
+        .is_some_and(|worker| worker.key == key && worker.is_alive());
+    if !reusable {
+        if let Some(mut stale) = slot.take() {
+            let _ = stale.child.kill().await;
+        }
+        *slot = Some(WarmWorker::spawn(key, &language, idle)?);
+    }
+    Ok(slot)
+}
+
+fn lock(session: &StdMutex<Option<WarmSession>>) -> std::sync::MutexGuard<'_, Option<WarmSession>> {
+    session
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// One session on the kept-warm worker.
+struct WarmWorkerEngine {
+    slot: Arc<Mutex<Option<WarmWorker>>>,
+    done: Option<oneshot::Receiver<()>>,
+}
+
+impl WarmWorkerEngine {
+    /// Hands the session to the kept-warm worker, starting one first if none
+    /// is running or it runs another model.
+    async fn start(
+        state: &SharedState,
+        command: Command,
+        idle: Duration,
+    ) -> anyhow::Result<(Self, UnboundedReceiver<EngineEvent>)> {
+        let (program, script, session_args) = split_worker_command(&command)?;
+        let mut slot = warm_worker_for(state, program, script, idle).await?;
+        let worker = slot.as_mut().expect("warm_worker_for fills the slot");
+
+        let (events_tx, events) = mpsc::unbounded_channel();
+        let (done_tx, done) = oneshot::channel();
+        {
+            let mut current = lock(&worker.session);
+            if current.is_some() {
+                return Err(anyhow!("the worker is still finishing the last session"));
+            }
+            *current = Some(WarmSession {
+                events: events_tx,
+                done: done_tx,
+            });
+        }
+        let line = format!("start {}", serde_json::to_string(&session_args)?);
+        if let Err(error) = worker.send_line(&line).await {
+            lock(&worker.session).take();
+            return Err(error);
+        }
+
+        Ok((
+            Self {
+                slot: state.warm_worker.clone(),
+                done: Some(done),
+            },
+            events,
+        ))
+    }
+
+    async fn send_line(&self, line: &str) -> anyhow::Result<()> {
+        self.slot
+            .lock()
+            .await
+            .as_mut()
+            .ok_or_else(|| anyhow!("missing worker process"))?
+            .send_line(line)
+            .await
+    }
+}
+
+impl Engine for WarmWorkerEngine {
+    fn feed(&mut self, input: EngineInput) -> EngineFuture<'_, anyhow::Result<()>> {
+        Box::pin(async move { self.send_line(&input_line(input)).await })
+    }
+
+    fn stop(&mut self, grace: Duration) -> EngineFuture<'_, anyhow::Result<bool>> {
+        Box::pin(async move {
+            let signaled = self.send_line("stop").await;
+            let Some(done) = self.done.take() else {
+                return signaled.map(|()| false);
+            };
+            // An error means the worker exited, which also ends the session.
+            if timeout(grace, done).await.is_ok() {
+                return signaled.map(|()| false);
+            }
+            if let Some(mut worker) = self.slot.lock().await.take() {
+                worker.child.kill().await.context("failed killing worker")?;
+                let _ = worker.child.wait().await;
+            }
+            Ok(true)
+        })
+    }
+}
+
+fn input_line(input: EngineInput) -> String {
+    match input {
+        EngineInput::Mute(source) => format!("mute {}", source.as_str()),
+        EngineInput::Mic(capture_name) => format!("mic {capture_name}"),
+        EngineInput::Preroll(audio) => format!(
+            "preroll {}",
+            base64::engine::general_purpose::STANDARD.encode(audio)
+        ),
+    }
+}
+
+fn forward_stderr(stderr: ChildStderr, events: UnboundedSender<EngineEvent>) {
+    tauri::async_runtime::spawn(async move {
+        let mut lines = BufReader::new(stderr).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            if !line.trim().is_empty() && events.send(EngineEvent::Log(line)).is_err() {
+                break;
+            }
+        }
+    });
+}
@@ -0,0 +1,126 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::app_state::{emit_state, AppStatus, SessionState, SharedState};
+
+/// How long a warning stays on screen before it dismisses itself.
+pub const WARNING_DISMISS_SECS: u64 = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ErrorKind {
+    Install,
+    Recording,
+    Worker,
+    NoSpeech,
+    Dictation,
+    DurationLimit,
+    /// The desktop capture contains the microphone signal, so speech would
+    /// be transcribed twice.
+    Loopback,
+    /// The linked calendar event is past its scheduled end.
+    MeetingEnded,
+    /// The selected model needs more unified memory than is free.
+    LowMemory,
+    /// Model files do not match the manifest written at install time.
+    ModelCorrupted,
+    /// Too little free disk space to install the selected model.
+    LowDiskSpace,
+    /// The hallucination filter dropped a segment.
+    Hallucination,
+    /// A post-processing step failed; the transcript was saved without it.
+    Postprocess,
+    /// A moment screenshot could not be taken; the recording goes on.
+    Moment,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ErrorSeverity {
+    /// Transient; auto-dismissed and suppressible per kind.
+    Warning,
+    /// Stays until `clear_error` (or a retry) resolves it.
+    Fatal,
+}
+
+impl ErrorKind {
+    pub fn severity(self) -> ErrorSeverity {
+        match self {
+            ErrorKind::Install
+            | ErrorKind::Recording
+            | ErrorKind::Worker
+            | ErrorKind::ModelCorrupted
+            | ErrorKind::LowDiskSpace => ErrorSeverity::Fatal,
+            ErrorKind::NoSpeech
+            | ErrorKind::Dictation
+            | ErrorKind::DurationLimit
+            | ErrorKind::Loopback
+            | ErrorKind::MeetingEnded
+            | ErrorKind::LowMemory
+            | ErrorKind::Hallucination
+            | ErrorKind::Postprocess
+            | ErrorKind::Moment => ErrorSeverity::Warning,
+        }
+    }
+}
+
+/// Applies `update` to the session and records the error on it in one step.
+///
+/// Warnings whose kind the user suppressed are dropped (the session update
+/// still happens, then the error is dismissed). Other warnings are dismissed
+/// after `WARNING_DISMISS_SECS` unless a newer error replaced them.
+pub async fn report<F>(
+    app: &AppHandle,
+    state: &SharedState,
+    kind: ErrorKind,
+    message: String,
+    update: F,
+) where
+    F: FnOnce(&mut SessionState),
+{
+    let warning = kind.severity() == ErrorSeverity::Warning;
+    let suppressed = {
+        let settings = state.settings.lock().await;
+        warning && settings.suppressed_errors.contains(&kind)
+    };
+
+    let serial = {
+        let mut session = state.session.lock().await;
+        update(&mut session);
+        if suppressed {
+            dismiss(&mut session);
+            None
+        } else {
+            session.set_error(kind, message);
+            Some(session.error_serial)
+        }
+    };
+
+    emit_state(app, state).await;
+
+    if let (true, Some(serial)) = (warning, serial) {
+        let app = app.clone();
+        let state = state.clone();
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(Duration::from_secs(WARNING_DISMISS_SECS)).await;
+            {
+                let mut session = state.session.lock().await;
+                if session.error_serial != serial || session.error_message.is_none() {
+                    return;
+                }
+                dismiss(&mut session);
+            }
+            emit_state(&app, &state).await;
+        });
+    }
+}
+
+/// Clears the current error and leaves the `Error` status if it was set.
+pub fn dismiss(session: &mut SessionState) {
+    session.reset_error();
+    if session.status == AppStatus::Error {
+        session.status = AppStatus::Ready;
+        session.status_message = "Ready".to_string();
+    }
+}
@@ -0,0 +1,152 @@
+use std::{
+    ffi::{c_char, c_void, CStr, CString},
+    os::unix::ffi::OsStrExt,
+    path::{Path, PathBuf},
+    ptr,
+};
+
+use anyhow::anyhow;
+
+type FsEventStreamCallback = extern "C" fn(
+    stream: *const c_void,
+    client_info: *mut c_void,
+    event_count: usize,
+    event_paths: *mut c_void,
+    event_flags: *const u32,
+    event_ids: *const u64,
+);
+
+/// `kFSEventStreamEventIdSinceNow`.
+const EVENT_ID_SINCE_NOW: u64 = u64::MAX;
+/// `kFSEventStreamCreateFlagNoDefer | kFSEventStreamCreateFlagFileEvents`:
+/// report the first change of a burst right away, and per file rather than
+/// per directory.
+const CREATE_FLAGS: u32 = 0x02 | 0x10;
+/// `kCFStringEncodingUTF8`.
+const STRING_ENCODING_UTF8: u32 = 0x0800_0100;
+
+#[repr(C)]
+struct FsEventStreamContext {
+    version: isize,
+    info: *mut c_void,
+    retain: *const c_void,
+    release: *const c_void,
+    copy_description: *const c_void,
+}
+
+#[link(name = "CoreServices", kind = "framework")]
+extern "C" {
+    fn FSEventStreamCreate(
+        allocator: *const c_void,
+        callback: FsEventStreamCallback,
+        context: *const FsEventStreamContext,
+        paths_to_watch: *const c_void,
+        since_when: u64,
+        latency: f64,
+        flags: u32,
+    ) -> *mut c_void;
+    fn FSEventStreamSetDispatchQueue(stream: *mut c_void, queue: *mut c_void);
+    fn FSEventStreamStart(stream: *mut c_void) -> bool;
+    fn FSEventStreamInvalidate(stream: *mut c_void);
+    fn FSEventStreamRelease(stream: *mut c_void);
+}
+
+#[link(name = "CoreFoundation", kind = "framework")]
+extern "C" {
+    static kCFTypeArrayCallBacks: c_void;
+    fn CFStringCreateWithCString(
+        allocator: *const c_void,
+        string: *const c_char,
+        encoding: u32,
+    ) -> *const c_void;
+    fn CFArrayCreate(
+        allocator: *const c_void,
+        values: *const *const c_void,
+        count: isize,
+        callbacks: *const c_void,
+    ) -> *const c_void;
+    fn CFRelease(cf: *const c_void);
+}
+
+extern "C" {
+    fn dispatch_queue_create(label: *const c_char, attr: *const c_void) -> *mut c_void;
+}
+
+/// Calls `on_change` with the files created, modified, renamed or removed
+/// under `dir`, batched over `latency` seconds. FSEvents invokes it on its
+/// own dispatch queue, so it should only hand off work. The stream stays
+/// open for the rest of the process.
+pub fn watch<F>(dir: &Path, latency: f64, on_change: F) -> anyhow::Result<()>
+where
+    F: Fn(Vec<PathBuf>) + Send + Sync + 'static,
+{
+    extern "C" fn notify(
+        _stream: *const c_void,
+        client_info: *mut c_void,
+        event_count: usize,
+        event_paths: *mut c_void,
+        _event_flags: *const u32,
+        _event_ids: *const u64,
+    ) {
+        let on_change =
+            unsafe { &*(client_info as *const Box<dyn Fn(Vec<PathBuf>) + Send + Sync>) };
+        // Without `kFSEventStreamCreateFlagUseCFTypes` the paths are C strings.
+        let paths = unsafe {
+            std::slice::from_raw_parts(event_paths as *const *const c_char, event_count)
+                .iter()
+                .map(|path| PathBuf::from(CStr::from_ptr(*path).to_string_lossy().into_owned()))
+                .collect()
+        };
+        on_change(paths);
+    }
+
+    let dir = CString::new(dir.as_os_str().as_bytes())
+        .map_err(|_| anyhow!("{} contains a NUL byte", dir.display()))?;
+    let callback: Box<Box<dyn Fn(Vec<PathBuf>) + Send + Sync>> = Box::new(Box::new(on_change));
+    let client_info = Box::into_raw(callback);
+    let context = FsEventStreamContext {
+        version: 0,
+        info: client_info.cast(),
+        retain: ptr::null(),
+        release: ptr::null(),
+        copy_description: ptr::null(),
+    };
+
+    let started = unsafe {
+        let path = CFStringCreateWithCString(ptr::null(), dir.as_ptr(), STRING_ENCODING_UTF8);
+        let paths = CFArrayCreate(ptr::null(), &path, 1, ptr::addr_of!(kCFTypeArrayCallBacks));
+        CFRelease(path);
+        let stream = FSEventStreamCreate(
+            ptr::null(),
+            notify,
+            &context,
+            paths,
+            EVENT_ID_SINCE_NOW,
+            latency,
+            CREATE_FLAGS,
+        );
+        CFRelease(paths);
+
+        if stream.is_null() {
+            false
+        } else {
+            let queue = dispatch_queue_create(c"whisperbar.fs-events".as_ptr(), ptr::null());
+            FSEventStreamSetDispatchQueue(stream, queue);
+            let started = FSEventStreamStart(stream);
+            if !started {
+                FSEventStreamInvalidate(stream);
+                FSEventStreamRelease(stream);
+            }
+            started
+        }
+    };
+
+    if !started {
+        drop(unsafe { Box::from_raw(client_info) });
+        return Err(anyhow!(
+            "FSEvents stream for {} failed to start",
+            dir.to_string_lossy()
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,126 @@
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Whisper only looks at the last ~224 prompt tokens; stay well below that.
+const MAX_PROMPT_CHARS: usize = 400;
+
+const TITLE_STOPWORDS: &[&str] = &[
+    "and", "the", "for", "with", "from", "about", "into", "weekly", "daily", "meeting", "sync",
+    "call", "chat", "review", "update", "reunião", "com", "para", "sobre", "semanal",
+];
+
+/// The calendar event a session was started for, as sent by the UI.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CalendarEvent {
+    pub title: String,
+    /// Display names or email addresses.
+    #[serde(default)]
+    pub attendees: Vec<String>,
+    /// Scheduled end, RFC 3339 (`2026-03-02T15:30:00-03:00`).
+    #[serde(default)]
+    pub end: Option<String>,
+}
+
+/// What happens once a linked meeting is past its scheduled end by the
+/// configured grace period.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MeetingEndAction {
+    #[default]
+    Off,
+    /// Raise a `meetingEnded` warning asking whether to stop.
+    Prompt,
+    /// Stop and save the recording.
+    Stop,
+}
+
+impl CalendarEvent {
+    pub fn end_time(&self) -> anyhow::Result<Option<DateTime<Utc>>> {
+        self.end
+            .as_deref()
+            .filter(|end| !end.trim().is_empty())
+            .map(|end| {
+                DateTime::parse_from_rfc3339(end.trim())
+                    .map(|end| end.with_timezone(&Utc))
+                    .with_context(|| format!("invalid calendar event end `{end}`"))
+            })
+            .transpose()
+    }
+}
+
+/// Builds the worker's initial prompt from the event: attendee names first,
+/// then the title words that are likely names or jargon. Returns `None` when
+/// nothing useful is left.
+pub fn initial_prompt(event: &CalendarEvent) -> Option<String> {
+    let mut terms: Vec<String> = Vec::new();
+    let mut push = |term: String| {
+        if !term.is_empty()
+            && !terms
+                .iter()
+                .any(|existing| existing.eq_ignore_ascii_case(&term))
+        {
+            terms.push(term);
+        }
+    };
+
+    for attendee in &event.attendees {
+        push(attendee_name(attendee));
+    }
+
+    for word in event
+        .title
+        .split(|c: char| !(c.is_alphanumeric() || c == '-' || c == '\''))
+    {
+        let word = word.trim_matches(|c: char| c == '-' || c == '\'');
+        if word.chars().count() < 3 || word.chars().all(|c| c.is_ascii_digit()) {
+            continue;
+        }
+        if TITLE_STOPWORDS.contains(&word.to_lowercase().as_str()) {
+            continue;
+        }
+        push(word.to_string());
+    }
+
+    let mut prompt = String::new();
+    for term in terms {
+        let separator = if prompt.is_empty() { "" } else { ", " };
+        if prompt.len() + separator.len() + term.len() > MAX_PROMPT_CHARS {
+            break;
+        }
+        prompt.push_str(separator);
+        prompt.push_str(&term);
+    }
+
+    (!prompt.is_empty()).then_some(prompt)
+}
+
+/// `"Ana Souza <ana@x.com>"` -> `Ana Souza`; `"joao.silva@x.com"` -> `Joao Silva`.
+fn attendee_name(raw: &str) -> String {
+    let raw = raw.trim();
+    let display = raw
+        .split('<')
+        .next()
+        .unwrap_or(raw)
+        .trim()
+        .trim_matches('"');
+    if !display.is_empty() && !display.contains('@') {
+        return display.to_string();
+    }
+
+    let address = raw.trim_matches(|c| c == '<' || c == '>');
+    let local = address.split('@').next().unwrap_or_default();
+    local
+        .split(['.', '_', '-', '+'])
+        .filter(|part| !part.is_empty() && !part.chars().all(|c| c.is_ascii_digit()))
+        .map(|part| {
+            let mut chars = part.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
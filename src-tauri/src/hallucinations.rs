@@ -0,0 +1,198 @@
+use std::collections::VecDeque;
+
+use crate::{app_state::TranscriptSegment, audio::AudioSource};
+
+/// Lines Whisper writes over silence or noise, learned from subtitled
+/// videos, written the way `words` leaves them. A segment is dropped only
+/// when it says nothing else.
+const STOCK_PHRASES: [&str; 14] = [
+    "thanks for watching",
+    "thank you for watching",
+    "thank you so much for watching",
+    "thanks for watching and see you next time",
+    "please subscribe",
+    "like and subscribe",
+    "subscribe to my channel",
+    "dont forget to like and subscribe",
+    "subtitles by the amaraorg community",
+    "transcription by castingwords",
+    "obrigado por assistir",
+    "obrigada por assistir",
+    "inscrevase no canal",
+    "legendas pela comunidade amaraorg",
+];
+
+/// A phrase of up to this many words repeated back to back...
+const MAX_LOOP_WORDS: usize = 4;
+/// ...at least this many times is the decoder looping...
+const MIN_LOOP_REPEATS: usize = 4;
+/// ...when the loop is at least this share of the segment's words. "No, no,
+/// no, no. Don't merge that yet." is someone talking.
+const MIN_LOOP_SHARE: f64 = 0.75;
+
+/// A line with at least this many words that matches one of the last
+/// `RECENT_LINES` kept from the same source is a repeat, not new speech.
+const MIN_REPEAT_WORDS: usize = 3;
+const RECENT_LINES: usize = 3;
+
+/// Faster than anyone speaks; text this dense for its time span was not
+/// in the audio.
+const MAX_CHARS_PER_SECOND: f64 = 30.0;
+/// Spans shorter than this are too coarse to judge speed by.
+const MIN_RATE_SECONDS: f64 = 0.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reason {
+    StockPhrase,
+    Loop,
+    RepeatedLine,
+    TooFast,
+}
+
+impl Reason {
+    pub fn describe(self) -> &'static str {
+        match self {
+            Reason::StockPhrase => "a stock phrase Whisper writes over silence",
+            Reason::Loop => "the same words repeated over and over",
+            Reason::RepeatedLine => "a repeat of a line just transcribed",
+            Reason::TooFast => "more text than could be spoken in its time",
+        }
+    }
+}
+
+/// Checks incoming segments for the usual Whisper hallucinations. Holds the
+/// last few kept lines per source, so one filter covers one session.
+#[derive(Debug, Default)]
+pub struct Filter {
+    recent: VecDeque<(Option<AudioSource>, String)>,
+}
+
+impl Filter {
+    /// Why `segment` should be dropped, or `None` to keep it. A kept
+    /// segment is remembered for the repeated-line check.
+    pub fn check(&mut self, segment: &TranscriptSegment) -> Option<Reason> {
+        let words = words(&segment.text);
+        if words.is_empty() {
+            return None;
+        }
+        let normalized = words.join(" ");
+        let compact = words.concat();
+
+        if STOCK_PHRASES
+            .iter()
+            .any(|phrase| phrase.replace(' ', "") == compact)
+        {
+            return Some(Reason::StockPhrase);
+        }
+        if loop_words(&words) as f64 >= words.len() as f64 * MIN_LOOP_SHARE {
+            return Some(Reason::Loop);
+        }
+        if words.len() >= MIN_REPEAT_WORDS
+            && self
+                .recent
+                .iter()
+                .any(|(source, text)| *source == segment.source && *text == normalized)
+        {
+            return Some(Reason::RepeatedLine);
+        }
+        if let (Some(start), Some(end)) = (segment.start, segment.end) {
+            let seconds = end - start;
+            let chars = segment.text.chars().filter(|c| !c.is_whitespace()).count();
+            if seconds >= MIN_RATE_SECONDS && chars as f64 / seconds > MAX_CHARS_PER_SECOND {
+                return Some(Reason::TooFast);
+            }
+        }
+
+        if self.recent.len() == RECENT_LINES {
+            self.recent.pop_front();
+        }
+        self.recent.push_back((segment.source, normalized));
+        None
+    }
+}
+
+/// Lowercase words with punctuation dropped, so `Thanks for watching!` and
+/// `thanks for watching` compare equal.
+fn words(text: &str) -> Vec<String> {
+    text.split_whitespace()
+        .map(|word| {
+            word.chars()
+                .filter(|c| c.is_alphanumeric())
+                .flat_map(char::to_lowercase)
+                .collect::<String>()
+        })
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+/// How many words the longest loop covers: a run of 1 to `MAX_LOOP_WORDS`
+/// words repeated back to back at least `MIN_LOOP_REPEATS` times. 0 without
+/// one.
+fn loop_words(words: &[String]) -> usize {
+    let mut longest = 0;
+    for size in 1..=MAX_LOOP_WORDS {
+        for start in 0..words.len().saturating_sub(size * MIN_LOOP_REPEATS - 1) {
+            let phrase = &words[start..start + size];
+            let repeats = words[start..]
+                .chunks_exact(size)
+                .take_while(|chunk| *chunk == phrase)
+                .count();
+            if repeats >= MIN_LOOP_REPEATS {
+                longest = longest.max(repeats * size);
+            }
+        }
+    }
+    longest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(text: &str) -> TranscriptSegment {
+        TranscriptSegment {
+            text: text.to_string(),
+            speaker: None,
+            source: None,
+            paragraph: false,
+            start: None,
+            end: None,
+            words: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn a_segment_that_is_one_loop_is_dropped() {
+        let mut filter = Filter::default();
+        assert_eq!(
+            filter.check(&segment("I'm going. I'm going. I'm going. I'm going.")),
+            Some(Reason::Loop)
+        );
+        assert_eq!(
+            filter.check(&segment("you you you you you you you")),
+            Some(Reason::Loop)
+        );
+    }
+
+    #[test]
+    fn speech_that_repeats_a_word_is_kept() {
+        let mut filter = Filter::default();
+        assert_eq!(
+            filter.check(&segment("No, no, no, no. Don't merge that yet.")),
+            None
+        );
+        assert_eq!(
+            filter.check(&segment("Não, não, não, não. Espera um pouco, pessoal.")),
+            None
+        );
+    }
+
+    #[test]
+    fn stock_phrases_are_dropped() {
+        let mut filter = Filter::default();
+        assert_eq!(
+            filter.check(&segment("Thanks for watching!")),
+            Some(Reason::StockPhrase)
+        );
+    }
+}
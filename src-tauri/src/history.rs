@@ -0,0 +1,584 @@
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Context};
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+use crate::{
+    app_state::{
+        emit_state, update_session, AppStatus, SessionNote, SharedState, TranscriptSegment,
+    },
+    errors::{self, ErrorKind},
+    memory, models, recording_audio, scripts, search, transcript_file, usage, worker,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryEntry {
+    pub id: String,
+    pub path: String,
+    /// RFC 3339 local time the recording was saved.
+    pub created_at: String,
+    pub model_id: String,
+    pub language: String,
+    #[serde(default)]
+    pub mic_device: Option<String>,
+    #[serde(default)]
+    pub diarization_enabled: bool,
+    /// Seconds of audio covered by the transcript, when segment times are known.
+    #[serde(default)]
+    pub duration_seconds: Option<f64>,
+    #[serde(default)]
+    pub word_count: usize,
+    #[serde(default)]
+    pub rating: Option<SessionRating>,
+    /// Session recording saved next to the transcript, if audio was kept.
+    #[serde(default)]
+    pub audio_path: Option<String>,
+}
+
+/// The user's own verdict on a transcript, kept alongside the settings that
+/// produced it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionRating {
+    /// 1 to 5.
+    pub stars: u8,
+    pub note: Option<String>,
+    pub rated_at: String,
+}
+
+/// Recorded sessions, newest first. Mirrors `history.json` in the app data
+/// directory; every mutation is written through immediately.
+pub struct HistoryIndex {
+    pub entries: Vec<HistoryEntry>,
+}
+
+impl HistoryIndex {
+    pub fn load(app_data_dir: &Path) -> Self {
+        let entries = fs::read_to_string(index_path(app_data_dir))
+            .ok()
+            .and_then(|raw| serde_json::from_str::<Vec<HistoryEntry>>(&raw).ok())
+            .unwrap_or_default();
+
+        Self { entries }
+    }
+
+    fn save(&self, app_data_dir: &Path) -> anyhow::Result<()> {
+        fs::create_dir_all(app_data_dir)?;
+        let json = serde_json::to_string_pretty(&self.entries)?;
+        fs::write(index_path(app_data_dir), json)?;
+        Ok(())
+    }
+}
+
+fn index_path(app_data_dir: &Path) -> PathBuf {
+    app_data_dir.join("history.json")
+}
+
+/// Lists history, newest first. Transcripts in the output directory that are
+/// not indexed yet (saved before the index existed, or copied in by hand) are
+/// imported first.
+pub async fn list(state: &SharedState) -> Vec<HistoryEntry> {
+    let _ = import_untracked(state).await;
+    state.history.lock().await.entries.clone()
+}
+
+pub async fn record_session(
+    app: &AppHandle,
+    state: &SharedState,
+    path: &Path,
+    audio_path: Option<&Path>,
+    segments: &[TranscriptSegment],
+    notes: &[SessionNote],
+) {
+    let model_id = state.settings.lock().await.selected_model_id.clone();
+    usage::record(state, &model_id, duration_seconds(segments));
+
+    insert_entry(app, state, path, audio_path, segments, notes).await;
+}
+
+/// Indexes a saved transcript without counting it toward usage; a
+/// re-transcription covers audio that was already counted once.
+async fn insert_entry(
+    app: &AppHandle,
+    state: &SharedState,
+    path: &Path,
+    audio_path: Option<&Path>,
+    segments: &[TranscriptSegment],
+    notes: &[SessionNote],
+) {
+    let (model_id, language, mic_device, diarization_enabled) = {
+        let settings = state.settings.lock().await;
+        (
+            settings.selected_model_id.clone(),
+            settings.language.clone(),
+            settings.selected_mic_device.clone(),
+            settings.diarization_enabled,
+        )
+    };
+
+    let now = Local::now();
+    let entry = HistoryEntry {
+        id: now.format("%Y%m%d-%H%M%S%3f").to_string(),
+        path: path.display().to_string(),
+        created_at: now.to_rfc3339(),
+        model_id,
+        language,
+        mic_device,
+        diarization_enabled,
+        duration_seconds: duration_seconds(segments),
+        word_count: segment_word_count(segments),
+        rating: None,
+        audio_path: audio_path.map(|path| path.display().to_string()),
+    };
+
+    let (path, created_at) = (entry.path.clone(), entry.created_at.clone());
+    {
+        let mut history = state.history.lock().await;
+        history.entries.insert(0, entry);
+        let _ = history.save(&state.paths.app_data_dir);
+    }
+
+    search::index(state, &path, &created_at, &search_body(segments, notes)).await;
+    emit_history(app, state).await;
+}
+
+/// Adds a session received from someone else (`bundles::import`), sorted in
+/// by when it was recorded.
+pub async fn add_imported(
+    app: &AppHandle,
+    state: &SharedState,
+    entry: HistoryEntry,
+    text: &str,
+) -> anyhow::Result<()> {
+    let (path, created_at) = (entry.path.clone(), entry.created_at.clone());
+    {
+        let mut history = state.history.lock().await;
+        history.entries.push(entry);
+        history
+            .entries
+            .sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        history.save(&state.paths.app_data_dir)?;
+    }
+
+    search::index(state, &path, &created_at, text).await;
+    emit_history(app, state).await;
+    Ok(())
+}
+
+/// Refreshes the stats of the entry saved at `path` after its transcript was
+/// edited and rewritten.
+pub async fn update_stats(
+    app: &AppHandle,
+    state: &SharedState,
+    path: &Path,
+    segments: &[TranscriptSegment],
+    notes: &[SessionNote],
+) {
+    let path = path.display().to_string();
+    let created_at = {
+        let mut history = state.history.lock().await;
+        let Some(entry) = history.entries.iter_mut().find(|entry| entry.path == path) else {
+            return;
+        };
+        entry.word_count = segment_word_count(segments);
+        entry.duration_seconds = duration_seconds(segments);
+        let created_at = entry.created_at.clone();
+        let _ = history.save(&state.paths.app_data_dir);
+        created_at
+    };
+
+    search::index(state, &path, &created_at, &search_body(segments, notes)).await;
+    emit_history(app, state).await;
+}
+
+/// Brings entries in line with transcripts changed outside the app, such as
+/// a file edited and saved in another editor: the word count and search
+/// index are rebuilt from the file on disk. A transcript deleted by hand drops
+/// out of search but keeps its entry, so its settings and rating survive.
+/// Paths that are not history entries are ignored.
+pub async fn sync_from_disk(app: &AppHandle, state: &SharedState, paths: &[PathBuf]) {
+    let mut changed = false;
+    for path in paths {
+        let path = path.display().to_string();
+        let Some(created_at) = state
+            .history
+            .lock()
+            .await
+            .entries
+            .iter()
+            .find(|entry| entry.path == path)
+            .map(|entry| entry.created_at.clone())
+        else {
+            continue;
+        };
+
+        let text = match tokio::fs::read_to_string(&path).await {
+            Ok(text) => text,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                search::remove(state, &path).await;
+                continue;
+            }
+            Err(_) => continue,
+        };
+
+        let word_count = markdown_word_count(&text);
+        {
+            let mut history = state.history.lock().await;
+            if let Some(entry) = history
+                .entries
+                .iter_mut()
+                .find(|entry| entry.path == path && entry.word_count != word_count)
+            {
+                entry.word_count = word_count;
+                let _ = history.save(&state.paths.app_data_dir);
+                changed = true;
+            }
+        }
+        search::index(state, &path, &created_at, &text).await;
+    }
+
+    if changed {
+        emit_history(app, state).await;
+    }
+}
+
+pub async fn open(state: &SharedState, id: &str) -> anyhow::Result<()> {
+    let entry = find(state, id).await?;
+    std::process::Command::new("open")
+        .arg(&entry.path)
+        .spawn()
+        .with_context(|| format!("failed opening {}", entry.path))?;
+    Ok(())
+}
+
+/// Copies the transcript into `~/Downloads` and reveals it in Finder.
+pub async fn export(state: &SharedState, id: &str) -> anyhow::Result<String> {
+    let entry = find(state, id).await?;
+    export_file(Path::new(&entry.path)).await
+}
+
+/// Copies a transcript file into `~/Downloads` and reveals it in Finder.
+pub async fn export_file(source: &Path) -> anyhow::Result<String> {
+    let file_name = source
+        .file_name()
+        .ok_or_else(|| anyhow!("invalid transcript path {}", source.display()))?;
+
+    let downloads_dir =
+        dirs::download_dir().ok_or_else(|| anyhow!("unable to locate Downloads directory"))?;
+    let file_name = Path::new(file_name);
+    let stem = file_name
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "Transcript".to_string());
+    let extension = file_name
+        .extension()
+        .map(|extension| extension.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "md".to_string());
+    // An earlier export of the same transcript is kept, not replaced.
+    let (destination, _) =
+        transcript_file::create_unique(&downloads_dir, &stem, &extension).await?;
+    tokio::fs::copy(source, &destination)
+        .await
+        .with_context(|| format!("failed exporting {}", source.display()))?;
+
+    let _ = std::process::Command::new("open")
+        .arg("-R")
+        .arg(&destination)
+        .spawn();
+
+    Ok(destination.display().to_string())
+}
+
+/// Removes the session from the index and deletes its transcript file (and
+/// recording, if one was kept).
+pub async fn delete(app: &AppHandle, state: &SharedState, id: &str) -> anyhow::Result<()> {
+    state.paths.ensure_writable()?;
+    let removed = {
+        let mut history = state.history.lock().await;
+        let position = history
+            .entries
+            .iter()
+            .position(|entry| entry.id == id)
+            .ok_or_else(|| anyhow!("history entry {id} does not exist"))?;
+        let removed = history.entries.remove(position);
+        history.save(&state.paths.app_data_dir)?;
+        removed
+    };
+
+    for path in std::iter::once(&removed.path).chain(removed.audio_path.as_ref()) {
+        match tokio::fs::remove_file(path).await {
+            Ok(()) => {}
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {}
+            Err(error) => return Err(error).with_context(|| format!("failed deleting {path}")),
+        }
+    }
+
+    search::remove(state, &removed.path).await;
+    emit_history(app, state).await;
+    Ok(())
+}
+
+/// Runs the selected model over the recording kept with session `id` and
+/// saves the result as a new session, `<original name>-retranscribed`, with
+/// its own copy of the audio. The original transcript is left as it was.
+/// Returns the new transcript's path.
+pub async fn retranscribe(
+    app: &AppHandle,
+    state: &SharedState,
+    id: &str,
+) -> anyhow::Result<String> {
+    state.paths.ensure_writable()?;
+    let entry = find(state, id).await?;
+    let audio = entry
+        .audio_path
+        .as_deref()
+        .map(Path::new)
+        .filter(|audio| audio.is_file())
+        .ok_or_else(|| anyhow!("this session has no kept recording to transcribe again"))?;
+
+    let selected_model_id = state.settings.lock().await.selected_model_id.clone();
+    let memory_warning = match models::find_model(&selected_model_id) {
+        Some(model) => memory::preflight(&model)?,
+        None => None,
+    };
+
+    // Claimed under one lock so a recording or install cannot start in
+    // between; released whatever the outcome.
+    {
+        let mut session = state.session.lock().await;
+        if !matches!(session.status, AppStatus::Ready | AppStatus::Idle)
+            || session.countdown.is_some()
+        {
+            return Err(anyhow!(
+                "a session can only be transcribed again while idle"
+            ));
+        }
+        session.status = AppStatus::Transcribing;
+        session.status_message = "Transcribing recording".to_string();
+    }
+    emit_state(app, state).await;
+    if let Some(message) = memory_warning {
+        errors::report(app, state, ErrorKind::LowMemory, message, |_| {}).await;
+    }
+
+    let segments = worker::transcribe_recording(state, audio).await;
+    update_session(app, state, |session| {
+        session.status = AppStatus::Ready;
+        session.status_message = "Ready".to_string();
+    })
+    .await;
+    let segments = segments?;
+    if segments.is_empty() {
+        return Err(anyhow!("no speech was recognized in the recording"));
+    }
+
+    let (options, naming) = {
+        let settings = state.settings.lock().await;
+        (settings.render_options(), settings.file_naming())
+    };
+    let text = naming.frontmatter(Local::now()).unwrap_or_default()
+        + &transcript_file::render_markdown(&segments, &[], &[], options);
+    let stem = Path::new(&entry.path)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let path = transcript_file::save_imported(&format!("{stem}-retranscribed"), &text).await?;
+    let audio_copy = recording_audio::copy_alongside(audio, &path).await?;
+
+    insert_entry(app, state, &path, Some(&audio_copy), &segments, &[]).await;
+    Ok(path.display().to_string())
+}
+
+pub async fn rate(
+    app: &AppHandle,
+    state: &SharedState,
+    id: &str,
+    stars: u8,
+    note: Option<String>,
+) -> anyhow::Result<()> {
+    state.paths.ensure_writable()?;
+    if !(1..=5).contains(&stars) {
+        return Err(anyhow!("rating must be between 1 and 5 stars"));
+    }
+
+    let note = note
+        .map(|note| note.trim().to_string())
+        .filter(|note| !note.is_empty());
+
+    {
+        let mut history = state.history.lock().await;
+        let entry = history
+            .entries
+            .iter_mut()
+            .find(|entry| entry.id == id)
+            .ok_or_else(|| anyhow!("history entry {id} does not exist"))?;
+        entry.rating = Some(SessionRating {
+            stars,
+            note,
+            rated_at: Local::now().to_rfc3339(),
+        });
+        history.save(&state.paths.app_data_dir)?;
+    }
+
+    emit_history(app, state).await;
+    Ok(())
+}
+
+pub async fn find(state: &SharedState, id: &str) -> anyhow::Result<HistoryEntry> {
+    state
+        .history
+        .lock()
+        .await
+        .entries
+        .iter()
+        .find(|entry| entry.id == id)
+        .cloned()
+        .ok_or_else(|| anyhow!("history entry {id} does not exist"))
+}
+
+async fn import_untracked(state: &SharedState) -> anyhow::Result<()> {
+    let output_dir = transcript_file::output_dir()?;
+    let mut dir = match tokio::fs::read_dir(&output_dir).await {
+        Ok(dir) => dir,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(error) => return Err(error.into()),
+    };
+
+    let known = {
+        let history = state.history.lock().await;
+        history
+            .entries
+            .iter()
+            .map(|entry| entry.path.clone())
+            .collect::<HashSet<_>>()
+    };
+
+    let mut imported = Vec::new();
+    while let Some(item) = dir.next_entry().await? {
+        let path = item.path();
+        let path_str = path.display().to_string();
+        if known.contains(&path_str) {
+            continue;
+        }
+
+        // Only files named by the default template.
+        let Some(stem) = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .filter(|_| path.extension().is_some_and(|ext| ext == "md"))
+        else {
+            continue;
+        };
+        let Some(created) = default_name_time(stem) else {
+            continue;
+        };
+
+        let text = tokio::fs::read_to_string(&path).await.unwrap_or_default();
+        let created_at = created.to_rfc3339();
+        search::index(state, &path_str, &created_at, &text).await;
+        imported.push(HistoryEntry {
+            id: format!("imported-{stem}"),
+            path: path_str,
+            created_at,
+            model_id: "unknown".to_string(),
+            language: "unknown".to_string(),
+            mic_device: None,
+            diarization_enabled: false,
+            duration_seconds: None,
+            word_count: markdown_word_count(&text),
+            rating: None,
+            audio_path: None,
+        });
+    }
+
+    if imported.is_empty() {
+        return Ok(());
+    }
+
+    let mut history = state.history.lock().await;
+    history.entries.extend(imported);
+    history
+        .entries
+        .sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    history.save(&state.paths.app_data_dir)
+}
+
+/// When a transcript named by the default template was saved:
+/// `Transcript-2026-02-27-14-35-08`, or `Transcript-2026-02-27-14-35` from
+/// before names had seconds, either with the `-2` a taken name gets.
+fn default_name_time(stem: &str) -> Option<DateTime<Local>> {
+    let raw = stem.strip_prefix("Transcript-")?;
+    [("%Y-%m-%d-%H-%M-%S", 19), ("%Y-%m-%d-%H-%M", 16)]
+        .iter()
+        .find_map(|&(format, width)| {
+            let (timestamp, suffix) = raw.split_at_checked(width)?;
+            let suffixed = suffix.is_empty()
+                || suffix
+                    .strip_prefix('-')
+                    .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()));
+            suffixed
+                .then(|| NaiveDateTime::parse_from_str(timestamp, format).ok())
+                .flatten()
+        })
+        .and_then(|naive| Local.from_local_datetime(&naive).earliest())
+}
+
+fn duration_seconds(segments: &[TranscriptSegment]) -> Option<f64> {
+    segments
+        .iter()
+        .filter_map(|segment| segment.end)
+        .reduce(f64::max)
+}
+
+pub fn segment_word_count(segments: &[TranscriptSegment]) -> usize {
+    segments
+        .iter()
+        .map(|segment| scripts::word_count(&segment.text))
+        .sum()
+}
+
+/// What the search index holds for a session: the transcript followed by
+/// the notes typed while recording.
+fn search_body(segments: &[TranscriptSegment], notes: &[SessionNote]) -> String {
+    std::iter::once(transcript_file::render_plain(segments))
+        .chain(notes.iter().map(|note| note.text.clone()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Counts words in a saved transcript, skipping its frontmatter and
+/// post-processing sections, `[HH:MM:SS]` prefixes and `**Speaker N:**`
+/// labels.
+fn markdown_word_count(text: &str) -> usize {
+    let (_, body) = transcript_file::split_frontmatter(text);
+    let (text, _) = transcript_file::split_appendix(body);
+    let mut count = 0;
+    let mut in_label = false;
+    for token in text.split_whitespace() {
+        if token.starts_with("**") {
+            in_label = !token.ends_with(":**");
+            continue;
+        }
+        if in_label {
+            in_label = !token.ends_with(":**");
+            continue;
+        }
+        if token.starts_with('[') && token.ends_with(']') {
+            continue;
+        }
+        count += scripts::token_word_count(token);
+    }
+    count
+}
+
+async fn emit_history(app: &AppHandle, state: &SharedState) {
+    let entries = list(state).await;
+    let _ = app.emit("whisperbar://history", entries);
+}
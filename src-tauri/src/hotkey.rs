@@ -0,0 +1,111 @@
+use std::{ffi::c_void, ptr};
+
+use anyhow::anyhow;
+
+type OsStatus = i32;
+type EventHandler =
+    extern "C" fn(next: *mut c_void, event: *mut c_void, user_data: *mut c_void) -> OsStatus;
+
+const fn four_cc(code: &[u8; 4]) -> u32 {
+    u32::from_be_bytes(*code)
+}
+
+const EVENT_CLASS_KEYBOARD: u32 = four_cc(b"keyb");
+const EVENT_HOT_KEY_PRESSED: u32 = 5;
+
+/// Carbon modifier masks (`cmdKey`, `shiftKey`, `optionKey`).
+pub const COMMAND: u32 = 1 << 8;
+pub const SHIFT: u32 = 1 << 9;
+pub const OPTION: u32 = 1 << 11;
+
+/// `kVK_ANSI_S`.
+pub const KEY_S: u32 = 0x01;
+
+#[repr(C)]
+struct EventTypeSpec {
+    event_class: u32,
+    event_kind: u32,
+}
+
+#[repr(C)]
+struct EventHotKeyId {
+    signature: u32,
+    id: u32,
+}
+
+#[link(name = "Carbon", kind = "framework")]
+extern "C" {
+    fn GetApplicationEventTarget() -> *mut c_void;
+    fn InstallEventHandler(
+        target: *mut c_void,
+        handler: EventHandler,
+        type_count: usize,
+        types: *const EventTypeSpec,
+        user_data: *mut c_void,
+        handler_ref: *mut *mut c_void,
+    ) -> OsStatus;
+    fn RegisterEventHotKey(
+        key_code: u32,
+        modifiers: u32,
+        id: EventHotKeyId,
+        target: *mut c_void,
+        options: u32,
+        hot_key_ref: *mut *mut c_void,
+    ) -> OsStatus;
+}
+
+/// Calls `on_press` whenever `key_code` with `modifiers` is pressed, whichever
+/// app is frontmost. Needs no Accessibility permission. Must be called on the
+/// main thread; the handler stays installed for the rest of the process and
+/// runs on the main thread too, so it should only hand off work.
+pub fn register<F>(key_code: u32, modifiers: u32, on_press: F) -> anyhow::Result<()>
+where
+    F: Fn() + Send + Sync + 'static,
+{
+    extern "C" fn pressed(
+        _next: *mut c_void,
+        _event: *mut c_void,
+        user_data: *mut c_void,
+    ) -> OsStatus {
+        let on_press = unsafe { &*(user_data as *const Box<dyn Fn() + Send + Sync>) };
+        on_press();
+        0
+    }
+
+    let callback: Box<Box<dyn Fn() + Send + Sync>> = Box::new(Box::new(on_press));
+    let user_data = Box::into_raw(callback);
+    let event_type = EventTypeSpec {
+        event_class: EVENT_CLASS_KEYBOARD,
+        event_kind: EVENT_HOT_KEY_PRESSED,
+    };
+
+    let target = unsafe { GetApplicationEventTarget() };
+    let status = unsafe {
+        InstallEventHandler(
+            target,
+            pressed,
+            1,
+            &event_type,
+            user_data.cast(),
+            ptr::null_mut(),
+        )
+    };
+    if status != 0 {
+        drop(unsafe { Box::from_raw(user_data) });
+        return Err(anyhow!("installing the shortcut handler failed ({status})"));
+    }
+
+    let mut hot_key = ptr::null_mut();
+    let id = EventHotKeyId {
+        signature: four_cc(b"WBar"),
+        id: key_code,
+    };
+    // The handler stays installed even if this fails, so its data stays too.
+    let status = unsafe { RegisterEventHotKey(key_code, modifiers, id, target, 0, &mut hot_key) };
+    if status != 0 {
+        return Err(anyhow!(
+            "registering the global shortcut failed ({status}); another app may already use it"
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,163 @@
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+
+/// Weight of the newest sample in the smoothed transfer speed.
+const SPEED_SMOOTHING: f64 = 0.3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum InstallPhaseId {
+    Venv,
+    Packages,
+    Model,
+}
+
+impl InstallPhaseId {
+    fn label(self) -> &'static str {
+        match self {
+            InstallPhaseId::Venv => "Create Python environment",
+            InstallPhaseId::Packages => "Install Python packages",
+            InstallPhaseId::Model => "Download model files",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum InstallPhaseStatus {
+    Pending,
+    Active,
+    Done,
+    /// Already installed; nothing to do.
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallPhase {
+    pub id: InstallPhaseId,
+    pub label: String,
+    pub status: InstallPhaseStatus,
+    /// 0.0 to 1.0 within this phase.
+    pub progress: f32,
+    /// Package or model file currently being worked on.
+    pub file: Option<String>,
+    pub bytes_done: Option<u64>,
+    pub bytes_total: Option<u64>,
+    /// Part of `bytes_done` an interrupted earlier install had already
+    /// downloaded; speed and ETA only count what this run transfers.
+    pub bytes_resumed: Option<u64>,
+    pub bytes_per_second: Option<f64>,
+    pub eta_seconds: Option<f64>,
+    #[serde(skip)]
+    last_sample: Option<(Instant, u64)>,
+}
+
+impl InstallPhase {
+    fn new(id: InstallPhaseId) -> Self {
+        Self {
+            id,
+            label: id.label().to_string(),
+            status: InstallPhaseStatus::Pending,
+            progress: 0.0,
+            file: None,
+            bytes_done: None,
+            bytes_total: None,
+            bytes_resumed: None,
+            bytes_per_second: None,
+            eta_seconds: None,
+            last_sample: None,
+        }
+    }
+}
+
+/// Structured install progress, one entry per bootstrap phase in run order.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallStatus {
+    pub phases: Vec<InstallPhase>,
+}
+
+/// One `phase` event from `bootstrap.py`.
+#[derive(Debug, Clone)]
+pub struct PhaseUpdate {
+    pub phase: InstallPhaseId,
+    pub status: InstallPhaseStatus,
+    pub progress: f32,
+    pub file: Option<String>,
+    pub bytes_done: Option<u64>,
+    pub bytes_total: Option<u64>,
+    pub bytes_resumed: Option<u64>,
+}
+
+impl Default for InstallStatus {
+    fn default() -> Self {
+        Self {
+            phases: [
+                InstallPhaseId::Venv,
+                InstallPhaseId::Packages,
+                InstallPhaseId::Model,
+            ]
+            .into_iter()
+            .map(InstallPhase::new)
+            .collect(),
+        }
+    }
+}
+
+impl InstallStatus {
+    /// Applies a phase event. Speed and ETA are derived here from successive
+    /// byte counts so the script only has to report totals.
+    pub fn apply(&mut self, update: PhaseUpdate) {
+        let Some(phase) = self
+            .phases
+            .iter_mut()
+            .find(|phase| phase.id == update.phase)
+        else {
+            return;
+        };
+
+        phase.status = update.status;
+        phase.progress = update.progress.clamp(0.0, 1.0);
+        if update.file.is_some() {
+            phase.file = update.file;
+        }
+        if update.bytes_resumed.is_some() {
+            phase.bytes_resumed = update.bytes_resumed;
+        }
+
+        if update.status != InstallPhaseStatus::Active {
+            phase.file = None;
+            phase.bytes_per_second = None;
+            phase.eta_seconds = None;
+            phase.last_sample = None;
+            if let Some(total) = phase.bytes_total {
+                phase.bytes_done = Some(total);
+            }
+            return;
+        }
+
+        let (Some(done), Some(total)) = (update.bytes_done, update.bytes_total) else {
+            return;
+        };
+        let now = Instant::now();
+        if let Some((at, previous)) = phase.last_sample {
+            let elapsed = now.duration_since(at).as_secs_f64();
+            if elapsed > 0.0 {
+                let sample = done.saturating_sub(previous) as f64 / elapsed;
+                let speed = match phase.bytes_per_second {
+                    Some(speed) => speed + SPEED_SMOOTHING * (sample - speed),
+                    None => sample,
+                };
+                phase.bytes_per_second = Some(speed);
+                phase.eta_seconds =
+                    (speed > 0.0).then(|| total.saturating_sub(done) as f64 / speed);
+            }
+        }
+
+        phase.last_sample = Some((now, done));
+        phase.bytes_done = Some(done);
+        phase.bytes_total = Some(total);
+    }
+}
@@ -0,0 +1,136 @@
+use std::{collections::BTreeMap, path::Path};
+
+use anyhow::{anyhow, Context};
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+/// Written into a model folder at install time with what each file should
+/// be, from the Hugging Face metadata. Folders without one (installed before
+/// it existed, or custom models) are not checked.
+pub const MANIFEST_FILE: &str = "whisperbar-manifest.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub size: u64,
+    /// Hex digest; only Git LFS files (the weights) have one.
+    pub sha256: Option<String>,
+}
+
+/// Relative path inside the model folder -> expected contents.
+pub type Manifest = BTreeMap<String, ManifestEntry>;
+
+/// A model folder whose files do not match its manifest.
+#[derive(Debug)]
+pub struct ModelCorrupted(pub String);
+
+impl std::fmt::Display for ModelCorrupted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Model corrupted, reinstall it: {}", self.0)
+    }
+}
+
+impl std::error::Error for ModelCorrupted {}
+
+pub async fn write_manifest(model_path: &Path, manifest: &Manifest) -> anyhow::Result<()> {
+    let path = model_path.join(MANIFEST_FILE);
+    tokio::fs::write(&path, serde_json::to_vec_pretty(manifest)?)
+        .await
+        .with_context(|| format!("failed writing {}", path.display()))
+}
+
+/// Missing or wrongly sized files, without reading them. Cheap enough to run
+/// before every session. Leaves the folder as it is; the reinstall removes
+/// the bad files (`remove_wrongly_sized`).
+pub fn check_sizes(model_path: &Path) -> Result<(), ModelCorrupted> {
+    let bad = wrongly_sized(model_path);
+    if bad.is_empty() {
+        Ok(())
+    } else {
+        Err(ModelCorrupted(bad.join(", ")))
+    }
+}
+
+/// Deletes the files `check_sizes` reports, before an install: its "already
+/// installed" check only looks for weights, and a truncated file must not
+/// pass it.
+pub fn remove_wrongly_sized(model_path: &Path) {
+    for name in wrongly_sized(model_path) {
+        let _ = std::fs::remove_file(model_path.join(name));
+    }
+}
+
+fn wrongly_sized(model_path: &Path) -> Vec<String> {
+    let Some(manifest) = read_manifest(model_path) else {
+        return Vec::new();
+    };
+    manifest
+        .iter()
+        .filter(|(name, entry)| {
+            !std::fs::metadata(model_path.join(name)).is_ok_and(|meta| meta.len() == entry.size)
+        })
+        .map(|(name, _)| name.clone())
+        .collect()
+}
+
+/// Checks sizes and SHA-256 digests after an install. Files that do not
+/// match are deleted, so a reinstall downloads them again instead of
+/// resuming from bad bytes. Fails with `ModelCorrupted` naming them.
+pub async fn verify(model_path: &Path) -> anyhow::Result<()> {
+    let Some(manifest) = read_manifest(model_path) else {
+        return Ok(());
+    };
+    verify_against(model_path, &manifest).await
+}
+
+/// `verify` against a manifest that is not written yet, so one is only
+/// saved for files that match it.
+pub async fn verify_against(model_path: &Path, manifest: &Manifest) -> anyhow::Result<()> {
+    let mut bad = Vec::new();
+    for (name, entry) in manifest {
+        let path = model_path.join(name);
+        let size = tokio::fs::metadata(&path).await.ok().map(|meta| meta.len());
+        let matches = match size {
+            Some(size) if size == entry.size => match &entry.sha256 {
+                Some(expected) => sha256(&path).await?.eq_ignore_ascii_case(expected),
+                None => true,
+            },
+            _ => false,
+        };
+        if !matches {
+            let _ = tokio::fs::remove_file(&path).await;
+            bad.push(name.clone());
+        }
+    }
+
+    if bad.is_empty() {
+        Ok(())
+    } else {
+        Err(ModelCorrupted(bad.join(", ")).into())
+    }
+}
+
+fn read_manifest(model_path: &Path) -> Option<Manifest> {
+    let raw = std::fs::read(model_path.join(MANIFEST_FILE)).ok()?;
+    serde_json::from_slice(&raw).ok()
+}
+
+pub async fn sha256(path: &Path) -> anyhow::Result<String> {
+    let output = Command::new("shasum")
+        .args(["-a", "256"])
+        .arg(path)
+        .output()
+        .await
+        .context("failed starting shasum")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "shasum failed for {}: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .split_whitespace()
+        .next()
+        .map(str::to_string)
+        .ok_or_else(|| anyhow!("shasum printed no digest for {}", path.display()))
+}
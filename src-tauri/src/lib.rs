@@ -1,241 +1,1647 @@
 #![cfg(target_os = "macos")]
 
+mod acronyms;
 mod app_state;
 mod audio;
 mod bootstrap;
+mod bundles;
+mod calibration;
+mod casing;
+mod chapters;
+mod cli;
+mod core_audio;
+mod denoise;
+mod device_monitor;
+mod diagnostics;
+mod dictation;
+mod engine;
+mod errors;
+mod fs_events;
+mod glossary;
+mod hallucinations;
+mod history;
+mod hotkey;
+mod install_status;
+mod integrity;
+mod links;
+mod llm;
+mod maintenance;
+mod managed;
+mod memory;
 mod models;
+mod moments;
+mod normalize;
+mod orphans;
+mod partials;
+mod pipeline_timings;
+mod postprocess;
+mod preroll;
+mod python_runtime;
+mod recording_audio;
+mod recovery;
+mod replacements;
 mod runtime_scripts;
 mod sck_audio_helper;
+mod scripts;
+mod search;
+mod snapshot_log;
+mod spellcheck;
+mod storage;
+mod text_cleanup;
+mod titles;
+mod transcript_edit;
 mod transcript_file;
+mod transcript_watch;
 mod tray;
 mod ui;
+mod usage;
+mod vad;
+mod viewer;
+mod whisper_cpp;
 mod worker;
+mod worker_log;
 
-use app_state::{save_settings, snapshot, update_state, AppSnapshot, AppStatus, SharedState};
-use audio::AudioDeviceOption;
-use models::ModelOption;
-use tauri::{ActivationPolicy, AppHandle, Manager, State};
+use app_state::{
+    update_session, update_settings, AppSnapshot, AppStatus, SharedState, TranscriptSegment,
+};
+use audio::{AudioDeviceOption, AudioSource, DeviceProfile};
+use diagnostics::Diagnostics;
+use dictation::DictationOutput;
+use errors::{ErrorKind, ErrorSeverity};
+use glossary::{CalendarEvent, MeetingEndAction};
+use history::HistoryEntry;
+use links::AutoLinkSink;
+use llm::{Llm, LlmApi};
+use managed::Integration;
+use models::{CustomModel, ModelOption};
+use partials::PartialMetrics;
+use postprocess::Postprocess;
+use sck_audio_helper::ResampleQuality;
+use search::SearchHit;
+use snapshot_log::CatchUp;
+use spellcheck::SpellingIssue;
+use storage::StorageBreakdown;
+use tauri::{ActivationPolicy, AppHandle, Manager, RunEvent, State, WebviewWindow};
+use ui::{DisplayInfo, FloatingPlacement, ScreenCorner};
 
 #[tauri::command]
-async fn get_app_state(state: State<'_, SharedState>) -> Result<AppSnapshot, String> {
-    Ok(snapshot(state.inner()).await)
+async fn get_app_state(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+) -> Result<AppSnapshot, String> {
+    Ok(app_state::latest_snapshot(&app, state.inner()).await)
+}
+
+#[tauri::command]
+async fn get_app_state_since(state: State<'_, SharedState>, seq: u64) -> Result<CatchUp, String> {
+    Ok(state.inner().snapshots.lock().await.since(seq))
+}
+
+#[tauri::command]
+async fn set_language(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    language: String,
+) -> Result<(), String> {
+    if language != "en" && language != "pt-BR" {
+        return Err("unsupported language".to_string());
+    }
+
+    update_settings(&app, state.inner(), |settings| {
+        settings.language = language;
+    })
+    .await;
+
+    state.inner().schedule_settings_save();
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_partial_update_rate(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    updates_per_second: u32,
+) -> Result<(), String> {
+    if !partials::is_valid_rate(updates_per_second) {
+        return Err(format!(
+            "partial update rate must be between 1 and {} per second",
+            partials::MAX_UPDATES_PER_SECOND
+        ));
+    }
+
+    update_settings(&app, state.inner(), |settings| {
+        settings.partial_updates_per_second = updates_per_second;
+    })
+    .await;
+
+    state.inner().schedule_settings_save();
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_separate_sources(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    enabled: bool,
+) -> Result<(), String> {
+    if state.inner().status().await == AppStatus::Recording {
+        return Err("cannot change source separation while recording".to_string());
+    }
+
+    update_settings(&app, state.inner(), |settings| {
+        settings.separate_sources = enabled;
+    })
+    .await;
+
+    state.inner().schedule_settings_save();
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_diarization(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    enabled: bool,
+) -> Result<(), String> {
+    if state.inner().status().await == AppStatus::Recording {
+        return Err("cannot change diarization while recording".to_string());
+    }
+    if enabled && !state.inner().settings.lock().await.diarization_available() {
+        return Err("Speaker labels are not available with whisper.cpp models".to_string());
+    }
+
+    update_settings(&app, state.inner(), |settings| {
+        settings.diarization_enabled = enabled;
+    })
+    .await;
+
+    state.inner().schedule_settings_save();
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_include_timestamps(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    enabled: bool,
+) -> Result<(), String> {
+    update_settings(&app, state.inner(), |settings| {
+        settings.include_timestamps = enabled;
+    })
+    .await;
+
+    state.inner().schedule_settings_save();
+
+    Ok(())
+}
+
+/// Sets what new transcripts are named after; an empty template restores
+/// the default. Files already saved keep their names.
+#[tauri::command]
+async fn set_name_template(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    template: String,
+) -> Result<(), String> {
+    let template = match template.trim() {
+        "" => transcript_file::DEFAULT_NAME_TEMPLATE.to_string(),
+        template => {
+            transcript_file::validate_name_template(template).map_err(|error| error.to_string())?;
+            template.to_string()
+        }
+    };
+    update_settings(&app, state.inner(), |settings| {
+        settings.name_template = template;
+    })
+    .await;
+
+    state.inner().schedule_settings_save();
+
+    Ok(())
+}
+
+/// Sets the LLM that titles transcripts after stop; `api: null` turns it
+/// off. `apiKey: null` keeps the saved key and `""` clears it.
+#[tauri::command]
+async fn set_title_llm(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    api: Option<LlmApi>,
+    endpoint: String,
+    model: String,
+    api_key: Option<String>,
+) -> Result<(), String> {
+    let title_llm = match api {
+        Some(api) => {
+            let api_key = match api_key {
+                Some(key) => Some(key),
+                None => state
+                    .inner()
+                    .settings
+                    .lock()
+                    .await
+                    .title_llm
+                    .as_ref()
+                    .and_then(|llm| llm.api_key.clone()),
+            };
+            let llm =
+                Llm::new(api, &endpoint, &model, api_key).map_err(|error| error.to_string())?;
+            state
+                .inner()
+                .managed
+                .check_llm(Integration::Titles, &llm)
+                .map_err(|error| error.to_string())?;
+            Some(llm)
+        }
+        None => None,
+    };
+    update_settings(&app, state.inner(), |settings| {
+        settings.title_llm = title_llm;
+    })
+    .await;
+
+    state.inner().schedule_settings_save();
+
+    Ok(())
+}
+
+/// Sets the post-processing run after stop; `api: null` turns it off.
+/// `steps: null` keeps the current steps (the default summary and action
+/// items the first time). `apiKey: null` keeps the saved key and `""` clears
+/// it.
+#[tauri::command]
+async fn set_postprocess(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    api: Option<LlmApi>,
+    endpoint: String,
+    model: String,
+    api_key: Option<String>,
+    steps: Option<String>,
+) -> Result<(), String> {
+    let config = match api {
+        Some(api) => {
+            let current = state.inner().settings.lock().await.postprocess.clone();
+            let api_key = match api_key {
+                Some(key) => Some(key),
+                None => current
+                    .as_ref()
+                    .and_then(|config| config.llm.api_key.clone()),
+            };
+            let steps = match steps {
+                Some(text) => postprocess::parse(&text).map_err(|error| error.to_string())?,
+                None => current
+                    .map(|config| config.steps)
+                    .unwrap_or_else(postprocess::default_steps),
+            };
+            let llm =
+                Llm::new(api, &endpoint, &model, api_key).map_err(|error| error.to_string())?;
+            state
+                .inner()
+                .managed
+                .check_llm(Integration::Postprocess, &llm)
+                .map_err(|error| error.to_string())?;
+            Some(Postprocess { llm, steps })
+        }
+        None => None,
+    };
+    update_settings(&app, state.inner(), |settings| {
+        settings.postprocess = config;
+    })
+    .await;
+
+    state.inner().schedule_settings_save();
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_spell_check_enabled(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    enabled: bool,
+) -> Result<(), String> {
+    update_settings(&app, state.inner(), |settings| {
+        settings.spell_check_enabled = enabled;
+    })
+    .await;
+
+    state.inner().schedule_settings_save();
+
+    Ok(())
+}
+
+/// Turns link detection on or off for one export sink.
+#[tauri::command]
+async fn set_auto_link(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    sink: AutoLinkSink,
+    enabled: bool,
+) -> Result<(), String> {
+    update_settings(&app, state.inner(), |settings| {
+        settings
+            .auto_link_sinks
+            .retain(|existing| *existing != sink);
+        if enabled {
+            settings.auto_link_sinks.push(sink);
+        }
+    })
+    .await;
+
+    state.inner().schedule_settings_save();
+
+    Ok(())
+}
+
+/// Proxy and Hugging Face mirror for model installs; empty or `null` values
+/// clear them. The proxy shown without credentials keeps the saved ones.
+/// Takes effect on the next install or retry.
+#[tauri::command]
+async fn set_download_network(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    proxy: Option<String>,
+    hf_endpoint: Option<String>,
+) -> Result<(), String> {
+    let network =
+        bootstrap::DownloadNetwork::new(proxy, hf_endpoint).map_err(|error| error.to_string())?;
+    update_settings(&app, state.inner(), |settings| {
+        settings.download_network = network.keep_credentials(&settings.download_network);
+    })
+    .await;
+
+    state.inner().schedule_settings_save();
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn get_partial_metrics(state: State<'_, SharedState>) -> Result<PartialMetrics, String> {
+    let transcript = state.inner().transcript.lock().await;
+    Ok(transcript.partial_metrics.clone())
+}
+
+#[tauri::command]
+fn get_pipeline_timings() -> Vec<pipeline_timings::StageTiming> {
+    pipeline_timings::report()
+}
+
+#[tauri::command]
+async fn get_model_options(state: State<'_, SharedState>) -> Result<Vec<ModelOption>, String> {
+    let settings = state.inner().settings.lock().await;
+    Ok(allowed_options(state.inner(), &settings.custom_models))
+}
+
+/// The model list without the models a configuration profile rules out.
+fn allowed_options(state: &SharedState, custom_models: &[CustomModel]) -> Vec<ModelOption> {
+    models::model_options(custom_models)
+        .into_iter()
+        .filter(|option| state.managed.allows_model(&option.id))
+        .collect()
+}
+
+/// Registers a local model folder (fine-tuned or gated weights) and returns
+/// the updated model list. The folder must pass the same check as an
+/// installed model: `config.json` plus weights for MLX, or `ggml-model.bin`.
+#[tauri::command]
+async fn add_custom_model(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    path: String,
+    name: Option<String>,
+) -> Result<Vec<ModelOption>, String> {
+    let path = std::path::PathBuf::from(path.trim());
+    if !path.is_dir() {
+        return Err(format!("{} is not a folder", path.display()));
+    }
+    if !app_state::is_model_installed(&path) {
+        return Err(format!(
+            "{} does not look like a Whisper model: expected config.json with weights, or {}",
+            path.display(),
+            models::GGML_MODEL_FILE
+        ));
+    }
+
+    let mut options = Vec::new();
+    let mut refused = None;
+    update_settings(&app, state.inner(), |settings| {
+        let model = models::custom_model(&path, name.as_deref(), &settings.custom_models);
+        if let Err(error) = state.inner().managed.check_model(&model.id) {
+            refused = Some(error);
+            return;
+        }
+        if !settings
+            .custom_models
+            .iter()
+            .any(|existing| existing.id == model.id)
+        {
+            settings.custom_models.push(model);
+        }
+        options = allowed_options(state.inner(), &settings.custom_models);
+    })
+    .await;
+    if let Some(error) = refused {
+        return Err(error.to_string());
+    }
+
+    state.inner().schedule_settings_save();
+
+    Ok(options)
+}
+
+#[tauri::command]
+async fn refresh_audio_devices(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+) -> Result<Vec<AudioDeviceOption>, String> {
+    refresh_audio_devices_inner(&app, state.inner()).await
+}
+
+async fn refresh_audio_devices_inner(
+    app: &AppHandle,
+    state: &SharedState,
+) -> Result<Vec<AudioDeviceOption>, String> {
+    let devices = audio::list_audio_devices().map_err(|error| error.to_string())?;
+
+    // Settings written before device UIDs hold an avfoundation index.
+    let selected = state.settings.lock().await.selected_mic_device.clone();
+    let migrated = match selected.as_deref() {
+        Some(id) if audio::is_legacy_device_id(id) => {
+            Some(audio::migrate_legacy_device_id(id, &devices).await)
+        }
+        _ => None,
+    };
+
+    update_settings(app, state, |settings| {
+        if let Some(migrated) = migrated {
+            settings.selected_mic_device = migrated;
+        }
+        // A selected UID that is not connected right now is kept, so the
+        // device is used again when it comes back.
+        if settings.selected_mic_device.is_none() {
+            settings.selected_mic_device = audio::choose_default_mic(&devices);
+        }
+    })
+    .await;
+
+    state.schedule_settings_save();
+
+    Ok(devices)
+}
+
+#[tauri::command]
+async fn set_audio_inputs(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    mic_device: Option<String>,
+) -> Result<(), String> {
+    if state.inner().status().await == AppStatus::Recording {
+        return Err("cannot change audio input while recording".to_string());
+    }
+
+    update_settings(&app, state.inner(), |settings| {
+        settings.selected_mic_device = mic_device.clone().filter(|value| !value.trim().is_empty());
+    })
+    .await;
+
+    state.inner().schedule_settings_save();
+    preroll::sync(state.inner()).await;
+    app_state::emit_state(&app, state.inner()).await;
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_system_audio_enabled(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    enabled: bool,
+) -> Result<(), String> {
+    if state.inner().status().await == AppStatus::Recording {
+        return Err("cannot change system audio while recording".to_string());
+    }
+
+    update_settings(&app, state.inner(), |settings| {
+        settings.system_audio_enabled = enabled;
+    })
+    .await;
+
+    state.inner().schedule_settings_save();
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_preroll_seconds(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    seconds: u32,
+) -> Result<(), String> {
+    if seconds > preroll::MAX_PREROLL_SECONDS {
+        return Err(format!(
+            "pre-roll must be at most {} seconds",
+            preroll::MAX_PREROLL_SECONDS
+        ));
+    }
+
+    update_settings(&app, state.inner(), |settings| {
+        settings.preroll_seconds = seconds;
+    })
+    .await;
+
+    state.inner().schedule_settings_save();
+    preroll::sync(state.inner()).await;
+    app_state::emit_state(&app, state.inner()).await;
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_keep_audio(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    enabled: bool,
+) -> Result<(), String> {
+    update_settings(&app, state.inner(), |settings| {
+        settings.keep_audio = enabled;
+    })
+    .await;
+
+    state.inner().schedule_settings_save();
+
+    Ok(())
+}
+
+/// `padding_seconds: None` keeps kept recordings whole.
+#[tauri::command]
+async fn set_silence_trim(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    padding_seconds: Option<u32>,
+) -> Result<(), String> {
+    if padding_seconds.is_some_and(|padding| padding > recording_audio::MAX_SILENCE_TRIM_PADDING) {
+        return Err(format!(
+            "the padding must be at most {} seconds",
+            recording_audio::MAX_SILENCE_TRIM_PADDING
+        ));
+    }
+
+    update_settings(&app, state.inner(), |settings| {
+        settings.silence_trim_padding = padding_seconds;
+    })
+    .await;
+
+    state.inner().schedule_settings_save();
+
+    Ok(())
+}
+
+/// Takes effect from the next session.
+#[tauri::command]
+async fn set_hallucination_filter(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    enabled: bool,
+) -> Result<(), String> {
+    update_settings(&app, state.inner(), |settings| {
+        settings.hallucination_filter = enabled;
+    })
+    .await;
+
+    state.inner().schedule_settings_save();
+
+    Ok(())
+}
+
+/// Takes effect from the next session.
+#[tauri::command]
+async fn set_remove_fillers(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    enabled: bool,
+) -> Result<(), String> {
+    update_settings(&app, state.inner(), |settings| {
+        settings.remove_fillers = enabled;
+    })
+    .await;
+
+    state.inner().schedule_settings_save();
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_resample_quality(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    quality: ResampleQuality,
+) -> Result<(), String> {
+    update_settings(&app, state.inner(), |settings| {
+        settings.resample_quality = quality;
+    })
+    .await;
+
+    state.inner().schedule_settings_save();
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_noise_suppression(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    enabled: bool,
+) -> Result<(), String> {
+    update_settings(&app, state.inner(), |settings| {
+        settings.noise_suppression = enabled;
+    })
+    .await;
+
+    state.inner().schedule_settings_save();
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_skip_music(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    enabled: bool,
+) -> Result<(), String> {
+    update_settings(&app, state.inner(), |settings| {
+        settings.skip_music = enabled;
+    })
+    .await;
+
+    state.inner().schedule_settings_save();
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_word_timestamps(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    enabled: bool,
+) -> Result<(), String> {
+    update_settings(&app, state.inner(), |settings| {
+        settings.word_timestamps = enabled;
+    })
+    .await;
+
+    state.inner().schedule_settings_save();
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_voice_activity_detection(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    enabled: bool,
+) -> Result<(), String> {
+    update_settings(&app, state.inner(), |settings| {
+        settings.voice_activity_detection = enabled;
+    })
+    .await;
+
+    state.inner().schedule_settings_save();
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_warm_worker_minutes(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    minutes: u32,
+) -> Result<(), String> {
+    if minutes > app_state::MAX_WARM_WORKER_MINUTES {
+        return Err(format!(
+            "the worker can be kept warm for at most {} minutes",
+            app_state::MAX_WARM_WORKER_MINUTES
+        ));
+    }
+
+    update_settings(&app, state.inner(), |settings| {
+        settings.warm_worker_minutes = minutes;
+    })
+    .await;
+
+    state.inner().schedule_settings_save();
+
+    // A running warm worker keeps the idle timeout it was started with. One
+    // still recording is left to its idle timeout.
+    if minutes == 0 && state.inner().status().await != AppStatus::Recording {
+        engine::shutdown_warm_worker(state.inner()).await;
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_preload_model(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    enabled: bool,
+) -> Result<(), String> {
+    update_settings(&app, state.inner(), |settings| {
+        settings.preload_model = enabled;
+    })
+    .await;
+
+    state.inner().schedule_settings_save();
+
+    // Loading takes a while; the setting is saved without waiting for it.
+    if enabled {
+        let state = state.inner().clone();
+        tauri::async_runtime::spawn(async move {
+            let _ = engine::preload(&state).await;
+        });
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_auto_stop_silence(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    minutes: u32,
+) -> Result<(), String> {
+    if minutes > app_state::MAX_AUTO_STOP_SILENCE_MINUTES {
+        return Err(format!(
+            "auto-stop must be at most {} minutes",
+            app_state::MAX_AUTO_STOP_SILENCE_MINUTES
+        ));
+    }
+
+    update_settings(&app, state.inner(), |settings| {
+        settings.auto_stop_silence_minutes = minutes;
+    })
+    .await;
+
+    state.inner().schedule_settings_save();
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_maintenance_hour(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    hour: u32,
+) -> Result<(), String> {
+    if hour > 23 {
+        return Err("maintenance hour must be between 0 and 23".to_string());
+    }
+
+    update_settings(&app, state.inner(), |settings| {
+        settings.maintenance_hour = hour;
+    })
+    .await;
+
+    state.inner().schedule_settings_save();
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_retention_days(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    days: u32,
+) -> Result<(), String> {
+    if days > maintenance::MAX_RETENTION_DAYS {
+        return Err(format!(
+            "retention must be at most {} days",
+            maintenance::MAX_RETENTION_DAYS
+        ));
+    }
+
+    update_settings(&app, state.inner(), |settings| {
+        settings.retention_days = days;
+    })
+    .await;
+
+    state.inner().schedule_settings_save();
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_meeting_end_action(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    action: MeetingEndAction,
+    grace_minutes: u32,
+) -> Result<(), String> {
+    if grace_minutes > app_state::MAX_MEETING_END_GRACE_MINUTES {
+        return Err(format!(
+            "meeting end grace period must be at most {} minutes",
+            app_state::MAX_MEETING_END_GRACE_MINUTES
+        ));
+    }
+
+    update_settings(&app, state.inner(), |settings| {
+        settings.meeting_end_action = action;
+        settings.meeting_end_grace_minutes = grace_minutes;
+    })
+    .await;
+
+    state.inner().schedule_settings_save();
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_max_recording_minutes(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    minutes: u32,
+) -> Result<(), String> {
+    if minutes > app_state::MAX_RECORDING_MINUTES_LIMIT {
+        return Err(format!(
+            "recording limit must be at most {} minutes",
+            app_state::MAX_RECORDING_MINUTES_LIMIT
+        ));
+    }
+
+    update_settings(&app, state.inner(), |settings| {
+        settings.max_recording_minutes = minutes;
+    })
+    .await;
+
+    state.inner().schedule_settings_save();
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_device_profile(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    device_name: String,
+    profile: Option<DeviceProfile>,
+) -> Result<(), String> {
+    if device_name.trim().is_empty() {
+        return Err("device name is required".to_string());
+    }
+    if let Some(profile) = &profile {
+        profile.validate().map_err(|error| error.to_string())?;
+    }
+
+    update_settings(&app, state.inner(), |settings| match profile {
+        Some(profile) => {
+            settings.device_profiles.insert(device_name, profile);
+        }
+        None => {
+            settings.device_profiles.remove(&device_name);
+        }
+    })
+    .await;
+
+    state.inner().schedule_settings_save();
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn save_acronym_profile(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    name: String,
+    text: Option<String>,
+) -> Result<usize, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("profile name is required".to_string());
+    }
+    let entries = text
+        .as_deref()
+        .map(acronyms::parse)
+        .transpose()
+        .map_err(|error| error.to_string())?;
+    let count = entries.as_ref().map_or(0, Vec::len);
+
+    update_settings(&app, state.inner(), |settings| match entries {
+        Some(entries) => {
+            settings.acronym_profiles.insert(name, entries);
+        }
+        None => {
+            settings.acronym_profiles.remove(&name);
+            if settings.acronym_profile.as_ref() == Some(&name) {
+                settings.acronym_profile = None;
+            }
+        }
+    })
+    .await;
+
+    state.inner().schedule_settings_save();
+
+    Ok(count)
+}
+
+/// Replaces the whole dictionary with the entries in `text`; returns how
+/// many there are. Sessions compile it when they start.
+#[tauri::command]
+async fn set_replacements(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    text: String,
+) -> Result<usize, String> {
+    let entries = replacements::parse(&text).map_err(|error| error.to_string())?;
+    let count = entries.len();
+
+    update_settings(&app, state.inner(), |settings| {
+        settings.replacements = entries;
+    })
+    .await;
+
+    state.inner().schedule_settings_save();
+
+    Ok(count)
+}
+
+#[tauri::command]
+async fn set_acronym_profile(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    name: Option<String>,
+) -> Result<(), String> {
+    if let Some(name) = &name {
+        if !state
+            .inner()
+            .settings
+            .lock()
+            .await
+            .acronym_profiles
+            .contains_key(name)
+        {
+            return Err(format!("acronym profile `{name}` does not exist"));
+        }
+    }
+
+    // Sessions compile the map when they start; a running one keeps its own.
+    update_settings(&app, state.inner(), |settings| {
+        settings.acronym_profile = name;
+    })
+    .await;
+
+    state.inner().schedule_settings_save();
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn export_acronym_profile(
+    state: State<'_, SharedState>,
+    name: String,
+) -> Result<String, String> {
+    let text = {
+        let settings = state.inner().settings.lock().await;
+        let entries = settings
+            .acronym_profiles
+            .get(&name)
+            .ok_or_else(|| format!("acronym profile `{name}` does not exist"))?;
+        acronyms::to_text(&name, entries)
+    };
+    acronyms::export(&name, &text)
+        .await
+        .map_err(|error| error.to_string())
+}
+
+#[tauri::command]
+async fn start_calibration(app: AppHandle, state: State<'_, SharedState>) -> Result<(), String> {
+    calibration::start(&app, state.inner())
+        .await
+        .map_err(|error| error.to_string())
+}
+
+#[tauri::command]
+async fn cancel_calibration(state: State<'_, SharedState>) -> Result<(), String> {
+    if !calibration::cancel(state.inner()).await {
+        return Err("no calibration is running".to_string());
+    }
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_model(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    model_id: String,
+) -> Result<(), String> {
+    if state.inner().status().await == AppStatus::Recording {
+        return Err("cannot change model while recording".to_string());
+    }
+
+    state
+        .inner()
+        .managed
+        .check_model(&model_id)
+        .map_err(|error| error.to_string())?;
+
+    {
+        let mut settings = state.inner().settings.lock().await;
+        let model_path = models::resolve_path(
+            &state.inner().paths.app_data_dir,
+            &settings.custom_models,
+            &model_id,
+        )
+        .ok_or_else(|| format!("unsupported model id: {model_id}"))?;
+        settings.selected_model_id = model_id.clone();
+        settings.model_path = model_path;
+    }
+
+    update_session(&app, state.inner(), |session| {
+        session.reset_error();
+        session.status = AppStatus::Ready;
+        session.status_message = "Model selected. Click Install Model if missing.".to_string();
+    })
+    .await;
+
+    state.inner().schedule_settings_save();
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn install_selected_model(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+) -> Result<(), String> {
+    let selected_model_id = state
+        .inner()
+        .settings
+        .lock()
+        .await
+        .selected_model_id
+        .clone();
+
+    bootstrap::run_bootstrap_for_model(&app, state.inner(), &selected_model_id)
+        .await
+        .map_err(|error| error.to_string())
+}
+
+#[tauri::command]
+async fn start_recording(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    dictation: Option<bool>,
+    calendar_event: Option<CalendarEvent>,
+) -> Result<(), String> {
+    let dictation = if dictation.unwrap_or(false) {
+        DictationOutput::Paste
+    } else {
+        DictationOutput::Off
+    };
+    worker::start_recording(&app, state.inner(), dictation, calendar_event)
+        .await
+        .map_err(|error| {
+            let message = error.to_string();
+            if let Some(kind) = worker::start_error_kind(&error) {
+                set_error(&app, state.inner(), kind, message.clone());
+            }
+            message
+        })
+}
+
+#[tauri::command]
+fn get_accessibility_permission() -> bool {
+    dictation::accessibility_trusted()
+}
+
+#[tauri::command]
+async fn start_recording_countdown(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    seconds: Option<u32>,
+) -> Result<(), String> {
+    let seconds = seconds
+        .unwrap_or(worker::QUICK_RECORD_DELAY_SECS)
+        .clamp(1, 60);
+    worker::start_recording_after(&app, state.inner(), seconds)
+        .await
+        .map_err(|error| error.to_string())
+}
+
+#[tauri::command]
+async fn cancel_recording_countdown(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+) -> Result<(), String> {
+    worker::cancel_countdown(&app, state.inner())
+        .await
+        .map_err(|error| error.to_string())
+}
+
+#[tauri::command]
+async fn stop_recording(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+) -> Result<worker::RecordingResult, String> {
+    worker::stop_recording(&app, state.inner())
+        .await
+        .map_err(|error| {
+            let message = error.to_string();
+            if let Some(kind) = worker::stop_error_kind(&error) {
+                set_error(&app, state.inner(), kind, message.clone());
+            }
+            message
+        })
+}
+
+#[tauri::command]
+async fn mute_session_source(
+    state: State<'_, SharedState>,
+    source: AudioSource,
+) -> Result<(), String> {
+    worker::mute_source(state.inner(), source)
+        .await
+        .map_err(|error| error.to_string())
+}
+
+#[tauri::command]
+async fn append_session_note(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    text: String,
+) -> Result<(), String> {
+    worker::append_note(&app, state.inner(), &text)
+        .await
+        .map_err(|error| error.to_string())
+}
+
+/// Records until speech is followed by a short silence or `max_seconds`
+/// pass, then returns the transcript and leaves it on the clipboard.
+#[tauri::command]
+async fn quick_dictation(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    max_seconds: Option<u32>,
+) -> Result<String, String> {
+    worker::quick_dictation(&app, state.inner(), max_seconds)
+        .await
+        .map_err(|error| error.to_string())
+}
+
+/// Screenshots the main display into the running session's transcript;
+/// returns the image path.
+#[tauri::command]
+async fn capture_moment(app: AppHandle, state: State<'_, SharedState>) -> Result<String, String> {
+    moments::capture(&app, state.inner())
+        .await
+        .map(|path| path.display().to_string())
+        .map_err(|error| error.to_string())
 }
 
 #[tauri::command]
-async fn set_language(
+async fn export_completed_chapters(state: State<'_, SharedState>) -> Result<Vec<String>, String> {
+    chapters::export_completed(state.inner())
+        .await
+        .map(|paths| {
+            paths
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect()
+        })
+        .map_err(|error| error.to_string())
+}
+
+#[tauri::command]
+async fn cancel_recording(app: AppHandle, state: State<'_, SharedState>) -> Result<(), String> {
+    worker::cancel_recording(&app, state.inner())
+        .await
+        .map_err(|error| error.to_string())
+}
+
+#[tauri::command]
+async fn get_transcript_segments(
+    state: State<'_, SharedState>,
+) -> Result<Vec<TranscriptSegment>, String> {
+    Ok(transcript_edit::segments(state.inner()).await)
+}
+
+#[tauri::command]
+async fn update_transcript_segment(
     app: AppHandle,
     state: State<'_, SharedState>,
-    language: String,
+    index: usize,
+    text: String,
 ) -> Result<(), String> {
-    if language != "en" && language != "pt-BR" {
-        return Err("unsupported language".to_string());
-    }
+    transcript_edit::update_segment(&app, state.inner(), index, text)
+        .await
+        .map_err(|error| error.to_string())
+}
 
-    update_state(&app, state.inner(), |inner| {
-        inner.language = language;
-    })
-    .await;
+#[tauri::command]
+async fn split_segment(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    index: usize,
+    offset: usize,
+) -> Result<(), String> {
+    transcript_edit::split_segment(&app, state.inner(), index, offset)
+        .await
+        .map_err(|error| error.to_string())
+}
 
-    {
-        let guard = state.inner().0.lock().await;
-        let _ = save_settings(&guard);
-    }
+#[tauri::command]
+async fn merge_segments(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    indices: Vec<usize>,
+) -> Result<(), String> {
+    transcript_edit::merge_segments(&app, state.inner(), indices)
+        .await
+        .map_err(|error| error.to_string())
+}
 
-    Ok(())
+#[tauri::command]
+async fn replace_in_transcript(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    pattern: String,
+    replacement: String,
+    regex: bool,
+) -> Result<usize, String> {
+    transcript_edit::replace_all(&app, state.inner(), &pattern, &replacement, regex)
+        .await
+        .map_err(|error| error.to_string())
 }
 
 #[tauri::command]
-async fn get_model_options() -> Result<Vec<ModelOption>, String> {
-    Ok(models::model_options())
+async fn undo_transcript_edit(app: AppHandle, state: State<'_, SharedState>) -> Result<(), String> {
+    transcript_edit::undo(&app, state.inner())
+        .await
+        .map_err(|error| error.to_string())
 }
 
 #[tauri::command]
-async fn refresh_audio_devices(
+async fn redo_transcript_edit(app: AppHandle, state: State<'_, SharedState>) -> Result<(), String> {
+    transcript_edit::redo(&app, state.inner())
+        .await
+        .map_err(|error| error.to_string())
+}
+
+#[tauri::command]
+async fn check_transcript_spelling(
+    state: State<'_, SharedState>,
+) -> Result<Vec<SpellingIssue>, String> {
+    let language = state.inner().settings.lock().await.language.clone();
+    let segments = transcript_edit::segments(state.inner()).await;
+    spellcheck::check(&segments, &language)
+        .await
+        .map_err(|error| error.to_string())
+}
+
+#[tauri::command]
+async fn get_recoverable_session(
+    state: State<'_, SharedState>,
+) -> Result<Option<recovery::RecoverableSession>, String> {
+    recovery::find_recoverable(state.inner())
+        .await
+        .map_err(|error| error.to_string())
+}
+
+#[tauri::command]
+async fn recover_session(
     app: AppHandle,
     state: State<'_, SharedState>,
-) -> Result<Vec<AudioDeviceOption>, String> {
-    refresh_audio_devices_inner(&app, state.inner()).await
+    id: String,
+    restore: bool,
+) -> Result<Option<String>, String> {
+    recovery::recover(&app, state.inner(), &id, restore)
+        .await
+        .map_err(|error| error.to_string())
 }
 
-async fn refresh_audio_devices_inner(
-    app: &AppHandle,
-    state: &SharedState,
-) -> Result<Vec<AudioDeviceOption>, String> {
-    let devices = audio::list_audio_devices()
+#[tauri::command]
+async fn save_transcript(app: AppHandle, state: State<'_, SharedState>) -> Result<String, String> {
+    transcript_edit::save(&app, state.inner())
         .await
-        .map_err(|error| error.to_string())?;
+        .map_err(|error| error.to_string())
+}
 
-    update_state(app, state, |inner| {
-        let mic_valid = inner
-            .selected_mic_device
-            .as_ref()
-            .map(|id| devices.iter().any(|device| device.id == *id))
-            .unwrap_or(false);
+#[tauri::command]
+async fn discard_unsaved_transcript(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+) -> Result<(), String> {
+    transcript_edit::discard_unsaved(&app, state.inner())
+        .await
+        .map_err(|error| error.to_string())
+}
 
-        if !mic_valid {
-            inner.selected_mic_device = audio::choose_default_mic(&devices);
-        }
+#[tauri::command]
+async fn get_transcript_history(
+    state: State<'_, SharedState>,
+) -> Result<Vec<HistoryEntry>, String> {
+    Ok(history::list(state.inner()).await)
+}
 
-    })
-    .await;
+#[tauri::command]
+async fn open_history_entry(state: State<'_, SharedState>, id: String) -> Result<(), String> {
+    history::open(state.inner(), &id)
+        .await
+        .map_err(|error| error.to_string())
+}
 
-    {
-        let guard = state.0.lock().await;
-        let _ = save_settings(&guard);
-    }
+#[tauri::command]
+async fn export_history_entry(state: State<'_, SharedState>, id: String) -> Result<String, String> {
+    history::export(state.inner(), &id)
+        .await
+        .map_err(|error| error.to_string())
+}
 
-    Ok(devices)
+#[tauri::command]
+async fn export_session_bundle(
+    state: State<'_, SharedState>,
+    id: String,
+    include_audio: bool,
+) -> Result<String, String> {
+    bundles::export(state.inner(), &id, include_audio)
+        .await
+        .map_err(|error| error.to_string())
 }
 
 #[tauri::command]
-async fn set_audio_inputs(
+async fn import_session_bundle(
     app: AppHandle,
     state: State<'_, SharedState>,
-    mic_device: Option<String>,
+    path: String,
+) -> Result<String, String> {
+    bundles::import(&app, state.inner(), std::path::Path::new(path.trim()))
+        .await
+        .map_err(|error| error.to_string())
+}
+
+#[tauri::command]
+async fn delete_history_entry(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    id: String,
 ) -> Result<(), String> {
-    {
-        let guard = state.inner().0.lock().await;
-        if guard.status == AppStatus::Recording {
-            return Err("cannot change audio input while recording".to_string());
-        }
-    }
+    history::delete(&app, state.inner(), &id)
+        .await
+        .map_err(|error| error.to_string())
+}
 
-    update_state(&app, state.inner(), |inner| {
-        inner.selected_mic_device = mic_device.clone().filter(|value| !value.trim().is_empty());
-    })
-    .await;
+#[tauri::command]
+async fn retranscribe_history_entry(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    id: String,
+) -> Result<String, String> {
+    history::retranscribe(&app, state.inner(), &id)
+        .await
+        .map_err(|error| error.to_string())
+}
 
-    {
-        let guard = state.inner().0.lock().await;
-        let _ = save_settings(&guard);
-    }
+#[tauri::command]
+async fn rate_session(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    id: String,
+    stars: u8,
+    note: Option<String>,
+) -> Result<(), String> {
+    history::rate(&app, state.inner(), &id, stars, note)
+        .await
+        .map_err(|error| error.to_string())
+}
 
-    Ok(())
+#[tauri::command]
+async fn search_transcripts(
+    state: State<'_, SharedState>,
+    query: String,
+) -> Result<Vec<SearchHit>, String> {
+    search::search(state.inner(), &query)
+        .await
+        .map_err(|error| error.to_string())
 }
 
 #[tauri::command]
-async fn set_model(
+fn show_history_window(app: AppHandle) -> Result<(), String> {
+    ui::ensure_history_window(&app).map_err(|error| error.to_string())
+}
+
+#[tauri::command]
+async fn open_transcript_viewer(
     app: AppHandle,
     state: State<'_, SharedState>,
-    model_id: String,
+    path_or_session_id: String,
+) -> Result<(), String> {
+    viewer::open(&app, state.inner(), &path_or_session_id)
+        .await
+        .map_err(|error| error.to_string())
+}
+
+#[tauri::command]
+async fn get_viewer_transcript(
+    window: WebviewWindow,
+    state: State<'_, SharedState>,
+) -> Result<viewer::ViewerTranscript, String> {
+    viewer::transcript(state.inner(), window.label())
+        .await
+        .map_err(|error| error.to_string())
+}
+
+#[tauri::command]
+async fn copy_viewer_transcript(
+    window: WebviewWindow,
+    state: State<'_, SharedState>,
 ) -> Result<(), String> {
-    if models::find_model(&model_id).is_none() {
-        return Err(format!("unsupported model id: {model_id}"));
+    viewer::copy(state.inner(), window.label())
+        .await
+        .map_err(|error| error.to_string())
+}
+
+#[tauri::command]
+async fn export_viewer_transcript(
+    window: WebviewWindow,
+    state: State<'_, SharedState>,
+) -> Result<String, String> {
+    viewer::export(state.inner(), window.label())
+        .await
+        .map_err(|error| error.to_string())
+}
+
+#[tauri::command]
+async fn get_storage_breakdown(state: State<'_, SharedState>) -> Result<StorageBreakdown, String> {
+    storage::breakdown(&state.inner().paths)
+        .await
+        .map_err(|error| error.to_string())
+}
+
+#[tauri::command]
+async fn get_diagnostics(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+) -> Result<Diagnostics, String> {
+    Ok(diagnostics::collect(&app, state.inner()).await)
+}
+
+#[tauri::command]
+fn open_app_data_dir(state: State<'_, SharedState>) -> Result<(), String> {
+    storage::open_app_data_dir(&state.inner().paths).map_err(|error| error.to_string())
+}
+
+#[tauri::command]
+async fn reopen_live_window(app: AppHandle, state: State<'_, SharedState>) -> Result<(), String> {
+    if state.inner().status().await != AppStatus::Recording {
+        return Err("no recording in progress".to_string());
     }
 
-    {
-        let guard = state.inner().0.lock().await;
-        if guard.status == AppStatus::Recording {
-            return Err("cannot change model while recording".to_string());
+    let placement = state.inner().settings.lock().await.floating_placement;
+    ui::ensure_floating_window(&app, placement).map_err(|error| error.to_string())
+}
+
+#[tauri::command]
+fn list_displays(app: AppHandle) -> Result<Vec<DisplayInfo>, String> {
+    ui::list_displays(&app).map_err(|error| error.to_string())
+}
+
+/// Pins the live window to `corner` of `display` (primary when omitted) and
+/// remembers it for later sessions. A `null` corner unpins it.
+#[tauri::command]
+async fn set_floating_window_position(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    corner: Option<ScreenCorner>,
+    display: Option<usize>,
+) -> Result<(), String> {
+    if let Some(display) = display {
+        let count = ui::list_displays(&app)
+            .map_err(|error| error.to_string())?
+            .len();
+        if display >= count {
+            return Err(format!("display {display} is not connected"));
         }
     }
 
-    let model_path = models::model_path(
-        &{
-            let guard = state.inner().0.lock().await;
-            guard.app_data_dir.clone()
-        },
-        &model_id,
-    )
-    .ok_or_else(|| format!("unsupported model id: {model_id}"))?;
-
-    update_state(&app, state.inner(), |inner| {
-        inner.selected_model_id = model_id.clone();
-        inner.model_path = model_path.clone();
-        inner.error_message = None;
-        inner.status = AppStatus::Ready;
-        inner.status_message = "Model selected. Click Install Model if missing.".to_string();
+    let placement = corner.map(|corner| FloatingPlacement { corner, display });
+    update_settings(&app, state.inner(), |settings| {
+        settings.floating_placement = placement;
     })
     .await;
+    state.inner().schedule_settings_save();
 
-    {
-        let guard = state.inner().0.lock().await;
-        let _ = save_settings(&guard);
+    if let Some(placement) = placement {
+        ui::place_floating_window(&app, placement).map_err(|error| error.to_string())?;
     }
 
     Ok(())
 }
 
+/// Adds or removes the one-click record button in the menu bar.
 #[tauri::command]
-async fn install_selected_model(
+async fn set_record_button(
     app: AppHandle,
     state: State<'_, SharedState>,
+    enabled: bool,
 ) -> Result<(), String> {
-    let selected_model_id = {
-        let guard = state.inner().0.lock().await;
-        guard.selected_model_id.clone()
-    };
+    tray::set_record_button(&app, enabled).map_err(|error| error.to_string())?;
+    update_settings(&app, state.inner(), |settings| {
+        settings.record_button = enabled;
+    })
+    .await;
 
-    bootstrap::run_bootstrap_for_model(&app, state.inner(), &selected_model_id)
+    state.inner().schedule_settings_save();
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn retry_bootstrap(app: AppHandle, state: State<'_, SharedState>) -> Result<(), String> {
+    bootstrap::run_bootstrap(&app, state.inner())
         .await
         .map_err(|error| error.to_string())
 }
 
 #[tauri::command]
-async fn start_recording(app: AppHandle, state: State<'_, SharedState>) -> Result<(), String> {
-    if let Err(error) = worker::start_recording(&app, state.inner()).await {
-        let message = error.to_string();
-        set_error(&app, state.inner(), message.clone());
-        return Err(message);
-    }
+async fn flush_settings(state: State<'_, SharedState>) -> Result<(), String> {
+    state
+        .inner()
+        .flush_settings()
+        .await
+        .map_err(|error| error.to_string())
+}
+
+#[tauri::command]
+async fn clear_error(app: AppHandle, state: State<'_, SharedState>) -> Result<(), String> {
+    update_session(&app, state.inner(), errors::dismiss).await;
 
     Ok(())
 }
 
 #[tauri::command]
-async fn stop_recording(app: AppHandle, state: State<'_, SharedState>) -> Result<String, String> {
-    match worker::stop_recording(&app, state.inner()).await {
-        Ok(path) => Ok(path),
-        Err(error) => {
-            let message = error.to_string();
-            set_error(&app, state.inner(), message.clone());
-            Err(message)
+async fn suppress_error_kind(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    kind: ErrorKind,
+) -> Result<(), String> {
+    if kind.severity() != ErrorSeverity::Warning {
+        return Err("only warnings can be suppressed".to_string());
+    }
+
+    update_settings(&app, state.inner(), |settings| {
+        if !settings.suppressed_errors.contains(&kind) {
+            settings.suppressed_errors.push(kind);
+        }
+    })
+    .await;
+
+    {
+        let mut session = state.inner().session.lock().await;
+        if session.error_kind == Some(kind) {
+            errors::dismiss(&mut session);
         }
     }
-}
+    app_state::emit_state(&app, state.inner()).await;
 
-#[tauri::command]
-async fn retry_bootstrap(app: AppHandle, state: State<'_, SharedState>) -> Result<(), String> {
-    bootstrap::run_bootstrap(&app, state.inner())
-        .await
-        .map_err(|error| error.to_string())
+    state.inner().schedule_settings_save();
+
+    Ok(())
 }
 
 #[tauri::command]
-async fn clear_error(app: AppHandle, state: State<'_, SharedState>) -> Result<(), String> {
-    update_state(&app, state.inner(), |inner| {
-        inner.error_message = None;
-        if inner.status == AppStatus::Error {
-            if inner.install_progress == Some(1.0) {
-                inner.status = AppStatus::Ready;
-                inner.status_message = "Ready".to_string();
-            } else {
-                inner.status = AppStatus::Ready;
-                inner.status_message = "Ready".to_string();
-            }
-        }
+async fn reset_suppressed_errors(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+) -> Result<(), String> {
+    update_settings(&app, state.inner(), |settings| {
+        settings.suppressed_errors.clear();
     })
     .await;
 
+    state.inner().schedule_settings_save();
+
     Ok(())
 }
 
-fn set_error(app: &AppHandle, state: &SharedState, message: String) {
+fn set_error(app: &AppHandle, state: &SharedState, kind: ErrorKind, message: String) {
     let app = app.clone();
     let state = state.clone();
 
     tauri::async_runtime::spawn(async move {
-        update_state(&app, &state, move |inner| {
-            inner.status = AppStatus::Error;
-            inner.status_message = "Error".to_string();
-            inner.error_message = Some(message);
-            inner.install_progress = None;
+        state.install.lock().await.progress = None;
+        errors::report(&app, &state, kind, message, |session| {
+            session.status = AppStatus::Error;
+            session.status_message = "Error".to_string();
         })
         .await;
     });
 }
 
 pub fn run() {
+    pipeline_timings::install();
     tauri::Builder::default()
         .setup(|app| {
             let app_handle = app.handle().clone();
@@ -244,35 +1650,160 @@ pub fn run() {
             let app_data_dir = app.path().app_data_dir()?;
 
             let state = SharedState::new(app_data_dir);
+            state.spawn_settings_writer();
             app.manage(state.clone());
 
+            // Without notifications the device list only refreshes on demand.
+            let _ = device_monitor::start(&app_handle, &state);
+            let _ = transcript_watch::start(&app_handle, &state);
+            let _ = moments::start_hotkey(&app_handle, &state);
+            maintenance::spawn(&app_handle, &state);
+            usage::spawn(&app_handle, &state);
+
             ui::ensure_tray_window(&app_handle)?;
             tray::build_tray(&app_handle)?;
 
             let state_for_bootstrap = state.clone();
             tauri::async_runtime::spawn(async move {
+                if state_for_bootstrap.settings.lock().await.record_button {
+                    let _ = tray::set_record_button(&app_handle, true);
+                }
                 app_state::emit_state(&app_handle, &state_for_bootstrap).await;
+                let _ = orphans::reap(&state_for_bootstrap.paths).await;
+                preroll::sync(&state_for_bootstrap).await;
+                app_state::emit_state(&app_handle, &state_for_bootstrap).await;
+                // Best effort: a missing model or environment is reported when
+                // recording starts, as without preloading.
+                if state_for_bootstrap.settings.lock().await.preload_model {
+                    let _ = engine::preload(&state_for_bootstrap).await;
+                }
             });
 
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             get_app_state,
+            get_app_state_since,
             set_language,
+            set_partial_update_rate,
+            get_partial_metrics,
+            get_pipeline_timings,
+            set_diarization,
+            set_separate_sources,
+            set_include_timestamps,
+            set_name_template,
+            set_title_llm,
+            set_postprocess,
+            set_spell_check_enabled,
+            set_auto_link,
+            set_download_network,
             get_model_options,
+            add_custom_model,
             refresh_audio_devices,
             set_audio_inputs,
+            set_system_audio_enabled,
+            set_device_profile,
+            save_acronym_profile,
+            set_acronym_profile,
+            export_acronym_profile,
+            set_replacements,
+            start_calibration,
+            cancel_calibration,
+            set_preroll_seconds,
+            set_keep_audio,
+            set_silence_trim,
+            set_hallucination_filter,
+            set_remove_fillers,
+            set_resample_quality,
+            set_noise_suppression,
+            set_voice_activity_detection,
+            set_skip_music,
+            set_word_timestamps,
+            set_auto_stop_silence,
+            set_warm_worker_minutes,
+            set_preload_model,
+            set_max_recording_minutes,
+            set_meeting_end_action,
+            set_maintenance_hour,
+            set_retention_days,
             set_model,
             install_selected_model,
             start_recording,
+            start_recording_countdown,
+            get_accessibility_permission,
+            cancel_recording_countdown,
             stop_recording,
+            cancel_recording,
+            mute_session_source,
+            append_session_note,
+            quick_dictation,
+            capture_moment,
+            export_completed_chapters,
+            reopen_live_window,
+            list_displays,
+            set_floating_window_position,
+            set_record_button,
+            get_transcript_segments,
+            update_transcript_segment,
+            split_segment,
+            merge_segments,
+            replace_in_transcript,
+            undo_transcript_edit,
+            redo_transcript_edit,
+            check_transcript_spelling,
+            get_recoverable_session,
+            recover_session,
+            save_transcript,
+            discard_unsaved_transcript,
+            get_transcript_history,
+            open_history_entry,
+            export_history_entry,
+            export_session_bundle,
+            import_session_bundle,
+            delete_history_entry,
+            retranscribe_history_entry,
+            rate_session,
+            search_transcripts,
+            show_history_window,
+            open_transcript_viewer,
+            get_viewer_transcript,
+            copy_viewer_transcript,
+            export_viewer_transcript,
+            get_storage_breakdown,
+            get_diagnostics,
+            open_app_data_dir,
             retry_bootstrap,
-            clear_error
+            flush_settings,
+            clear_error,
+            suppress_error_kind,
+            reset_suppressed_errors
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let RunEvent::Exit = event {
+                let state = app.state::<SharedState>().inner().clone();
+                let _ = tauri::async_runtime::block_on(state.flush_settings());
+            }
+        });
+}
+
+pub fn run_cli_record(args: &[String]) -> Result<(), String> {
+    cli::record(args).map_err(|error| error.to_string())
 }
 
 pub fn run_sck_audio_helper() -> Result<(), String> {
     sck_audio_helper::run().map_err(|error| error.to_string())
 }
+
+pub fn run_denoise_filter() -> Result<(), String> {
+    denoise::run().map_err(|error| error.to_string())
+}
+
+pub fn run_vad_filter() -> Result<(), String> {
+    vad::run().map_err(|error| error.to_string())
+}
+
+pub fn run_native_worker(args: &[String]) -> Result<(), String> {
+    whisper_cpp::run(args).map_err(|error| error.to_string())
+}
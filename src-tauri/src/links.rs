@@ -0,0 +1,175 @@
+use std::sync::OnceLock;
+
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+
+/// Where transcript text leaves the app. Link detection is enabled per sink
+/// (`set_auto_link`), since a Markdown link that helps in a saved transcript
+/// is noise when pasted into a chat box.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AutoLinkSink {
+    /// The saved Markdown transcript (and the copy `export_history_entry` makes).
+    Transcript,
+    /// Text pasted into the frontmost app at the end of a dictation.
+    Dictation,
+}
+
+/// Top-level domains a written address has to end in to become a link.
+/// Kept short on purpose: "the data dot point" must stay a sentence.
+const LINK_TLDS: &str = "com|org|net|io|dev|app|ai|co|edu|gov|info|me|br|pt|uk|de|fr|es|us|ca";
+
+/// The ones a spoken `dot`/`ponto` may end in. Those that are also everyday
+/// words (`de`, `me`, `us`, ...) are left out, so "ponto de vista" stays a
+/// phrase.
+const SPOKEN_TLDS: &str = "com|org|net|io|dev|app|ai|edu|gov|info|br|pt|uk|fr";
+
+/// Articles, pronouns and prepositions (English and Portuguese) that never
+/// name a site, so "o ponto com ela" and "my point dot com" stay speech.
+const NOT_DOMAIN_LABELS: [&str; 61] = [
+    "a", "an", "the", "my", "your", "his", "her", "its", "our", "their", "this", "that", "to",
+    "of", "in", "on", "at", "for", "and", "or", "is", "it", "i", "you", "we", "they", "o", "os",
+    "as", "um", "uma", "meu", "minha", "seu", "sua", "nosso", "nossa", "de", "do", "da", "dos",
+    "das", "no", "na", "nos", "nas", "em", "ao", "pelo", "pela", "por", "para", "pra", "e", "ou",
+    "que", "esse", "essa", "este", "esta", "isso",
+];
+
+/// Words that are followed by "at" in ordinary speech, so `look at example.com`
+/// is not turned into an email address.
+const NOT_EMAIL_USERS: [&str; 16] = [
+    "look", "me", "us", "him", "her", "them", "it", "this", "that", "here", "there", "home",
+    "work", "go", "visit", "check",
+];
+
+fn regex(cell: &'static OnceLock<Regex>, pattern: impl FnOnce() -> String) -> &'static Regex {
+    cell.get_or_init(|| Regex::new(&pattern()).expect("invalid link pattern"))
+}
+
+/// Rewrites addresses the model wrote out as speech: `example dot com` ->
+/// `example.com`, `ana at example dot com` -> `ana@example.com`. Portuguese
+/// `ponto` and `arroba` work the same way.
+pub fn written(text: &str) -> String {
+    static DOMAIN: OnceLock<Regex> = OnceLock::new();
+    static DOT: OnceLock<Regex> = OnceLock::new();
+    static EMAIL: OnceLock<Regex> = OnceLock::new();
+
+    let domain = regex(&DOMAIN, || {
+        format!(r"(?i)\b(?:[a-z0-9-]+(?:\.|\s+(?:dot|ponto)\s+))+(?:{SPOKEN_TLDS})\b")
+    });
+    let dot = regex(&DOT, || r"(?i)\s+(?:dot|ponto)\s+".to_string());
+    let text = domain.replace_all(text, |caps: &Captures| {
+        // Spoken at the start of a sentence it comes out capitalized.
+        let address = dot.replace_all(&caps[0], ".").to_lowercase();
+        let mut labels = address.split('.').collect::<Vec<_>>();
+        labels.pop();
+        if dot.is_match(&caps[0]) && !labels.iter().any(|label| NOT_DOMAIN_LABELS.contains(label)) {
+            address
+        } else {
+            caps[0].to_string()
+        }
+    });
+
+    let email = regex(&EMAIL, || {
+        format!(r"(?i)\b([a-z0-9._+-]+)\s+(?:at|arroba)\s+((?:[a-z0-9-]+\.)+(?:{LINK_TLDS}))\b")
+    });
+    email
+        .replace_all(&text, |caps: &Captures| {
+            let user = &caps[1];
+            if NOT_EMAIL_USERS.contains(&user.to_lowercase().as_str()) {
+                caps[0].to_string()
+            } else {
+                format!("{}@{}", user.to_lowercase(), &caps[2])
+            }
+        })
+        .into_owned()
+}
+
+/// `written`, then every URL, bare domain and email address as a Markdown
+/// link: `[example.com](https://example.com)`, `[ana@example.com](mailto:ana@example.com)`.
+pub fn markdown(text: &str) -> String {
+    static LINK: OnceLock<Regex> = OnceLock::new();
+
+    let link = regex(&LINK, || {
+        // Trailing punctuation belongs to the sentence, not the address.
+        let path_end = r#"[^\s<>()\[\].,;:!?'"]"#;
+        format!(
+            r"(?i)\b(?:https?://[^\s<>()\[\]]*{path_end}|[a-z0-9._%+-]+@(?:[a-z0-9-]+\.)+[a-z]{{2,}}\b|(?:[a-z0-9-]+\.)+(?:{LINK_TLDS})\b(?:/[^\s<>()\[\]]*{path_end})?)"
+        )
+    });
+
+    link.replace_all(&written(text), |caps: &Captures| {
+        let address = &caps[0];
+        if address.contains("://") {
+            format!("[{address}]({address})")
+        } else if address.contains('@') {
+            format!("[{address}](mailto:{address})")
+        } else {
+            format!("[{address}](https://{address})")
+        }
+    })
+    .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spoken_addresses_are_written_out() {
+        assert_eq!(written("see example dot com"), "see example.com");
+        assert_eq!(
+            written("acesse google ponto com ponto br"),
+            "acesse google.com.br"
+        );
+        assert_eq!(written("Example dot org has it"), "example.org has it");
+        assert_eq!(
+            written("mail ana at example dot com"),
+            "mail ana@example.com"
+        );
+        assert_eq!(
+            written("escreva para ana arroba empresa ponto com ponto br"),
+            "escreva para ana@empresa.com.br"
+        );
+    }
+
+    #[test]
+    fn portuguese_phrases_with_ponto_stay_speech() {
+        for text in [
+            "no meu ponto de vista",
+            "do ponto de vista dele",
+            "bom ponto de partida",
+            "o ponto com ela",
+            "esse ponto com o cliente",
+        ] {
+            assert_eq!(written(text), text);
+        }
+    }
+
+    #[test]
+    fn english_phrases_with_dot_stay_speech() {
+        for text in [
+            "the data dot point",
+            "my dot com days",
+            "connect the dot me",
+        ] {
+            assert_eq!(written(text), text);
+        }
+    }
+
+    #[test]
+    fn look_at_is_not_an_email() {
+        assert_eq!(written("look at example.com"), "look at example.com");
+    }
+
+    #[test]
+    fn written_addresses_become_links() {
+        assert_eq!(
+            markdown("see example.com, then https://a.io/x."),
+            "see [example.com](https://example.com), then [https://a.io/x](https://a.io/x)."
+        );
+        assert_eq!(
+            markdown("ana@example.de"),
+            "[ana@example.de](mailto:ana@example.de)"
+        );
+    }
+}
@@ -0,0 +1,170 @@
+use std::{process::Stdio, time::Duration};
+
+use anyhow::{anyhow, bail, Context};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::{io::AsyncWriteExt, process::Command};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LlmApi {
+    /// `POST <endpoint>/api/generate`.
+    Ollama,
+    /// `POST <endpoint>/chat/completions`.
+    OpenAi,
+}
+
+/// An LLM server transcripts can be sent to after stop: for titles
+/// (`set_title_llm`) and post-processing (`set_postprocess`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Llm {
+    pub api: LlmApi,
+    /// `http://localhost:11434` for Ollama; the base URL up to and
+    /// including `/v1` for OpenAI-compatible servers.
+    pub endpoint: String,
+    pub model: String,
+    /// Sent as a bearer token. Stored in `settings.json` with the rest of
+    /// the settings and never included in snapshots.
+    pub api_key: Option<String>,
+}
+
+/// What snapshots show of an `Llm`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LlmSummary {
+    pub api: LlmApi,
+    pub endpoint: String,
+    pub model: String,
+    pub has_api_key: bool,
+}
+
+impl Llm {
+    /// Trims every value, treats a blank key as none and rejects an endpoint
+    /// that is not an http(s) URL or a missing model.
+    pub fn new(
+        api: LlmApi,
+        endpoint: &str,
+        model: &str,
+        api_key: Option<String>,
+    ) -> anyhow::Result<Self> {
+        let endpoint = endpoint.trim().trim_end_matches('/').to_string();
+        if !endpoint.starts_with("http://") && !endpoint.starts_with("https://") {
+            bail!("the LLM endpoint must start with http:// or https://");
+        }
+        let model = model.trim().to_string();
+        if model.is_empty() {
+            bail!("pick the LLM model to use");
+        }
+        Ok(Self {
+            api,
+            endpoint,
+            model,
+            api_key: api_key
+                .map(|key| key.trim().to_string())
+                .filter(|key| !key.is_empty()),
+        })
+    }
+
+    pub fn summary(&self) -> LlmSummary {
+        LlmSummary {
+            api: self.api,
+            endpoint: self.endpoint.clone(),
+            model: self.model.clone(),
+            has_api_key: self.api_key.is_some(),
+        }
+    }
+
+    /// Sends `prompt` as one user message and returns the reply text. Fails
+    /// when the server cannot be reached within `timeout` or answers
+    /// without text.
+    pub async fn complete(
+        &self,
+        prompt: &str,
+        max_tokens: u32,
+        timeout: Duration,
+    ) -> anyhow::Result<String> {
+        let (url, body) = match self.api {
+            LlmApi::Ollama => (
+                format!("{}/api/generate", self.endpoint),
+                json!({
+                    "model": self.model,
+                    "prompt": prompt,
+                    "stream": false,
+                    "options": { "temperature": 0.2, "num_predict": max_tokens },
+                }),
+            ),
+            LlmApi::OpenAi => (
+                format!("{}/chat/completions", self.endpoint),
+                json!({
+                    "model": self.model,
+                    "messages": [{ "role": "user", "content": prompt }],
+                    "temperature": 0.2,
+                    "max_tokens": max_tokens,
+                }),
+            ),
+        };
+
+        let response = post(&url, &body.to_string(), self.api_key.as_deref(), timeout).await?;
+        let response: Value =
+            serde_json::from_str(&response).context("the LLM endpoint did not answer with JSON")?;
+        match self.api {
+            LlmApi::Ollama => response["response"].as_str(),
+            LlmApi::OpenAi => response["choices"][0]["message"]["content"].as_str(),
+        }
+        .map(str::to_string)
+        .ok_or_else(|| anyhow!("the LLM endpoint answered without text"))
+    }
+}
+
+/// POSTs `body` with curl. Everything, the key included, goes through
+/// curl's config on stdin so none of it shows up in the process list.
+async fn post(
+    url: &str,
+    body: &str,
+    api_key: Option<&str>,
+    timeout: Duration,
+) -> anyhow::Result<String> {
+    let quote = |value: &str| format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""));
+    let mut config = format!(
+        "url = {}\nheader = {}\ndata = {}\n",
+        quote(url),
+        quote("Content-Type: application/json"),
+        quote(body)
+    );
+    if let Some(key) = api_key {
+        config.push_str(&format!(
+            "header = {}\n",
+            quote(&format!("Authorization: Bearer {key}"))
+        ));
+    }
+
+    let mut child = Command::new("curl")
+        .args(["--silent", "--show-error", "--fail", "--max-time"])
+        .arg(timeout.as_secs().max(1).to_string())
+        .args(["--config", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .context("failed to start curl")?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(config.as_bytes())
+            .await
+            .context("failed sending the LLM request to curl")?;
+    }
+
+    let output = child
+        .wait_with_output()
+        .await
+        .context("failed waiting for curl")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "LLM request failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    String::from_utf8(output.stdout).context("the LLM endpoint answered with invalid UTF-8")
+}
@@ -9,5 +9,38 @@ fn main() {
         return;
     }
 
+    if std::env::args().any(|arg| arg == "--denoise-filter") {
+        if let Err(error) = whisperbar_lib::run_denoise_filter() {
+            eprintln!("{error}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if std::env::args().any(|arg| arg == "--vad-filter") {
+        if let Err(error) = whisperbar_lib::run_vad_filter() {
+            eprintln!("{error}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("record") {
+        if let Err(error) = whisperbar_lib::run_cli_record(&args[2..]) {
+            eprintln!("{error}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if args.get(1).map(String::as_str) == Some("--native-worker") {
+        if let Err(error) = whisperbar_lib::run_native_worker(&args[2..]) {
+            eprintln!("{error}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
     whisperbar_lib::run();
 }
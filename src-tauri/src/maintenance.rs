@@ -0,0 +1,214 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use anyhow::Context;
+use chrono::{DateTime, Local, TimeZone};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::{
+    app_state::{is_model_installed, AppPaths, AppStatus, SharedState},
+    history, integrity, models, worker_log,
+};
+
+pub const DEFAULT_MAINTENANCE_HOUR: u32 = 3;
+pub const MAX_RETENTION_DAYS: u32 = 3650;
+
+/// How often the scheduler checks whether a run is due. A due run that finds
+/// the app busy waits for the next check.
+const CHECK_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// Outcome of the last maintenance run, kept in `maintenance.json`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MaintenanceReport {
+    /// RFC 3339 local time the run finished.
+    pub finished_at: String,
+    pub duration_ms: u64,
+    /// Bytes `VACUUM` freed from `search.sqlite`.
+    pub search_index_bytes_reclaimed: u64,
+    /// Every model with a folder on disk.
+    pub models: Vec<ModelCheck>,
+    /// Sessions deleted by the retention policy.
+    pub sessions_removed: usize,
+    /// Worker logs older than `worker_log::RETENTION_DAYS` that were deleted.
+    #[serde(default)]
+    pub logs_removed: usize,
+    /// Steps that failed; the others still ran.
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelCheck {
+    pub id: String,
+    /// Has its config and weights; an interrupted download does not.
+    pub complete: bool,
+    /// Every file has the size its install manifest lists, so a truncated
+    /// file shows up. Folders without a manifest count as intact.
+    #[serde(default)]
+    pub intact: bool,
+}
+
+/// Runs maintenance once a day, at the first check after
+/// `maintenance_hour` (local time) while nothing is recording or installing.
+/// A day the Mac was asleep or the app closed is caught up at the next check.
+/// Never runs while read-only.
+pub fn spawn(app: &AppHandle, state: &SharedState) {
+    if state.paths.read_only {
+        return;
+    }
+    let app = app.clone();
+    let state = state.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(CHECK_INTERVAL).await;
+            if is_due(&state).await && is_idle(&state).await {
+                let _ = run(&app, &state).await;
+            }
+        }
+    });
+}
+
+pub fn last_report(paths: &AppPaths) -> Option<MaintenanceReport> {
+    std::fs::read_to_string(report_path(&paths.app_data_dir))
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+}
+
+async fn is_due(state: &SharedState) -> bool {
+    let hour = state.settings.lock().await.maintenance_hour;
+    let now = Local::now();
+    let Some(mut scheduled) = now
+        .date_naive()
+        .and_hms_opt(hour, 0, 0)
+        .and_then(|time| Local.from_local_datetime(&time).earliest())
+    else {
+        return false;
+    };
+    if scheduled > now {
+        scheduled -= chrono::Duration::days(1);
+    }
+
+    last_report(&state.paths)
+        .and_then(|report| DateTime::parse_from_rfc3339(&report.finished_at).ok())
+        .is_none_or(|finished_at| finished_at < scheduled)
+}
+
+async fn is_idle(state: &SharedState) -> bool {
+    let session = state.session.lock().await;
+    !matches!(
+        session.status,
+        AppStatus::Recording | AppStatus::Installing | AppStatus::Transcribing
+    ) && session.countdown.is_none()
+        && session.calibration.is_none()
+}
+
+/// Compacts the search index, checks installed models, prunes old worker
+/// logs and applies the retention policy, then saves the report.
+async fn run(app: &AppHandle, state: &SharedState) -> anyhow::Result<MaintenanceReport> {
+    let started_at = Instant::now();
+    let app_data_dir = state.paths.app_data_dir.clone();
+    let mut errors = Vec::new();
+
+    let search_index_bytes_reclaimed = match vacuum_search_index(&app_data_dir).await {
+        Ok(bytes) => bytes,
+        Err(error) => {
+            errors.push(format!("search index: {error}"));
+            0
+        }
+    };
+
+    let mut models = Vec::new();
+    for model in models::MODEL_SPECS.iter() {
+        let Some(path) = models::model_path(&app_data_dir, model.id) else {
+            continue;
+        };
+        if !path.exists() {
+            continue;
+        }
+        let intact = match integrity::check_sizes(&path) {
+            Ok(()) => true,
+            Err(error) => {
+                errors.push(format!("model {}: {error}", model.id));
+                false
+            }
+        };
+        models.push(ModelCheck {
+            id: model.id.to_string(),
+            complete: is_model_installed(&path),
+            intact,
+        });
+    }
+
+    let logs_removed = match worker_log::prune(&app_data_dir, Local::now().date_naive()).await {
+        Ok(removed) => removed,
+        Err(error) => {
+            errors.push(format!("logs: {error}"));
+            0
+        }
+    };
+
+    let retention_days = state.settings.lock().await.retention_days;
+    let mut sessions_removed = 0;
+    if retention_days > 0 {
+        let cutoff = Local::now() - chrono::Duration::days(i64::from(retention_days));
+        for entry in history::list(state).await {
+            let expired = DateTime::parse_from_rfc3339(&entry.created_at)
+                .is_ok_and(|created_at| created_at < cutoff);
+            if !expired {
+                continue;
+            }
+            match history::delete(app, state, &entry.id).await {
+                Ok(()) => sessions_removed += 1,
+                Err(error) => errors.push(format!("retention: {error}")),
+            }
+        }
+    }
+
+    let report = MaintenanceReport {
+        finished_at: Local::now().to_rfc3339(),
+        duration_ms: started_at.elapsed().as_millis() as u64,
+        search_index_bytes_reclaimed,
+        models,
+        sessions_removed,
+        logs_removed,
+        errors,
+    };
+    let path = report_path(&app_data_dir);
+    tokio::fs::write(&path, serde_json::to_string_pretty(&report)?)
+        .await
+        .with_context(|| format!("failed writing {}", path.display()))?;
+
+    Ok(report)
+}
+
+/// Merges the FTS5 segments and rebuilds the database file. Uses its own
+/// connection so searches are not blocked while it runs.
+async fn vacuum_search_index(app_data_dir: &Path) -> anyhow::Result<u64> {
+    let path = app_data_dir.join("search.sqlite");
+    if !path.exists() {
+        return Ok(0);
+    }
+
+    tauri::async_runtime::spawn_blocking(move || -> anyhow::Result<u64> {
+        let before = std::fs::metadata(&path)?.len();
+        let connection = Connection::open(&path)?;
+        connection.execute_batch(
+            "INSERT INTO transcripts (transcripts) VALUES ('optimize');
+             VACUUM;",
+        )?;
+        drop(connection);
+        let after = std::fs::metadata(&path)?.len();
+        Ok(before.saturating_sub(after))
+    })
+    .await
+    .context("search index maintenance stopped")?
+}
+
+fn report_path(app_data_dir: &Path) -> PathBuf {
+    app_data_dir.join("maintenance.json")
+}
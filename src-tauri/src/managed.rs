@@ -0,0 +1,250 @@
+use std::{
+    net::IpAddr,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::bail;
+use serde_json::{Map, Value};
+
+use crate::{app_state::SettingsState, llm::Llm, models, usage::UsageReporting};
+
+/// The app's bundle identifier, which names its managed preferences file.
+const BUNDLE_ID: &str = "com.whisperbar";
+
+/// Settings an administrator pushed with a configuration profile. Read once
+/// at startup; changing the profile takes effect on the next launch.
+#[derive(Debug, Default)]
+pub struct ManagedPreferences {
+    /// Aggregate usage counters sent to the team's endpoint. Off unless the
+    /// profile sets `UsageReportEndpoint`.
+    pub usage_reporting: Option<UsageReporting>,
+    /// `AllowedModels`: the model ids that can be selected, custom ones
+    /// included (`custom-<folder>`). `None` allows every model.
+    pub allowed_models: Option<Vec<String>>,
+    /// `LocalOnly`: transcript text never leaves the Mac, so titles and
+    /// post-processing only work with an LLM server on this Mac.
+    pub local_only: bool,
+    /// `DisabledIntegrations`: LLM integrations that are off and cannot be
+    /// turned on.
+    pub disabled_integrations: Vec<Integration>,
+    /// `OutputDirectory`: where transcripts are saved instead of
+    /// `~/Documents/WhisperBar`.
+    pub output_dir: Option<PathBuf>,
+}
+
+/// A feature that sends transcript text to an LLM server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Integration {
+    /// `set_title_llm`.
+    Titles,
+    /// `set_postprocess`.
+    Postprocess,
+}
+
+impl Integration {
+    fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "titles" => Some(Self::Titles),
+            "postprocess" => Some(Self::Postprocess),
+            _ => None,
+        }
+    }
+
+    /// The snapshot field the integration's setting is shown in.
+    fn setting(self) -> &'static str {
+        match self {
+            Self::Titles => "titleLlm",
+            Self::Postprocess => "postprocess",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Titles => "Generated titles",
+            Self::Postprocess => "Post-processing",
+        }
+    }
+}
+
+impl ManagedPreferences {
+    /// Reads `/Library/Managed Preferences/<user>/com.whisperbar.plist`, or
+    /// the computer-wide file next to it when there is no per-user one. A
+    /// missing or unreadable file leaves everything unmanaged.
+    pub fn load() -> Self {
+        let Some(values) = candidates().iter().find_map(|path| read_plist(path)) else {
+            return Self::default();
+        };
+        Self {
+            usage_reporting: UsageReporting::from_managed(&values),
+            allowed_models: values
+                .get("AllowedModels")
+                .and_then(Value::as_array)
+                .map(|ids| {
+                    ids.iter()
+                        .filter_map(Value::as_str)
+                        .map(|id| id.trim().to_string())
+                        .filter(|id| !id.is_empty())
+                        .collect::<Vec<_>>()
+                })
+                // An empty list would leave nothing to record with.
+                .filter(|ids| !ids.is_empty()),
+            local_only: values
+                .get("LocalOnly")
+                .and_then(Value::as_bool)
+                .unwrap_or(false),
+            disabled_integrations: values
+                .get("DisabledIntegrations")
+                .and_then(Value::as_array)
+                .map(|keys| {
+                    keys.iter()
+                        .filter_map(Value::as_str)
+                        .filter_map(Integration::from_key)
+                        .collect()
+                })
+                .unwrap_or_default(),
+            output_dir: values
+                .get("OutputDirectory")
+                .and_then(Value::as_str)
+                .and_then(output_dir),
+        }
+    }
+
+    pub fn allows_model(&self, model_id: &str) -> bool {
+        self.allowed_models
+            .as_ref()
+            .is_none_or(|allowed| allowed.iter().any(|id| id == model_id))
+    }
+
+    /// Refuses a model the profile does not allow, to select, install or
+    /// record with.
+    pub fn check_model(&self, model_id: &str) -> anyhow::Result<()> {
+        if !self.allows_model(model_id) {
+            bail!("your organization does not allow the model {model_id}");
+        }
+        Ok(())
+    }
+
+    /// Refuses an LLM server the profile does not allow for `integration`.
+    pub fn check_llm(&self, integration: Integration, llm: &Llm) -> anyhow::Result<()> {
+        if self.disabled_integrations.contains(&integration) {
+            bail!(
+                "{} are turned off by your organization",
+                integration.label()
+            );
+        }
+        if self.local_only && !is_loopback(&llm.endpoint) {
+            bail!("your organization only allows an LLM server on this Mac (localhost)");
+        }
+        Ok(())
+    }
+
+    /// Brings settings saved before the profile arrived in line with it: a
+    /// model that is not allowed gives way to the first allowed one that
+    /// resolves, and an LLM server that is not allowed is turned off. When no
+    /// allowed model resolves the selection stays, and `check_model` refuses
+    /// to install or record with it.
+    pub fn apply(&self, settings: &mut SettingsState, app_data_dir: &Path) {
+        if let Some(allowed) = &self.allowed_models {
+            if !self.allows_model(&settings.selected_model_id) {
+                let fallback = allowed.iter().find_map(|id| {
+                    models::resolve_path(app_data_dir, &settings.custom_models, id)
+                        .map(|path| (id.clone(), path))
+                });
+                if let Some((model_id, model_path)) = fallback {
+                    settings.selected_model_id = model_id;
+                    settings.model_path = model_path;
+                }
+            }
+        }
+        if settings
+            .title_llm
+            .as_ref()
+            .is_some_and(|llm| self.check_llm(Integration::Titles, llm).is_err())
+        {
+            settings.title_llm = None;
+        }
+        if settings.postprocess.as_ref().is_some_and(|config| {
+            self.check_llm(Integration::Postprocess, &config.llm)
+                .is_err()
+        }) {
+            settings.postprocess = None;
+        }
+    }
+
+    /// Snapshot fields the profile decides, which the panel shows locked.
+    /// Under `LocalOnly` the LLM settings stay editable, within its limit.
+    pub fn locked_settings(&self) -> Vec<String> {
+        let mut locked = Vec::new();
+        if self.allowed_models.is_some() {
+            locked.push("selectedModelId".to_string());
+        }
+        locked.extend(
+            self.disabled_integrations
+                .iter()
+                .map(|integration| integration.setting().to_string()),
+        );
+        if self.output_dir.is_some() {
+            locked.push("outputDir".to_string());
+        }
+        locked
+    }
+}
+
+/// An absolute path, or one starting with `~/`. Anything else is ignored.
+fn output_dir(value: &str) -> Option<PathBuf> {
+    let value = value.trim();
+    let path = match value.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir()?.join(rest),
+        None => PathBuf::from(value),
+    };
+    path.is_absolute().then_some(path)
+}
+
+/// `http://localhost:11434`, `http://127.0.0.1/v1`, `http://[::1]:8080`.
+fn is_loopback(endpoint: &str) -> bool {
+    let Some((_, rest)) = endpoint.split_once("://") else {
+        return false;
+    };
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host_port = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host_port)| host_port);
+    let host = match host_port.strip_prefix('[') {
+        Some(rest) => rest.split(']').next().unwrap_or_default(),
+        None => host_port.split(':').next().unwrap_or_default(),
+    };
+    host.eq_ignore_ascii_case("localhost")
+        || host.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
+
+fn candidates() -> Vec<PathBuf> {
+    let root = Path::new("/Library/Managed Preferences");
+    let file = format!("{BUNDLE_ID}.plist");
+    let mut paths = Vec::with_capacity(2);
+    if let Ok(user) = std::env::var("USER") {
+        paths.push(root.join(user).join(&file));
+    }
+    paths.push(root.join(file));
+    paths
+}
+
+/// The plist's top-level dictionary, converted with `plutil` so binary and
+/// XML plists both work.
+fn read_plist(path: &Path) -> Option<Map<String, Value>> {
+    if !path.is_file() {
+        return None;
+    }
+    let output = Command::new("plutil")
+        .args(["-convert", "json", "-o", "-"])
+        .arg(path)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    match serde_json::from_slice(&output.stdout).ok()? {
+        Value::Object(values) => Some(values),
+        _ => None,
+    }
+}
@@ -0,0 +1,105 @@
+use std::process::Command;
+
+use anyhow::anyhow;
+
+use crate::models::{self, ModelSpec};
+
+const MB: u64 = 1024 * 1024;
+
+/// Share of physical memory Metal lets one process keep resident on the
+/// GPU (`recommendedMaxWorkingSetSize` is about three quarters of RAM).
+const GPU_WORKING_SET_SHARE: f64 = 0.75;
+
+/// Checks the selected model against unified memory before a session starts.
+///
+/// Fails when the model can never fit in the GPU working set of this Mac, so
+/// the worker would run out of memory however much else is closed. Returns a
+/// warning when it fits but not in what is free right now, since macOS will
+/// have to compress or swap other apps mid-session. Both suggest the largest
+/// model that would fit. `Ok(None)` when memory could not be read.
+pub fn preflight(model: &ModelSpec) -> anyhow::Result<Option<String>> {
+    let Some(total) = total_bytes() else {
+        return Ok(None);
+    };
+    let required = model.memory_mb * MB;
+    let working_set = (total as f64 * GPU_WORKING_SET_SHARE) as u64;
+
+    if required > working_set {
+        return Err(anyhow!(
+            "{} needs about {} of memory, more than this Mac can give it ({} total).{}",
+            model.name,
+            format_gb(required),
+            format_gb(total),
+            suggestion(model, working_set)
+        ));
+    }
+
+    let Some(available) = available_bytes() else {
+        return Ok(None);
+    };
+    if required > available {
+        return Ok(Some(format!(
+            "{} needs about {} of memory and only {} is free. Quit other apps or the session may stall.{}",
+            model.name,
+            format_gb(required),
+            format_gb(available),
+            suggestion(model, available)
+        )));
+    }
+
+    Ok(None)
+}
+
+/// Points at the largest other model that needs at most `budget` bytes.
+fn suggestion(model: &ModelSpec, budget: u64) -> String {
+    models::MODEL_SPECS
+        .iter()
+        .filter(|candidate| candidate.id != model.id && candidate.memory_mb * MB <= budget)
+        .max_by_key(|candidate| candidate.memory_mb)
+        .map(|candidate| format!(" Switch to {} instead.", candidate.name))
+        .unwrap_or_default()
+}
+
+fn total_bytes() -> Option<u64> {
+    let output = Command::new("sysctl")
+        .args(["-n", "hw.memsize"])
+        .output()
+        .ok()?;
+    String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+}
+
+/// Memory macOS can hand out without swapping: free pages plus the ones it
+/// can reclaim (inactive, speculative, purgeable), from `vm_stat`.
+fn available_bytes() -> Option<u64> {
+    let output = Command::new("vm_stat").output().ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut lines = stdout.lines();
+
+    // "Mach Virtual Memory Statistics: (page size of 16384 bytes)"
+    let page_size: u64 = lines
+        .next()?
+        .split("page size of ")
+        .nth(1)?
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()?;
+
+    let pages = lines
+        .filter_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            matches!(
+                name.trim(),
+                "Pages free" | "Pages inactive" | "Pages speculative" | "Pages purgeable"
+            )
+            .then(|| value.trim().trim_end_matches('.').parse::<u64>().ok())
+            .flatten()
+        })
+        .sum::<u64>();
+
+    Some(pages * page_size)
+}
+
+fn format_gb(bytes: u64) -> String {
+    format!("{:.1} GB", bytes as f64 / (1024.0 * MB as f64))
+}
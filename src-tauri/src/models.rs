@@ -1,36 +1,169 @@
 use std::path::{Path, PathBuf};
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+
+use crate::storage;
+
+const GIB: f64 = 1024.0 * 1024.0 * 1024.0;
+
+/// File a whisper.cpp model is stored as inside its folder.
+pub const GGML_MODEL_FILE: &str = "ggml-model.bin";
+
+/// What runs a model.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ModelBackend {
+    /// MLX Whisper in the Python worker; needs the bootstrapped venv.
+    Mlx,
+    /// whisper.cpp with Metal inside the app (`whisper_cpp`); needs no Python.
+    WhisperCpp,
+}
 
 #[derive(Clone, Copy, Debug)]
 pub struct ModelSpec {
     pub id: &'static str,
     pub name: &'static str,
     pub size_label: &'static str,
+    /// Download size, in bytes.
+    pub size_bytes: u64,
     pub folder: &'static str,
+    /// Hugging Face repo the model is downloaded from.
+    pub hf_repo: &'static str,
+    pub backend: ModelBackend,
+    /// Download URL of the GGML file, for `WhisperCpp` models.
+    pub ggml_url: Option<&'static str>,
+    /// Unified memory a session needs: weights plus the encoder/decoder
+    /// working set, with some headroom.
+    pub memory_mb: u64,
 }
 
-pub const MODEL_SPECS: [ModelSpec; 2] = [
+pub const MODEL_SPECS: [ModelSpec; 8] = [
     ModelSpec {
         id: "large-v3-turbo",
         name: "Large v3 Turbo",
         size_label: "0.81 GB",
+        size_bytes: (0.81 * GIB) as u64,
         folder: "whisper-large-v3-turbo",
+        hf_repo: "mlx-community/whisper-large-v3-turbo",
+        backend: ModelBackend::Mlx,
+        ggml_url: None,
+        memory_mb: 2_500,
     },
     ModelSpec {
         id: "large-v3",
         name: "Large v3",
         size_label: "3.10 GB",
+        size_bytes: (3.10 * GIB) as u64,
         folder: "whisper-large-v3",
+        hf_repo: "mlx-community/whisper-large-v3-mlx",
+        backend: ModelBackend::Mlx,
+        ggml_url: None,
+        memory_mb: 6_000,
+    },
+    ModelSpec {
+        id: "medium",
+        name: "Medium",
+        size_label: "1.53 GB",
+        size_bytes: (1.53 * GIB) as u64,
+        folder: "whisper-medium",
+        hf_repo: "mlx-community/whisper-medium-mlx",
+        backend: ModelBackend::Mlx,
+        ggml_url: None,
+        memory_mb: 3_000,
+    },
+    ModelSpec {
+        id: "small",
+        name: "Small",
+        size_label: "0.48 GB",
+        size_bytes: (0.48 * GIB) as u64,
+        folder: "whisper-small",
+        hf_repo: "mlx-community/whisper-small-mlx",
+        backend: ModelBackend::Mlx,
+        ggml_url: None,
+        memory_mb: 1_200,
+    },
+    ModelSpec {
+        id: "base",
+        name: "Base",
+        size_label: "0.15 GB",
+        size_bytes: (0.15 * GIB) as u64,
+        folder: "whisper-base",
+        hf_repo: "mlx-community/whisper-base-mlx",
+        backend: ModelBackend::Mlx,
+        ggml_url: None,
+        memory_mb: 700,
+    },
+    ModelSpec {
+        id: "tiny",
+        name: "Tiny",
+        size_label: "0.08 GB",
+        size_bytes: (0.08 * GIB) as u64,
+        folder: "whisper-tiny",
+        hf_repo: "mlx-community/whisper-tiny-mlx",
+        backend: ModelBackend::Mlx,
+        ggml_url: None,
+        memory_mb: 500,
+    },
+    ModelSpec {
+        id: "large-v3-turbo-cpp",
+        name: "Large v3 Turbo (whisper.cpp)",
+        size_label: "1.62 GB",
+        size_bytes: (1.62 * GIB) as u64,
+        folder: "whisper-cpp-large-v3-turbo",
+        hf_repo: "ggerganov/whisper.cpp",
+        backend: ModelBackend::WhisperCpp,
+        ggml_url: Some(
+            "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-large-v3-turbo.bin",
+        ),
+        memory_mb: 2_500,
+    },
+    ModelSpec {
+        id: "large-v3-cpp",
+        name: "Large v3 (whisper.cpp)",
+        size_label: "3.10 GB",
+        size_bytes: (3.10 * GIB) as u64,
+        folder: "whisper-cpp-large-v3",
+        hf_repo: "ggerganov/whisper.cpp",
+        backend: ModelBackend::WhisperCpp,
+        ggml_url: Some(
+            "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-large-v3.bin",
+        ),
+        memory_mb: 4_500,
     },
 ];
 
+/// A local model folder registered with `add_custom_model`, e.g. a
+/// fine-tuned or gated checkpoint. It is used where it is; nothing is copied
+/// or downloaded.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomModel {
+    /// `custom-` followed by the folder name.
+    pub id: String,
+    pub name: String,
+    pub path: PathBuf,
+    pub backend: ModelBackend,
+}
+
+const CUSTOM_ID_PREFIX: &str = "custom-";
+
 #[derive(Clone, Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ModelOption {
     pub id: String,
     pub name: String,
+    /// Deprecated: preformatted `size_bytes` (`"0.81 GB"`, or `"local"` for
+    /// custom models). Kept for existing consumers; format `size_bytes`
+    /// instead.
     pub size_label: String,
+    /// Download size, or what a custom model's folder holds on disk.
+    pub size_bytes: u64,
+    /// Unified memory a session needs, in bytes. `None` for custom models,
+    /// where it is unknown.
+    pub memory_bytes: Option<u64>,
+    pub backend: ModelBackend,
+    /// Registered with `add_custom_model` rather than shipped with the app.
+    pub custom: bool,
 }
 
 pub fn default_model_id() -> &'static str {
@@ -49,13 +182,163 @@ pub fn model_path(app_data_dir: &Path, model_id: &str) -> Option<PathBuf> {
     Some(app_data_dir.join("models").join(model.folder))
 }
 
-pub fn model_options() -> Vec<ModelOption> {
+/// Files of `model` in a Hugging Face hub cache the user already has
+/// (`HF_HUB_CACHE`, `HF_HOME/hub` or `~/.cache/huggingface/hub`), as pairs of
+/// name inside the model folder and cached file. `None` unless a cache holds
+/// everything the model needs.
+pub fn find_in_hf_cache(model: &ModelSpec) -> Option<Vec<(String, PathBuf)>> {
+    let repo_dir = format!("models--{}", model.hf_repo.replace('/', "--"));
+    hf_hub_dirs().into_iter().find_map(|hub| {
+        let snapshot = cached_snapshot(&hub.join(&repo_dir))?;
+        cached_files(model, &snapshot)
+    })
+}
+
+fn hf_hub_dirs() -> Vec<PathBuf> {
+    let mut hubs = Vec::new();
+    if let Some(cache) = std::env::var_os("HF_HUB_CACHE") {
+        hubs.push(PathBuf::from(cache));
+    }
+    if let Some(home) = std::env::var_os("HF_HOME") {
+        hubs.push(PathBuf::from(home).join("hub"));
+    }
+    if let Some(home) = dirs::home_dir() {
+        hubs.push(home.join(".cache/huggingface/hub"));
+    }
+    hubs
+}
+
+/// The snapshot `refs/main` points at, or else the newest one.
+fn cached_snapshot(repo_dir: &Path) -> Option<PathBuf> {
+    let snapshots = repo_dir.join("snapshots");
+    let main = std::fs::read_to_string(repo_dir.join("refs/main"))
+        .ok()
+        .map(|revision| snapshots.join(revision.trim()))
+        .filter(|snapshot| snapshot.is_dir());
+    main.or_else(|| {
+        std::fs::read_dir(&snapshots)
+            .ok()?
+            .flatten()
+            .filter(|entry| entry.path().is_dir())
+            .max_by_key(|entry| entry.metadata().and_then(|meta| meta.modified()).ok())
+            .map(|entry| entry.path())
+    })
+}
+
+/// Snapshot files are symlinks into `blobs/`; `is_file` skips the ones
+/// whose blob never finished downloading.
+fn cached_files(model: &ModelSpec, snapshot: &Path) -> Option<Vec<(String, PathBuf)>> {
+    if let Some(url) = model.ggml_url {
+        let cached = snapshot.join(url.rsplit('/').next()?);
+        return cached
+            .is_file()
+            .then(|| vec![(GGML_MODEL_FILE.to_string(), cached)]);
+    }
+
+    let files = std::fs::read_dir(snapshot)
+        .ok()?
+        .flatten()
+        .filter(|entry| entry.path().is_file())
+        .map(|entry| {
+            (
+                entry.file_name().to_string_lossy().to_string(),
+                entry.path(),
+            )
+        })
+        .collect::<Vec<_>>();
+    let has_config = files.iter().any(|(name, _)| name == "config.json");
+    let has_weights = files
+        .iter()
+        .any(|(name, _)| name.starts_with("weights.") || name.starts_with("model"));
+    (has_config && has_weights).then_some(files)
+}
+
+/// Folder of a built-in or custom model.
+pub fn resolve_path(
+    app_data_dir: &Path,
+    custom: &[CustomModel],
+    model_id: &str,
+) -> Option<PathBuf> {
+    model_path(app_data_dir, model_id).or_else(|| {
+        custom
+            .iter()
+            .find(|model| model.id == model_id)
+            .map(|model| model.path.clone())
+    })
+}
+
+pub fn backend(custom: &[CustomModel], model_id: &str) -> Option<ModelBackend> {
+    find_model(model_id).map(|model| model.backend).or_else(|| {
+        custom
+            .iter()
+            .find(|model| model.id == model_id)
+            .map(|model| model.backend)
+    })
+}
+
+/// Describes the model folder at `path` for `add_custom_model`. The caller
+/// checks it with `is_model_installed` first; a `ggml-model.bin` makes it a
+/// whisper.cpp model, anything else runs in the MLX worker. The id is made
+/// unique against `existing` unless the same folder is already registered,
+/// in which case that entry is returned.
+pub fn custom_model(path: &Path, name: Option<&str>, existing: &[CustomModel]) -> CustomModel {
+    if let Some(model) = existing.iter().find(|model| model.path == path) {
+        return model.clone();
+    }
+
+    let folder = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "model".to_string());
+    let slug = folder
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect::<String>();
+    let base = format!("{CUSTOM_ID_PREFIX}{}", slug.trim_matches('-'));
+    let mut id = base.clone();
+    let mut suffix = 2;
+    while existing.iter().any(|model| model.id == id) {
+        id = format!("{base}-{suffix}");
+        suffix += 1;
+    }
+
+    CustomModel {
+        id,
+        name: name
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(str::to_string)
+            .unwrap_or(folder),
+        path: path.to_path_buf(),
+        backend: if path.join(GGML_MODEL_FILE).is_file() {
+            ModelBackend::WhisperCpp
+        } else {
+            ModelBackend::Mlx
+        },
+    }
+}
+
+pub fn model_options(custom: &[CustomModel]) -> Vec<ModelOption> {
     MODEL_SPECS
         .iter()
         .map(|model| ModelOption {
             id: model.id.to_string(),
             name: model.name.to_string(),
             size_label: model.size_label.to_string(),
+            size_bytes: model.size_bytes,
+            memory_bytes: Some(model.memory_mb * 1024 * 1024),
+            backend: model.backend,
+            custom: false,
         })
+        .chain(custom.iter().map(|model| ModelOption {
+            id: model.id.clone(),
+            name: model.name.clone(),
+            size_label: "local".to_string(),
+            size_bytes: storage::disk_size(&model.path),
+            memory_bytes: None,
+            backend: model.backend,
+            custom: true,
+        }))
         .collect()
 }
@@ -0,0 +1,95 @@
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Context};
+use tauri::AppHandle;
+use tokio::{process::Command, sync::mpsc};
+
+use crate::{
+    app_state::{emit_state, AppStatus, SessionNote, SharedState},
+    errors::{self, ErrorKind},
+    hotkey, transcript_file,
+};
+
+/// Screenshots the captured display and pins it to the transcript at the
+/// current session time, so the saved Markdown shows what was on screen when
+/// it was said. Images go to
+/// `~/Documents/WhisperBar/Transcript-<started>-moments/Moment-HH-MM-SS.png`
+/// and are linked relative to the output directory. Returns the image path.
+pub async fn capture(app: &AppHandle, state: &SharedState) -> anyhow::Result<PathBuf> {
+    let (at, started) = {
+        let session = state.session.lock().await;
+        match (
+            session.status,
+            session.recording_started_at,
+            session.recording_started_local,
+        ) {
+            (AppStatus::Recording, Some(started_at), Some(started)) => {
+                (started_at.elapsed().as_secs_f64(), started)
+            }
+            _ => return Err(anyhow!("recording is not active")),
+        }
+    };
+
+    let folder = format!("Transcript-{}-moments", started.format("%Y-%m-%d-%H-%M"));
+    let dir = transcript_file::output_dir()?.join(&folder);
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .with_context(|| format!("failed creating {}", dir.display()))?;
+    let name = format!(
+        "Moment-{}.png",
+        transcript_file::format_timestamp(at).replace(':', "-")
+    );
+    let path = dir.join(&name);
+
+    // `screencapture` goes through ScreenCaptureKit, so it needs the Screen
+    // Recording permission desktop audio already asked for. Display 1 is the
+    // main display, the one the audio helper captures.
+    let output = Command::new("screencapture")
+        .args(["-x", "-t", "png", "-D", "1"])
+        .arg(&path)
+        .output()
+        .await
+        .context("failed starting screencapture")?;
+    if !output.status.success() || !path.is_file() {
+        return Err(anyhow!(
+            "screenshot failed: {}. Check Screen Recording permission in macOS settings.",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    state.transcript.lock().await.notes.push(SessionNote {
+        at,
+        text: String::new(),
+        image: Some(format!("{folder}/{name}")),
+    });
+    emit_state(app, state).await;
+    Ok(path)
+}
+
+/// Binds ⌥⇧⌘S to `capture` for the lifetime of the app. Presses outside a
+/// recording, or while it is stopping, do nothing.
+pub fn start_hotkey(app: &AppHandle, state: &SharedState) -> anyhow::Result<()> {
+    let (presses, mut receiver) = mpsc::unbounded_channel();
+    hotkey::register(
+        hotkey::KEY_S,
+        hotkey::COMMAND | hotkey::SHIFT | hotkey::OPTION,
+        move || {
+            let _ = presses.send(());
+        },
+    )?;
+
+    let app = app.clone();
+    let state = state.clone();
+    tauri::async_runtime::spawn(async move {
+        while receiver.recv().await.is_some() {
+            if !state.availability().await.can_stop {
+                continue;
+            }
+            if let Err(error) = capture(&app, &state).await {
+                errors::report(&app, &state, ErrorKind::Moment, error.to_string(), |_| {}).await;
+            }
+        }
+    });
+
+    Ok(())
+}
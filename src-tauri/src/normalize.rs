@@ -0,0 +1,169 @@
+use std::sync::OnceLock;
+
+use regex::{Captures, Regex};
+
+/// Applies the output conventions of `language` to a transcribed segment.
+/// Model output already follows English conventions, so only pt-BR is
+/// rewritten.
+pub fn apply(text: &str, language: &str) -> String {
+    match language {
+        "pt-BR" => portuguese(text),
+        _ => text.to_string(),
+    }
+}
+
+fn portuguese(text: &str) -> String {
+    let text = expand_chat_abbreviations(text);
+    let text = decimal_comma(&text);
+    capitalize_titles(&text)
+}
+
+fn regex(cell: &'static OnceLock<Regex>, pattern: &str) -> &'static Regex {
+    cell.get_or_init(|| Regex::new(pattern).expect("invalid normalization pattern"))
+}
+
+/// `vc` -> `você`, `tbm` -> `também`, keeping a leading capital. Only the
+/// lowercase and capitalized forms match, so units like `2 TB` are kept.
+fn expand_chat_abbreviations(text: &str) -> String {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    regex(&PATTERN, r"\b([vV]cs|[vV]c|[tT]bm|[tT]b)\b")
+        .replace_all(text, |caps: &Captures| {
+            let word = &caps[1];
+            let expanded = match word.to_lowercase().as_str() {
+                "vcs" => "vocês",
+                "vc" => "você",
+                _ => "também",
+            };
+            if word.starts_with(char::is_uppercase) {
+                capitalize(expanded)
+            } else {
+                expanded.to_string()
+            }
+        })
+        .into_owned()
+}
+
+/// `3.5` -> `3,5`. Three-digit groups (`1.500`) are thousands separators in
+/// Portuguese and dotted sequences (`2.0.1`) are versions; both are kept.
+fn decimal_comma(text: &str) -> String {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    let pattern = regex(&PATTERN, r"\b(\d+)\.(\d+)\b");
+
+    let mut output = String::with_capacity(text.len());
+    let mut last = 0;
+    for caps in pattern.captures_iter(text) {
+        let whole = caps.get(0).expect("match");
+        let before = &text[..whole.start()];
+        let after = &text[whole.end()..];
+        let dotted = before.ends_with('.')
+            || after
+                .strip_prefix('.')
+                .is_some_and(|rest| rest.starts_with(|c: char| c.is_ascii_digit()));
+
+        output.push_str(&text[last..whole.start()]);
+        if caps[2].len() == 3 || dotted {
+            output.push_str(whole.as_str());
+        } else {
+            output.push_str(&format!("{},{}", &caps[1], &caps[2]));
+        }
+        last = whole.end();
+    }
+    output.push_str(&text[last..]);
+    output
+}
+
+/// `dr. silva` -> `Dr. Silva`: the title and the name after it are capitalized.
+fn capitalize_titles(text: &str) -> String {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    regex(
+        &PATTERN,
+        r"(?i)\b(dr|dra|sr|sra|srta|prof|profa|eng)\.(\s+)(\w+)",
+    )
+    .replace_all(text, |caps: &Captures| {
+        format!(
+            "{}.{}{}",
+            capitalize(&caps[1]),
+            &caps[2],
+            capitalize(&caps[3])
+        )
+    })
+    .into_owned()
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pt(text: &str) -> String {
+        apply(text, "pt-BR")
+    }
+
+    #[test]
+    fn english_is_left_alone() {
+        let text = "vc said 3.5 to dr. smith";
+        assert_eq!(apply(text, "en"), text);
+    }
+
+    #[test]
+    fn chat_abbreviations_are_expanded() {
+        assert_eq!(pt("vc vai tbm?"), "você vai também?");
+        assert_eq!(pt("vcs viram? eu tb"), "vocês viram? eu também");
+        assert_eq!(
+            pt("Vc sabe onde fica. Tbm não sei"),
+            "Você sabe onde fica. Também não sei"
+        );
+    }
+
+    #[test]
+    fn abbreviations_inside_words_are_kept() {
+        assert_eq!(
+            pt("o arquivo vcxproj e o tbmx"),
+            "o arquivo vcxproj e o tbmx"
+        );
+    }
+
+    #[test]
+    fn uppercase_units_are_not_abbreviations() {
+        assert_eq!(
+            pt("o disco tem 2 TB e o VC da empresa"),
+            "o disco tem 2 TB e o VC da empresa"
+        );
+    }
+
+    #[test]
+    fn decimals_take_a_comma() {
+        assert_eq!(
+            pt("a inflação foi de 3.5 por cento"),
+            "a inflação foi de 3,5 por cento"
+        );
+        assert_eq!(pt("subiu 0.25 ponto."), "subiu 0,25 ponto.");
+    }
+
+    #[test]
+    fn thousands_and_versions_are_kept() {
+        assert_eq!(pt("vieram 1.500 pessoas"), "vieram 1.500 pessoas");
+        assert_eq!(
+            pt("atualize para a 2.0.1 hoje"),
+            "atualize para a 2.0.1 hoje"
+        );
+        assert_eq!(pt("a 10.4.3 corrige o 3.5"), "a 10.4.3 corrige o 3,5");
+    }
+
+    #[test]
+    fn titles_and_names_are_capitalized() {
+        assert_eq!(
+            pt("falei com o dr. silva e a sra. costa"),
+            "falei com o Dr. Silva e a Sra. Costa"
+        );
+        assert_eq!(pt("a profa. ângela chegou"), "a Profa. Ângela chegou");
+        assert_eq!(pt("Dra. Ana já sabe"), "Dra. Ana já sabe");
+    }
+}
@@ -0,0 +1,104 @@
+use std::{collections::HashMap, time::Duration};
+
+use anyhow::Context;
+use tokio::process::Command;
+
+use crate::app_state::AppPaths;
+
+/// Grace period between SIGTERM and SIGKILL.
+const TERMINATE_GRACE: Duration = Duration::from_secs(2);
+
+struct ProcessInfo {
+    pid: u32,
+    ppid: u32,
+    command: String,
+}
+
+/// Terminates workers (Python and `--native-worker`) and `--sck-audio-helper`
+/// captures left behind by a previous run that crashed or was force quit,
+/// plus anything they spawned (the microphone ffmpeg, a native worker's
+/// capture helper). A lingering capture keeps the screen-recording
+/// indicator lit with no WhisperBar session running.
+///
+/// Only processes reparented to launchd count as orphans, so the helpers of
+/// another running WhisperBar are left alone. Returns how many were stopped.
+pub async fn reap(paths: &AppPaths) -> anyhow::Result<usize> {
+    let processes = list_processes().await?;
+
+    let exe = std::env::current_exe()
+        .ok()
+        .map(|path| path.display().to_string());
+    let worker_script = paths.worker_script.display().to_string();
+    let ours = |process: &ProcessInfo| {
+        process.command.contains(&worker_script)
+            || exe.as_ref().is_some_and(|exe| {
+                process.command.starts_with(exe.as_str())
+                    && (process.command.contains("--sck-audio-helper")
+                        || process.command.contains("--native-worker"))
+            })
+    };
+
+    let mut orphans = processes
+        .iter()
+        .filter(|process| process.ppid == 1 && ours(process))
+        .map(|process| (process.pid, process.command.as_str()))
+        .collect::<HashMap<_, _>>();
+
+    // Pull in everything the orphans spawned.
+    loop {
+        let before = orphans.len();
+        for process in &processes {
+            if orphans.contains_key(&process.ppid) {
+                orphans.insert(process.pid, &process.command);
+            }
+        }
+        if orphans.len() == before {
+            break;
+        }
+    }
+
+    if orphans.is_empty() {
+        return Ok(0);
+    }
+
+    let pids = orphans.keys().map(u32::to_string).collect::<Vec<_>>();
+    signal("-TERM", &pids).await;
+    tokio::time::sleep(TERMINATE_GRACE).await;
+
+    // A pid that exited during the grace period may already belong to an
+    // unrelated process; only one still running the same command is killed.
+    let survivors = list_processes()
+        .await?
+        .into_iter()
+        .filter(|process| orphans.get(&process.pid) == Some(&process.command.as_str()))
+        .map(|process| process.pid.to_string())
+        .collect::<Vec<_>>();
+    if !survivors.is_empty() {
+        signal("-KILL", &survivors).await;
+    }
+
+    Ok(orphans.len())
+}
+
+async fn list_processes() -> anyhow::Result<Vec<ProcessInfo>> {
+    let output = Command::new("ps")
+        .args(["-axo", "pid=,ppid=,command="])
+        .output()
+        .await
+        .context("failed listing processes")?;
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let pid = fields.next()?.parse().ok()?;
+            let ppid = fields.next()?.parse().ok()?;
+            let command = fields.collect::<Vec<_>>().join(" ");
+            Some(ProcessInfo { pid, ppid, command })
+        })
+        .collect())
+}
+
+async fn signal(signal: &str, pids: &[String]) {
+    let _ = Command::new("kill").arg(signal).args(pids).status().await;
+}
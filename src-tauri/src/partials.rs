@@ -0,0 +1,141 @@
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+use tracing::Instrument;
+
+use crate::{
+    app_state::{emit_state, AppStatus, SharedState, TranscriptSegment},
+    pipeline_timings::{self, Stage},
+};
+
+pub const DEFAULT_UPDATES_PER_SECOND: u32 = 4;
+pub const MAX_UPDATES_PER_SECOND: u32 = 30;
+
+/// Carries the timed words of the newest segment on each flush, so live
+/// windows can highlight them as they are spoken back.
+pub const LIVE_WORDS_EVENT: &str = "whisperbar://live-words";
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PartialMetrics {
+    pub events_received: u64,
+    pub state_updates: u64,
+    pub total_lock_hold_micros: u64,
+    pub max_lock_hold_micros: u64,
+    /// Segments the hallucination filter dropped instead of adding.
+    pub segments_suppressed: u64,
+}
+
+impl PartialMetrics {
+    fn record_flush(&mut self, events: u64, held: Duration) {
+        let held_micros = held.as_micros().min(u64::MAX as u128) as u64;
+        self.events_received += events;
+        self.state_updates += 1;
+        self.total_lock_hold_micros += held_micros;
+        self.max_lock_hold_micros = self.max_lock_hold_micros.max(held_micros);
+    }
+}
+
+pub fn is_valid_rate(updates_per_second: u32) -> bool {
+    (1..=MAX_UPDATES_PER_SECOND).contains(&updates_per_second)
+}
+
+/// Buffers partial transcript segments from the worker and applies them to
+/// the shared state at most `updates_per_second` times per second.
+pub struct PartialCoalescer {
+    pending: Vec<TranscriptSegment>,
+    /// When the oldest pending segment arrived.
+    pending_since: Option<Instant>,
+    interval: Duration,
+    last_flush: Option<Instant>,
+}
+
+impl PartialCoalescer {
+    pub fn new(updates_per_second: u32) -> Self {
+        let rate = updates_per_second.clamp(1, MAX_UPDATES_PER_SECOND);
+        Self {
+            pending: Vec::new(),
+            pending_since: None,
+            interval: Duration::from_secs(1) / rate,
+            last_flush: None,
+        }
+    }
+
+    pub fn push(&mut self, mut segment: TranscriptSegment) {
+        let trimmed = segment.text.trim();
+        if trimmed.is_empty() {
+            return;
+        }
+
+        if trimmed.len() != segment.text.len() {
+            segment.text = trimmed.to_string();
+        }
+        self.pending_since.get_or_insert_with(Instant::now);
+        self.pending.push(segment);
+    }
+
+    /// When the pending text should be flushed, or `None` if nothing is queued.
+    pub fn deadline(&self) -> Option<Instant> {
+        if self.pending.is_empty() {
+            return None;
+        }
+
+        Some(
+            self.last_flush
+                .map(|last| last + self.interval)
+                .unwrap_or_else(Instant::now),
+        )
+    }
+
+    pub async fn flush(&mut self, app: &AppHandle, state: &SharedState) {
+        if self.pending.is_empty() {
+            return;
+        }
+
+        let merged = std::mem::take(&mut self.pending);
+        let events = merged.len() as u64;
+        let live_words = merged
+            .iter()
+            .rev()
+            .find(|segment| !segment.words.is_empty())
+            .map(|segment| segment.words.clone());
+        self.last_flush = Some(Instant::now());
+        if let Some(since) = self.pending_since.take() {
+            pipeline_timings::record(Stage::Coalesce, since.elapsed());
+        }
+
+        async {
+            let mut transcript = state.transcript.lock().await;
+            let acquired_at = Instant::now();
+
+            transcript.segments.extend(merged);
+            transcript
+                .partial_metrics
+                .record_flush(events, acquired_at.elapsed());
+        }
+        .instrument(tracing::info_span!("pipeline.state_update"))
+        .await;
+
+        {
+            let mut session = state.session.lock().await;
+            if session.status == AppStatus::Recording {
+                session.status_message = "Recording".to_string();
+            }
+        }
+
+        emit_state(app, state)
+            .instrument(tracing::info_span!("pipeline.emit"))
+            .await;
+        if let Some(words) = live_words {
+            let _ = app.emit(LIVE_WORDS_EVENT, words);
+        }
+    }
+}
+
+pub async fn wait_for_deadline(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline.into()).await,
+        None => std::future::pending().await,
+    }
+}
@@ -0,0 +1,174 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::{Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+use tracing::{
+    span::{Attributes, Id},
+    Subscriber,
+};
+use tracing_subscriber::{
+    layer::{Context, SubscriberExt},
+    registry::LookupSpan,
+    Layer,
+};
+
+/// Samples kept per stage; older ones are dropped.
+const MAX_SAMPLES: usize = 512;
+
+/// Where a transcript line spends its time, from captured audio to the
+/// snapshot the windows render.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Stage {
+    /// In the worker: end of the captured chunk to the start of inference
+    /// (mixing, voice detection, earlier streams of the same chunk).
+    Capture,
+    /// In the worker: running the model on the chunk.
+    Inference,
+    /// Worker stdout to the app parsing the line.
+    Transport,
+    /// `handle_worker_event` for a partial.
+    HandleEvent,
+    /// Waiting in the `PartialCoalescer` for the next flush.
+    Coalesce,
+    /// Applying a flush to the transcript under its lock.
+    StateUpdate,
+    /// Building and emitting the snapshot.
+    Emit,
+}
+
+impl Stage {
+    /// Span names the layer records; other spans are ignored.
+    fn from_span(name: &str) -> Option<Self> {
+        match name {
+            "pipeline.handle_event" => Some(Self::HandleEvent),
+            "pipeline.state_update" => Some(Self::StateUpdate),
+            "pipeline.emit" => Some(Self::Emit),
+            _ => None,
+        }
+    }
+}
+
+/// Stage times the worker attaches to each `partial`.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkerTimings {
+    pub capture_ms: f64,
+    pub inference_ms: f64,
+    /// Unix time in seconds the worker wrote the line.
+    pub sent_at: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StageTiming {
+    pub stage: Stage,
+    pub samples: usize,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub max_ms: f64,
+}
+
+static SAMPLES: Mutex<BTreeMap<Stage, VecDeque<f64>>> = Mutex::new(BTreeMap::new());
+
+fn samples() -> MutexGuard<'static, BTreeMap<Stage, VecDeque<f64>>> {
+    SAMPLES
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+pub fn record(stage: Stage, elapsed: Duration) {
+    record_ms(stage, elapsed.as_secs_f64() * 1000.0);
+}
+
+fn record_ms(stage: Stage, ms: f64) {
+    if !ms.is_finite() || ms < 0.0 {
+        return;
+    }
+    let mut samples = samples();
+    let stage_samples = samples.entry(stage).or_default();
+    if stage_samples.len() == MAX_SAMPLES {
+        stage_samples.pop_front();
+    }
+    stage_samples.push_back(ms);
+}
+
+/// Records the worker-side stages of a `partial`, and how long its line took
+/// to reach the app.
+pub fn record_worker(timings: &WorkerTimings) {
+    record_ms(Stage::Capture, timings.capture_ms);
+    record_ms(Stage::Inference, timings.inference_ms);
+    let now = chrono::Utc::now().timestamp_micros() as f64 / 1_000_000.0;
+    record_ms(Stage::Transport, (now - timings.sent_at) * 1000.0);
+}
+
+/// Starts a new session's numbers.
+pub fn reset() {
+    samples().clear();
+}
+
+/// p50/p95/max per stage since the current session started, in pipeline order.
+pub fn report() -> Vec<StageTiming> {
+    samples()
+        .iter()
+        .filter(|(_, values)| !values.is_empty())
+        .map(|(stage, values)| {
+            let mut sorted = values.iter().copied().collect::<Vec<_>>();
+            sorted.sort_by(f64::total_cmp);
+            StageTiming {
+                stage: *stage,
+                samples: sorted.len(),
+                p50_ms: percentile(&sorted, 0.50),
+                p95_ms: percentile(&sorted, 0.95),
+                max_ms: sorted[sorted.len() - 1],
+            }
+        })
+        .collect()
+}
+
+fn percentile(sorted: &[f64], quantile: f64) -> f64 {
+    let rank = ((sorted.len() - 1) as f64 * quantile).round() as usize;
+    sorted[rank]
+}
+
+/// Records how long each `pipeline.*` span stays open, from creation to
+/// close, so awaits inside it count.
+struct PipelineLayer;
+
+struct Opened(Instant);
+
+impl<S> Layer<S> for PipelineLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        if Stage::from_span(attrs.metadata().name()).is_none() {
+            return;
+        }
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(Opened(Instant::now()));
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+        let Some(stage) = Stage::from_span(span.name()) else {
+            return;
+        };
+        if let Some(opened) = span.extensions().get::<Opened>() {
+            record(stage, opened.0.elapsed());
+        }
+    }
+}
+
+/// Installs the layer as the global subscriber. A no-op if one is already
+/// set.
+pub fn install() {
+    let subscriber = tracing_subscriber::registry().with(PipelineLayer);
+    let _ = tracing::subscriber::set_global_default(subscriber);
+}
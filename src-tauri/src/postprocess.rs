@@ -0,0 +1,196 @@
+use std::{path::PathBuf, time::Duration};
+
+use anyhow::{bail, Context};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::{
+    app_state::{update_session, AppStatus, SharedState, TranscriptSegment},
+    errors::{self, ErrorKind},
+    llm::{Llm, LlmSummary},
+    transcript_file,
+};
+
+/// Replaced with the transcript in every prompt.
+const TRANSCRIPT_TOKEN: &str = "{transcript}";
+
+pub const MAX_STEPS: usize = 8;
+
+/// Per step; a local model can take a while over a long meeting.
+const TIMEOUT: Duration = Duration::from_secs(180);
+
+/// Transcript text sent with each prompt, to stay inside a small local
+/// model's context.
+const MAX_TRANSCRIPT_CHARS: usize = 32_000;
+
+const MAX_TOKENS: u32 = 1_024;
+
+const DEFAULT_STEPS: &str = "## Summary
+Summarize this transcript in one short paragraph. Use the transcript's language.
+
+{transcript}
+
+## Action Items
+List the action items in this transcript as a Markdown bullet list, with the owner in bold when one is named. Reply `None` if there are none. Use the transcript's language.
+
+{transcript}
+";
+
+/// One section appended to the transcript: its heading and the prompt whose
+/// answer fills it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Step {
+    pub heading: String,
+    pub prompt: String,
+}
+
+/// The post-processing run after stop (`set_postprocess`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Postprocess {
+    pub llm: Llm,
+    pub steps: Vec<Step>,
+}
+
+/// What snapshots show of `Postprocess`: the steps in the format `parse`
+/// reads.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PostprocessSummary {
+    pub llm: LlmSummary,
+    pub steps: String,
+}
+
+impl Postprocess {
+    pub fn summary(&self) -> PostprocessSummary {
+        PostprocessSummary {
+            llm: self.llm.summary(),
+            steps: to_text(&self.steps),
+        }
+    }
+}
+
+/// A summary and an action item list.
+pub fn default_steps() -> Vec<Step> {
+    parse(DEFAULT_STEPS).expect("default post-processing steps are valid")
+}
+
+/// Parses steps written as a `## Heading` line followed by the prompt, which
+/// runs until the next heading and has to contain `{transcript}`.
+pub fn parse(text: &str) -> anyhow::Result<Vec<Step>> {
+    let mut steps: Vec<Step> = Vec::new();
+    for line in text.lines() {
+        if let Some(heading) = line.strip_prefix("## ") {
+            steps.push(Step {
+                heading: heading.trim().to_string(),
+                prompt: String::new(),
+            });
+            continue;
+        }
+        match steps.last_mut() {
+            Some(step) => {
+                step.prompt.push_str(line);
+                step.prompt.push('\n');
+            }
+            None if line.trim().is_empty() => {}
+            None => bail!("start each step with a `## Heading` line"),
+        }
+    }
+    for step in &mut steps {
+        step.prompt = step.prompt.trim().to_string();
+    }
+
+    if steps.is_empty() {
+        bail!("add at least one `## Heading` step");
+    }
+    if steps.len() > MAX_STEPS {
+        bail!("post-processing runs at most {MAX_STEPS} steps");
+    }
+    for step in &steps {
+        if step.heading.is_empty() {
+            bail!("every step needs a heading");
+        }
+        if !step.prompt.contains(TRANSCRIPT_TOKEN) {
+            bail!(
+                "the `{}` prompt needs a {TRANSCRIPT_TOKEN} placeholder",
+                step.heading
+            );
+        }
+    }
+    Ok(steps)
+}
+
+/// Writes `steps` in the format `parse` reads.
+pub fn to_text(steps: &[Step]) -> String {
+    steps
+        .iter()
+        .map(|step| format!("## {}\n{}\n", step.heading, step.prompt))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Runs every step over a saved transcript in the background and appends
+/// the answers to it as sections, all at once when the last one is in. A
+/// failed step leaves the file as it was and raises a `postprocess` warning.
+pub fn spawn(
+    app: &AppHandle,
+    state: &SharedState,
+    config: Postprocess,
+    path: PathBuf,
+    segments: Vec<TranscriptSegment>,
+) {
+    let app = app.clone();
+    let state = state.clone();
+    tauri::async_runtime::spawn(async move {
+        match run(&config, &path, &segments).await {
+            Ok(()) => {
+                let headings = config
+                    .steps
+                    .iter()
+                    .map(|step| step.heading.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                update_session(&app, &state, move |session| {
+                    if matches!(session.status, AppStatus::Ready | AppStatus::Idle) {
+                        session.status_message = format!("Added {headings} to the transcript");
+                    }
+                })
+                .await;
+            }
+            Err(error) => {
+                errors::report(
+                    &app,
+                    &state,
+                    ErrorKind::Postprocess,
+                    format!("Could not post-process the transcript: {error:#}"),
+                    |_| {},
+                )
+                .await;
+            }
+        }
+    });
+}
+
+async fn run(
+    config: &Postprocess,
+    path: &std::path::Path,
+    segments: &[TranscriptSegment],
+) -> anyhow::Result<()> {
+    let transcript = transcript_file::render_plain(segments)
+        .chars()
+        .take(MAX_TRANSCRIPT_CHARS)
+        .collect::<String>();
+
+    let mut sections = Vec::with_capacity(config.steps.len());
+    for step in &config.steps {
+        let prompt = step.prompt.replace(TRANSCRIPT_TOKEN, &transcript);
+        let answer = config
+            .llm
+            .complete(&prompt, MAX_TOKENS, TIMEOUT)
+            .await
+            .with_context(|| format!("{} failed", step.heading))?;
+        sections.push(format!("## {}\n\n{}", step.heading, answer.trim()));
+    }
+    transcript_file::append_sections(path, &sections).await
+}
@@ -0,0 +1,170 @@
+use std::{
+    collections::VecDeque,
+    process::Stdio,
+    sync::{Arc, Mutex as StdMutex, PoisonError},
+};
+
+use tauri::async_runtime::JoinHandle;
+use tokio::{
+    io::AsyncReadExt,
+    process::{Child, Command},
+};
+
+use crate::{
+    app_state::{AppStatus, SharedState},
+    audio,
+    engine::EngineInput,
+};
+
+pub const MAX_PREROLL_SECONDS: u32 = 60;
+/// 16 kHz mono s16le, the format the worker transcribes.
+const BYTES_PER_SECOND: usize = 16_000 * 2;
+
+/// Always-on microphone ring buffer that holds the last N seconds before a
+/// recording starts. The audio never leaves memory: it is handed to the
+/// engine and dropped.
+#[derive(Default)]
+pub struct PrerollState {
+    capture: Option<Capture>,
+}
+
+struct Capture {
+    device: String,
+    seconds: u32,
+    child: Child,
+    buffer: Arc<StdMutex<VecDeque<u8>>>,
+    reader: JoinHandle<()>,
+}
+
+impl Capture {
+    async fn stop(mut self) -> Vec<u8> {
+        self.reader.abort();
+        let _ = self.child.kill().await;
+        let mut buffer = self.buffer.lock().unwrap_or_else(PoisonError::into_inner);
+        buffer.drain(..).collect()
+    }
+}
+
+/// Starts, restarts or stops the capture to match the current settings. Runs
+/// only while no recording is active.
+pub async fn sync(state: &SharedState) {
+    let (seconds, selected_mic) = {
+        let settings = state.settings.lock().await;
+        (
+            settings.preroll_seconds,
+            settings.selected_mic_device.clone(),
+        )
+    };
+    let wanted = seconds > 0 && state.status().await != AppStatus::Recording;
+
+    let device = if wanted {
+        resolve_mic(selected_mic)
+    } else {
+        None
+    };
+
+    let mut preroll = state.preroll.lock().await;
+    let unchanged = preroll.capture.as_ref().is_some_and(|capture| {
+        Some(&capture.device) == device.as_ref() && capture.seconds == seconds
+    });
+    if unchanged {
+        return;
+    }
+
+    if let Some(capture) = preroll.capture.take() {
+        capture.stop().await;
+    }
+    if let Some(device) = device {
+        preroll.capture = start(device, seconds).ok();
+    }
+}
+
+pub async fn is_active(state: &SharedState) -> bool {
+    state.preroll.lock().await.capture.is_some()
+}
+
+/// Stops the capture and returns what it buffered.
+pub async fn take(state: &SharedState) -> Vec<u8> {
+    let capture = state.preroll.lock().await.capture.take();
+    match capture {
+        Some(capture) => capture.stop().await,
+        None => Vec::new(),
+    }
+}
+
+/// Sends the buffered audio to a worker started with `--preroll`. Called when
+/// the worker reports that capture began, so nothing between pressing record
+/// and the worker opening the microphone is lost.
+pub async fn send_to_worker(state: &SharedState) {
+    let audio = take(state).await;
+
+    let mut session = state.session.lock().await;
+    if let Some(worker) = session.worker.as_mut() {
+        let _ = worker.engine.feed(EngineInput::Preroll(audio)).await;
+    }
+}
+
+/// The ffmpeg capture name of the selected (or default) microphone.
+fn resolve_mic(selected: Option<String>) -> Option<String> {
+    let devices = audio::list_audio_devices().ok()?;
+    let id = match selected {
+        Some(selected) => selected,
+        None => audio::choose_default_mic(&devices)?,
+    };
+    audio::capture_name(&devices, &id)
+}
+
+fn start(device: String, seconds: u32) -> anyhow::Result<Capture> {
+    let mut child = Command::new("ffmpeg")
+        .arg("-hide_banner")
+        .arg("-loglevel")
+        .arg("error")
+        .arg("-f")
+        .arg("avfoundation")
+        .arg("-i")
+        .arg(format!(":{device}"))
+        .arg("-ac")
+        .arg("1")
+        .arg("-ar")
+        .arg("16000")
+        .arg("-f")
+        .arg("s16le")
+        .arg("-")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()?;
+
+    let mut stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| anyhow::anyhow!("unable to capture pre-roll stdout"))?;
+    let capacity = seconds as usize * BYTES_PER_SECOND;
+    let buffer = Arc::new(StdMutex::new(VecDeque::with_capacity(capacity)));
+
+    let reader_buffer = buffer.clone();
+    let reader = tauri::async_runtime::spawn(async move {
+        let mut chunk = vec![0u8; BYTES_PER_SECOND / 10];
+        while let Ok(read) = stdout.read(&mut chunk).await {
+            if read == 0 {
+                break;
+            }
+
+            let mut buffer = reader_buffer.lock().unwrap_or_else(PoisonError::into_inner);
+            buffer.extend(&chunk[..read]);
+            // Drop whole samples only so the buffer stays 2-byte aligned.
+            let len = buffer.len();
+            let overflow = (len.saturating_sub(capacity) + 1) & !1;
+            buffer.drain(..overflow.min(len));
+        }
+    });
+
+    Ok(Capture {
+        device,
+        seconds,
+        child,
+        buffer,
+        reader,
+    })
+}
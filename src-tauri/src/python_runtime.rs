@@ -0,0 +1,157 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context};
+use tokio::process::Command;
+
+use crate::{bootstrap::DownloadNetwork, integrity};
+
+/// python-build-standalone release the bootstrap script and the worker's
+/// venv run on. Bumping either constant installs the new runtime next launch.
+const RELEASE: &str = "20241016";
+const PYTHON_VERSION: &str = "3.12.7";
+const RELEASE_URL: &str = "https://github.com/indygreg/python-build-standalone/releases/download";
+
+/// SHA-256 of each `install_only` archive of `RELEASE`, copied from the
+/// release's `SHA256SUMS` when it was pinned. They live here rather than
+/// being fetched next to the archive, so a swapped archive cannot come with
+/// a matching checksum. Update them together with `RELEASE`; an empty
+/// digest refuses the install.
+const AARCH64_SHA256: &str = "";
+const X86_64_SHA256: &str = "";
+
+/// `<app data>/python-runtime`, holding the extracted `python/` tree.
+const RUNTIME_DIR: &str = "python-runtime";
+/// Written last, with the archive name, once a runtime is verified and
+/// extracted; a runtime without it (or from another release) is replaced.
+const VERSION_FILE: &str = "whisperbar-runtime.txt";
+
+fn pinned_sha256() -> &'static str {
+    if cfg!(target_arch = "aarch64") {
+        AARCH64_SHA256
+    } else {
+        X86_64_SHA256
+    }
+}
+
+fn asset_name() -> String {
+    let target = if cfg!(target_arch = "aarch64") {
+        "aarch64-apple-darwin"
+    } else {
+        "x86_64-apple-darwin"
+    };
+    format!("cpython-{PYTHON_VERSION}+{RELEASE}-{target}-install_only.tar.gz")
+}
+
+pub fn runtime_dir(app_data_dir: &Path) -> PathBuf {
+    app_data_dir.join(RUNTIME_DIR)
+}
+
+/// Returns the managed `python3`, downloading the pinned runtime first when
+/// it is missing or from another release. The archive is checked against
+/// the digest pinned in this file before anything is extracted, and the
+/// new runtime only replaces the old one once it is complete. Environments
+/// built on the replaced runtime are rebuilt by `bootstrap.py`, which runs
+/// on the new one right after this returns.
+pub async fn ensure(app_data_dir: &Path, network: &DownloadNetwork) -> anyhow::Result<PathBuf> {
+    let dir = runtime_dir(app_data_dir);
+    let python = dir.join("python/bin/python3");
+    let asset = asset_name();
+    let installed = tokio::fs::read_to_string(dir.join(VERSION_FILE))
+        .await
+        .is_ok_and(|version| version.trim() == asset);
+    if installed && python.is_file() {
+        return Ok(python);
+    }
+
+    let staging = app_data_dir.join(format!("{RUNTIME_DIR}.partial"));
+    let _ = tokio::fs::remove_dir_all(&staging).await;
+    tokio::fs::create_dir_all(&staging)
+        .await
+        .with_context(|| format!("failed creating {}", staging.display()))?;
+
+    let result = install(&staging, &asset, network).await;
+    if let Err(error) = result {
+        let _ = tokio::fs::remove_dir_all(&staging).await;
+        return Err(error);
+    }
+
+    let _ = tokio::fs::remove_dir_all(&dir).await;
+    tokio::fs::rename(&staging, &dir)
+        .await
+        .with_context(|| format!("failed moving the Python runtime into {}", dir.display()))?;
+    Ok(python)
+}
+
+async fn install(staging: &Path, asset: &str, network: &DownloadNetwork) -> anyhow::Result<()> {
+    let expected = pinned_sha256();
+    if expected.is_empty() {
+        return Err(anyhow!("no checksum is pinned for {asset}"));
+    }
+    let archive = staging.join(asset);
+    curl(
+        network,
+        &format!("{RELEASE_URL}/{RELEASE}/{}", asset.replace('+', "%2B")),
+        &archive,
+    )
+    .await?;
+
+    let actual = integrity::sha256(&archive).await?;
+    if !actual.eq_ignore_ascii_case(&expected) {
+        return Err(anyhow!(
+            "Python runtime download is corrupted (SHA-256 {actual}, expected {expected})"
+        ));
+    }
+
+    let status = Command::new("tar")
+        .arg("-xzf")
+        .arg(&archive)
+        .arg("-C")
+        .arg(staging)
+        .status()
+        .await
+        .context("failed starting tar")?;
+    if !status.success() {
+        return Err(anyhow!("failed extracting {asset} ({status})"));
+    }
+    tokio::fs::remove_file(&archive).await?;
+    tokio::fs::write(staging.join(VERSION_FILE), asset).await?;
+    Ok(())
+}
+
+/// Fetches `url` into `output`.
+async fn curl(network: &DownloadNetwork, url: &str, output: &Path) -> anyhow::Result<()> {
+    let mut command = Command::new("curl");
+    network.apply(&mut command);
+    command.args(["-L", "--fail", "--silent", "--show-error"]);
+    let result = command
+        .arg("-o")
+        .arg(output)
+        .arg(url)
+        .output()
+        .await
+        .context("failed to start curl")?;
+    if !result.status.success() {
+        return Err(anyhow!(
+            "downloading {url} failed: {}",
+            String::from_utf8_lossy(&result.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_architecture_has_a_pinned_digest() {
+        for digest in [AARCH64_SHA256, X86_64_SHA256] {
+            assert_eq!(
+                digest.len(),
+                64,
+                "pin the SHA-256 of {RELEASE} from its SHA256SUMS"
+            );
+            assert!(digest.bytes().all(|byte| byte.is_ascii_hexdigit()));
+        }
+    }
+}
@@ -0,0 +1,244 @@
+use std::{
+    io::SeekFrom,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context};
+use chrono::Local;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+
+use crate::app_state::{AppPaths, MuteInterval, SessionNote, TranscriptSegment};
+
+/// Most silence `silence_trim_padding` keeps on either side of the speech.
+pub const MAX_SILENCE_TRIM_PADDING: u32 = 60;
+
+/// Where the worker writes the WAV while a session is running. Files left here
+/// belong to sessions that never reached `stop_recording` (a crash or a force
+/// quit) and are kept so the audio can be recovered.
+pub fn recordings_dir(paths: &AppPaths) -> PathBuf {
+    paths.app_data_dir.join("recordings")
+}
+
+/// Path the worker should write the session audio to.
+pub fn session_path(paths: &AppPaths) -> anyhow::Result<PathBuf> {
+    let dir = recordings_dir(paths);
+    std::fs::create_dir_all(&dir).with_context(|| format!("failed creating {}", dir.display()))?;
+    Ok(dir.join(format!(
+        "Recording-{}.wav",
+        Local::now().format("%Y-%m-%d-%H-%M-%S")
+    )))
+}
+
+/// Moves the finished recording next to its transcript, sharing its file stem.
+pub async fn keep_alongside(audio: &Path, transcript: &Path) -> anyhow::Result<PathBuf> {
+    let destination = transcript.with_extension("wav");
+    if tokio::fs::rename(audio, &destination).await.is_err() {
+        // `rename` fails across volumes (e.g. an external output directory).
+        tokio::fs::copy(audio, &destination)
+            .await
+            .with_context(|| format!("failed moving recording to {}", destination.display()))?;
+        let _ = tokio::fs::remove_file(audio).await;
+    }
+    Ok(destination)
+}
+
+/// Copies a recording next to another transcript, sharing its file stem, so
+/// deleting either session leaves the other's audio in place.
+pub async fn copy_alongside(audio: &Path, transcript: &Path) -> anyhow::Result<PathBuf> {
+    let destination = transcript.with_extension("wav");
+    tokio::fs::copy(audio, &destination)
+        .await
+        .with_context(|| format!("failed copying recording to {}", destination.display()))?;
+    Ok(destination)
+}
+
+pub async fn discard(audio: &Path) {
+    let _ = tokio::fs::remove_file(audio).await;
+}
+
+/// Cuts the recording down to the transcribed speech with `padding_seconds`
+/// kept before and after it, and moves the session's timestamps back by
+/// what was cut from the start so they still point into the audio.
+/// Recordings without segment times are left as they are.
+pub async fn trim_silence(
+    audio: &Path,
+    padding_seconds: u32,
+    segments: &mut [TranscriptSegment],
+    mutes: &mut Vec<MuteInterval>,
+    notes: &mut [SessionNote],
+) -> anyhow::Result<()> {
+    let Some((first, last)) = speech_span(segments) else {
+        return Ok(());
+    };
+    let padding = f64::from(padding_seconds);
+    let cut = trim(audio, (first - padding).max(0.0), last + padding).await?;
+    if cut > 0.0 {
+        shift(segments, mutes, notes, cut);
+    }
+    Ok(())
+}
+
+/// Start of the first segment and end of the last, in session seconds.
+fn speech_span(segments: &[TranscriptSegment]) -> Option<(f64, f64)> {
+    let first = segments.iter().find_map(|segment| segment.start)?;
+    let last = segments
+        .iter()
+        .rev()
+        .find_map(|segment| segment.end.or(segment.start))?;
+    (last >= first).then_some((first, last))
+}
+
+/// Rewrites the WAV to hold only `from..to` seconds and returns how many
+/// seconds were cut from the start.
+async fn trim(audio: &Path, from: f64, to: f64) -> anyhow::Result<f64> {
+    let mut file = tokio::fs::File::open(audio)
+        .await
+        .with_context(|| format!("failed opening {}", audio.display()))?;
+    let file_len = file.metadata().await?.len();
+    let mut header = vec![0u8; 4096.min(file_len as usize)];
+    file.read_exact(&mut header)
+        .await
+        .context("failed reading the WAV header")?;
+    let wav = parse_header(&header)?;
+
+    // Both writers leave the size unset until they close the file.
+    let data_len = match wav.data_len {
+        0 => file_len.saturating_sub(wav.data_start),
+        len => len.min(file_len.saturating_sub(wav.data_start)),
+    };
+    let frame = u64::from(wav.block_align.max(1));
+    let rate = f64::from(wav.sample_rate);
+    let start = ((from * rate).floor() as u64 * frame).min(data_len);
+    let end = ((to * rate).ceil() as u64 * frame).min(data_len);
+    if (start == 0 && end == data_len) || end <= start {
+        return Ok(0.0);
+    }
+    let kept = end - start;
+    let kept_len = u32::try_from(kept).context("the recording is too long to trim")?;
+
+    let temporary = audio.with_extension("wav.trim");
+    let mut output = tokio::fs::File::create(&temporary)
+        .await
+        .with_context(|| format!("failed creating {}", temporary.display()))?;
+    let mut riff = Vec::with_capacity(12 + wav.fmt.len() + 8);
+    riff.extend_from_slice(b"RIFF");
+    riff.extend_from_slice(&(4 + wav.fmt.len() as u32 + 8 + kept_len).to_le_bytes());
+    riff.extend_from_slice(b"WAVE");
+    riff.extend_from_slice(&wav.fmt);
+    riff.extend_from_slice(b"data");
+    riff.extend_from_slice(&kept_len.to_le_bytes());
+    output.write_all(&riff).await?;
+
+    file.seek(SeekFrom::Start(wav.data_start + start)).await?;
+    let copied = tokio::io::copy(&mut (&mut file).take(kept), &mut output)
+        .await
+        .context("failed copying the trimmed recording")?;
+    output.flush().await?;
+    drop(output);
+    if copied != kept {
+        let _ = tokio::fs::remove_file(&temporary).await;
+        bail!("the recording ended early while trimming");
+    }
+    tokio::fs::rename(&temporary, audio)
+        .await
+        .with_context(|| format!("failed replacing {}", audio.display()))?;
+
+    Ok((start / frame) as f64 / rate)
+}
+
+/// The 16-bit samples of a kept recording, for a worker's `--transcribe-file`.
+/// Only the 16 kHz mono format the workers write is accepted.
+pub fn read_pcm(audio: &Path) -> anyhow::Result<Vec<u8>> {
+    let mut bytes =
+        std::fs::read(audio).with_context(|| format!("failed reading {}", audio.display()))?;
+    let wav = parse_header(&bytes[..4096.min(bytes.len())])?;
+    let channels = u16::from_le_bytes(wav.fmt[10..12].try_into()?);
+    let bits = u16::from_le_bytes(wav.fmt[22..24].try_into()?);
+    if wav.sample_rate != 16_000 || channels != 1 || bits != 16 {
+        bail!("the recording is not 16 kHz mono 16-bit audio");
+    }
+    let available = (bytes.len() as u64).saturating_sub(wav.data_start);
+    let data_len = match wav.data_len {
+        0 => available,
+        len => len.min(available),
+    };
+    bytes.truncate((wav.data_start + data_len) as usize);
+    Ok(bytes.split_off(wav.data_start as usize))
+}
+
+struct WavHeader {
+    /// The whole `fmt ` chunk, id and size included.
+    fmt: Vec<u8>,
+    sample_rate: u32,
+    block_align: u16,
+    data_start: u64,
+    data_len: u64,
+}
+
+/// Walks the RIFF chunks up to `data`.
+fn parse_header(bytes: &[u8]) -> anyhow::Result<WavHeader> {
+    if bytes.len() < 12 || &bytes[..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        bail!("the recording is not a WAV file");
+    }
+    let mut fmt = None;
+    let mut offset = 12;
+    while offset + 8 <= bytes.len() {
+        let id = &bytes[offset..offset + 4];
+        let size = u32::from_le_bytes(bytes[offset + 4..offset + 8].try_into()?) as usize;
+        let body = offset + 8;
+        if id == b"data" {
+            let (fmt, sample_rate, block_align): (Vec<u8>, u32, u16) =
+                fmt.context("the WAV file has no format chunk")?;
+            if sample_rate == 0 {
+                bail!("the WAV file has no sample rate");
+            }
+            return Ok(WavHeader {
+                fmt,
+                sample_rate,
+                block_align,
+                data_start: body as u64,
+                data_len: size as u64,
+            });
+        }
+        if id == b"fmt " {
+            if size < 16 || body + size > bytes.len() {
+                bail!("the WAV format chunk is truncated");
+            }
+            fmt = Some((
+                bytes[offset..body + size].to_vec(),
+                u32::from_le_bytes(bytes[body + 4..body + 8].try_into()?),
+                u16::from_le_bytes(bytes[body + 12..body + 14].try_into()?),
+            ));
+        }
+        // Chunks are padded to an even length.
+        offset = body + size + size % 2;
+    }
+    bail!("the WAV file has no data chunk")
+}
+
+/// Moves every timestamp `seconds` earlier. Notes and mutes from before the
+/// kept audio land on its start; mutes that ended before it are dropped.
+fn shift(
+    segments: &mut [TranscriptSegment],
+    mutes: &mut Vec<MuteInterval>,
+    notes: &mut [SessionNote],
+    seconds: f64,
+) {
+    let earlier = |at: f64| (at - seconds).max(0.0);
+    for segment in segments.iter_mut() {
+        segment.start = segment.start.map(earlier);
+        segment.end = segment.end.map(earlier);
+        for word in &mut segment.words {
+            word.start = earlier(word.start);
+            word.end = earlier(word.end);
+        }
+    }
+    mutes.retain(|mute| mute.end.is_none_or(|end| end > seconds));
+    for mute in mutes.iter_mut() {
+        mute.start = earlier(mute.start);
+        mute.end = mute.end.map(earlier);
+    }
+    for note in notes.iter_mut() {
+        note.at = earlier(note.at);
+    }
+}
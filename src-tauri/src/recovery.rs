@@ -0,0 +1,333 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Context};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::{
+    app_state::{
+        update_session, AppPaths, AppStatus, MuteInterval, SessionNote, SessionState, SharedState,
+        TranscriptSegment,
+    },
+    history, recording_audio, scripts, transcript_file,
+};
+
+/// How often a running session's transcript is written out.
+const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Characters of transcript shown in the recovery prompt.
+const PREVIEW_CHARS: usize = 160;
+
+/// A running session's transcript as `spawn_autosave` last wrote it. Any
+/// file other than the running session's belongs to a session that never
+/// finished `stop_recording`: the app crashed or was force quit, or the
+/// worker failed mid-session.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Autosave {
+    /// RFC 3339 local times.
+    started_at: String,
+    saved_at: String,
+    audio_path: Option<PathBuf>,
+    segments: Vec<TranscriptSegment>,
+    mutes: Vec<MuteInterval>,
+    notes: Vec<SessionNote>,
+}
+
+/// A leftover session `recover_session` can restore.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoverableSession {
+    pub id: String,
+    /// RFC 3339 local times: when recording started, and when the transcript
+    /// was last written out.
+    pub started_at: String,
+    pub saved_at: String,
+    pub word_count: usize,
+    pub preview: String,
+    /// The session's WAV (`keep_audio`) is still in `recordings/`.
+    pub has_audio: bool,
+}
+
+fn autosave_dir(paths: &AppPaths) -> PathBuf {
+    paths.app_data_dir.join("autosave")
+}
+
+fn autosave_path(paths: &AppPaths, id: &str) -> anyhow::Result<PathBuf> {
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Err(anyhow!("invalid recoverable session id `{id}`"));
+    }
+    Ok(autosave_dir(paths).join(format!("{id}.json")))
+}
+
+/// Writes the transcript of the session started at `started_at` to
+/// `autosave/` every `AUTOSAVE_INTERVAL` while it has text, until the session
+/// ends. Returns the file, which `stop_recording` and `cancel_recording`
+/// remove once they are done with the session.
+pub fn spawn_autosave(state: &SharedState, started_at: Instant) -> PathBuf {
+    let now = Local::now();
+    let path = autosave_dir(&state.paths)
+        .join(format!("Session-{}.json", now.format("%Y-%m-%d-%H-%M-%S")));
+    let started_at_local = now.to_rfc3339();
+    let state = state.clone();
+    let task_path = path.clone();
+
+    tauri::async_runtime::spawn(async move {
+        let mut last_written = None;
+
+        loop {
+            tokio::time::sleep(AUTOSAVE_INTERVAL).await;
+
+            // Checked before anything else so a session that ended without
+            // text does not keep this loop running.
+            if !is_running(&*state.session.lock().await, started_at) {
+                return;
+            }
+
+            let (segments, mutes, notes) = {
+                let transcript = state.transcript.lock().await;
+                if !transcript.has_text() {
+                    continue;
+                }
+                (
+                    transcript.segments.clone(),
+                    transcript.mute_intervals.clone(),
+                    transcript.notes.clone(),
+                )
+            };
+            // `saved_at` changes every time; compare what matters.
+            let Ok(content) = serde_json::to_string(&(&segments, &mutes, &notes)) else {
+                continue;
+            };
+            if last_written.as_ref() == Some(&content) {
+                continue;
+            }
+
+            // Written under the session lock: once `stop_recording` has
+            // ended the session, no late write can bring the file back after
+            // it is removed.
+            let session = state.session.lock().await;
+            if !is_running(&session, started_at) {
+                return;
+            }
+            let autosave = Autosave {
+                started_at: started_at_local.clone(),
+                saved_at: Local::now().to_rfc3339(),
+                audio_path: session.audio_path.clone(),
+                segments,
+                mutes,
+                notes,
+            };
+            if write(&task_path, &autosave).await.is_ok() {
+                last_written = Some(content);
+            }
+        }
+    });
+
+    path
+}
+
+fn is_running(session: &SessionState, started_at: Instant) -> bool {
+    session.status == AppStatus::Recording && session.recording_started_at == Some(started_at)
+}
+
+/// Replaces the file in one rename, so a crash mid-write leaves the previous
+/// autosave intact.
+async fn write(path: &Path, autosave: &Autosave) -> anyhow::Result<()> {
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir)
+            .await
+            .with_context(|| format!("failed creating {}", dir.display()))?;
+    }
+    let temp = path.with_extension("json.tmp");
+    tokio::fs::write(&temp, serde_json::to_vec(autosave)?)
+        .await
+        .with_context(|| format!("failed writing {}", temp.display()))?;
+    tokio::fs::rename(&temp, path)
+        .await
+        .with_context(|| format!("failed replacing {}", path.display()))
+}
+
+async fn read(path: &Path) -> anyhow::Result<Autosave> {
+    let content = tokio::fs::read(path)
+        .await
+        .with_context(|| format!("failed reading {}", path.display()))?;
+    serde_json::from_slice(&content).with_context(|| format!("invalid autosave {}", path.display()))
+}
+
+/// Drops a session's autosave once its transcript is saved or discarded.
+pub async fn remove(path: &Path) {
+    let _ = tokio::fs::remove_file(path).await;
+}
+
+/// The running session's autosave, which is not up for recovery.
+async fn running_autosave(state: &SharedState) -> Option<PathBuf> {
+    let session = state.session.lock().await;
+    (session.status == AppStatus::Recording)
+        .then(|| session.autosave_path.clone())
+        .flatten()
+}
+
+/// The most recent session left behind unsaved, if any.
+pub async fn find_recoverable(state: &SharedState) -> anyhow::Result<Option<RecoverableSession>> {
+    let running = running_autosave(state).await;
+    let mut dir = match tokio::fs::read_dir(autosave_dir(&state.paths)).await {
+        Ok(dir) => dir,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(error) => return Err(error).context("failed listing autosaved sessions"),
+    };
+
+    let mut newest: Option<(DateTime<Local>, RecoverableSession)> = None;
+    while let Some(entry) = dir.next_entry().await? {
+        let path = entry.path();
+        if path.extension().and_then(|extension| extension.to_str()) != Some("json")
+            || running.as_ref() == Some(&path)
+        {
+            continue;
+        }
+        let (Some(id), Ok(autosave)) = (
+            path.file_stem().and_then(|stem| stem.to_str()),
+            read(&path).await,
+        ) else {
+            continue;
+        };
+        let Ok(saved_at) = DateTime::parse_from_rfc3339(&autosave.saved_at) else {
+            continue;
+        };
+        let saved_at = saved_at.with_timezone(&Local);
+        if newest
+            .as_ref()
+            .is_some_and(|(newest, _)| *newest >= saved_at)
+        {
+            continue;
+        }
+
+        let text = scripts::join_lines(
+            autosave
+                .segments
+                .iter()
+                .map(|segment| segment.text.trim())
+                .filter(|text| !text.is_empty()),
+        );
+        let mut preview = text.chars().take(PREVIEW_CHARS).collect::<String>();
+        if preview.len() < text.len() {
+            preview.push('…');
+        }
+        newest = Some((
+            saved_at,
+            RecoverableSession {
+                id: id.to_string(),
+                started_at: autosave.started_at.clone(),
+                saved_at: autosave.saved_at.clone(),
+                word_count: history::segment_word_count(&autosave.segments),
+                preview,
+                has_audio: autosave.audio_path.as_deref().is_some_and(Path::is_file),
+            },
+        ));
+    }
+
+    Ok(newest.map(|(_, session)| session))
+}
+
+/// Saves a leftover session as a transcript (and its audio, if kept) the way
+/// `stop_recording` would have, and opens it for review. With `restore` off
+/// the autosave is just deleted; its audio stays in `recordings/`. Returns
+/// the saved transcript path.
+pub async fn recover(
+    app: &AppHandle,
+    state: &SharedState,
+    id: &str,
+    restore: bool,
+) -> anyhow::Result<Option<String>> {
+    let path = autosave_path(&state.paths, id)?;
+    if running_autosave(state).await.as_ref() == Some(&path) {
+        return Err(anyhow!("that session is still recording"));
+    }
+    let mut autosave = read(&path).await?;
+    if !restore {
+        remove(&path).await;
+        return Ok(None);
+    }
+
+    state.paths.ensure_writable()?;
+    if !matches!(state.status().await, AppStatus::Ready | AppStatus::Idle) {
+        return Err(anyhow!("a session can only be recovered while idle"));
+    }
+    if state.transcript.lock().await.dirty {
+        return Err(anyhow!(
+            "save or discard the unsaved transcript before recovering another"
+        ));
+    }
+
+    let (render_options, naming, trim_padding) = {
+        let settings = state.settings.lock().await;
+        (
+            settings.render_options(),
+            settings.file_naming(),
+            settings.silence_trim_padding,
+        )
+    };
+    let audio_path = autosave.audio_path.clone().filter(|path| path.is_file());
+    if let (Some(audio_path), Some(padding)) = (&audio_path, trim_padding) {
+        let _ = recording_audio::trim_silence(
+            audio_path,
+            padding,
+            &mut autosave.segments,
+            &mut autosave.mutes,
+            &mut autosave.notes,
+        )
+        .await;
+    }
+    let file_path = transcript_file::save_markdown(
+        &autosave.segments,
+        &autosave.mutes,
+        &autosave.notes,
+        render_options,
+        &naming,
+    )
+    .await?;
+    let saved_audio = match audio_path.as_deref() {
+        Some(audio_path) => recording_audio::keep_alongside(audio_path, &file_path)
+            .await
+            .ok(),
+        None => None,
+    };
+    history::record_session(
+        app,
+        state,
+        &file_path,
+        saved_audio.as_deref(),
+        &autosave.segments,
+        &autosave.notes,
+    )
+    .await;
+
+    {
+        let mut transcript = state.transcript.lock().await;
+        transcript.segments = autosave.segments;
+        transcript.mute_intervals = autosave.mutes;
+        transcript.notes = autosave.notes;
+        transcript.partial_metrics = Default::default();
+        transcript.dirty = false;
+        transcript.edits = Default::default();
+    }
+
+    let transcript_path = file_path.display().to_string();
+    let saved_path = transcript_path.clone();
+    let started = DateTime::parse_from_rfc3339(&autosave.started_at)
+        .map(|started| started.with_timezone(&Local).format("%H:%M").to_string())
+        .unwrap_or_default();
+    update_session(app, state, move |session| {
+        session.last_saved_path = Some(saved_path);
+        session.last_audio_path = saved_audio.map(|path| path.display().to_string());
+        session.status_message = format!("Recovered the transcript from {started}");
+    })
+    .await;
+
+    remove(&path).await;
+    Ok(Some(transcript_path))
+}
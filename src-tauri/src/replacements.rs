@@ -0,0 +1,160 @@
+use anyhow::{anyhow, bail};
+use regex::{NoExpand, Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+pub const MAX_REPLACEMENTS: usize = 200;
+
+/// Separates the two sides of an entry in the text format.
+const SEPARATOR: &str = "=>";
+
+/// Compiled size a single pattern may take, so one entry cannot make every
+/// segment slow.
+const MAX_PATTERN_BYTES: usize = 1 << 20;
+
+/// A systematic mis-transcription and its fix. Plain entries match whole
+/// words, ignoring case and how much space is between them; regex entries
+/// are used as written and can refer to groups (`$1`) in `replace`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Replacement {
+    pub find: String,
+    pub replace: String,
+    #[serde(default)]
+    pub regex: bool,
+}
+
+/// The dictionary compiled for a session. Entries apply one after another,
+/// in the order they are listed.
+#[derive(Debug, Clone, Default)]
+pub struct Replacer {
+    rules: Vec<(Regex, Replacement)>,
+}
+
+impl Replacer {
+    pub fn new(entries: &[Replacement]) -> Self {
+        Self {
+            rules: entries
+                .iter()
+                .filter_map(|entry| Some((compile(entry).ok()?, entry.clone())))
+                .collect(),
+        }
+    }
+
+    pub fn apply(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        for (pattern, entry) in &self.rules {
+            text = if entry.regex {
+                pattern.replace_all(&text, entry.replace.as_str())
+            } else {
+                pattern.replace_all(&text, NoExpand(&entry.replace))
+            }
+            .into_owned();
+        }
+        text
+    }
+}
+
+fn compile(entry: &Replacement) -> anyhow::Result<Regex> {
+    let pattern = if entry.regex {
+        entry.find.clone()
+    } else {
+        plain_pattern(&entry.find)
+    };
+    RegexBuilder::new(&pattern)
+        .case_insensitive(!entry.regex)
+        .size_limit(MAX_PATTERN_BYTES)
+        .build()
+        .map_err(|error| anyhow!("`{}` is not a valid pattern: {error}", entry.find))
+}
+
+/// `whisper bar` -> `\bwhisper\s+bar\b`. The `\b` is left off an edge that
+/// is not a word character, where it could never match.
+fn plain_pattern(find: &str) -> String {
+    let words = find.split_whitespace().collect::<Vec<_>>();
+    let body = words
+        .iter()
+        .map(|word| regex::escape(word))
+        .collect::<Vec<_>>()
+        .join(r"\s+");
+    let word_char = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+    let start = if word_char(find.trim().chars().next()) {
+        r"\b"
+    } else {
+        ""
+    };
+    let end = if word_char(find.trim().chars().last()) {
+        r"\b"
+    } else {
+        ""
+    };
+    format!("{start}{body}{end}")
+}
+
+pub fn validate(entries: &[Replacement]) -> anyhow::Result<()> {
+    if entries.len() > MAX_REPLACEMENTS {
+        bail!("the replacement dictionary holds at most {MAX_REPLACEMENTS} entries");
+    }
+    for entry in entries {
+        if entry.find.trim().is_empty() {
+            bail!("every replacement needs something to find");
+        }
+        let pattern = compile(entry)?;
+        if pattern.is_match("") {
+            bail!("`{}` matches empty text", entry.find);
+        }
+    }
+    Ok(())
+}
+
+/// Parses the editable format: one `find => replace` entry per line, or
+/// `/pattern/ => replace` for a regex. The replacement may be empty to
+/// delete what was found. Blank lines and lines starting with `#` are
+/// ignored.
+pub fn parse(text: &str) -> anyhow::Result<Vec<Replacement>> {
+    let mut entries = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        // Split at the last separator so a regex can contain `=>`.
+        let (find, replace) = line.rsplit_once(SEPARATOR).ok_or_else(|| {
+            anyhow!(
+                "line {}: expected `find {SEPARATOR} replace`, got `{line}`",
+                index + 1
+            )
+        })?;
+        let find = find.trim();
+        let entry = match find
+            .strip_prefix('/')
+            .and_then(|rest| rest.strip_suffix('/'))
+        {
+            Some(pattern) => Replacement {
+                find: pattern.to_string(),
+                replace: replace.trim().to_string(),
+                regex: true,
+            },
+            None => Replacement {
+                find: find.to_string(),
+                replace: replace.trim().to_string(),
+                regex: false,
+            },
+        };
+        validate(std::slice::from_ref(&entry))
+            .map_err(|error| anyhow!("line {}: {error}", index + 1))?;
+        entries.push(entry);
+    }
+    validate(&entries)?;
+    Ok(entries)
+}
+
+/// Writes `entries` in the format `parse` reads.
+pub fn to_text(entries: &[Replacement]) -> String {
+    entries
+        .iter()
+        .map(|entry| match entry.regex {
+            true => format!("/{}/ {SEPARATOR} {}\n", entry.find, entry.replace),
+            false => format!("{} {SEPARATOR} {}\n", entry.find, entry.replace),
+        })
+        .collect()
+}
@@ -7,24 +7,18 @@ const BOOTSTRAP_SCRIPT: &str = include_str!("../python/bootstrap.py");
 const WORKER_SCRIPT: &str = include_str!("../python/worker.py");
 
 pub async fn ensure_scripts(state: &SharedState) -> anyhow::Result<()> {
-    let (scripts_dir, bootstrap_script, worker_script) = {
-        let guard = state.0.lock().await;
-        (
-            guard.scripts_dir.clone(),
-            guard.bootstrap_script.clone(),
-            guard.worker_script.clone(),
-        )
-    };
+    let paths = &state.paths;
+    let scripts_dir = &paths.scripts_dir;
 
-    fs::create_dir_all(&scripts_dir).await.with_context(|| {
+    fs::create_dir_all(scripts_dir).await.with_context(|| {
         format!(
             "failed creating scripts directory {}",
             scripts_dir.display()
         )
     })?;
 
-    write_if_changed(&bootstrap_script, BOOTSTRAP_SCRIPT).await?;
-    write_if_changed(&worker_script, WORKER_SCRIPT).await?;
+    write_if_changed(&paths.bootstrap_script, BOOTSTRAP_SCRIPT).await?;
+    write_if_changed(&paths.worker_script, WORKER_SCRIPT).await?;
 
     Ok(())
 }
@@ -9,11 +9,74 @@ use std::{
 };
 
 use anyhow::{anyhow, Context};
+use rubato::{
+    Resampler as _, SincFixedIn, SincInterpolationParameters, SincInterpolationType, WindowFunction,
+};
 use screencapturekit::prelude::*;
-use screencapturekit::AudioBufferList;
+use serde::{Deserialize, Serialize};
 
 const OUTPUT_SAMPLE_RATE: f32 = 16_000.0;
-const OUTPUT_GAIN: f32 = 1.25;
+/// Level the automatic gain control steers speech towards (-20 dBFS RMS),
+/// the same target the worker uses for the microphone.
+const AGC_TARGET_RMS: f32 = 0.1;
+/// +20 dB: enough for a quiet call without turning hiss into speech.
+const AGC_MAX_GAIN: f32 = 10.0;
+/// -10 dB for a loud local playback.
+const AGC_MIN_GAIN: f32 = 0.316;
+/// Blocks quieter than this are pauses: the gain holds instead of rising.
+const AGC_GATE_RMS: f32 = 0.002;
+/// 10 ms at the output rate.
+const AGC_BLOCK_FRAMES: usize = 160;
+/// Gain falls within a few blocks when speech gets louder...
+const AGC_ATTACK_SECONDS: f32 = 0.02;
+/// ...and rises slowly, so it doesn't pump between words.
+const AGC_RELEASE_SECONDS: f32 = 1.5;
+/// Source frames the sinc resampler consumes per step (about 21 ms at 48 kHz).
+const SINC_CHUNK_FRAMES: usize = 1024;
+/// `kAudioFormatFlagIsNonInterleaved` from CoreAudio's `AudioStreamBasicDescription`.
+const NON_INTERLEAVED_FLAG: u32 = 1 << 5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum BufferLayout {
+    /// Each buffer holds frames of `number_channels` interleaved samples.
+    Interleaved,
+    /// Each buffer holds a single channel; channels arrive as separate buffers.
+    NonInterleaved,
+}
+
+/// How system audio is converted from the capture rate to 16 kHz.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ResampleQuality {
+    /// Linear interpolation: almost free, but folds everything above 8 kHz
+    /// back into the speech band.
+    Fast,
+    /// Band-limited sinc interpolation with state kept across callbacks.
+    #[default]
+    High,
+}
+
+impl ResampleQuality {
+    /// Value of the helper's `--resample-quality` argument.
+    pub fn as_arg(self) -> &'static str {
+        match self {
+            ResampleQuality::Fast => "fast",
+            ResampleQuality::High => "high",
+        }
+    }
+
+    fn from_args() -> Self {
+        let args: Vec<String> = std::env::args().collect();
+        let value = args
+            .iter()
+            .position(|arg| arg == "--resample-quality")
+            .and_then(|index| args.get(index + 1));
+        match value.map(String::as_str) {
+            Some("fast") => ResampleQuality::Fast,
+            _ => ResampleQuality::High,
+        }
+    }
+}
 
 pub fn run() -> anyhow::Result<()> {
     let stop = Arc::new(AtomicBool::new(false));
@@ -38,7 +101,7 @@ pub fn run() -> anyhow::Result<()> {
         .with_sample_rate(48_000)
         .with_channel_count(2);
 
-    let handler = AudioOutputHandler::new(stop.clone());
+    let handler = AudioOutputHandler::new(stop.clone(), ResampleQuality::from_args());
     let mut stream = SCStream::new(&filter, &config);
     stream.add_output_handler(handler, SCStreamOutputType::Audio);
     stream
@@ -83,14 +146,224 @@ fn spawn_stdin_stop_watcher(stop: Arc<AtomicBool>) {
 }
 
 struct AudioOutputHandler {
-    writer: Arc<Mutex<BufWriter<io::Stdout>>>,
+    output: Arc<Mutex<HandlerOutput>>,
     stop: Arc<AtomicBool>,
 }
 
+struct HandlerOutput {
+    writer: BufWriter<io::Stdout>,
+    path: AudioPath,
+}
+
+/// Mono mix, resampling, gain and PCM conversion of one callback, with
+/// scratch buffers reused across callbacks: once they have grown to the
+/// callback size the steady state does not allocate (see the tests).
+struct AudioPath {
+    scratch: Scratch,
+    resampler: OutputResampler,
+    gain: AutoGain,
+}
+
+impl AudioPath {
+    fn new(quality: ResampleQuality) -> Self {
+        Self {
+            scratch: Scratch::default(),
+            resampler: match quality {
+                ResampleQuality::Fast => OutputResampler::Linear,
+                ResampleQuality::High => OutputResampler::Sinc(None),
+            },
+            gain: AutoGain::new(),
+        }
+    }
+
+    /// One callback's buffers as 16 kHz PCM; empty while the resampler is
+    /// still filling its first chunk.
+    fn process<'a>(
+        &mut self,
+        buffers: impl IntoIterator<Item = (&'a [u8], u32)>,
+        layout: BufferLayout,
+        is_float: bool,
+        source_rate: f32,
+    ) -> &[u8] {
+        let Self {
+            scratch,
+            resampler,
+            gain,
+        } = self;
+        mix_to_mono(
+            buffers,
+            layout,
+            is_float,
+            &mut scratch.channels,
+            &mut scratch.mono,
+        );
+        if scratch.mono.is_empty() {
+            return &[];
+        }
+
+        resampler.process(&scratch.mono, source_rate, &mut scratch.resampled);
+        if scratch.resampled.is_empty() {
+            return &[];
+        }
+
+        gain.process(&mut scratch.resampled);
+        float_to_pcm_bytes(&scratch.resampled, &mut scratch.pcm);
+        &scratch.pcm
+    }
+}
+
+/// Automatic gain control: moves a single gain towards `AGC_TARGET_RMS`
+/// block by block, quickly down and slowly up, and never lets a block clip.
+/// Also levels the microphone in the native engine.
+pub struct AutoGain {
+    gain: f32,
+    attack: f32,
+    release: f32,
+}
+
+impl AutoGain {
+    pub fn new() -> Self {
+        let block_seconds = AGC_BLOCK_FRAMES as f32 / OUTPUT_SAMPLE_RATE;
+        Self {
+            gain: 1.0,
+            attack: 1.0 - (-block_seconds / AGC_ATTACK_SECONDS).exp(),
+            release: 1.0 - (-block_seconds / AGC_RELEASE_SECONDS).exp(),
+        }
+    }
+
+    pub fn gain(&self) -> f32 {
+        self.gain
+    }
+
+    pub fn process(&mut self, samples: &mut [f32]) {
+        for block in samples.chunks_mut(AGC_BLOCK_FRAMES) {
+            let (sum, peak) = block.iter().fold((0.0f32, 0.0f32), |(sum, peak), sample| {
+                (sum + sample * sample, peak.max(sample.abs()))
+            });
+            let rms = (sum / block.len() as f32).sqrt();
+
+            if rms > AGC_GATE_RMS {
+                let desired = (AGC_TARGET_RMS / rms).clamp(AGC_MIN_GAIN, AGC_MAX_GAIN);
+                let rate = if desired < self.gain {
+                    self.attack
+                } else {
+                    self.release
+                };
+                self.gain += (desired - self.gain) * rate;
+            }
+
+            let gain = if peak > 0.0 {
+                self.gain.min(0.99 / peak)
+            } else {
+                self.gain
+            };
+            for sample in block {
+                *sample *= gain;
+            }
+        }
+    }
+}
+
+enum OutputResampler {
+    Linear,
+    /// Created on the first callback, once the source rate is known.
+    Sinc(Option<SincStream>),
+}
+
+/// Streaming sinc resampler. Callbacks deliver arbitrary frame counts while
+/// the resampler consumes fixed chunks, so leftover input waits in `pending`.
+struct SincStream {
+    source_rate: f32,
+    resampler: SincFixedIn<f32>,
+    pending: Vec<f32>,
+    output: Vec<f32>,
+}
+
+impl SincStream {
+    fn new(source_rate: f32) -> anyhow::Result<Self> {
+        let parameters = SincInterpolationParameters {
+            sinc_len: 128,
+            f_cutoff: 0.95,
+            interpolation: SincInterpolationType::Linear,
+            oversampling_factor: 128,
+            window: WindowFunction::BlackmanHarris2,
+        };
+        let resampler = SincFixedIn::new(
+            f64::from(OUTPUT_SAMPLE_RATE / source_rate),
+            1.0,
+            parameters,
+            SINC_CHUNK_FRAMES,
+            1,
+        )?;
+        let output = vec![0.0; resampler.output_frames_max()];
+        Ok(Self {
+            source_rate,
+            resampler,
+            pending: Vec::with_capacity(SINC_CHUNK_FRAMES * 2),
+            output,
+        })
+    }
+
+    fn process(&mut self, input: &[f32], out: &mut Vec<f32>) {
+        out.clear();
+        self.pending.extend_from_slice(input);
+
+        let mut consumed = 0;
+        while self.pending.len() - consumed >= self.resampler.input_frames_next() {
+            let chunk = &self.pending[consumed..consumed + self.resampler.input_frames_next()];
+            let Ok((read, written)) =
+                self.resampler
+                    .process_into_buffer(&[chunk], &mut [&mut self.output[..]], None)
+            else {
+                break;
+            };
+            out.extend_from_slice(&self.output[..written]);
+            consumed += read;
+        }
+        self.pending.drain(..consumed);
+    }
+}
+
+impl OutputResampler {
+    fn process(&mut self, input: &[f32], source_rate: f32, out: &mut Vec<f32>) {
+        let OutputResampler::Sinc(stream) = self else {
+            resample_to_output_rate(input, source_rate, out);
+            return;
+        };
+        if (source_rate - OUTPUT_SAMPLE_RATE).abs() < 1.0 {
+            out.clear();
+            out.extend_from_slice(input);
+            return;
+        }
+
+        if stream
+            .as_ref()
+            .is_none_or(|stream| stream.source_rate != source_rate)
+        {
+            *stream = SincStream::new(source_rate).ok();
+        }
+        match stream {
+            Some(stream) => stream.process(input, out),
+            None => resample_to_output_rate(input, source_rate, out),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Scratch {
+    channels: Vec<Vec<f32>>,
+    mono: Vec<f32>,
+    resampled: Vec<f32>,
+    pcm: Vec<u8>,
+}
+
 impl AudioOutputHandler {
-    fn new(stop: Arc<AtomicBool>) -> Self {
+    fn new(stop: Arc<AtomicBool>, quality: ResampleQuality) -> Self {
         Self {
-            writer: Arc::new(Mutex::new(BufWriter::new(io::stdout()))),
+            output: Arc::new(Mutex::new(HandlerOutput {
+                writer: BufWriter::new(io::stdout()),
+                path: AudioPath::new(quality),
+            })),
             stop,
         }
     }
@@ -111,89 +384,115 @@ impl SCStreamOutputTrait for AudioOutputHandler {
         let Some(buffers) = sample.audio_buffer_list() else {
             return;
         };
+        let layout = detect_layout(format.audio_format_flags(), (&buffers).into_iter().count());
 
-        let mono = mix_to_mono(&buffers, is_float);
-        if mono.is_empty() {
-            return;
-        }
-
-        let resampled = resample_to_output_rate(&mono, source_rate);
-        if resampled.is_empty() {
+        let Ok(mut output) = self.output.lock() else {
+            self.stop.store(true, Ordering::Relaxed);
             return;
-        }
-
-        let pcm = float_to_pcm_bytes(&resampled);
+        };
+        let HandlerOutput { writer, path } = &mut *output;
+        let pcm = path.process(
+            (&buffers)
+                .into_iter()
+                .map(|buffer| (buffer.data(), buffer.number_channels)),
+            layout,
+            is_float,
+            source_rate,
+        );
         if pcm.is_empty() {
             return;
         }
 
-        if let Ok(mut writer) = self.writer.lock() {
-            if writer.write_all(&pcm).is_err() || writer.flush().is_err() {
-                self.stop.store(true, Ordering::Relaxed);
-            }
-        } else {
+        if writer.write_all(pcm).is_err() || writer.flush().is_err() {
             self.stop.store(true, Ordering::Relaxed);
         }
     }
 }
 
-fn mix_to_mono(buffers: &AudioBufferList, is_float: bool) -> Vec<f32> {
-    let mut per_buffer: Vec<Vec<f32>> = Vec::new();
-    for buffer in buffers {
-        let channel_count = (buffer.number_channels.max(1)) as usize;
-        let bytes = buffer.data();
-        let decoded = if is_float {
-            decode_f32_mono(bytes, channel_count)
-        } else {
-            decode_i16_mono(bytes, channel_count)
+/// Uses the format flags when the description carries them; otherwise several
+/// buffers in one list can only mean one channel per buffer.
+fn detect_layout(format_flags: Option<u32>, buffer_count: usize) -> BufferLayout {
+    match format_flags {
+        Some(flags) if flags & NON_INTERLEAVED_FLAG != 0 => BufferLayout::NonInterleaved,
+        Some(_) => BufferLayout::Interleaved,
+        None if buffer_count > 1 => BufferLayout::NonInterleaved,
+        None => BufferLayout::Interleaved,
+    }
+}
+
+/// Decodes every buffer (its bytes and the channel count it reports) into
+/// `channels` and averages them into `mono`. Both vectors are cleared and
+/// refilled in place.
+fn mix_to_mono<'a>(
+    buffers: impl IntoIterator<Item = (&'a [u8], u32)>,
+    layout: BufferLayout,
+    is_float: bool,
+    channels: &mut Vec<Vec<f32>>,
+    mono: &mut Vec<f32>,
+) {
+    mono.clear();
+
+    let mut used = 0usize;
+    for (bytes, number_channels) in buffers {
+        // Non-interleaved buffers may still report the stream's channel count;
+        // treating them as interleaved would average consecutive frames.
+        let channel_count = match layout {
+            BufferLayout::Interleaved => number_channels.max(1) as usize,
+            BufferLayout::NonInterleaved => 1,
         };
+        if channels.len() == used {
+            channels.push(Vec::new());
+        }
+        let decoded = &mut channels[used];
+        if is_float {
+            decode_f32_mono(bytes, channel_count, decoded);
+        } else {
+            decode_i16_mono(bytes, channel_count, decoded);
+        }
         if !decoded.is_empty() {
-            per_buffer.push(decoded);
+            used += 1;
         }
     }
 
-    if per_buffer.is_empty() {
-        return Vec::new();
-    }
-
-    if per_buffer.len() == 1 {
-        return per_buffer.remove(0);
-    }
-
-    let Some(min_len) = per_buffer.iter().map(Vec::len).min() else {
-        return Vec::new();
+    let streams = &channels[..used];
+    let Some(min_len) = streams.iter().map(Vec::len).min() else {
+        return;
     };
     if min_len == 0 {
-        return Vec::new();
+        return;
     }
 
-    let mut mixed = vec![0.0_f32; min_len];
-    for stream in &per_buffer {
-        for (index, sample) in stream.iter().take(min_len).enumerate() {
-            mixed[index] += *sample;
+    if streams.len() == 1 {
+        mono.extend_from_slice(&streams[0]);
+        return;
+    }
+
+    mono.resize(min_len, 0.0);
+    for stream in streams {
+        for (mixed, sample) in mono.iter_mut().zip(stream.iter()) {
+            *mixed += *sample;
         }
     }
 
-    let divisor = per_buffer.len() as f32;
-    for sample in &mut mixed {
+    let divisor = streams.len() as f32;
+    for sample in mono.iter_mut() {
         *sample /= divisor;
     }
-
-    mixed
 }
 
-fn decode_f32_mono(bytes: &[u8], channel_count: usize) -> Vec<f32> {
-    let mut out = Vec::new();
+fn decode_f32_mono(bytes: &[u8], channel_count: usize, out: &mut Vec<f32>) {
+    out.clear();
     if channel_count == 0 {
-        return out;
+        return;
     }
 
     if channel_count == 1 {
-        out.reserve(bytes.len() / 4);
-        for chunk in bytes.chunks_exact(4) {
-            out.push(f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]));
-        }
-        return out;
+        out.extend(
+            bytes
+                .chunks_exact(4)
+                .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]])),
+        );
+        return;
     }
 
     let mut frame_acc = 0.0_f32;
@@ -208,23 +507,21 @@ fn decode_f32_mono(bytes: &[u8], channel_count: usize) -> Vec<f32> {
             frame_acc = 0.0;
         }
     }
-
-    out
 }
 
-fn decode_i16_mono(bytes: &[u8], channel_count: usize) -> Vec<f32> {
-    let mut out = Vec::new();
+fn decode_i16_mono(bytes: &[u8], channel_count: usize, out: &mut Vec<f32>) {
+    out.clear();
     if channel_count == 0 {
-        return out;
+        return;
     }
 
     if channel_count == 1 {
-        out.reserve(bytes.len() / 2);
-        for chunk in bytes.chunks_exact(2) {
-            let sample = i16::from_le_bytes([chunk[0], chunk[1]]);
-            out.push(sample as f32 / 32768.0);
-        }
-        return out;
+        out.extend(
+            bytes
+                .chunks_exact(2)
+                .map(|chunk| i16::from_le_bytes([chunk[0], chunk[1]]) as f32 / 32768.0),
+        );
+        return;
     }
 
     let mut frame_acc = 0.0_f32;
@@ -239,26 +536,26 @@ fn decode_i16_mono(bytes: &[u8], channel_count: usize) -> Vec<f32> {
             frame_acc = 0.0;
         }
     }
-
-    out
 }
 
-fn resample_to_output_rate(input: &[f32], source_rate: f32) -> Vec<f32> {
+fn resample_to_output_rate(input: &[f32], source_rate: f32, out: &mut Vec<f32>) {
+    out.clear();
     if input.is_empty() || source_rate <= 0.0 {
-        return Vec::new();
+        return;
     }
 
     if (source_rate - OUTPUT_SAMPLE_RATE).abs() < 1.0 {
-        return input.to_vec();
+        out.extend_from_slice(input);
+        return;
     }
 
     let ratio = source_rate / OUTPUT_SAMPLE_RATE;
     let output_len = ((input.len() as f32) / ratio).floor().max(0.0) as usize;
     if output_len == 0 {
-        return Vec::new();
+        return;
     }
 
-    let mut out = Vec::with_capacity(output_len);
+    out.reserve(output_len);
     for index in 0..output_len {
         let source_pos = index as f32 * ratio;
         let base_index = source_pos.floor() as usize;
@@ -270,16 +567,235 @@ fn resample_to_output_rate(input: &[f32], source_rate: f32) -> Vec<f32> {
         let value = input[base_index] * (1.0 - fraction) + input[next_index] * fraction;
         out.push(value);
     }
-
-    out
 }
 
-fn float_to_pcm_bytes(input: &[f32]) -> Vec<u8> {
-    let mut bytes = Vec::with_capacity(input.len() * 2);
+fn float_to_pcm_bytes(input: &[f32], out: &mut Vec<u8>) {
+    out.clear();
+    out.reserve(input.len() * 2);
     for sample in input {
-        let amplified = (sample * OUTPUT_GAIN).clamp(-1.0, 1.0);
-        let pcm = (amplified * i16::MAX as f32).round() as i16;
-        bytes.extend_from_slice(&pcm.to_le_bytes());
+        let pcm = (sample.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16;
+        out.extend_from_slice(&pcm.to_le_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        alloc::{GlobalAlloc, Layout, System},
+        cell::Cell,
+        time::Instant,
+    };
+
+    use super::*;
+
+    /// Counts heap allocations per thread, so tests running in parallel do
+    /// not show up in each other's counts.
+    struct CountingAllocator;
+
+    thread_local! {
+        static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+            unsafe { System.alloc(layout) }
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            unsafe { System.dealloc(ptr, layout) }
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+            unsafe { System.realloc(ptr, layout, new_size) }
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    fn allocations() -> usize {
+        ALLOCATIONS.with(Cell::get)
+    }
+
+    /// Callbacks are timed and counted after this many, once the scratch
+    /// buffers and the sinc resampler are set up.
+    const WARM_UP_CALLBACKS: usize = 50;
+
+    /// 20 ms callbacks of 48 kHz stereo float, one buffer per channel, as
+    /// ScreenCaptureKit delivers them.
+    fn callbacks(count: usize) -> Vec<[Vec<u8>; 2]> {
+        const FRAMES: usize = 960;
+        (0..count)
+            .map(|index| {
+                let tone = |pitch: f32| {
+                    (0..FRAMES)
+                        .map(|frame| ((index * FRAMES + frame) as f32 * pitch).sin() * 0.3)
+                        .collect::<Vec<_>>()
+                };
+                [f32_bytes(&tone(0.05)), f32_bytes(&tone(0.07))]
+            })
+            .collect()
+    }
+
+    fn run_callback<'a>(path: &'a mut AudioPath, [left, right]: &[Vec<u8>; 2]) -> &'a [u8] {
+        path.process(
+            [(&left[..], 2), (&right[..], 2)],
+            BufferLayout::NonInterleaved,
+            true,
+            48_000.0,
+        )
+    }
+
+    fn f32_bytes(samples: &[f32]) -> Vec<u8> {
+        samples
+            .iter()
+            .flat_map(|sample| sample.to_le_bytes())
+            .collect()
+    }
+
+    fn mono<'a>(
+        buffers: impl IntoIterator<Item = (&'a [u8], u32)>,
+        layout: BufferLayout,
+        is_float: bool,
+    ) -> Vec<f32> {
+        let (mut channels, mut mono) = (Vec::new(), Vec::new());
+        mix_to_mono(buffers, layout, is_float, &mut channels, &mut mono);
+        mono
+    }
+
+    fn assert_close(actual: &[f32], expected: &[f32]) {
+        assert_eq!(actual.len(), expected.len(), "{actual:?} vs {expected:?}");
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-6, "{actual:?} vs {expected:?}");
+        }
+    }
+
+    #[test]
+    fn layout_follows_the_format_flag() {
+        assert_eq!(
+            detect_layout(Some(NON_INTERLEAVED_FLAG), 1),
+            BufferLayout::NonInterleaved
+        );
+        assert_eq!(
+            detect_layout(Some(NON_INTERLEAVED_FLAG | 1), 2),
+            BufferLayout::NonInterleaved
+        );
+        // The flag wins over the buffer count.
+        assert_eq!(detect_layout(Some(1), 2), BufferLayout::Interleaved);
+        assert_eq!(detect_layout(Some(0), 1), BufferLayout::Interleaved);
+    }
+
+    #[test]
+    fn layout_without_flags_follows_the_buffer_count() {
+        assert_eq!(detect_layout(None, 2), BufferLayout::NonInterleaved);
+        assert_eq!(detect_layout(None, 6), BufferLayout::NonInterleaved);
+        assert_eq!(detect_layout(None, 1), BufferLayout::Interleaved);
+        assert_eq!(detect_layout(None, 0), BufferLayout::Interleaved);
+    }
+
+    #[test]
+    fn interleaved_stereo_averages_each_frame() {
+        let bytes = f32_bytes(&[0.2, 0.4, -1.0, 1.0, 0.5, 0.5]);
+        let mixed = mono([(&bytes[..], 2)], BufferLayout::Interleaved, true);
+        assert_close(&mixed, &[0.3, 0.0, 0.5]);
+    }
+
+    #[test]
+    fn non_interleaved_channels_average_sample_by_sample() {
+        let left = f32_bytes(&[0.2, -0.4, 1.0]);
+        let right = f32_bytes(&[0.4, 0.4, 0.0]);
+        // Each buffer still reports the stream's two channels.
+        let mixed = mono(
+            [(&left[..], 2), (&right[..], 2)],
+            BufferLayout::NonInterleaved,
+            true,
+        );
+        assert_close(&mixed, &[0.3, 0.0, 0.5]);
+    }
+
+    #[test]
+    fn non_interleaved_channels_mix_to_the_shortest() {
+        let left = f32_bytes(&[0.2, 0.2, 0.2]);
+        let right = f32_bytes(&[0.4, 0.4]);
+        let mixed = mono(
+            [(&left[..], 1), (&right[..], 1)],
+            BufferLayout::NonInterleaved,
+            true,
+        );
+        assert_close(&mixed, &[0.3, 0.3]);
+    }
+
+    #[test]
+    fn empty_buffers_are_left_out_of_the_mix() {
+        let left = f32_bytes(&[0.2, 0.4]);
+        let mixed = mono(
+            [(&left[..], 1), (&[][..], 1)],
+            BufferLayout::NonInterleaved,
+            true,
+        );
+        assert_close(&mixed, &[0.2, 0.4]);
+    }
+
+    #[test]
+    fn pcm16_channels_are_scaled_and_averaged() {
+        let bytes = [16_384i16, 0, -16_384, -16_384]
+            .iter()
+            .flat_map(|sample| sample.to_le_bytes())
+            .collect::<Vec<_>>();
+        let mixed = mono([(&bytes[..], 2)], BufferLayout::Interleaved, false);
+        assert_close(&mixed, &[0.25, -0.5]);
+    }
+
+    #[test]
+    fn steady_state_callbacks_do_not_allocate() {
+        let callbacks = callbacks(WARM_UP_CALLBACKS + 200);
+        let (warm_up, steady) = callbacks.split_at(WARM_UP_CALLBACKS);
+        for quality in [ResampleQuality::Fast, ResampleQuality::High] {
+            let mut path = AudioPath::new(quality);
+            for callback in warm_up {
+                run_callback(&mut path, callback);
+            }
+
+            let before = allocations();
+            let mut written = 0;
+            for callback in steady {
+                written += run_callback(&mut path, callback).len();
+            }
+            assert_eq!(allocations() - before, 0, "{quality:?}");
+            // 16-bit samples at 16 kHz, give or take what the sinc
+            // resampler holds back.
+            let expected = steady.len() * 320 * 2;
+            assert!(
+                written.abs_diff(expected) < 2 * SINC_CHUNK_FRAMES,
+                "{quality:?}: {written}"
+            );
+        }
+    }
+
+    /// Time spent per callback. Not run by default; for numbers worth
+    /// comparing use
+    /// `cargo test --release -- --ignored --nocapture callback_latency`.
+    #[test]
+    #[ignore]
+    fn callback_latency() {
+        let callbacks = callbacks(WARM_UP_CALLBACKS + 1_000);
+        for quality in [ResampleQuality::Fast, ResampleQuality::High] {
+            let mut path = AudioPath::new(quality);
+            let mut times = Vec::with_capacity(callbacks.len());
+            for callback in &callbacks {
+                let started = Instant::now();
+                run_callback(&mut path, callback);
+                times.push(started.elapsed());
+            }
+
+            let steady = &times[WARM_UP_CALLBACKS..];
+            let mean = steady.iter().sum::<Duration>() / steady.len() as u32;
+            let worst = steady.iter().max().copied().unwrap_or_default();
+            eprintln!("{quality:?}: mean {mean:?}, worst {worst:?} per 20 ms callback");
+            // Far less than the audio a callback carries.
+            assert!(worst < Duration::from_millis(20), "{quality:?}: {worst:?}");
+        }
     }
-    bytes
 }
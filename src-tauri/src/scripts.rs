@@ -0,0 +1,167 @@
+use std::borrow::Cow;
+
+/// First strong isolate / pop directional isolate: text between them gets its
+/// direction from its own first strong character and cannot reorder what
+/// surrounds it.
+const FSI: char = '\u{2068}';
+const PDI: char = '\u{2069}';
+
+/// Hebrew, Arabic, Syriac, Thaana, N'Ko and the Arabic and Hebrew
+/// presentation forms.
+fn is_rtl(c: char) -> bool {
+    matches!(c,
+        '\u{0590}'..='\u{08FF}'
+        | '\u{FB1D}'..='\u{FDFF}'
+        | '\u{FE70}'..='\u{FEFF}'
+        | '\u{10800}'..='\u{10FFF}'
+        | '\u{1E800}'..='\u{1EFFF}')
+}
+
+/// Scripts written without spaces between words: Han, kana, and the CJK
+/// punctuation and full-width forms around them. Hangul is spaced, so it is
+/// not included.
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3000}'..='\u{30FF}'
+        | '\u{3400}'..='\u{4DBF}'
+        | '\u{4E00}'..='\u{9FFF}'
+        | '\u{F900}'..='\u{FAFF}'
+        | '\u{FF00}'..='\u{FFEF}'
+        | '\u{20000}'..='\u{3FFFF}')
+}
+
+fn is_isolate(c: char) -> bool {
+    matches!(c, FSI | PDI)
+}
+
+/// Wraps right-to-left text in a directional isolate, so an LTR prefix such
+/// as `[00:01:02] **Me:**` keeps its place and a trailing number or
+/// punctuation mark stays with the text it belongs to. Other text is
+/// returned unchanged.
+pub fn isolate(text: &str) -> Cow<'_, str> {
+    if text.chars().any(is_rtl) {
+        Cow::Owned(format!("{FSI}{text}{PDI}"))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+/// Joins transcript lines into one paragraph. Lines meet with a space, except
+/// between two CJK characters, where a space would not be there in writing.
+pub fn join_lines<'a>(lines: impl IntoIterator<Item = &'a str>) -> String {
+    let mut joined = String::new();
+    for line in lines {
+        let spaced = match (joined.chars().last(), line.chars().next()) {
+            (None, _) => false,
+            (Some(last), Some(next)) => !(is_cjk(last) && is_cjk(next)),
+            (Some(_), None) => false,
+        };
+        if spaced {
+            joined.push(' ');
+        }
+        joined.push_str(line);
+    }
+    joined
+}
+
+/// Words in `text`: runs between whitespace, except that each CJK character
+/// counts as a word of its own (and CJK punctuation as none). Directional
+/// isolates are not counted.
+pub fn word_count(text: &str) -> usize {
+    text.split_whitespace().map(token_word_count).sum()
+}
+
+pub fn token_word_count(token: &str) -> usize {
+    let mut count = 0;
+    let mut in_run = false;
+    for c in token.chars() {
+        if is_cjk(c) {
+            if c.is_alphanumeric() {
+                count += 1;
+            }
+            in_run = false;
+        } else if !is_isolate(c) && !in_run {
+            count += 1;
+            in_run = true;
+        }
+    }
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ARABIC: &str = "سأرسل التقرير غدًا";
+    const ARABIC_MIXED: &str = "الاجتماع مع Acme الساعة 3:30";
+    const HEBREW: &str = "נדבר על זה מחר";
+    const HEBREW_MIXED: &str = "הגרסה 2.0 של iOS יצאה!";
+    const JAPANESE_LINES: [&str; 2] = ["今日は会議があります。", "資料を確認してください。"];
+    const CHINESE_LINES: [&str; 2] = ["我们明天开会", "请准备好报告。"];
+
+    fn isolated(text: &str) -> String {
+        format!("{FSI}{text}{PDI}")
+    }
+
+    #[test]
+    fn right_to_left_text_is_isolated() {
+        assert_eq!(isolate(ARABIC), isolated(ARABIC));
+        assert_eq!(isolate(HEBREW), isolated(HEBREW));
+    }
+
+    #[test]
+    fn mixed_direction_text_is_isolated_whole() {
+        // Latin words, numbers and a trailing `!` stay inside the isolate,
+        // with the right-to-left text they belong to.
+        assert_eq!(isolate(ARABIC_MIXED), isolated(ARABIC_MIXED));
+        assert_eq!(isolate(HEBREW_MIXED), isolated(HEBREW_MIXED));
+        let line = format!("[00:01:02] **Me:** {}", isolate(HEBREW_MIXED));
+        assert!(line.starts_with("[00:01:02] **Me:** \u{2068}"));
+        assert!(line.ends_with("!\u{2069}"));
+    }
+
+    #[test]
+    fn left_to_right_text_is_borrowed_unchanged() {
+        for text in ["Ship it on Friday", JAPANESE_LINES[0], CHINESE_LINES[0]] {
+            assert!(matches!(isolate(text), Cow::Borrowed(borrowed) if borrowed == text));
+        }
+    }
+
+    #[test]
+    fn cjk_lines_join_without_spaces() {
+        assert_eq!(
+            join_lines(JAPANESE_LINES),
+            "今日は会議があります。資料を確認してください。"
+        );
+        assert_eq!(join_lines(CHINESE_LINES), "我们明天开会请准备好报告。");
+    }
+
+    #[test]
+    fn other_lines_join_with_a_space() {
+        assert_eq!(
+            join_lines(["Hello there.", "How are you?"]),
+            "Hello there. How are you?"
+        );
+        assert_eq!(join_lines([HEBREW, HEBREW]), format!("{HEBREW} {HEBREW}"));
+        assert_eq!(join_lines(["我们明天开会", "OK"]), "我们明天开会 OK");
+        assert_eq!(
+            join_lines(["Meeting at 3", "我们明天开会"]),
+            "Meeting at 3 我们明天开会"
+        );
+        assert_eq!(join_lines(["", "first", ""]), "first");
+    }
+
+    #[test]
+    fn cjk_characters_count_as_words() {
+        assert_eq!(word_count(CHINESE_LINES[0]), 6);
+        // The full stop is punctuation, not a word.
+        assert_eq!(word_count("会議。"), 2);
+        assert_eq!(word_count("Acme 会議 today"), 4);
+    }
+
+    #[test]
+    fn isolates_are_not_words() {
+        assert_eq!(word_count(&isolate(HEBREW)), 4);
+        assert_eq!(word_count(&isolate(ARABIC_MIXED)), 5);
+    }
+}
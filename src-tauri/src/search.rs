@@ -0,0 +1,185 @@
+use std::path::Path;
+
+use anyhow::{anyhow, Context};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+use crate::{app_state::SharedState, history};
+
+const MAX_RESULTS: usize = 50;
+/// Marker bytes FTS5 wraps around matched terms; split back out into
+/// `SnippetPart`s so the UI never has to render markup.
+const MATCH_START: char = '\u{2}';
+const MATCH_END: char = '\u{3}';
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    pub path: String,
+    pub created_at: String,
+    pub snippet: Vec<SnippetPart>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnippetPart {
+    pub text: String,
+    pub highlighted: bool,
+}
+
+/// SQLite FTS5 index of saved transcripts in `search.sqlite`. Kept in step
+/// with the history index: every save, edit, import and delete goes through
+/// `history`, which updates this too. Edits made outside the app reach it
+/// through `transcript_watch`.
+pub struct SearchIndex {
+    connection: Option<Connection>,
+}
+
+impl SearchIndex {
+    pub fn open(app_data_dir: &Path) -> Self {
+        let connection = std::fs::create_dir_all(app_data_dir)
+            .ok()
+            .and_then(|_| Connection::open(app_data_dir.join("search.sqlite")).ok())
+            .filter(|connection| {
+                connection
+                    .execute_batch(
+                        "CREATE VIRTUAL TABLE IF NOT EXISTS transcripts USING fts5(
+                            path UNINDEXED,
+                            created_at UNINDEXED,
+                            body,
+                            tokenize = 'unicode61 remove_diacritics 2'
+                        );",
+                    )
+                    .is_ok()
+            });
+
+        Self { connection }
+    }
+
+    fn connection(&self) -> anyhow::Result<&Connection> {
+        self.connection
+            .as_ref()
+            .ok_or_else(|| anyhow!("search index is unavailable"))
+    }
+
+    pub fn upsert(&self, path: &str, created_at: &str, body: &str) -> anyhow::Result<()> {
+        let connection = self.connection()?;
+        connection.execute("DELETE FROM transcripts WHERE path = ?1", params![path])?;
+        connection.execute(
+            "INSERT INTO transcripts (path, created_at, body) VALUES (?1, ?2, ?3)",
+            params![path, created_at, body],
+        )?;
+        Ok(())
+    }
+
+    pub fn remove(&self, path: &str) -> anyhow::Result<()> {
+        self.connection()?
+            .execute("DELETE FROM transcripts WHERE path = ?1", params![path])?;
+        Ok(())
+    }
+
+    fn is_empty(&self) -> anyhow::Result<bool> {
+        let count: i64 =
+            self.connection()?
+                .query_row("SELECT count(*) FROM transcripts", [], |row| row.get(0))?;
+        Ok(count == 0)
+    }
+
+    fn query(&self, query: &str) -> anyhow::Result<Vec<SearchHit>> {
+        let connection = self.connection()?;
+        let mut statement = connection.prepare(
+            "SELECT path, created_at, snippet(transcripts, 2, char(2), char(3), '…', 16)
+             FROM transcripts
+             WHERE transcripts MATCH ?1
+             ORDER BY rank
+             LIMIT ?2",
+        )?;
+
+        let rows = statement.query_map(params![query, MAX_RESULTS as i64], |row| {
+            Ok(SearchHit {
+                path: row.get(0)?,
+                created_at: row.get(1)?,
+                snippet: split_snippet(&row.get::<_, String>(2)?),
+            })
+        })?;
+
+        rows.collect::<Result<Vec<_>, _>>()
+            .context("failed reading search results")
+    }
+}
+
+/// Indexes (or re-indexes) the transcript saved at `path`.
+pub async fn index(state: &SharedState, path: &str, created_at: &str, body: &str) {
+    let _ = state.search.lock().await.upsert(path, created_at, body);
+}
+
+pub async fn remove(state: &SharedState, path: &str) {
+    let _ = state.search.lock().await.remove(path);
+}
+
+pub async fn search(state: &SharedState, query: &str) -> anyhow::Result<Vec<SearchHit>> {
+    let Some(match_expr) = match_expression(query) else {
+        return Ok(Vec::new());
+    };
+
+    // First search after upgrading: index whatever history already knows.
+    if state.search.lock().await.is_empty()? {
+        backfill(state).await;
+    }
+
+    state.search.lock().await.query(&match_expr)
+}
+
+async fn backfill(state: &SharedState) {
+    for entry in history::list(state).await {
+        if let Ok(body) = tokio::fs::read_to_string(&entry.path).await {
+            index(state, &entry.path, &entry.created_at, &body).await;
+        }
+    }
+}
+
+/// Turns free text into an FTS5 query that matches every word, each quoted so
+/// user input can never be parsed as FTS5 syntax.
+fn match_expression(query: &str) -> Option<String> {
+    let terms = query
+        .split_whitespace()
+        .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+        .collect::<Vec<_>>();
+
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+fn split_snippet(raw: &str) -> Vec<SnippetPart> {
+    let mut parts = Vec::new();
+    let mut text = String::new();
+    let mut highlighted = false;
+
+    for ch in raw.chars() {
+        let toggle = match ch {
+            MATCH_START => !highlighted,
+            MATCH_END => highlighted,
+            _ => false,
+        };
+        if !toggle {
+            text.push(ch);
+            continue;
+        }
+
+        if !text.is_empty() {
+            parts.push(SnippetPart {
+                text: std::mem::take(&mut text),
+                highlighted,
+            });
+        }
+        highlighted = !highlighted;
+    }
+
+    if !text.is_empty() {
+        parts.push(SnippetPart { text, highlighted });
+    }
+    parts
+}
@@ -0,0 +1,65 @@
+use std::collections::VecDeque;
+
+use serde::Serialize;
+
+use crate::app_state::AppSnapshot;
+
+/// How many emitted snapshots are kept for windows catching up.
+pub const CAPACITY: usize = 32;
+
+/// Numbers every emitted state event and keeps the most recent ones, so a
+/// window that opens (or falls behind) mid-session can replay what it missed
+/// instead of rendering a gapped transcript.
+#[derive(Default)]
+pub struct SnapshotLog {
+    last_seq: u64,
+    recent: VecDeque<AppSnapshot>,
+}
+
+/// Reply to `get_app_state_since`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CatchUp {
+    /// Snapshots newer than the requested sequence number, oldest first.
+    pub snapshots: Vec<AppSnapshot>,
+    /// Set when the requested number fell out of the buffer; `snapshots` then
+    /// only holds the latest state, which supersedes everything missed.
+    pub reset: bool,
+}
+
+impl SnapshotLog {
+    /// Assigns the next sequence number to `snapshot` and remembers it.
+    pub fn record(&mut self, mut snapshot: AppSnapshot) -> AppSnapshot {
+        self.last_seq += 1;
+        snapshot.seq = self.last_seq;
+
+        if self.recent.len() == CAPACITY {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(snapshot.clone());
+        snapshot
+    }
+
+    pub fn latest(&self) -> Option<&AppSnapshot> {
+        self.recent.back()
+    }
+
+    pub fn since(&self, seq: u64) -> CatchUp {
+        let oldest = self.recent.front().map(|snapshot| snapshot.seq);
+        match oldest {
+            Some(oldest) if seq + 1 >= oldest => CatchUp {
+                snapshots: self
+                    .recent
+                    .iter()
+                    .filter(|snapshot| snapshot.seq > seq)
+                    .cloned()
+                    .collect(),
+                reset: false,
+            },
+            _ => CatchUp {
+                snapshots: self.latest().cloned().into_iter().collect(),
+                reset: true,
+            },
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use std::{path::PathBuf, process::Stdio};
+
+use anyhow::{anyhow, Context};
+use serde::Serialize;
+use tokio::{io::AsyncWriteExt, process::Command};
+
+use crate::app_state::TranscriptSegment;
+
+const MAX_SUGGESTIONS: usize = 5;
+
+/// A word hunspell does not know, with its replacement candidates.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpellingIssue {
+    pub segment: usize,
+    pub word: String,
+    /// Offset of `word` in the segment text, in UTF-16 code units so the UI
+    /// can slice the JavaScript string with it.
+    pub offset: usize,
+    pub suggestions: Vec<String>,
+}
+
+/// Runs every segment through `hunspell -a` with the dictionary for
+/// `language`. Dictionaries are the locally installed ones (`en_US`,
+/// `pt_BR`), e.g. from `~/Library/Spelling`.
+pub async fn check(
+    segments: &[TranscriptSegment],
+    language: &str,
+) -> anyhow::Result<Vec<SpellingIssue>> {
+    if segments.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let hunspell = resolve_hunspell().ok_or_else(|| {
+        anyhow!("hunspell is required for spell-check. Install it with Homebrew (`brew install hunspell`) or set WHISPERBAR_HUNSPELL_PATH.")
+    })?;
+
+    let mut child = Command::new(&hunspell)
+        .arg("-a")
+        .arg("-d")
+        .arg(dictionary(language))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("failed starting {}", hunspell.display()))?;
+
+    // `^` makes hunspell treat the rest of the line as text, never a command.
+    let input = segments
+        .iter()
+        .map(|segment| format!("^{}\n", segment.text.replace('\n', " ")))
+        .collect::<String>();
+    let mut stdin = child
+        .stdin
+        .take()
+        .context("unable to open hunspell stdin")?;
+    stdin.write_all(input.as_bytes()).await?;
+    drop(stdin);
+
+    let output = child.wait_with_output().await?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "hunspell failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(parse(&String::from_utf8_lossy(&output.stdout), segments))
+}
+
+/// Parses ispell pipe output: a banner line, then one block of result lines
+/// per input line, each block ended by an empty line.
+fn parse(output: &str, segments: &[TranscriptSegment]) -> Vec<SpellingIssue> {
+    let mut issues = Vec::new();
+    let mut segment = 0;
+
+    for line in output.lines().skip(1) {
+        if line.is_empty() {
+            segment += 1;
+            continue;
+        }
+        let Some(text) = segments.get(segment).map(|segment| segment.text.as_str()) else {
+            break;
+        };
+
+        // `& word count offset: a, b` has suggestions; `# word offset` has none.
+        let (head, suggestions) = match line.split_once(": ") {
+            Some((head, list)) => (
+                head,
+                list.split(", ")
+                    .take(MAX_SUGGESTIONS)
+                    .map(str::to_string)
+                    .collect(),
+            ),
+            None => (line, Vec::new()),
+        };
+        let mut fields = head.split_whitespace();
+        let (Some(kind), Some(word)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+        if kind != "&" && kind != "#" {
+            continue;
+        }
+        let reported = fields
+            .last()
+            .and_then(|value| value.parse::<usize>().ok())
+            .unwrap_or(0);
+
+        issues.push(SpellingIssue {
+            segment,
+            word: word.to_string(),
+            offset: nearest_occurrence(text, word, reported)
+                .map(|offset| text[..offset].encode_utf16().count())
+                .unwrap_or(0),
+            suggestions,
+        });
+    }
+
+    issues
+}
+
+/// hunspell counts offsets in characters from its own view of the line; pick
+/// the actual occurrence closest to that position.
+fn nearest_occurrence(text: &str, word: &str, reported: usize) -> Option<usize> {
+    text.match_indices(word)
+        .map(|(offset, _)| offset)
+        .min_by_key(|offset| text[..*offset].chars().count().abs_diff(reported))
+}
+
+fn dictionary(language: &str) -> &'static str {
+    match language {
+        "pt-BR" => "pt_BR",
+        _ => "en_US",
+    }
+}
+
+fn resolve_hunspell() -> Option<PathBuf> {
+    let explicit = std::env::var("WHISPERBAR_HUNSPELL_PATH")
+        .ok()
+        .filter(|path| !path.trim().is_empty())
+        .map(PathBuf::from);
+    let in_path = std::env::var_os("PATH").and_then(|paths| {
+        std::env::split_paths(&paths)
+            .map(|dir| dir.join("hunspell"))
+            .find(|candidate| candidate.is_file())
+    });
+
+    explicit
+        .into_iter()
+        .chain(in_path)
+        .chain(
+            ["/opt/homebrew/bin/hunspell", "/usr/local/bin/hunspell"]
+                .into_iter()
+                .map(PathBuf::from),
+        )
+        .find(|candidate| candidate.is_file())
+}
@@ -0,0 +1,234 @@
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::Context;
+use serde::Serialize;
+
+use crate::{
+    app_state::AppPaths,
+    models::{self, ModelBackend, ModelSpec},
+    python_runtime, recording_audio,
+};
+
+const MIB: u64 = 1024 * 1024;
+
+/// What `python-env` grows to once mlx-whisper, numpy and their wheels are
+/// installed, with pip's temporary files on top.
+const VENV_BYTES: u64 = 1536 * MIB;
+
+/// The extracted python-build-standalone runtime, plus its archive while it
+/// is being verified.
+const RUNTIME_BYTES: u64 = 256 * MIB;
+
+/// Left free after an install, so it does not fill the disk to the last byte.
+const HEADROOM_BYTES: u64 = 512 * MIB;
+
+/// Not enough free space in the app data volume for an install.
+#[derive(Debug)]
+pub struct LowDiskSpace {
+    pub required: u64,
+    pub available: u64,
+}
+
+impl std::fmt::Display for LowDiskSpace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Not enough disk space: the install needs about {} free and only {} is available. Free up space or choose a smaller model.",
+            format_gb(self.required),
+            format_gb(self.available)
+        )
+    }
+}
+
+impl std::error::Error for LowDiskSpace {}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageBreakdown {
+    pub app_data_dir: String,
+    pub total_bytes: u64,
+    pub categories: Vec<StorageCategory>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageCategory {
+    pub id: &'static str,
+    pub label: &'static str,
+    pub bytes: u64,
+}
+
+/// Sizes what WhisperBar keeps in Application Support. Anything not covered
+/// by a named category is reported as `other`.
+pub async fn breakdown(paths: &AppPaths) -> anyhow::Result<StorageBreakdown> {
+    let app_data_dir = paths.app_data_dir.clone();
+    let groups: Vec<(&'static str, &'static str, Vec<PathBuf>)> = vec![
+        ("models", "Models", vec![app_data_dir.join("models")]),
+        (
+            "pythonEnv",
+            "Python environment",
+            python_envs(&app_data_dir)
+                .into_iter()
+                .chain([python_runtime::runtime_dir(&app_data_dir)])
+                .collect(),
+        ),
+        (
+            "downloadCache",
+            "Download cache",
+            vec![app_data_dir.join("hf-cache")],
+        ),
+        ("scripts", "Scripts", vec![paths.scripts_dir.clone()]),
+        (
+            "history",
+            "History and search index",
+            vec![
+                app_data_dir.join("history.json"),
+                app_data_dir.join("search.sqlite"),
+            ],
+        ),
+        (
+            "recoveredAudio",
+            "Recovered audio",
+            vec![recording_audio::recordings_dir(paths)],
+        ),
+    ];
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let total_bytes = disk_size(&app_data_dir);
+        let mut categories = groups
+            .into_iter()
+            .map(|(id, label, paths)| StorageCategory {
+                id,
+                label,
+                bytes: paths.iter().map(|path| disk_size(path)).sum(),
+            })
+            .collect::<Vec<_>>();
+
+        let named = categories
+            .iter()
+            .map(|category| category.bytes)
+            .sum::<u64>();
+        categories.push(StorageCategory {
+            id: "other",
+            label: "Other",
+            bytes: total_bytes.saturating_sub(named),
+        });
+
+        StorageBreakdown {
+            app_data_dir: app_data_dir.display().to_string(),
+            total_bytes,
+            categories,
+        }
+    })
+    .await
+    .context("failed computing storage breakdown")
+}
+
+/// `python-env`, a symlink to the active environment, and the versioned
+/// environments next to it (`python-env-v2`, the previous one kept for
+/// rollback).
+fn python_envs(app_data_dir: &Path) -> Vec<PathBuf> {
+    std::fs::read_dir(app_data_dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|entry| {
+                    entry
+                        .file_name()
+                        .to_string_lossy()
+                        .starts_with("python-env")
+                })
+                .map(|entry| entry.path())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+pub fn open_app_data_dir(paths: &AppPaths) -> anyhow::Result<()> {
+    std::fs::create_dir_all(&paths.app_data_dir)?;
+    std::process::Command::new("open")
+        .arg(&paths.app_data_dir)
+        .spawn()
+        .with_context(|| format!("failed opening {}", paths.app_data_dir.display()))?;
+    Ok(())
+}
+
+/// Checks free space before an install starts, so it fails up front instead
+/// of pip or a model download dying halfway. Counts what is still missing:
+/// the model minus any files already downloaded, and the Python runtime and
+/// environment when an MLX model needs them and they do not exist yet. Passes when free
+/// space cannot be read.
+pub fn install_preflight(app_data_dir: &Path, model: &ModelSpec) -> Result<(), LowDiskSpace> {
+    let downloaded = models::model_path(app_data_dir, model.id)
+        .map(|path| disk_size(&path))
+        .unwrap_or(0);
+    let mut missing = model.size_bytes.saturating_sub(downloaded);
+    if model.backend == ModelBackend::Mlx {
+        if !app_data_dir.join("python-env").exists() {
+            missing += VENV_BYTES;
+        }
+        if !python_runtime::runtime_dir(app_data_dir).exists() {
+            missing += RUNTIME_BYTES;
+        }
+    }
+    if missing == 0 {
+        return Ok(());
+    }
+
+    let Some(available) = available_bytes(app_data_dir) else {
+        return Ok(());
+    };
+    let required = missing + HEADROOM_BYTES;
+    if required > available {
+        return Err(LowDiskSpace {
+            required,
+            available,
+        });
+    }
+    Ok(())
+}
+
+/// Free space on the volume holding `path` (or its nearest existing
+/// ancestor), from `df`.
+fn available_bytes(path: &Path) -> Option<u64> {
+    let existing = path.ancestors().find(|ancestor| ancestor.exists())?;
+    let output = Command::new("df").arg("-k").arg(existing).output().ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    // "Filesystem 1024-blocks Used Available Capacity ..." then one volume.
+    let kib: u64 = stdout
+        .lines()
+        .nth(1)?
+        .split_whitespace()
+        .nth(3)?
+        .parse()
+        .ok()?;
+    Some(kib * 1024)
+}
+
+fn format_gb(bytes: u64) -> String {
+    format!("{:.1} GB", bytes as f64 / (1024.0 * MIB as f64))
+}
+
+/// Bytes used by a file, or by everything under a directory. Symlinks are not
+/// followed; a missing path counts as zero.
+pub fn disk_size(path: &Path) -> u64 {
+    let Ok(metadata) = std::fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+
+    std::fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| disk_size(&entry.path()))
+                .sum()
+        })
+        .unwrap_or(0)
+}
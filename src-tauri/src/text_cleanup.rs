@@ -0,0 +1,279 @@
+/// Hesitation sounds dropped wherever they stand.
+const ENGLISH_FILLERS: [&str; 10] = [
+    "um", "umm", "uh", "uhh", "uhm", "er", "erm", "hmm", "mm", "ah",
+];
+const PORTUGUESE_FILLERS: [&str; 9] = ["hã", "ãh", "ã", "hum", "humm", "uhm", "éé", "ééé", "ahn"];
+
+/// Dropped only when set off by a comma (`it was, like, huge`), where they
+/// carry no meaning; `I like it` keeps its `like`.
+const ENGLISH_HEDGES: [&str; 1] = ["like"];
+const PORTUGUESE_HEDGES: [&str; 1] = ["tipo"];
+
+/// Words that legitimately appear twice in a row (`had had`, `que que`);
+/// only a third one in a row counts as a repetition.
+const ENGLISH_DOUBLES: [&str; 4] = ["had", "that", "is", "bye"];
+const PORTUGUESE_DOUBLES: [&str; 3] = ["que", "se", "já"];
+
+/// Longest stutter fragment: `b-but`, `w- want`, `th- the`.
+const MAX_STUTTER_CHARS: usize = 3;
+
+/// Fragments that are prefixes rather than stutters: `re-read`, `co-counsel`.
+const PREFIXES: [&str; 9] = ["re", "co", "de", "pre", "pro", "non", "sub", "un", "des"];
+
+/// Strips filler words, comma-set hedges, repeated words and stutters from a
+/// segment, in the word lists of `language` (English for anything but
+/// pt-BR). A sentence that started with a dropped word gets its capital
+/// back on the next one, and a dropped word's sentence-ending punctuation
+/// moves to the word before it.
+pub fn remove_fillers(text: &str, language: &str) -> String {
+    let (fillers, hedges, doubles) = match language {
+        "pt-BR" => (
+            &PORTUGUESE_FILLERS[..],
+            &PORTUGUESE_HEDGES[..],
+            &PORTUGUESE_DOUBLES[..],
+        ),
+        _ => (
+            &ENGLISH_FILLERS[..],
+            &ENGLISH_HEDGES[..],
+            &ENGLISH_DOUBLES[..],
+        ),
+    };
+
+    let tokens = text.split_whitespace().collect::<Vec<_>>();
+    let mut kept: Vec<String> = Vec::with_capacity(tokens.len());
+    let mut capitalize_next = false;
+    // The last kept word and how many times in a row it was seen.
+    let mut run: Option<(String, usize)> = None;
+
+    for (index, token) in tokens.iter().enumerate() {
+        let (word, trailing) = split_trailing(token);
+        let lower = word.to_lowercase();
+        let starts_sentence = kept.last().is_none_or(|last| ends_sentence(last));
+
+        let drop = fillers.contains(&lower.as_str())
+            || (hedges.contains(&lower.as_str()) && trailing.starts_with(','))
+            || is_stutter_fragment(token, tokens.get(index + 1).copied());
+        if drop {
+            if hedges.contains(&lower.as_str()) {
+                // `was, like, huge` -> `was huge`.
+                if let Some(last) = kept.last_mut() {
+                    if last.ends_with(',') {
+                        last.pop();
+                    }
+                }
+            }
+            if ends_sentence(trailing) {
+                if let Some(last) = kept.last_mut() {
+                    let last_trailing = split_trailing(last).1.to_string();
+                    if last_trailing.is_empty() || last_trailing == "," {
+                        last.truncate(last.len() - last_trailing.len());
+                        last.push_str(trailing.trim_start_matches(','));
+                    }
+                }
+            }
+            capitalize_next |= starts_sentence && word.starts_with(char::is_uppercase);
+            continue;
+        }
+
+        // `b-but` -> `but`.
+        let token = match inline_stutter(word) {
+            Some(rest) => format!("{rest}{trailing}"),
+            None => token.to_string(),
+        };
+        let (word, _) = split_trailing(&token);
+        let lower = word.to_lowercase();
+
+        let repeats = match &run {
+            Some((previous, count))
+                if *previous == lower
+                    && lower.chars().any(char::is_alphabetic)
+                    && kept
+                        .last()
+                        .is_some_and(|last| split_trailing(last).1.is_empty()) =>
+            {
+                count + 1
+            }
+            _ => 1,
+        };
+        run = Some((lower.clone(), repeats));
+        let allowed = if doubles.contains(&lower.as_str()) {
+            2
+        } else {
+            1
+        };
+        if repeats > allowed {
+            if let Some(last) = kept.last_mut() {
+                // The repeat may carry the punctuation the run ends with.
+                let (_, trailing) = split_trailing(&token);
+                last.push_str(trailing);
+            }
+            continue;
+        }
+
+        let token = if capitalize_next {
+            capitalize(&token)
+        } else {
+            token
+        };
+        capitalize_next = false;
+        kept.push(token);
+    }
+
+    kept.join(" ")
+}
+
+/// Splits `word,` into `word` and `,`; leading punctuation stays with the
+/// word.
+fn split_trailing(token: &str) -> (&str, &str) {
+    let end = token
+        .char_indices()
+        .rev()
+        .find(|(_, c)| c.is_alphanumeric())
+        .map_or(0, |(index, c)| index + c.len_utf8());
+    token.split_at(end)
+}
+
+fn ends_sentence(token: &str) -> bool {
+    token.ends_with(['.', '?', '!', '…'])
+}
+
+fn is_fragment(fragment: &str) -> bool {
+    (1..=MAX_STUTTER_CHARS).contains(&fragment.chars().count())
+        && fragment.chars().all(char::is_alphabetic)
+        && !PREFIXES.contains(&fragment.to_lowercase().as_str())
+}
+
+/// `w-` followed by `want`.
+fn is_stutter_fragment(token: &str, next: Option<&str>) -> bool {
+    match (token.strip_suffix('-'), next) {
+        (Some(fragment), Some(next)) => {
+            is_fragment(fragment) && next.to_lowercase().starts_with(&fragment.to_lowercase())
+        }
+        _ => false,
+    }
+}
+
+/// What follows the stutter in `b-but`, keeping the fragment's capital.
+fn inline_stutter(word: &str) -> Option<String> {
+    let (fragment, rest) = word.split_once('-')?;
+    if !is_fragment(fragment)
+        || rest.chars().count() <= fragment.chars().count()
+        || !rest.to_lowercase().starts_with(&fragment.to_lowercase())
+    {
+        return None;
+    }
+    Some(if fragment.starts_with(char::is_uppercase) {
+        capitalize(rest)
+    } else {
+        rest.to_string()
+    })
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn en(text: &str) -> String {
+        remove_fillers(text, "en")
+    }
+
+    fn pt(text: &str) -> String {
+        remove_fillers(text, "pt-BR")
+    }
+
+    #[test]
+    fn english_fillers_are_dropped() {
+        assert_eq!(
+            en("So um I think uh we should go"),
+            "So I think we should go"
+        );
+        assert_eq!(en("Um, the build is green."), "The build is green.");
+        assert_eq!(en("It works, hmm."), "It works.");
+    }
+
+    #[test]
+    fn portuguese_fillers_are_dropped() {
+        assert_eq!(
+            pt("Então hã a gente vai hum amanhã"),
+            "Então a gente vai amanhã"
+        );
+        assert_eq!(pt("Ahn, pode ser."), "Pode ser.");
+    }
+
+    #[test]
+    fn hedges_go_only_between_commas() {
+        assert_eq!(en("It was, like, huge"), "It was huge");
+        assert_eq!(en("I like it"), "I like it");
+        assert_eq!(pt("Foi, tipo, enorme"), "Foi enorme");
+        assert_eq!(pt("Esse tipo de coisa"), "Esse tipo de coisa");
+    }
+
+    #[test]
+    fn repeated_words_are_collapsed() {
+        assert_eq!(en("I I think the the plan works"), "I think the plan works");
+        assert_eq!(pt("eu eu acho que sim"), "eu acho que sim");
+    }
+
+    #[test]
+    fn legitimate_doubles_are_kept() {
+        assert_eq!(en("She had had enough"), "She had had enough");
+        assert_eq!(
+            en("He said that that was fine"),
+            "He said that that was fine"
+        );
+        assert_eq!(en("She had had had enough"), "She had had enough");
+        assert_eq!(pt("O que que você disse?"), "O que que você disse?");
+    }
+
+    #[test]
+    fn reduplicated_expressions_are_kept() {
+        assert_eq!(en("Okay, bye bye"), "Okay, bye bye");
+        assert_eq!(pt("Eu volto já já"), "Eu volto já já");
+        assert_eq!(pt("Eu volto já já já"), "Eu volto já já");
+    }
+
+    #[test]
+    fn repeats_across_punctuation_are_kept() {
+        assert_eq!(en("No. No, not that"), "No. No, not that");
+    }
+
+    #[test]
+    fn stutters_are_removed() {
+        assert_eq!(en("b-but I w- want it"), "but I want it");
+        assert_eq!(en("Th- the plan is fine"), "The plan is fine");
+        assert_eq!(en("B-but why?"), "But why?");
+        assert_eq!(pt("e-eu q- quero ir"), "eu quero ir");
+    }
+
+    #[test]
+    fn prefixes_are_not_stutters() {
+        assert_eq!(
+            en("Please re-read the co-counsel brief"),
+            "Please re-read the co-counsel brief"
+        );
+        assert_eq!(
+            en("a non-native pre- presentation"),
+            "a non-native pre- presentation"
+        );
+        assert_eq!(pt("vou des-fazer isso"), "vou des-fazer isso");
+    }
+
+    #[test]
+    fn a_dropped_sentence_start_capitalizes_the_next_word() {
+        assert_eq!(en("Done. Uh we ship it"), "Done. We ship it");
+    }
+
+    #[test]
+    fn a_dropped_word_hands_its_punctuation_back() {
+        assert_eq!(en("We ship it um."), "We ship it.");
+        assert_eq!(pt("Vamos nessa, hã?"), "Vamos nessa?");
+    }
+}
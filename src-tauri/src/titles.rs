@@ -0,0 +1,56 @@
+use std::time::Duration;
+
+use anyhow::anyhow;
+
+use crate::{app_state::TranscriptSegment, llm::Llm, scripts};
+
+/// How long `stop_recording` waits for a title before saving without one.
+const TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Transcript text sent with the prompt; the start of a session is enough
+/// to name it.
+const MAX_PROMPT_CHARS: usize = 8_000;
+
+const MAX_TITLE_CHARS: usize = 60;
+
+/// Asks `llm` for a short title for the transcript. Fails when the server
+/// cannot be reached within `TIMEOUT` or answers with nothing usable.
+pub async fn generate(llm: &Llm, segments: &[TranscriptSegment]) -> anyhow::Result<String> {
+    let text = scripts::join_lines(
+        segments
+            .iter()
+            .map(|segment| segment.text.trim())
+            .filter(|text| !text.is_empty()),
+    )
+    .chars()
+    .take(MAX_PROMPT_CHARS)
+    .collect::<String>();
+    let prompt = format!(
+        "Write a short title, at most eight words, for this transcript. Use the \
+         transcript's language. Reply with the title only.\n\n{text}"
+    );
+
+    let raw = llm.complete(&prompt, 32, TIMEOUT).await?;
+    clean(&raw).ok_or_else(|| anyhow!("the title endpoint answered with an empty title"))
+}
+
+/// The first line of the answer without the quotes, `Title:` prefix or
+/// Markdown emphasis models like to add.
+fn clean(raw: &str) -> Option<String> {
+    let line = raw.lines().map(str::trim).find(|line| !line.is_empty())?;
+    let line = line.trim_start_matches('#').trim();
+    let line = match line.get(..6) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("title:") => &line[6..],
+        _ => line,
+    };
+    let title = line
+        .trim_matches(|c: char| {
+            matches!(c, '"' | '\'' | '*' | '_' | '“' | '”') || c.is_whitespace()
+        })
+        .trim_end_matches('.')
+        .chars()
+        .take(MAX_TITLE_CHARS)
+        .collect::<String>();
+    let title = title.trim().to_string();
+    (!title.is_empty()).then_some(title)
+}
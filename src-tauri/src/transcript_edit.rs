@@ -0,0 +1,387 @@
+use std::{collections::VecDeque, path::PathBuf};
+
+use anyhow::anyhow;
+use regex::{NoExpand, Regex};
+use tauri::AppHandle;
+
+use crate::{
+    app_state::{emit_state, AppStatus, SharedState, TranscriptSegment, TranscriptState},
+    history, scripts, transcript_file,
+};
+
+/// Edits `undo` can step back through; older ones are dropped.
+const MAX_UNDO_STEPS: usize = 50;
+
+/// Whole-transcript snapshots taken before each edit. Transcripts are small
+/// and edits are per segment, so copies keep undo exact without diffing.
+#[derive(Default)]
+pub struct EditHistory {
+    undo: VecDeque<Vec<TranscriptSegment>>,
+    redo: Vec<Vec<TranscriptSegment>>,
+}
+
+impl EditHistory {
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Call before changing `segments`; a new edit discards the redo stack.
+    fn record(&mut self, segments: &[TranscriptSegment]) {
+        if self.undo.len() == MAX_UNDO_STEPS {
+            self.undo.pop_front();
+        }
+        self.undo.push_back(segments.to_vec());
+        self.redo.clear();
+    }
+}
+
+pub async fn segments(state: &SharedState) -> Vec<TranscriptSegment> {
+    state.transcript.lock().await.segments.clone()
+}
+
+pub async fn update_segment(
+    app: &AppHandle,
+    state: &SharedState,
+    index: usize,
+    text: String,
+) -> anyhow::Result<()> {
+    ensure_not_recording(state).await?;
+
+    {
+        let mut transcript = state.transcript.lock().await;
+        let segment = transcript
+            .segments
+            .get(index)
+            .ok_or_else(|| anyhow!("transcript segment {index} does not exist"))?;
+        if segment.text == text {
+            return Ok(());
+        }
+
+        let TranscriptState {
+            segments, edits, ..
+        } = &mut *transcript;
+        edits.record(segments);
+        // Word timing no longer lines up with hand-edited text.
+        segments[index].text = text;
+        segments[index].words.clear();
+        transcript.dirty = true;
+    }
+
+    emit_state(app, state).await;
+    Ok(())
+}
+
+/// Splits segment `index` in two at `offset` (UTF-16 code units, as the UI
+/// counts them). Speaker and source carry over to both halves; the split
+/// time is interpolated from how much of the text falls before `offset`,
+/// and timed words go to whichever half they start in.
+pub async fn split_segment(
+    app: &AppHandle,
+    state: &SharedState,
+    index: usize,
+    offset: usize,
+) -> anyhow::Result<()> {
+    ensure_not_recording(state).await?;
+
+    {
+        let mut transcript = state.transcript.lock().await;
+        let segment = transcript
+            .segments
+            .get(index)
+            .ok_or_else(|| anyhow!("transcript segment {index} does not exist"))?;
+        let split_at = byte_offset(&segment.text, offset)
+            .ok_or_else(|| anyhow!("offset {offset} is outside segment {index}"))?;
+        let (before, after) = segment.text.split_at(split_at);
+        let (before, after) = (before.trim_end(), after.trim_start());
+        if before.is_empty() || after.is_empty() {
+            return Err(anyhow!("both parts of a split need text"));
+        }
+
+        let split_time = match (segment.start, segment.end) {
+            (Some(start), Some(end)) => {
+                let share = before.chars().count() as f64
+                    / (before.chars().count() + after.chars().count()) as f64;
+                Some(start + (end - start) * share)
+            }
+            _ => None,
+        };
+        let (words_before, words_after) = match split_time {
+            Some(split_time) => segment
+                .words
+                .iter()
+                .cloned()
+                .partition(|word| word.start < split_time),
+            None => (Vec::new(), Vec::new()),
+        };
+        let first = TranscriptSegment {
+            text: before.to_string(),
+            end: split_time.or(segment.end),
+            words: words_before,
+            ..segment.clone()
+        };
+        let second = TranscriptSegment {
+            text: after.to_string(),
+            start: split_time.or(segment.start),
+            paragraph: false,
+            words: words_after,
+            ..segment.clone()
+        };
+
+        let TranscriptState {
+            segments, edits, ..
+        } = &mut *transcript;
+        edits.record(segments);
+        segments.splice(index..=index, [first, second]);
+        transcript.dirty = true;
+    }
+
+    emit_state(app, state).await;
+    Ok(())
+}
+
+/// Joins consecutive segments into one, spanning from the first start to the
+/// last end. The merged segment keeps the first segment's speaker and source.
+pub async fn merge_segments(
+    app: &AppHandle,
+    state: &SharedState,
+    mut indices: Vec<usize>,
+) -> anyhow::Result<()> {
+    ensure_not_recording(state).await?;
+
+    indices.sort_unstable();
+    indices.dedup();
+    if indices.len() < 2 {
+        return Err(anyhow!("select at least two segments to merge"));
+    }
+    let (first, last) = (indices[0], indices[indices.len() - 1]);
+    if last - first + 1 != indices.len() {
+        return Err(anyhow!("only adjacent segments can be merged"));
+    }
+
+    {
+        let mut transcript = state.transcript.lock().await;
+        if last >= transcript.segments.len() {
+            return Err(anyhow!("transcript segment {last} does not exist"));
+        }
+
+        let parts = &transcript.segments[first..=last];
+        let merged = TranscriptSegment {
+            text: scripts::join_lines(
+                parts
+                    .iter()
+                    .map(|segment| segment.text.trim())
+                    .filter(|text| !text.is_empty()),
+            ),
+            start: parts.iter().find_map(|segment| segment.start),
+            end: parts.iter().rev().find_map(|segment| segment.end),
+            words: parts
+                .iter()
+                .flat_map(|segment| segment.words.iter().cloned())
+                .collect(),
+            ..parts[0].clone()
+        };
+
+        let TranscriptState {
+            segments, edits, ..
+        } = &mut *transcript;
+        edits.record(segments);
+        segments.splice(first..=last, [merged]);
+        transcript.dirty = true;
+    }
+
+    emit_state(app, state).await;
+    Ok(())
+}
+
+/// Byte offset of the `utf16_offset`-th UTF-16 code unit, if it falls on a
+/// character boundary within `text`.
+fn byte_offset(text: &str, utf16_offset: usize) -> Option<usize> {
+    let mut units = 0;
+    for (byte, ch) in text.char_indices() {
+        if units == utf16_offset {
+            return Some(byte);
+        }
+        units += ch.len_utf16();
+    }
+    (units == utf16_offset).then_some(text.len())
+}
+
+/// Replaces every match of `pattern` in every segment, as one undoable edit.
+/// `pattern` is literal text unless `regex` is set, in which case
+/// `replacement` may refer to capture groups (`$1`). Returns the number of
+/// matches replaced.
+pub async fn replace_all(
+    app: &AppHandle,
+    state: &SharedState,
+    pattern: &str,
+    replacement: &str,
+    regex: bool,
+) -> anyhow::Result<usize> {
+    ensure_not_recording(state).await?;
+    if pattern.is_empty() {
+        return Err(anyhow!("search text is required"));
+    }
+
+    let matcher = if regex {
+        Regex::new(pattern).map_err(|error| anyhow!("invalid regular expression: {error}"))?
+    } else {
+        Regex::new(&regex::escape(pattern))?
+    };
+
+    let count = {
+        let mut transcript = state.transcript.lock().await;
+        let replaced: Vec<String> = transcript
+            .segments
+            .iter()
+            .map(|segment| {
+                let text = if regex {
+                    matcher.replace_all(&segment.text, replacement)
+                } else {
+                    matcher.replace_all(&segment.text, NoExpand(replacement))
+                };
+                text.into_owned()
+            })
+            .collect();
+        let count = transcript
+            .segments
+            .iter()
+            .map(|segment| matcher.find_iter(&segment.text).count())
+            .sum();
+        let changed = transcript
+            .segments
+            .iter()
+            .zip(&replaced)
+            .any(|(segment, text)| segment.text != *text);
+
+        if changed {
+            let TranscriptState {
+                segments, edits, ..
+            } = &mut *transcript;
+            edits.record(segments);
+            for (segment, text) in segments.iter_mut().zip(replaced) {
+                if segment.text != text {
+                    segment.text = text;
+                    segment.words.clear();
+                }
+            }
+            transcript.dirty = true;
+        }
+        count
+    };
+
+    if count > 0 {
+        emit_state(app, state).await;
+    }
+    Ok(count)
+}
+
+/// Writes the edited transcript back over the file saved at stop (or a new
+/// file when there is none) and clears the dirty flag.
+pub async fn save(app: &AppHandle, state: &SharedState) -> anyhow::Result<String> {
+    ensure_not_recording(state).await?;
+
+    let segments = segments(state).await;
+    if segments.is_empty() {
+        return Err(anyhow!("there is no transcript to save"));
+    }
+
+    let (mutes, notes) = {
+        let transcript = state.transcript.lock().await;
+        (transcript.mute_intervals.clone(), transcript.notes.clone())
+    };
+    let (options, naming) = {
+        let settings = state.settings.lock().await;
+        (settings.render_options(), settings.file_naming())
+    };
+    let existing = state.session.lock().await.last_saved_path.clone();
+    let file_path = match existing {
+        Some(path) => {
+            let path = PathBuf::from(path);
+            transcript_file::write_markdown(&path, &segments, &mutes, &notes, options).await?;
+            history::update_stats(app, state, &path, &segments, &notes).await;
+            path
+        }
+        None => {
+            let path =
+                transcript_file::save_markdown(&segments, &mutes, &notes, options, &naming).await?;
+            history::record_session(app, state, &path, None, &segments, &notes).await;
+            path
+        }
+    };
+    let file_path = file_path.display().to_string();
+
+    state.transcript.lock().await.dirty = false;
+    state.session.lock().await.last_saved_path = Some(file_path.clone());
+    emit_state(app, state).await;
+
+    Ok(file_path)
+}
+
+pub async fn discard_unsaved(app: &AppHandle, state: &SharedState) -> anyhow::Result<()> {
+    ensure_not_recording(state).await?;
+
+    {
+        let mut transcript = state.transcript.lock().await;
+        transcript.segments.clear();
+        transcript.mute_intervals.clear();
+        transcript.notes.clear();
+        transcript.dirty = false;
+        transcript.edits = EditHistory::default();
+    }
+
+    emit_state(app, state).await;
+    Ok(())
+}
+
+/// Restores the transcript as it was before the last edit.
+pub async fn undo(app: &AppHandle, state: &SharedState) -> anyhow::Result<()> {
+    ensure_not_recording(state).await?;
+
+    {
+        let mut transcript = state.transcript.lock().await;
+        let TranscriptState {
+            segments, edits, ..
+        } = &mut *transcript;
+        let previous = edits
+            .undo
+            .pop_back()
+            .ok_or_else(|| anyhow!("there is no edit to undo"))?;
+        edits.redo.push(std::mem::replace(segments, previous));
+        transcript.dirty = true;
+    }
+
+    emit_state(app, state).await;
+    Ok(())
+}
+
+/// Reapplies the last undone edit.
+pub async fn redo(app: &AppHandle, state: &SharedState) -> anyhow::Result<()> {
+    ensure_not_recording(state).await?;
+
+    {
+        let mut transcript = state.transcript.lock().await;
+        let TranscriptState {
+            segments, edits, ..
+        } = &mut *transcript;
+        let next = edits
+            .redo
+            .pop()
+            .ok_or_else(|| anyhow!("there is no edit to redo"))?;
+        edits.undo.push_back(std::mem::replace(segments, next));
+        transcript.dirty = true;
+    }
+
+    emit_state(app, state).await;
+    Ok(())
+}
+
+async fn ensure_not_recording(state: &SharedState) -> anyhow::Result<()> {
+    if state.status().await == AppStatus::Recording {
+        return Err(anyhow!("cannot edit the transcript while recording"));
+    }
+    Ok(())
+}
@@ -1,20 +1,498 @@
-use anyhow::{anyhow, Context};
-use chrono::Local;
-use tokio::fs;
+use std::{path::PathBuf, sync::OnceLock};
 
-pub async fn save_markdown(transcript: &str) -> anyhow::Result<std::path::PathBuf> {
+use anyhow::{anyhow, bail, Context};
+use chrono::{DateTime, Local};
+use tokio::{fs, io::AsyncWriteExt};
+
+use crate::{
+    app_state::{MuteInterval, SessionNote, TranscriptSegment},
+    audio::AudioSource,
+    links, scripts,
+};
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RenderOptions {
+    /// Prefix each line (or speaker paragraph) with `[HH:MM:SS]`.
+    pub include_timestamps: bool,
+    /// Write out spoken URLs and email addresses and turn them into links.
+    pub link_addresses: bool,
+}
+
+/// What saved transcripts are named after when no template is set. History
+/// import recognizes files named this way.
+pub const DEFAULT_NAME_TEMPLATE: &str = "Transcript-{date}-{time}";
+
+/// Tokens a name template can use: `{date}` is `2025-01-31`, `{time}` is
+/// `14-05-09`, `{title}` the session's `Title`, `{model}` and `{language}`
+/// the session's model id and language.
+const NAME_TOKENS: [&str; 5] = ["date", "time", "title", "model", "language"];
+
+const MAX_TEMPLATE_CHARS: usize = 120;
+
+/// Words of the transcript `{title}` uses, and the most characters it takes.
+const TITLE_WORDS: usize = 6;
+const TITLE_CHARS: usize = 48;
+
+/// What `{title}` stands for.
+#[derive(Debug, Clone, Default)]
+pub enum Title {
+    /// The first words of the transcript.
+    #[default]
+    FirstWords,
+    /// Written by the title LLM; also goes into the frontmatter.
+    Generated(String),
+    /// The title LLM was asked but did not answer: the time stands in, and
+    /// the frontmatter gets the date and time.
+    Unavailable,
+}
+
+/// Where the transcript ends and post-processing sections begin. Invisible
+/// when the Markdown is rendered.
+const APPENDIX_MARKER: &str = "<!-- whisperbar:postprocess -->";
+
+/// How `save_markdown` names a transcript.
+#[derive(Debug, Clone)]
+pub struct FileNaming {
+    pub template: String,
+    pub model_id: String,
+    pub language: String,
+    pub title: Title,
+}
+
+impl FileNaming {
+    /// The file name, without `.md`, for a transcript saved at `now`.
+    fn stem(&self, segments: &[TranscriptSegment], now: DateTime<Local>) -> String {
+        let mut stem = String::new();
+        let mut rest = self.template.as_str();
+        while let Some(open) = rest.find('{') {
+            stem.push_str(&rest[..open]);
+            let Some(close) = rest[open..].find('}') else {
+                break;
+            };
+            let token = &rest[open + 1..open + close];
+            stem.push_str(&match token {
+                "date" => now.format("%Y-%m-%d").to_string(),
+                "time" => now.format("%H-%M-%S").to_string(),
+                "title" => match &self.title {
+                    Title::FirstWords => title(segments),
+                    Title::Generated(title) => title.clone(),
+                    Title::Unavailable => now.format("%H-%M-%S").to_string(),
+                },
+                "model" => self.model_id.clone(),
+                "language" => self.language.clone(),
+                _ => String::new(),
+            });
+            rest = &rest[open + close + 1..];
+        }
+        stem.push_str(rest);
+
+        let stem = sanitize(&stem);
+        if stem.is_empty() {
+            sanitize(&format!("Transcript-{}", now.format("%Y-%m-%d-%H-%M-%S")))
+        } else {
+            stem
+        }
+    }
+
+    /// YAML frontmatter carrying the title, for sessions the title LLM was
+    /// asked about.
+    fn frontmatter(&self, now: DateTime<Local>) -> Option<String> {
+        let title = match &self.title {
+            Title::FirstWords => return None,
+            Title::Generated(title) => title.clone(),
+            Title::Unavailable => format!("Transcript {}", now.format("%Y-%m-%d %H:%M")),
+        };
+        // A JSON string is a valid double-quoted YAML scalar.
+        Some(format!(
+            "---\ntitle: {}\n---\n\n",
+            serde_json::to_string(&title).ok()?
+        ))
+    }
+}
+
+/// Splits a saved transcript into its frontmatter (with the blank line after
+/// it) and the body `render_markdown` wrote. Files without frontmatter are
+/// all body.
+pub fn split_frontmatter(text: &str) -> (&str, &str) {
+    let Some(rest) = text.strip_prefix("---\n") else {
+        return ("", text);
+    };
+    let Some(close) = rest.find("\n---\n") else {
+        return ("", text);
+    };
+    let mut end = 4 + close + 5;
+    if text[end..].starts_with('\n') {
+        end += 1;
+    }
+    text.split_at(end)
+}
+
+/// Splits a body from `split_frontmatter` into what `render_markdown` wrote
+/// and the sections post-processing appended after it, marker included.
+pub fn split_appendix(body: &str) -> (&str, &str) {
+    match body.find(APPENDIX_MARKER) {
+        Some(start) => (body[..start].trim_end(), &body[start..]),
+        None => (body, ""),
+    }
+}
+
+/// Appends `sections` to a saved transcript, after the marker that keeps
+/// them through later rewrites.
+pub async fn append_sections(
+    file_path: &std::path::Path,
+    sections: &[String],
+) -> anyhow::Result<()> {
+    let existing = fs::read_to_string(file_path)
+        .await
+        .with_context(|| format!("failed reading {}", file_path.display()))?;
+    let (_, body) = split_frontmatter(&existing);
+    let mut content = existing.trim_end().to_string();
+    if split_appendix(body).1.is_empty() {
+        content.push_str("\n\n");
+        content.push_str(APPENDIX_MARKER);
+    }
+    for section in sections {
+        content.push_str("\n\n");
+        content.push_str(section);
+    }
+    content.push('\n');
+    fs::write(file_path, content)
+        .await
+        .with_context(|| format!("failed writing {}", file_path.display()))
+}
+
+/// Checks a template before it is saved: only known tokens, and nothing
+/// that would leave `output_dir`.
+pub fn validate_name_template(template: &str) -> anyhow::Result<()> {
+    let template = template.trim();
+    if template.is_empty() {
+        bail!("the file name template is empty");
+    }
+    if template.chars().count() > MAX_TEMPLATE_CHARS {
+        bail!("the file name template is longer than {MAX_TEMPLATE_CHARS} characters");
+    }
+    if template.contains(['/', ':']) || template.starts_with('.') {
+        bail!("the file name template cannot contain `/` or `:` or start with `.`");
+    }
+
+    let mut rest = template;
+    while let Some(open) = rest.find(['{', '}']) {
+        if rest[open..].starts_with('}') {
+            bail!("unmatched `}}` in the file name template");
+        }
+        let close = rest[open..]
+            .find('}')
+            .ok_or_else(|| anyhow!("unmatched `{{` in the file name template"))?;
+        let token = &rest[open + 1..open + close];
+        if !NAME_TOKENS.contains(&token) {
+            bail!(
+                "unknown token `{{{token}}}`; use {}",
+                NAME_TOKENS
+                    .iter()
+                    .map(|token| format!("{{{token}}}"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+        rest = &rest[open + close + 1..];
+    }
+    Ok(())
+}
+
+/// The first few words of the transcript, or `Untitled`.
+fn title(segments: &[TranscriptSegment]) -> String {
+    let text = scripts::join_lines(
+        segments
+            .iter()
+            .map(|segment| segment.text.trim())
+            .filter(|text| !text.is_empty()),
+    );
+    let title = text
+        .split_whitespace()
+        .take(TITLE_WORDS)
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .take(TITLE_CHARS)
+        .collect::<String>();
+    let title = title
+        .trim_end_matches(|c: char| c.is_ascii_punctuation() || c.is_whitespace())
+        .to_string();
+    if title.is_empty() {
+        "Untitled".to_string()
+    } else {
+        title
+    }
+}
+
+/// Keeps a resolved name to one file in `output_dir`: no path separators
+/// or control characters, no leading dot, and short enough for the file
+/// system once a `-2` and `.md` are added.
+fn sanitize(stem: &str) -> String {
+    let cleaned = stem
+        .chars()
+        .map(|c| match c {
+            '/' | ':' => '-',
+            c if c.is_control() => ' ',
+            c => c,
+        })
+        .collect::<String>();
+    let mut cleaned = cleaned.trim().trim_start_matches('.').trim().to_string();
+    while cleaned.len() > 200 {
+        cleaned.pop();
+    }
+    cleaned
+}
+
+/// The `OutputDirectory` of a configuration profile, set once at startup.
+static MANAGED_OUTPUT_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Saves every transcript to `dir` for the rest of the run.
+pub fn set_managed_output_dir(dir: PathBuf) {
+    let _ = MANAGED_OUTPUT_DIR.set(dir);
+}
+
+/// `~/Documents/WhisperBar`, where every transcript is saved, unless a
+/// configuration profile set another folder.
+pub fn output_dir() -> anyhow::Result<PathBuf> {
+    if let Some(dir) = MANAGED_OUTPUT_DIR.get() {
+        return Ok(dir.clone());
+    }
     let documents_dir =
         dirs::document_dir().ok_or_else(|| anyhow!("unable to locate Documents directory"))?;
-    let output_dir = documents_dir.join("WhisperBar");
+    Ok(documents_dir.join("WhisperBar"))
+}
+
+pub async fn save_markdown(
+    segments: &[TranscriptSegment],
+    mutes: &[MuteInterval],
+    notes: &[SessionNote],
+    options: RenderOptions,
+    naming: &FileNaming,
+) -> anyhow::Result<std::path::PathBuf> {
+    let output_dir = output_dir()?;
+    fs::create_dir_all(&output_dir)
+        .await
+        .with_context(|| format!("failed creating {}", output_dir.display()))?;
+
+    let now = Local::now();
+    let stem = naming.stem(segments, now);
+    let (file_path, mut file) = create_unique(&output_dir, &stem, "md").await?;
+    let content = naming.frontmatter(now).unwrap_or_default()
+        + &render_markdown(segments, mutes, notes, options);
+    file.write_all(content.as_bytes())
+        .await
+        .with_context(|| format!("failed writing {}", file_path.display()))?;
+
+    Ok(file_path)
+}
+
+/// Saves a transcript under `stem`, suffixed when that is taken: one received
+/// in a bundle (`bundles::import`) under its original name, or a
+/// re-transcription (`history::retranscribe`) next to the session it redoes.
+pub async fn save_imported(stem: &str, content: &str) -> anyhow::Result<std::path::PathBuf> {
+    let output_dir = output_dir()?;
     fs::create_dir_all(&output_dir)
         .await
         .with_context(|| format!("failed creating {}", output_dir.display()))?;
 
-    let timestamp = Local::now().format("%Y-%m-%d-%H-%M");
-    let file_path = output_dir.join(format!("Transcript-{timestamp}.md"));
-    fs::write(&file_path, transcript)
+    let stem = match sanitize(stem) {
+        stem if stem.is_empty() => "Transcript".to_string(),
+        stem => stem,
+    };
+    let (file_path, mut file) = create_unique(&output_dir, &stem, "md").await?;
+    file.write_all(content.as_bytes())
         .await
         .with_context(|| format!("failed writing {}", file_path.display()))?;
 
     Ok(file_path)
 }
+
+/// Creates `<stem>.<extension>`, or `<stem>-2.<extension>`, ... when a file
+/// with that name already exists, so a second save never replaces the first.
+pub async fn create_unique(
+    dir: &std::path::Path,
+    stem: &str,
+    extension: &str,
+) -> anyhow::Result<(std::path::PathBuf, fs::File)> {
+    for attempt in 1.. {
+        let file_path = match attempt {
+            1 => dir.join(format!("{stem}.{extension}")),
+            _ => dir.join(format!("{stem}-{attempt}.{extension}")),
+        };
+        match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&file_path)
+            .await
+        {
+            Ok(file) => return Ok((file_path, file)),
+            Err(error) if error.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(error) => {
+                return Err(error)
+                    .with_context(|| format!("failed creating {}", file_path.display()))
+            }
+        }
+    }
+    unreachable!("ran out of file name suffixes")
+}
+
+pub async fn write_markdown(
+    file_path: &std::path::Path,
+    segments: &[TranscriptSegment],
+    mutes: &[MuteInterval],
+    notes: &[SessionNote],
+    options: RenderOptions,
+) -> anyhow::Result<()> {
+    // A rewrite keeps the title the first save put at the top and the
+    // sections post-processing appended.
+    let existing = fs::read_to_string(file_path).await.unwrap_or_default();
+    let (frontmatter, body) = split_frontmatter(&existing);
+    let (_, appendix) = split_appendix(body);
+    let mut content = frontmatter.to_string() + &render_markdown(segments, mutes, notes, options);
+    if !appendix.is_empty() {
+        content.push_str("\n\n");
+        content.push_str(appendix);
+    }
+    fs::write(file_path, content)
+        .await
+        .with_context(|| format!("failed writing {}", file_path.display()))
+}
+
+/// One line per segment, prefixed with the speaker label when known.
+pub fn render_plain(segments: &[TranscriptSegment]) -> String {
+    segments
+        .iter()
+        .map(|segment| match segment_label(segment) {
+            Some(label) => format!("{label}: {}", scripts::isolate(&segment.text)),
+            None => segment.text.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Consecutive segments from the same speaker (or source) are merged into one
+/// paragraph with a bold label prefix. Unlabeled segments stay one per line.
+/// Right-to-left text is isolated from the timestamp and label before it.
+/// A segment marked `paragraph` always starts a new block, set off by a blank
+/// line in unlabeled transcripts. Each mute interval becomes an italic note,
+/// and each session note a quoted line, before the first block that starts
+/// after it.
+pub fn render_markdown(
+    segments: &[TranscriptSegment],
+    mutes: &[MuteInterval],
+    notes: &[SessionNote],
+    options: RenderOptions,
+) -> String {
+    let mut blocks: Vec<Block> = Vec::new();
+
+    for segment in segments {
+        let text = segment.text.trim();
+        if text.is_empty() {
+            continue;
+        }
+
+        let label = segment_label(segment);
+        match blocks.last_mut() {
+            Some(block) if !segment.paragraph && block.label.is_some() && block.label == label => {
+                block.lines.push(text)
+            }
+            _ => blocks.push(Block {
+                label,
+                paragraph: segment.paragraph,
+                start: segment.start,
+                lines: vec![text],
+            }),
+        }
+    }
+
+    let labeled = blocks.iter().any(|block| block.label.is_some());
+    let mut annotations = mutes
+        .iter()
+        .map(|mute| (mute.start, mute_note(mute)))
+        .chain(notes.iter().map(|note| (note.at, session_note(note))))
+        .collect::<Vec<_>>();
+    annotations.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let mut annotations = annotations.into_iter().peekable();
+    let mut rendered = Vec::new();
+    for block in &blocks {
+        if let Some(start) = block.start {
+            while let Some((_, annotation)) = annotations.next_if(|(at, _)| *at <= start) {
+                rendered.push(annotation);
+            }
+        }
+
+        if block.paragraph && !labeled && !rendered.is_empty() {
+            rendered.push(String::new());
+        }
+
+        let timestamp = match (options.include_timestamps, block.start) {
+            (true, Some(start)) => format!("[{}] ", format_timestamp(start)),
+            _ => String::new(),
+        };
+        let body = match &block.label {
+            Some(_) => scripts::join_lines(block.lines.iter().copied()),
+            None => block.lines.join("\n"),
+        };
+        let body = if options.link_addresses {
+            links::markdown(&body)
+        } else {
+            body
+        };
+        let body = scripts::isolate(&body);
+        rendered.push(match &block.label {
+            Some(label) => format!("{timestamp}**{label}:** {body}"),
+            None => format!("{timestamp}{body}"),
+        });
+    }
+    rendered.extend(annotations.map(|(_, annotation)| annotation));
+
+    rendered.join(if labeled { "\n\n" } else { "\n" })
+}
+
+struct Block<'a> {
+    label: Option<String>,
+    paragraph: bool,
+    start: Option<f64>,
+    lines: Vec<&'a str>,
+}
+
+fn mute_note(mute: &MuteInterval) -> String {
+    match mute.end {
+        Some(end) => format!(
+            "_[Mic muted {}–{}]_",
+            format_timestamp(mute.start),
+            format_timestamp(end)
+        ),
+        None => format!("_[Mic muted from {}]_", format_timestamp(mute.start)),
+    }
+}
+
+fn session_note(note: &SessionNote) -> String {
+    let at = format_timestamp(note.at);
+    match &note.image {
+        Some(image) => format!("> **Screen [{at}]:** ![Screen at {at}]({image})"),
+        None => format!("> **Note [{at}]:** {}", scripts::isolate(&note.text)),
+    }
+}
+
+/// `Me` / `Others` for segments transcribed per source, otherwise the
+/// diarized speaker.
+fn segment_label(segment: &TranscriptSegment) -> Option<String> {
+    match (segment.source, segment.speaker) {
+        (Some(AudioSource::Mic), _) => Some("Me".to_string()),
+        (Some(AudioSource::Desktop), _) => Some("Others".to_string()),
+        (None, Some(speaker)) => Some(format!("Speaker {speaker}")),
+        (None, None) => None,
+    }
+}
+
+pub fn format_timestamp(seconds: f64) -> String {
+    let total = seconds.max(0.0).floor() as u64;
+    format!(
+        "{:02}:{:02}:{:02}",
+        total / 3600,
+        (total % 3600) / 60,
+        total % 60
+    )
+}
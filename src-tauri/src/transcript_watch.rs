@@ -0,0 +1,49 @@
+use std::{collections::BTreeSet, path::PathBuf, time::Duration};
+
+use tauri::AppHandle;
+use tokio::sync::mpsc;
+
+use crate::{app_state::SharedState, fs_events, history, transcript_file};
+
+/// How long FSEvents batches changes before reporting them.
+const LATENCY_SECONDS: f64 = 0.5;
+
+/// Editors save in several steps (temporary file, rename, attributes).
+const SETTLE_DELAY: Duration = Duration::from_millis(500);
+
+/// Watches the output directory for the lifetime of the app and re-syncs
+/// history and search when a saved transcript is edited, replaced or
+/// deleted outside WhisperBar. The app's own writes come through here too;
+/// re-reading them is harmless.
+pub fn start(app: &AppHandle, state: &SharedState) -> anyhow::Result<()> {
+    let output_dir = transcript_file::output_dir()?;
+    // FSEvents can only watch a directory that exists.
+    std::fs::create_dir_all(&output_dir)?;
+
+    let (changes, mut receiver) = mpsc::unbounded_channel::<Vec<PathBuf>>();
+    fs_events::watch(&output_dir, LATENCY_SECONDS, move |paths| {
+        let _ = changes.send(paths);
+    })?;
+
+    let app = app.clone();
+    let state = state.clone();
+    tauri::async_runtime::spawn(async move {
+        while let Some(paths) = receiver.recv().await {
+            tokio::time::sleep(SETTLE_DELAY).await;
+            let mut changed = paths.into_iter().collect::<BTreeSet<_>>();
+            while let Ok(paths) = receiver.try_recv() {
+                changed.extend(paths);
+            }
+
+            let transcripts = changed
+                .into_iter()
+                .filter(|path| path.extension().is_some_and(|ext| ext == "md"))
+                .collect::<Vec<_>>();
+            if !transcripts.is_empty() {
+                history::sync_from_disk(&app, &state, &transcripts).await;
+            }
+        }
+    });
+
+    Ok(())
+}
@@ -1,18 +1,126 @@
+use std::sync::{Mutex, MutexGuard};
+
 use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
 use tauri::{
     image::Image,
     menu::{Menu, MenuItem},
-    AppHandle, Manager,
+    AppHandle, Manager, Wry,
+};
+
+use crate::{
+    app_state::{AppSnapshot, AppStatus, SharedState},
+    dictation::DictationOutput,
+    errors::{self, ErrorKind},
+    ui, worker,
 };
 
-use crate::{app_state::SharedState, ui, worker};
+const RECORD_BUTTON_ID: &str = "whisperbar-record";
+
+/// Side of the record button glyph in pixels: 18 points at 2x.
+const GLYPH_SIZE: u32 = 36;
+
+/// What the record button shows, one glyph per thing a click does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RecordGlyph {
+    /// Filled circle: starts recording.
+    Record,
+    /// Filled square: stops and saves.
+    Stop,
+    /// Ring: cancels the quick-record countdown.
+    Countdown,
+    /// Faded circle: nothing can start right now (installing, read-only, ...).
+    Unavailable,
+}
+
+impl RecordGlyph {
+    fn from_snapshot(snapshot: &AppSnapshot) -> Self {
+        if snapshot.recording_countdown.is_some() {
+            Self::Countdown
+        } else if snapshot.can_stop {
+            Self::Stop
+        } else if snapshot.can_start {
+            Self::Record
+        } else {
+            Self::Unavailable
+        }
+    }
+
+    fn tooltip(self) -> &'static str {
+        match self {
+            Self::Record => "Start Recording",
+            Self::Stop => "Stop Recording",
+            Self::Countdown => "Cancel Countdown",
+            Self::Unavailable => "WhisperBar cannot record right now",
+        }
+    }
+}
+
+/// The glyph the record button shows, so snapshots that change nothing do
+/// not redraw it. `None` while the button is off.
+#[derive(Default)]
+struct RecordButton {
+    glyph: Mutex<Option<RecordGlyph>>,
+}
+
+impl RecordButton {
+    fn glyph(&self) -> MutexGuard<'_, Option<RecordGlyph>> {
+        self.glyph
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Menu items enabled only while their command is available.
+struct RecordingItems {
+    show_live: MenuItem<Wry>,
+    quick_record: MenuItem<Wry>,
+    cancel_countdown: MenuItem<Wry>,
+    stop_recording: MenuItem<Wry>,
+    cancel_recording: MenuItem<Wry>,
+}
 
 pub fn build_tray(app: &AppHandle) -> tauri::Result<()> {
     let show = MenuItem::with_id(app, "show", "Show WhisperBar", true, None::<&str>)?;
+    let show_live =
+        MenuItem::with_id(app, "show_live", "Show Live Transcript", true, None::<&str>)?;
+    let quick_record = MenuItem::with_id(
+        app,
+        "quick_record",
+        "Record in 5 Seconds",
+        true,
+        None::<&str>,
+    )?;
+    let cancel_countdown = MenuItem::with_id(
+        app,
+        "cancel_countdown",
+        "Cancel Countdown",
+        true,
+        None::<&str>,
+    )?;
+    let history = MenuItem::with_id(app, "history", "Transcript History", true, None::<&str>)?;
     let stop_recording =
         MenuItem::with_id(app, "stop_recording", "Stop Recording", true, None::<&str>)?;
+    let cancel_recording = MenuItem::with_id(
+        app,
+        "cancel_recording",
+        "Cancel Recording",
+        true,
+        None::<&str>,
+    )?;
     let quit = MenuItem::with_id(app, "quit", "Quit WhisperBar", true, None::<&str>)?;
-    let menu = Menu::with_items(app, &[&show, &stop_recording, &quit])?;
+    let menu = Menu::with_items(
+        app,
+        &[
+            &show,
+            &show_live,
+            &history,
+            &quick_record,
+            &cancel_countdown,
+            &stop_recording,
+            &cancel_recording,
+            &quit,
+        ],
+    )?;
 
     let builder = TrayIconBuilder::with_id("whisperbar")
         .icon(tray_template_icon())
@@ -23,11 +131,59 @@ pub fn build_tray(app: &AppHandle) -> tauri::Result<()> {
             "show" => {
                 ui::show_tray_window(app);
             }
+            "show_live" => {
+                let app_handle = app.clone();
+                let state = app_handle.state::<SharedState>().inner().clone();
+                tauri::async_runtime::spawn(async move {
+                    if state.availability().await.can_stop {
+                        let placement = state.settings.lock().await.floating_placement;
+                        let _ = ui::ensure_floating_window(&app_handle, placement);
+                    }
+                });
+            }
+            "quick_record" => {
+                let app_handle = app.clone();
+                let state = app_handle.state::<SharedState>().inner().clone();
+                tauri::async_runtime::spawn(async move {
+                    if !state.availability().await.can_start {
+                        return;
+                    }
+                    let _ = worker::start_recording_after(
+                        &app_handle,
+                        &state,
+                        worker::QUICK_RECORD_DELAY_SECS,
+                    )
+                    .await;
+                });
+            }
+            "cancel_countdown" => {
+                let app_handle = app.clone();
+                let state = app_handle.state::<SharedState>().inner().clone();
+                tauri::async_runtime::spawn(async move {
+                    let _ = worker::cancel_countdown(&app_handle, &state).await;
+                });
+            }
+            "history" => {
+                let _ = ui::ensure_history_window(app);
+            }
             "stop_recording" => {
                 let app_handle = app.clone();
                 let state = app_handle.state::<SharedState>().inner().clone();
                 tauri::async_runtime::spawn(async move {
-                    let _ = worker::stop_recording(&app_handle, &state).await;
+                    if !state.availability().await.can_stop {
+                        return;
+                    }
+                    stop_recording(&app_handle, &state).await;
+                });
+            }
+            "cancel_recording" => {
+                let app_handle = app.clone();
+                let state = app_handle.state::<SharedState>().inner().clone();
+                tauri::async_runtime::spawn(async move {
+                    if !state.availability().await.can_stop {
+                        return;
+                    }
+                    let _ = worker::cancel_recording(&app_handle, &state).await;
                 });
             }
             "quit" => {
@@ -43,15 +199,188 @@ pub fn build_tray(app: &AppHandle) -> tauri::Result<()> {
             } = event
             {
                 let app = tray.app_handle();
-                let _ = ui::toggle_tray_window(&app);
+                let _ = ui::toggle_tray_window(app);
             }
         });
 
     builder.build(app)?;
 
+    app.manage(RecordingItems {
+        show_live,
+        quick_record,
+        cancel_countdown,
+        stop_recording,
+        cancel_recording,
+    });
+    app.manage(RecordButton::default());
+
+    Ok(())
+}
+
+/// Adds or removes a second menu bar item that has no menu: a click starts
+/// recording, stops it, or cancels a countdown, as its glyph shows.
+pub fn set_record_button(app: &AppHandle, enabled: bool) -> tauri::Result<()> {
+    let Some(button) = app.try_state::<RecordButton>() else {
+        return Ok(());
+    };
+    if !enabled {
+        let _ = app.remove_tray_by_id(RECORD_BUTTON_ID);
+        *button.glyph() = None;
+        return Ok(());
+    }
+    if app.tray_by_id(RECORD_BUTTON_ID).is_some() {
+        return Ok(());
+    }
+
+    TrayIconBuilder::with_id(RECORD_BUTTON_ID)
+        .icon(glyph_icon(RecordGlyph::Unavailable))
+        .icon_as_template(true)
+        .tooltip(RecordGlyph::Unavailable.tooltip())
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event
+            {
+                let app_handle = tray.app_handle().clone();
+                tauri::async_runtime::spawn(async move {
+                    toggle_recording(&app_handle).await;
+                });
+            }
+        })
+        .build(app)?;
+    // The next snapshot draws the real state.
+    *button.glyph() = Some(RecordGlyph::Unavailable);
     Ok(())
 }
 
+async fn toggle_recording(app: &AppHandle) {
+    let state = app.state::<SharedState>().inner().clone();
+    if state.session.lock().await.countdown.is_some() {
+        let _ = worker::cancel_countdown(app, &state).await;
+        return;
+    }
+    let availability = state.availability().await;
+    if availability.can_stop {
+        stop_recording(app, &state).await;
+        return;
+    }
+    if !availability.can_start {
+        return;
+    }
+    let Err(error) = worker::start_recording(app, &state, DictationOutput::Off, None).await else {
+        return;
+    };
+    match worker::start_error_kind(&error) {
+        Some(kind) => report_failure(app, &state, kind, error.to_string()).await,
+        // The panel asks to save or discard the previous transcript first.
+        None => ui::show_tray_window(app),
+    }
+}
+
+/// Stops and saves, reporting a failure the way the panel's Stop does.
+async fn stop_recording(app: &AppHandle, state: &SharedState) {
+    let Err(error) = worker::stop_recording(app, state).await else {
+        return;
+    };
+    if let Some(kind) = worker::stop_error_kind(&error) {
+        report_failure(app, state, kind, error.to_string()).await;
+    }
+}
+
+async fn report_failure(app: &AppHandle, state: &SharedState, kind: ErrorKind, message: String) {
+    errors::report(app, state, kind, message, |session| {
+        session.status = AppStatus::Error;
+        session.status_message = "Error".to_string();
+    })
+    .await;
+}
+
+/// Greys out the menu items whose command `snapshot` says cannot run. The
+/// handlers check again, since a click can race the next snapshot.
+pub fn set_availability(app: &AppHandle, snapshot: &AppSnapshot) {
+    let Some(items) = app.try_state::<RecordingItems>() else {
+        return;
+    };
+    let _ = items.show_live.set_enabled(snapshot.can_stop);
+    let _ = items
+        .quick_record
+        .set_enabled(snapshot.can_start && snapshot.recording_countdown.is_none());
+    let _ = items
+        .cancel_countdown
+        .set_enabled(snapshot.recording_countdown.is_some());
+    let _ = items.stop_recording.set_enabled(snapshot.can_stop);
+    let _ = items.cancel_recording.set_enabled(snapshot.can_stop);
+    set_record_glyph(app, RecordGlyph::from_snapshot(snapshot));
+}
+
+fn set_record_glyph(app: &AppHandle, next: RecordGlyph) {
+    let Some(button) = app.try_state::<RecordButton>() else {
+        return;
+    };
+    let mut glyph = button.glyph();
+    if glyph.is_none() || *glyph == Some(next) {
+        return;
+    }
+    let Some(tray) = app.tray_by_id(RECORD_BUTTON_ID) else {
+        return;
+    };
+    let _ = tray.set_icon(Some(glyph_icon(next)));
+    let _ = tray.set_icon_as_template(true);
+    let _ = tray.set_tooltip(Some(next.tooltip()));
+    *glyph = Some(next);
+}
+
+/// Shows `elapsed` next to the menu bar icon, or clears it with `None`.
+pub fn set_recording_timer(app: &AppHandle, elapsed: Option<u64>) {
+    let Some(tray) = app.tray_by_id("whisperbar") else {
+        return;
+    };
+    let _ = tray.set_title(elapsed.map(format_elapsed));
+}
+
+fn format_elapsed(seconds: u64) -> String {
+    let (hours, minutes, seconds) = (seconds / 3600, (seconds % 3600) / 60, seconds % 60);
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{minutes:02}:{seconds:02}")
+    }
+}
+
+/// Draws `glyph` as a black template image, 4x4 samples per pixel for
+/// smooth edges. macOS tints it for the menu bar's appearance.
+fn glyph_icon(glyph: RecordGlyph) -> Image<'static> {
+    const SAMPLES: u32 = 4;
+    let center = GLYPH_SIZE as f32 / 2.0;
+    let opacity = if glyph == RecordGlyph::Unavailable {
+        0.4
+    } else {
+        1.0
+    };
+    let mut rgba = Vec::with_capacity((GLYPH_SIZE * GLYPH_SIZE * 4) as usize);
+    for y in 0..GLYPH_SIZE {
+        for x in 0..GLYPH_SIZE {
+            let mut covered = 0;
+            for sample in 0..SAMPLES * SAMPLES {
+                let dx = x as f32 + ((sample % SAMPLES) as f32 + 0.5) / SAMPLES as f32 - center;
+                let dy = y as f32 + ((sample / SAMPLES) as f32 + 0.5) / SAMPLES as f32 - center;
+                let distance = (dx * dx + dy * dy).sqrt();
+                let inside = match glyph {
+                    RecordGlyph::Record | RecordGlyph::Unavailable => distance <= 12.0,
+                    RecordGlyph::Stop => dx.abs() <= 9.5 && dy.abs() <= 9.5,
+                    RecordGlyph::Countdown => (9.0..=12.0).contains(&distance),
+                };
+                covered += u32::from(inside);
+            }
+            let alpha = covered as f32 / (SAMPLES * SAMPLES) as f32 * opacity;
+            rgba.extend_from_slice(&[0, 0, 0, (alpha * 255.0).round() as u8]);
+        }
+    }
+    Image::new_owned(rgba, GLYPH_SIZE, GLYPH_SIZE)
+}
+
 fn tray_template_icon() -> Image<'static> {
     Image::from_bytes(include_bytes!("../icons/tray-template.png"))
         .expect("invalid tray-template icon bytes")
@@ -1,4 +1,37 @@
-use tauri::{AppHandle, Manager, PhysicalSize, WebviewUrl, WebviewWindowBuilder};
+use serde::{Deserialize, Serialize};
+use tauri::{
+    AppHandle, Manager, Monitor, PhysicalPosition, PhysicalSize, WebviewUrl, WebviewWindow,
+    WebviewWindowBuilder, WindowEvent,
+};
+
+/// Gap between a pinned live window and the edges of the screen, in points.
+const CORNER_MARGIN: f64 = 16.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ScreenCorner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+/// Where the live window snaps to when it opens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FloatingPlacement {
+    pub corner: ScreenCorner,
+    /// Index into `list_displays`; `None` follows the primary display.
+    pub display: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DisplayInfo {
+    pub index: usize,
+    pub name: String,
+    pub primary: bool,
+}
 
 pub fn ensure_tray_window(app: &AppHandle) -> tauri::Result<()> {
     if let Some(window) = app.get_webview_window("tray") {
@@ -51,7 +84,12 @@ pub fn hide_tray_window(app: &AppHandle) {
     }
 }
 
-pub fn ensure_floating_window(app: &AppHandle) -> tauri::Result<()> {
+/// Creates (or shows) the live window. A new window snaps to `placement`;
+/// an existing one keeps wherever it was.
+pub fn ensure_floating_window(
+    app: &AppHandle,
+    placement: Option<FloatingPlacement>,
+) -> tauri::Result<()> {
     if let Some(window) = app.get_webview_window("floating") {
         let _ = window.set_decorations(true);
         let _ = window.set_always_on_top(true);
@@ -61,7 +99,7 @@ pub fn ensure_floating_window(app: &AppHandle) -> tauri::Result<()> {
         return Ok(());
     }
 
-    WebviewWindowBuilder::new(app, "floating", WebviewUrl::App("index.html".into()))
+    let window = WebviewWindowBuilder::new(app, "floating", WebviewUrl::App("index.html".into()))
         .title("WhisperBar Live")
         .inner_size(1080.0, 760.0)
         .min_inner_size(860.0, 560.0)
@@ -74,15 +112,140 @@ pub fn ensure_floating_window(app: &AppHandle) -> tauri::Result<()> {
         .skip_taskbar(false)
         .build()?;
 
-    if let Some(window) = app.get_webview_window("floating") {
-        let _ = window.set_size(PhysicalSize::new(1080, 760));
+    // The live window only exists while recording. Closing it from the title
+    // bar hides it so the session view survives and `reopen_live_window` can
+    // bring it back; `close_floating_window` destroys it when the session ends.
+    let handle = window.clone();
+    window.on_window_event(move |event| {
+        if let WindowEvent::CloseRequested { api, .. } = event {
+            api.prevent_close();
+            let _ = handle.hide();
+        }
+    });
+
+    let _ = window.set_size(PhysicalSize::new(1080, 760));
+
+    if let Some(placement) = placement {
+        let _ = place_window(&window, placement);
     }
 
     Ok(())
 }
 
+/// Moves the live window, if open, to `placement`.
+pub fn place_floating_window(app: &AppHandle, placement: FloatingPlacement) -> tauri::Result<()> {
+    match app.get_webview_window("floating") {
+        Some(window) => place_window(&window, placement),
+        None => Ok(()),
+    }
+}
+
+pub fn list_displays(app: &AppHandle) -> tauri::Result<Vec<DisplayInfo>> {
+    let primary = app.primary_monitor()?;
+    let displays = app
+        .available_monitors()?
+        .into_iter()
+        .enumerate()
+        .map(|(index, monitor)| DisplayInfo {
+            index,
+            name: monitor
+                .name()
+                .cloned()
+                .unwrap_or_else(|| format!("Display {}", index + 1)),
+            primary: primary
+                .as_ref()
+                .is_some_and(|primary| same_monitor(primary, &monitor)),
+        })
+        .collect();
+    Ok(displays)
+}
+
+fn place_window(window: &WebviewWindow, placement: FloatingPlacement) -> tauri::Result<()> {
+    let monitors = window.available_monitors()?;
+    // A display that was unplugged falls back to the primary one.
+    let monitor = match placement.display.and_then(|index| monitors.get(index)) {
+        Some(monitor) => Some(monitor.clone()),
+        None => window.primary_monitor()?,
+    };
+    let Some(monitor) = monitor else {
+        return Ok(());
+    };
+
+    // The work area excludes the menu bar and the Dock.
+    let area = monitor.work_area();
+    let size = window.outer_size()?;
+    let margin = (CORNER_MARGIN * monitor.scale_factor()).round() as i32;
+    let width = size.width.min(area.size.width) as i32;
+    let height = size.height.min(area.size.height) as i32;
+
+    let left = area.position.x + margin;
+    let right = area.position.x + area.size.width as i32 - width - margin;
+    let top = area.position.y + margin;
+    let bottom = area.position.y + area.size.height as i32 - height - margin;
+
+    let (x, y) = match placement.corner {
+        ScreenCorner::TopLeft => (left, top),
+        ScreenCorner::TopRight => (right, top),
+        ScreenCorner::BottomLeft => (left, bottom),
+        ScreenCorner::BottomRight => (right, bottom),
+    };
+
+    window.set_position(PhysicalPosition::new(x.max(left), y.max(top)))
+}
+
+fn same_monitor(a: &Monitor, b: &Monitor) -> bool {
+    a.position() == b.position() && a.size() == b.size()
+}
+
 pub fn close_floating_window(app: &AppHandle) {
     if let Some(window) = app.get_webview_window("floating") {
-        let _ = window.close();
+        let _ = window.destroy();
     }
 }
+
+pub fn ensure_history_window(app: &AppHandle) -> tauri::Result<()> {
+    if let Some(window) = app.get_webview_window("history") {
+        let _ = window.show();
+        let _ = window.set_focus();
+        return Ok(());
+    }
+
+    WebviewWindowBuilder::new(app, "history", WebviewUrl::App("index.html".into()))
+        .title("WhisperBar History")
+        .inner_size(720.0, 620.0)
+        .min_inner_size(560.0, 420.0)
+        .resizable(true)
+        .decorations(true)
+        .transparent(false)
+        .shadow(true)
+        .always_on_top(false)
+        .visible(true)
+        .skip_taskbar(false)
+        .build()?;
+
+    Ok(())
+}
+
+/// Opens a transcript viewer window, or focuses it if `label` is open.
+pub fn ensure_viewer_window(app: &AppHandle, label: &str, title: &str) -> tauri::Result<()> {
+    if let Some(window) = app.get_webview_window(label) {
+        let _ = window.show();
+        let _ = window.set_focus();
+        return Ok(());
+    }
+
+    WebviewWindowBuilder::new(app, label, WebviewUrl::App("index.html".into()))
+        .title(title)
+        .inner_size(640.0, 720.0)
+        .min_inner_size(420.0, 360.0)
+        .resizable(true)
+        .decorations(true)
+        .transparent(false)
+        .shadow(true)
+        .always_on_top(false)
+        .visible(true)
+        .skip_taskbar(false)
+        .build()?;
+
+    Ok(())
+}
@@ -0,0 +1,297 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::Mutex,
+    time::Duration,
+};
+
+use anyhow::{anyhow, bail, Context};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use tauri::AppHandle;
+use tokio::{io::AsyncWriteExt, process::Command};
+
+use crate::{app_state::SharedState, models};
+
+/// How often the reporter checks whether a report is due. A failed report
+/// is retried at the next check.
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+const DEFAULT_INTERVAL_HOURS: u32 = 24 * 7;
+const MAX_INTERVAL_HOURS: u32 = 24 * 90;
+
+const DEFAULT_EPSILON: f64 = 1.0;
+const MIN_EPSILON: f64 = 0.1;
+const MAX_EPSILON: f64 = 10.0;
+
+/// Minutes one session can add to the total. Longer sessions count as this
+/// long, which bounds how much noise the minutes need.
+const MAX_SESSION_MINUTES: f64 = 120.0;
+
+/// Reported for every model that is not in the catalog, so a custom
+/// model's folder name never leaves the Mac.
+const CUSTOM_MODEL: &str = "custom";
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Guards `usage.json` between `record` and a report in flight.
+static COUNTERS_FILE: Mutex<()> = Mutex::new(());
+
+/// Where and how often aggregate counters are sent, from the
+/// `UsageReport*` keys of the managed preferences.
+#[derive(Debug, Clone)]
+pub struct UsageReporting {
+    pub endpoint: String,
+    pub interval_hours: u32,
+    /// Privacy budget of one report, split evenly between its three
+    /// counters.
+    pub epsilon: f64,
+}
+
+impl UsageReporting {
+    /// `UsageReportEndpoint` (an http(s) URL) turns reporting on;
+    /// `UsageReportIntervalHours` and `UsageReportEpsilon` are optional.
+    pub fn from_managed(values: &Map<String, Value>) -> Option<Self> {
+        let endpoint = values
+            .get("UsageReportEndpoint")?
+            .as_str()?
+            .trim()
+            .to_string();
+        if !endpoint.starts_with("https://") && !endpoint.starts_with("http://") {
+            return None;
+        }
+        let interval_hours = values
+            .get("UsageReportIntervalHours")
+            .and_then(Value::as_u64)
+            .map_or(DEFAULT_INTERVAL_HOURS, |hours| {
+                hours.clamp(1, u64::from(MAX_INTERVAL_HOURS)) as u32
+            });
+        let epsilon = values
+            .get("UsageReportEpsilon")
+            .and_then(Value::as_f64)
+            .map_or(DEFAULT_EPSILON, |epsilon| {
+                epsilon.clamp(MIN_EPSILON, MAX_EPSILON)
+            });
+        Some(Self {
+            endpoint,
+            interval_hours,
+            epsilon,
+        })
+    }
+}
+
+/// What has been counted since the last report, kept in `usage.json`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Counters {
+    /// RFC 3339 local time counting started.
+    period_start: Option<String>,
+    sessions: u64,
+    minutes: f64,
+    models: BTreeMap<String, u64>,
+}
+
+/// Counts a saved session, when usage reporting is managed on. Only the
+/// fact of the session, its length and the model are kept.
+pub fn record(state: &SharedState, model_id: &str, duration_seconds: Option<f64>) {
+    if state.managed.usage_reporting.is_none() || state.paths.read_only {
+        return;
+    }
+    let model = models::find_model(model_id).map_or(CUSTOM_MODEL, |model| model.id);
+    let _guard = COUNTERS_FILE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let path = counters_path(&state.paths.app_data_dir);
+    let mut counters = read(&path);
+    counters
+        .period_start
+        .get_or_insert_with(|| Local::now().to_rfc3339());
+    counters.sessions += 1;
+    counters.minutes += (duration_seconds.unwrap_or(0.0) / 60.0).clamp(0.0, MAX_SESSION_MINUTES);
+    *counters.models.entry(model.to_string()).or_default() += 1;
+    let _ = write(&path, &counters);
+}
+
+/// Sends the counters to the managed endpoint every `interval_hours`, then
+/// starts counting again. Does nothing unless a profile turned reporting on.
+pub fn spawn(app: &AppHandle, state: &SharedState) {
+    let Some(reporting) = state.managed.usage_reporting.clone() else {
+        return;
+    };
+    if state.paths.read_only {
+        return;
+    }
+    let version = app.package_info().version.to_string();
+    let state = state.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(CHECK_INTERVAL).await;
+            let _ = report_if_due(&state, &reporting, &version).await;
+        }
+    });
+}
+
+async fn report_if_due(
+    state: &SharedState,
+    reporting: &UsageReporting,
+    version: &str,
+) -> anyhow::Result<()> {
+    let path = counters_path(&state.paths.app_data_dir);
+    let counters = {
+        let _guard = COUNTERS_FILE
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut counters = read(&path);
+        if counters.period_start.is_none() {
+            counters.period_start = Some(Local::now().to_rfc3339());
+            write(&path, &counters)?;
+        }
+        counters
+    };
+    let started = counters
+        .period_start
+        .as_deref()
+        .and_then(|start| DateTime::parse_from_rfc3339(start).ok())
+        .map(|start| start.with_timezone(&Local))
+        .ok_or_else(|| anyhow!("usage.json has no valid period start"))?;
+    let now = Local::now();
+    if now - started < chrono::Duration::hours(i64::from(reporting.interval_hours)) {
+        return Ok(());
+    }
+
+    post(
+        reporting,
+        &payload(&counters, reporting.epsilon, started, now, version)?,
+    )
+    .await?;
+
+    // Sessions saved while the report was on its way stay for the next one.
+    let _guard = COUNTERS_FILE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let mut remaining = read(&path);
+    remaining.period_start = Some(now.to_rfc3339());
+    remaining.sessions = remaining.sessions.saturating_sub(counters.sessions);
+    remaining.minutes = (remaining.minutes - counters.minutes).max(0.0);
+    for (model, sent) in &counters.models {
+        if let Some(count) = remaining.models.get_mut(model) {
+            *count = count.saturating_sub(*sent);
+        }
+    }
+    remaining.models.retain(|_, count| *count > 0);
+    write(&path, &remaining)
+}
+
+/// The report body: dates without times, every counter with Laplace noise
+/// and rounded, no identifier of the Mac or its user. Each counter gets a
+/// third of `epsilon`; one session changes `sessions` and one model's count
+/// by 1 and `minutes` by at most `MAX_SESSION_MINUTES`. Every catalog model
+/// and `custom` is listed, used or not, so the keys do not give away which
+/// models ran.
+fn payload(
+    counters: &Counters,
+    epsilon: f64,
+    started: DateTime<Local>,
+    now: DateTime<Local>,
+    version: &str,
+) -> anyhow::Result<Value> {
+    let share = epsilon / 3.0;
+    let count = |value: f64, sensitivity: f64| -> anyhow::Result<u64> {
+        Ok((value + laplace(sensitivity / share)?).round().max(0.0) as u64)
+    };
+    let models = models::MODEL_SPECS
+        .iter()
+        .map(|model| model.id)
+        .chain([CUSTOM_MODEL])
+        .map(|model| {
+            let sessions = counters.models.get(model).copied().unwrap_or(0);
+            Ok((model.to_string(), count(sessions as f64, 1.0)?))
+        })
+        .collect::<anyhow::Result<BTreeMap<_, _>>>()?;
+    Ok(json!({
+        "schema": 1,
+        "appVersion": version,
+        "periodStart": started.format("%Y-%m-%d").to_string(),
+        "periodEnd": now.format("%Y-%m-%d").to_string(),
+        "epsilon": epsilon,
+        "sessions": count(counters.sessions as f64, 1.0)?,
+        "minutes": count(counters.minutes, MAX_SESSION_MINUTES)?,
+        "models": models,
+    }))
+}
+
+/// A sample from the Laplace distribution centred on 0 with scale `scale`.
+fn laplace(scale: f64) -> anyhow::Result<f64> {
+    // Drawn from the OS CSPRNG, so the noise cannot be predicted from other
+    // samples and subtracted out.
+    let mut bytes = [0u8; 8];
+    getrandom::getrandom(&mut bytes)
+        .map_err(|error| anyhow!("no random source for the usage report noise: {error}"))?;
+    let bits = u64::from_le_bytes(bytes);
+    let uniform = (bits >> 11) as f64 / (1u64 << 53) as f64 - 0.5;
+    Ok(-scale * uniform.signum() * (1.0 - 2.0 * uniform.abs()).max(f64::MIN_POSITIVE).ln())
+}
+
+/// POSTs the report with curl, the body through its config on stdin.
+async fn post(reporting: &UsageReporting, body: &Value) -> anyhow::Result<()> {
+    let quote = |value: &str| format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""));
+    let config = format!(
+        "url = {}\nheader = {}\ndata = {}\n",
+        quote(&reporting.endpoint),
+        quote("Content-Type: application/json"),
+        quote(&body.to_string())
+    );
+
+    let mut child = Command::new("curl")
+        .args([
+            "--silent",
+            "--show-error",
+            "--fail",
+            "--output",
+            "/dev/null",
+        ])
+        .arg("--max-time")
+        .arg(TIMEOUT.as_secs().to_string())
+        .args(["--config", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .context("failed to start curl")?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(config.as_bytes())
+            .await
+            .context("failed sending the usage report to curl")?;
+    }
+    let output = child
+        .wait_with_output()
+        .await
+        .context("failed waiting for curl")?;
+    if !output.status.success() {
+        bail!(
+            "usage report failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+fn counters_path(app_data_dir: &Path) -> PathBuf {
+    app_data_dir.join("usage.json")
+}
+
+fn read(path: &Path) -> Counters {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+fn write(path: &Path, counters: &Counters) -> anyhow::Result<()> {
+    std::fs::write(path, serde_json::to_string_pretty(counters)?)
+        .with_context(|| format!("failed writing {}", path.display()))
+}
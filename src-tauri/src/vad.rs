@@ -0,0 +1,63 @@
+use std::io::{self, Read, Write};
+
+use anyhow::{anyhow, Context};
+use webrtc_vad::{SampleRate, Vad, VadMode};
+
+/// 30 ms at 16 kHz, the longest frame WebRTC VAD accepts.
+pub const FRAME_SAMPLES: usize = 480;
+
+/// WebRTC VAD set up for the worker's 16 kHz mono audio.
+pub struct SpeechDetector {
+    vad: Vad,
+}
+
+impl SpeechDetector {
+    pub fn new() -> Self {
+        // The most aggressive mode: keyboards and fans should not count as speech.
+        Self {
+            vad: Vad::new_with_rate_and_mode(SampleRate::Rate16kHz, VadMode::VeryAggressive),
+        }
+    }
+
+    /// Whether one `FRAME_SAMPLES` frame holds speech.
+    pub fn is_speech(&mut self, frame: &[i16]) -> anyhow::Result<bool> {
+        self.vad
+            .is_voice_segment(frame)
+            .map_err(|()| anyhow!("voice detection rejected a {}-sample frame", frame.len()))
+    }
+}
+
+/// `whisperbar --vad-filter`: reads 16 kHz mono s16le on stdin and answers
+/// every 30 ms frame with one byte on stdout, `1` for speech and `0` for
+/// anything else. The worker sends a chunk padded to whole frames and reads
+/// back exactly one byte per frame. Runs until stdin closes.
+pub fn run() -> anyhow::Result<()> {
+    let mut detector = SpeechDetector::new();
+
+    let mut stdin = io::stdin().lock();
+    let mut stdout = io::stdout().lock();
+    let mut bytes = [0u8; FRAME_SAMPLES * 2];
+    let mut frame = [0i16; FRAME_SAMPLES];
+
+    loop {
+        match stdin.read_exact(&mut bytes) {
+            Ok(()) => {}
+            Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+            Err(error) => return Err(error).context("failed reading audio for voice detection"),
+        }
+
+        for (sample, pair) in frame.iter_mut().zip(bytes.chunks_exact(2)) {
+            *sample = i16::from_le_bytes([pair[0], pair[1]]);
+        }
+        let speech = detector.is_speech(&frame)?;
+
+        if stdout
+            .write_all(if speech { b"1" } else { b"0" })
+            .and_then(|()| stdout.flush())
+            .is_err()
+        {
+            // The worker stopped reading: the session is over.
+            return Ok(());
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use anyhow::{anyhow, Context};
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+use crate::{app_state::SharedState, dictation, history, transcript_file, ui};
+
+/// Numbers viewer window labels (`viewer-1`, `viewer-2`, ...); labels are
+/// never reused within a run.
+static NEXT_VIEWER: AtomicU64 = AtomicU64::new(1);
+
+/// What a viewer window shows, read from disk each time it is asked for so
+/// edits made elsewhere show up on reload.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ViewerTranscript {
+    pub path: String,
+    pub text: String,
+}
+
+/// Opens a read-only viewer for a history entry id or a transcript path. A
+/// transcript that already has a viewer gets that window focused instead.
+pub async fn open(app: &AppHandle, state: &SharedState, target: &str) -> anyhow::Result<()> {
+    let path = resolve(state, target).await?;
+    let title = format!(
+        "WhisperBar · {}",
+        path.file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.display().to_string())
+    );
+
+    let mut viewers = state.viewers.lock().await;
+    viewers.retain(|label, _| app.get_webview_window(label).is_some());
+    let label = viewers
+        .iter()
+        .find(|(_, open)| **open == path)
+        .map(|(label, _)| label.clone())
+        .unwrap_or_else(|| format!("viewer-{}", NEXT_VIEWER.fetch_add(1, Ordering::Relaxed)));
+
+    ui::ensure_viewer_window(app, &label, &title)?;
+    viewers.insert(label, path);
+    Ok(())
+}
+
+pub async fn transcript(state: &SharedState, label: &str) -> anyhow::Result<ViewerTranscript> {
+    let path = viewer_path(state, label).await?;
+    let text = tokio::fs::read_to_string(&path)
+        .await
+        .with_context(|| format!("failed reading {}", path.display()))?;
+    Ok(ViewerTranscript {
+        path: path.display().to_string(),
+        text,
+    })
+}
+
+/// Leaves the whole transcript on the clipboard.
+pub async fn copy(state: &SharedState, label: &str) -> anyhow::Result<()> {
+    let text = transcript(state, label).await?.text;
+    dictation::write_pasteboard(&text).await
+}
+
+pub async fn export(state: &SharedState, label: &str) -> anyhow::Result<String> {
+    let path = viewer_path(state, label).await?;
+    history::export_file(&path).await
+}
+
+/// A history entry's transcript, or the transcript at `target`: a Markdown
+/// file in the transcript folder or one the history lists. Nothing else is
+/// opened, so a window cannot be pointed at an arbitrary file to read it.
+async fn resolve(state: &SharedState, target: &str) -> anyhow::Result<PathBuf> {
+    let target = target.trim();
+    let history = state.history.lock().await;
+    if let Some(entry) = history.entries.iter().find(|entry| entry.id == target) {
+        return Ok(PathBuf::from(&entry.path));
+    }
+
+    let not_found = || anyhow!("no history entry or transcript file `{target}`");
+    let path = Path::new(target).canonicalize().map_err(|_| not_found())?;
+    if !path.is_file() || path.extension().is_none_or(|extension| extension != "md") {
+        return Err(not_found());
+    }
+    let in_output_dir = transcript_file::output_dir()
+        .and_then(|dir| Ok(dir.canonicalize()?))
+        .is_ok_and(|dir| path.starts_with(dir));
+    let in_history = history.entries.iter().any(|entry| {
+        Path::new(&entry.path)
+            .canonicalize()
+            .is_ok_and(|entry| entry == path)
+    });
+    if in_output_dir || in_history {
+        Ok(path)
+    } else {
+        Err(not_found())
+    }
+}
+
+async fn viewer_path(state: &SharedState, label: &str) -> anyhow::Result<PathBuf> {
+    state
+        .viewers
+        .lock()
+        .await
+        .get(label)
+        .cloned()
+        .ok_or_else(|| anyhow!("window {label} is not a transcript viewer"))
+}
@@ -0,0 +1,1379 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    process::{Child, ChildStdout, Command, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Context};
+use base64::Engine as _;
+use serde_json::{json, Value};
+use whisper_rs::{
+    FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters, WhisperState,
+};
+
+use crate::{
+    audio::{self, AudioSource, DeviceProfile, LevelReading},
+    models, recording_audio,
+    sck_audio_helper::AutoGain,
+    vad::{self, SpeechDetector},
+};
+
+const SAMPLE_RATE: usize = 16_000;
+const BYTES_PER_SECOND: usize = SAMPLE_RATE * 2;
+const LEVEL_UPDATES_PER_SECOND: usize = 4;
+const DEFAULT_CHUNK_SECONDS: f64 = 2.8;
+/// Chunks shorter than this (the last one of a session) are not transcribed.
+const MIN_CHUNK_SECONDS: f64 = 0.8;
+/// Same defaults as the Python worker, so switching engines changes nothing
+/// but the model runtime.
+const SILENCE_RMS: f32 = 0.0006;
+const MIC_MUTE_BLOCKS: u32 = 3;
+const MIC_SWITCH_GRACE: Duration = Duration::from_secs(3);
+const PREROLL_WAIT: Duration = Duration::from_secs(1);
+const STOP_GRACE: Duration = Duration::from_secs(2);
+const NO_SPEECH_THRESHOLD: f32 = 0.45;
+const VAD_FRAME_SECONDS: f64 = vad::FRAME_SAMPLES as f64 / SAMPLE_RATE as f64;
+const VAD_MIN_SPEECH_SECONDS: f64 = 0.3;
+const PARAGRAPH_PAUSE_SECONDS: f64 = 2.0;
+const MUSIC_MIN_RMS: f32 = 0.003;
+const MUSIC_MAX_LOW_ENERGY_RATIO: f32 = 0.1;
+const MUSIC_MAX_SPREAD_DB: f32 = 6.0;
+const MUSIC_CONFIRM_CHUNKS: u32 = 3;
+/// `--transcribe-file` feeds the model one full window at a time.
+const FILE_CHUNK_SECONDS: usize = 30;
+
+/// The Python worker's command line, minus what only MLX needs.
+struct Args {
+    language: String,
+    model_path: PathBuf,
+    mic_device: Option<String>,
+    helper_path: Option<PathBuf>,
+    chunk_seconds: f64,
+    separate_sources: bool,
+    system_audio: bool,
+    device_profiles: BTreeMap<String, DeviceProfile>,
+    resample_quality: String,
+    denoise: bool,
+    vad: bool,
+    skip_music: bool,
+    word_timestamps: bool,
+    preroll: bool,
+    audio_path: Option<PathBuf>,
+    initial_prompt: Option<String>,
+    transcribe_file: Option<PathBuf>,
+}
+
+/// `whisperbar --native-worker ...`: the transcription worker for
+/// whisper.cpp models. Takes the Python worker's arguments and stdin
+/// commands and emits the same JSON-line events, so the app drives both the
+/// same way. Speaker labels and loopback detection are Python-only.
+pub fn run(args: &[String]) -> anyhow::Result<()> {
+    let result = parse_args(args).and_then(|args| match &args.transcribe_file {
+        Some(path) => transcribe_file(&args, path),
+        None => record(&args),
+    });
+    if let Err(error) = &result {
+        emit(json!({ "type": "error", "message": format!("{error:#}") }));
+    }
+    result
+}
+
+fn parse_args(args: &[String]) -> anyhow::Result<Args> {
+    let mut parsed = Args {
+        language: "en".to_string(),
+        model_path: PathBuf::new(),
+        mic_device: None,
+        helper_path: None,
+        chunk_seconds: DEFAULT_CHUNK_SECONDS,
+        separate_sources: false,
+        system_audio: true,
+        device_profiles: BTreeMap::new(),
+        resample_quality: "high".to_string(),
+        denoise: false,
+        vad: false,
+        skip_music: false,
+        word_timestamps: false,
+        preroll: false,
+        audio_path: None,
+        initial_prompt: None,
+        transcribe_file: None,
+    };
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .cloned()
+                .ok_or_else(|| anyhow!("missing value for {arg}"))
+        };
+        match arg.as_str() {
+            "--language" => parsed.language = value()?,
+            "--model-path" => parsed.model_path = PathBuf::from(value()?),
+            "--mic-device" => parsed.mic_device = Some(value()?),
+            "--sck-helper-path" => parsed.helper_path = Some(PathBuf::from(value()?)),
+            "--chunk-seconds" => {
+                parsed.chunk_seconds = value()?.parse().context("invalid --chunk-seconds")?
+            }
+            "--device-profiles" => {
+                parsed.device_profiles = serde_json::from_str(&value()?).unwrap_or_default()
+            }
+            "--resample-quality" => parsed.resample_quality = value()?,
+            "--audio-path" => parsed.audio_path = Some(PathBuf::from(value()?)),
+            "--initial-prompt" => parsed.initial_prompt = Some(value()?),
+            "--transcribe-file" => parsed.transcribe_file = Some(PathBuf::from(value()?)),
+            "--diarize" => {
+                return Err(anyhow!(
+                    "Speaker labels are not available with whisper.cpp models"
+                ))
+            }
+            "--separate-sources" => parsed.separate_sources = true,
+            "--no-system-audio" => parsed.system_audio = false,
+            "--denoise" => parsed.denoise = true,
+            "--vad" => parsed.vad = true,
+            "--skip-music" => parsed.skip_music = true,
+            "--word-timestamps" => parsed.word_timestamps = true,
+            "--preroll" => parsed.preroll = true,
+            _ => return Err(anyhow!("unknown argument `{arg}`")),
+        }
+    }
+
+    if parsed.model_path.as_os_str().is_empty() {
+        return Err(anyhow!("--model-path is required"));
+    }
+    Ok(parsed)
+}
+
+/// Stdin commands other than `stop`, which sets a flag so capture loops can
+/// react to it between blocks.
+struct Controls {
+    stop: Arc<AtomicBool>,
+    mute: Receiver<String>,
+    mic: Receiver<String>,
+    preroll: Receiver<Vec<u8>>,
+}
+
+fn read_controls() -> Controls {
+    let stop = Arc::new(AtomicBool::new(false));
+    let (mute_tx, mute) = mpsc::channel();
+    let (mic_tx, mic) = mpsc::channel();
+    let (preroll_tx, preroll) = mpsc::channel();
+
+    let stop_flag = stop.clone();
+    thread::spawn(move || {
+        for line in io::stdin().lock().lines() {
+            let Ok(line) = line else { break };
+            let command = line.trim();
+            if command.eq_ignore_ascii_case("stop") {
+                break;
+            } else if let Some(source) = command.strip_prefix("mute ") {
+                let _ = mute_tx.send(source.trim().to_string());
+            } else if let Some(name) = command.strip_prefix("mic ") {
+                let _ = mic_tx.send(name.trim().to_string());
+            } else if let Some(payload) = command.strip_prefix("preroll") {
+                let audio = base64::engine::general_purpose::STANDARD
+                    .decode(payload.trim())
+                    .unwrap_or_default();
+                let _ = preroll_tx.send(audio);
+            }
+        }
+        // A closed stdin means the app is gone; stop either way.
+        stop_flag.store(true, Ordering::Relaxed);
+    });
+
+    Controls {
+        stop,
+        mute,
+        mic,
+        preroll,
+    }
+}
+
+fn record(args: &Args) -> anyhow::Result<()> {
+    let controls = read_controls();
+    let whisper = load_model(&args.model_path)?;
+
+    let helper_path = args.helper_path.as_deref().filter(|path| path.exists());
+    if args.system_audio && helper_path.is_none() {
+        return Err(anyhow!("ScreenCaptureKit helper binary not found"));
+    }
+
+    let (mic_name, mic_profile) = match choose_mic(args.mic_device.as_deref()) {
+        Some(name) => {
+            let profile = args.device_profiles.get(&name).cloned().unwrap_or_default();
+            (Some(name), profile)
+        }
+        None => (None, DeviceProfile::default()),
+    };
+    if !args.system_audio && mic_name.is_none() {
+        return Err(anyhow!(
+            "System audio is off and no microphone is available. Select a microphone or turn system audio back on."
+        ));
+    }
+    match (&mic_name, args.system_audio) {
+        (Some(name), true) => emit_status(&format!(
+            "Listening desktop (ScreenCaptureKit) + mic: {name}"
+        )),
+        (None, true) => emit_status("Listening desktop (ScreenCaptureKit)"),
+        (Some(name), false) => emit_status(&format!("Listening mic: {name}")),
+        (None, false) => {}
+    }
+
+    let denoise_helper = helper_path.filter(|_| args.denoise);
+    let desktop = match helper_path.filter(|_| args.system_audio) {
+        Some(helper) => Some(Capture::desktop(helper, &args.resample_quality)?),
+        None => None,
+    };
+    let mic = match &mic_name {
+        Some(name) => Some(Capture::mic(name, &mic_profile, denoise_helper)?),
+        None => None,
+    };
+    let audio_writer = match &args.audio_path {
+        Some(path) => Some(WavWriter::create(path)?),
+        None => None,
+    };
+
+    let mut session = Session {
+        args,
+        controls,
+        whisper,
+        language: normalize_language(&args.language),
+        chunk_bytes: ((BYTES_PER_SECOND as f64 * args.chunk_seconds) as usize)
+            .max(BYTES_PER_SECOND * 6 / 5)
+            & !1,
+        level_bytes: BYTES_PER_SECOND / LEVEL_UPDATES_PER_SECOND,
+        denoise_helper,
+        desktop,
+        mic,
+        mic_name,
+        mic_profile,
+        audio_writer,
+        mic_gain: AutoGain::new(),
+        detector: args.vad.then(SpeechDetector::new),
+        music_gate: (args.skip_music && args.system_audio).then(MusicGate::default),
+        pauses: HashMap::new(),
+        collected: Vec::new(),
+        session_seconds: 0.0,
+        awaiting_preroll: args.preroll,
+        muted: None,
+        silent_mic_blocks: 0,
+        mic_muted: false,
+    };
+    let result = session.run();
+    let collected = session.finish();
+    result?;
+
+    emit(json!({ "type": "final", "text": collected.join("\n").trim() }));
+    emit_status("Worker stopped");
+    Ok(())
+}
+
+fn load_model(model_path: &Path) -> anyhow::Result<WhisperState> {
+    emit_status("Loading model");
+    whisper_rs::install_logging_hooks();
+    let model_file = model_path.join(models::GGML_MODEL_FILE);
+    let context = WhisperContext::new_with_params(
+        &model_file.to_string_lossy(),
+        WhisperContextParameters::default(),
+    )
+    .with_context(|| format!("failed loading {}", model_file.display()))?;
+    context
+        .create_state()
+        .context("failed creating whisper.cpp state")
+}
+
+/// `--transcribe-file`: runs a kept recording through the model one window
+/// at a time and emits a `partial` per window with text, then `final`.
+/// Capture arguments are ignored.
+fn transcribe_file(args: &Args, path: &Path) -> anyhow::Result<()> {
+    let mut whisper = load_model(&args.model_path)?;
+    emit_status("Transcribing recording");
+    let audio = samples(&recording_audio::read_pcm(path)?);
+    let language = normalize_language(&args.language);
+
+    let mut collected = Vec::new();
+    let window = SAMPLE_RATE * FILE_CHUNK_SECONDS;
+    for (index, chunk) in audio.chunks(window).enumerate() {
+        let Some(text) = transcribe(
+            &mut whisper,
+            chunk,
+            language,
+            args.initial_prompt.as_deref(),
+            args.word_timestamps,
+        )?
+        else {
+            continue;
+        };
+        let chunk_start = (index * FILE_CHUNK_SECONDS) as f64;
+        let mut partial = json!({
+            "type": "partial",
+            "text": text.text,
+            "start": round2(chunk_start + text.start),
+            "end": round2(chunk_start + text.end),
+        });
+        if !text.words.is_empty() {
+            partial["words"] = partial_words(chunk_start, &text.words);
+        }
+        collected.push(text.text);
+        emit(partial);
+    }
+
+    emit(json!({ "type": "final", "text": collected.join("\n").trim() }));
+    Ok(())
+}
+
+/// Everything a recording carries from one chunk to the next.
+struct Session<'a> {
+    args: &'a Args,
+    controls: Controls,
+    whisper: WhisperState,
+    language: &'static str,
+    chunk_bytes: usize,
+    level_bytes: usize,
+    denoise_helper: Option<&'a Path>,
+    desktop: Option<Capture>,
+    mic: Option<Capture>,
+    mic_name: Option<String>,
+    mic_profile: DeviceProfile,
+    audio_writer: Option<WavWriter>,
+    mic_gain: AutoGain,
+    detector: Option<SpeechDetector>,
+    music_gate: Option<MusicGate>,
+    /// Silence at the end of the previous chunk, per stream.
+    pauses: HashMap<Option<&'static str>, f64>,
+    collected: Vec<String>,
+    session_seconds: f64,
+    awaiting_preroll: bool,
+    muted: Option<AudioSource>,
+    silent_mic_blocks: u32,
+    mic_muted: bool,
+}
+
+/// One recorded chunk, ready to transcribe.
+struct Chunk {
+    /// When the last block arrived; what follows until inference is queueing.
+    captured_at: Instant,
+    /// Session time where the chunk starts.
+    start: f64,
+    mixed: Vec<f32>,
+    /// The sources on their own, pre-roll included, for `--separate-sources`
+    /// and the music gate.
+    mic: Vec<f32>,
+    desktop: Vec<f32>,
+    /// Both sources are in the mix and are to be transcribed apart.
+    separate: bool,
+}
+
+/// One stream of a chunk that produced text.
+struct Decoded {
+    source: Option<AudioSource>,
+    text: ChunkText,
+    paragraph: bool,
+    capture_ms: f64,
+    inference_ms: f64,
+}
+
+impl Session<'_> {
+    fn run(&mut self) -> anyhow::Result<()> {
+        while !self.controls.stop.load(Ordering::Relaxed) {
+            self.apply_controls()?;
+            let Some(chunk) = self.capture()? else {
+                continue;
+            };
+            let start = chunk.start;
+            let decoded = self.decode(chunk)?;
+            self.emit_partials(start, decoded);
+        }
+        Ok(())
+    }
+
+    /// Closes the recording and the captures; returns the session's text.
+    fn finish(self) -> Vec<String> {
+        if let Some(writer) = self.audio_writer {
+            let _ = writer.finish();
+        }
+        for capture in [self.desktop, self.mic].into_iter().flatten() {
+            capture.stop();
+        }
+        self.collected
+    }
+
+    /// Applies the `mute` and `mic` commands received since the last chunk.
+    fn apply_controls(&mut self) -> anyhow::Result<()> {
+        while let Ok(source) = self.controls.mute.try_recv() {
+            let source = match source.as_str() {
+                "mic" => AudioSource::Mic,
+                "desktop" => AudioSource::Desktop,
+                _ => continue,
+            };
+            if self.muted == Some(source) {
+                continue;
+            }
+            let has_other = match source {
+                AudioSource::Desktop => self.mic.is_some(),
+                AudioSource::Mic => self.args.system_audio,
+            };
+            if !has_other || self.muted.is_some() {
+                emit_status(&format!(
+                    "Can't drop {}: it is the only source left",
+                    source.as_str()
+                ));
+                continue;
+            }
+            self.muted = Some(source);
+            emit(json!({ "type": "muted", "source": source.as_str() }));
+        }
+
+        while let Ok(requested) = self.controls.mic.try_recv() {
+            let Some(name) = resolve_mic(&requested) else {
+                continue;
+            };
+            if self.mic.as_mut().is_some_and(Capture::is_running)
+                && self.mic_name.as_deref() == Some(name.as_str())
+            {
+                continue;
+            }
+            // After a failure the app already explained the switch.
+            let recovering = self.mic.is_none();
+            if let Some(previous) = self.mic.take() {
+                previous.stop();
+            }
+            self.open_mic(&name)?;
+            if !recovering {
+                emit_status(&format!("Switched microphone to {name}"));
+            }
+            self.mic_name = Some(name);
+        }
+
+        if !self.args.system_audio && self.mic.is_none() {
+            return Err(anyhow!(
+                "Microphone disconnected and no other microphone is available."
+            ));
+        }
+        Ok(())
+    }
+
+    fn open_mic(&mut self, name: &str) -> anyhow::Result<()> {
+        self.mic_profile = self
+            .args
+            .device_profiles
+            .get(name)
+            .cloned()
+            .unwrap_or_default();
+        self.mic = Some(Capture::mic(name, &self.mic_profile, self.denoise_helper)?);
+        self.silent_mic_blocks = 0;
+        Ok(())
+    }
+
+    /// Reads and records one chunk. `None` when there is nothing to
+    /// transcribe this time around.
+    fn capture(&mut self) -> anyhow::Result<Option<Chunk>> {
+        // Read in short blocks so the level meter updates while a chunk
+        // fills. Desktop audio paces the loop; without it the mic does.
+        let chunk_bytes = self.chunk_bytes;
+        let mut desktop_buffer = Vec::with_capacity(chunk_bytes);
+        let mut mic_buffer = Vec::with_capacity(chunk_bytes);
+        while desktop_buffer.len().max(mic_buffer.len()) < chunk_bytes {
+            let block_size = self
+                .level_bytes
+                .min(chunk_bytes - desktop_buffer.len().max(mic_buffer.len()));
+
+            let mut desktop_block = Vec::new();
+            if let Some(capture) = self.desktop.as_mut() {
+                desktop_block = capture.read_block(block_size)?;
+                if desktop_block.is_empty() {
+                    break;
+                }
+                desktop_buffer.extend_from_slice(&desktop_block);
+            }
+
+            let mut mic_block = Vec::new();
+            if let Some(capture) = self.mic.as_mut() {
+                mic_block = capture.read_block(block_size)?;
+                if mic_block.is_empty() {
+                    self.replace_failed_mic()?;
+                } else {
+                    mic_buffer.extend_from_slice(&mic_block);
+                }
+            }
+
+            if !mic_block.is_empty() {
+                // Session time where this block starts.
+                let block_start = self.session_seconds
+                    + (mic_buffer.len() - mic_block.len()) as f64 / BYTES_PER_SECOND as f64;
+                self.track_mic_mute(&mic_block, block_start);
+            }
+
+            if !self.args.system_audio && mic_block.is_empty() {
+                break;
+            }
+
+            emit(json!({
+                "type": "level",
+                "desktop": self.desktop.is_some().then(|| level(&desktop_block)),
+                "mic": self.mic.is_some().then(|| level(&mic_block)),
+            }));
+            if self.controls.stop.load(Ordering::Relaxed) {
+                break;
+            }
+        }
+
+        if self.args.system_audio && desktop_buffer.is_empty() {
+            if let Some(capture) = self.desktop.as_mut() {
+                if !capture.is_running() {
+                    let stderr = capture.drain_errors();
+                    return Err(anyhow!(
+                        "ScreenCaptureKit helper stopped unexpectedly. {}",
+                        if stderr.is_empty() {
+                            "Check Screen Recording permission in macOS settings."
+                        } else {
+                            stderr.as_str()
+                        }
+                    ));
+                }
+            }
+            return Ok(None);
+        }
+
+        let captured_at = Instant::now();
+        let mut desktop = samples(&desktop_buffer);
+        let mut mic = samples(&mic_buffer);
+        if !mic.is_empty() {
+            self.mic_gain.process(&mut mic);
+        }
+
+        // Muted sources are still read so their pipes never fill up.
+        let mut mixed = match self.muted {
+            Some(AudioSource::Desktop) => mic.clone(),
+            Some(AudioSource::Mic) => desktop.clone(),
+            None => mix(&desktop, &mic),
+        };
+
+        // Both sources must be in the mix to be told apart.
+        let separate = self.args.separate_sources
+            && !mic.is_empty()
+            && !desktop.is_empty()
+            && self.muted.is_none();
+
+        if self.awaiting_preroll {
+            self.awaiting_preroll = false;
+            let preroll = self
+                .controls
+                .preroll
+                .recv_timeout(PREROLL_WAIT)
+                .map(|bytes| samples(&bytes))
+                .unwrap_or_default();
+            // Pre-roll is mic audio; pad the desktop so both stay aligned.
+            mixed.splice(0..0, preroll.iter().copied());
+            mic.splice(0..0, preroll.iter().copied());
+            desktop.splice(0..0, std::iter::repeat_n(0.0, preroll.len()));
+        }
+
+        if mixed.is_empty() {
+            return Ok(None);
+        }
+        if let Some(writer) = self.audio_writer.as_mut() {
+            writer.write(&mixed)?;
+        }
+
+        let start = self.session_seconds;
+        self.session_seconds += mixed.len() as f64 / SAMPLE_RATE as f64;
+        if (mixed.len() as f64) < SAMPLE_RATE as f64 * MIN_CHUNK_SECONDS {
+            return Ok(None);
+        }
+        Ok(Some(Chunk {
+            captured_at,
+            start,
+            mixed,
+            mic,
+            desktop,
+            separate,
+        }))
+    }
+
+    /// The mic stopped delivering audio (failed to open or unplugged): the
+    /// app picks a replacement. With nothing else to record, waits a moment
+    /// for it before giving up.
+    fn replace_failed_mic(&mut self) -> anyhow::Result<()> {
+        let failed = self.mic.take().map(Capture::stop).unwrap_or_default();
+        emit(json!({
+            "type": "mic_failed",
+            "device": self.mic_name,
+            "message": (!failed.is_empty()).then_some(&failed),
+        }));
+        if self.args.system_audio {
+            return Ok(());
+        }
+        let Ok(requested) = self.controls.mic.recv_timeout(MIC_SWITCH_GRACE) else {
+            return Err(anyhow!(
+                "Microphone capture stopped. {}",
+                if failed.is_empty() {
+                    "Check macOS Microphone permission."
+                } else {
+                    failed.as_str()
+                }
+            ));
+        };
+        let name = resolve_mic(&requested).unwrap_or(requested);
+        self.open_mic(&name)?;
+        self.mic_name = Some(name);
+        Ok(())
+    }
+
+    /// Reports a mic that delivers nothing but digital silence as muted,
+    /// and reports it again once sound comes back.
+    fn track_mic_mute(&mut self, block: &[u8], block_start: f64) {
+        if block.iter().all(|byte| *byte == 0) {
+            self.silent_mic_blocks += 1;
+            if !self.mic_muted && self.silent_mic_blocks >= MIC_MUTE_BLOCKS {
+                self.mic_muted = true;
+                let muted_at = block_start
+                    - f64::from(self.silent_mic_blocks - 1) * block.len() as f64
+                        / BYTES_PER_SECOND as f64;
+                emit(json!({
+                    "type": "mic_muted",
+                    "muted": true,
+                    "at": round2(muted_at.max(0.0)),
+                }));
+            }
+        } else {
+            self.silent_mic_blocks = 0;
+            if self.mic_muted {
+                self.mic_muted = false;
+                emit(json!({
+                    "type": "mic_muted",
+                    "muted": false,
+                    "at": round2(block_start),
+                }));
+            }
+        }
+    }
+
+    /// Splits a chunk into the streams to transcribe, skips the ones without
+    /// speech and transcribes the rest.
+    fn decode(&mut self, chunk: Chunk) -> anyhow::Result<Vec<Decoded>> {
+        // The calibrated threshold was measured before AGC; follow its gain.
+        let mic_threshold =
+            self.mic_profile.silence_threshold.unwrap_or(SILENCE_RMS) * self.mic_gain.gain();
+        let muted = self.muted;
+        let music_only = self.music_gate.as_mut().is_some_and(|gate| {
+            let desktop_audio = if muted == Some(AudioSource::Desktop) {
+                &[][..]
+            } else {
+                &chunk.desktop
+            };
+            gate.update(desktop_audio, chunk.start)
+        });
+        let streams = if chunk.separate {
+            let mut streams = vec![(Some(AudioSource::Mic), chunk.mic, mic_threshold)];
+            if !music_only {
+                streams.push((Some(AudioSource::Desktop), chunk.desktop, SILENCE_RMS));
+            }
+            streams
+        } else if music_only {
+            // Keep transcribing the microphone on its own.
+            if chunk.mic.is_empty() || muted == Some(AudioSource::Mic) {
+                Vec::new()
+            } else {
+                vec![(None, chunk.mic, mic_threshold)]
+            }
+        } else {
+            // Desktop audio has its own floor, so the mic's calibrated
+            // threshold only applies when the mic is all that is mixed.
+            let mic_only = !self.args.system_audio || muted == Some(AudioSource::Desktop);
+            let threshold = if mic_only { mic_threshold } else { SILENCE_RMS };
+            vec![(None, chunk.mixed, threshold)]
+        };
+
+        let mut decoded = Vec::new();
+        for (source, audio, threshold) in streams {
+            let mut paragraph = false;
+            if let Some(detector) = self.detector.as_mut() {
+                // The detector replaces the level threshold entirely.
+                let pause = self
+                    .pauses
+                    .entry(source.map(AudioSource::as_str))
+                    .or_insert(0.0);
+                match detect_speech(detector, &audio, pause)? {
+                    Some(new_paragraph) => paragraph = new_paragraph,
+                    None => continue,
+                }
+                emit(json!({ "type": "speech" }));
+            } else if rms(&audio) < threshold {
+                continue;
+            }
+
+            let inference_started = Instant::now();
+            let Some(text) = transcribe(
+                &mut self.whisper,
+                &audio,
+                self.language,
+                self.args.initial_prompt.as_deref(),
+                self.args.word_timestamps,
+            )?
+            else {
+                continue;
+            };
+            decoded.push(Decoded {
+                source,
+                text,
+                paragraph,
+                capture_ms: millis(inference_started - chunk.captured_at),
+                inference_ms: millis(inference_started.elapsed()),
+            });
+        }
+        Ok(decoded)
+    }
+
+    /// Emits a chunk's text as `partial` events. Both sources cover the
+    /// same span, so they go out in spoken order.
+    fn emit_partials(&mut self, chunk_start: f64, mut decoded: Vec<Decoded>) {
+        decoded.sort_by(|a, b| a.text.start.total_cmp(&b.text.start));
+        for stream in decoded {
+            let mut partial = json!({
+                "type": "partial",
+                "text": stream.text.text,
+                "start": round2(chunk_start + stream.text.start),
+                "end": round2(chunk_start + stream.text.end),
+                "source": stream.source.map(AudioSource::as_str),
+                "timings": {
+                    "captureMs": stream.capture_ms,
+                    "inferenceMs": stream.inference_ms,
+                    "sentAt": chrono::Utc::now().timestamp_micros() as f64 / 1_000_000.0,
+                },
+            });
+            if stream.paragraph {
+                partial["paragraph"] = json!(true);
+            }
+            if !stream.text.words.is_empty() {
+                partial["words"] = partial_words(chunk_start, &stream.text.words);
+            }
+            if self.detector.is_none() {
+                emit(json!({ "type": "speech" }));
+            }
+            self.collected.push(stream.text.text);
+            emit(partial);
+        }
+    }
+}
+
+/// The `words` of a partial, moved from chunk to session time.
+fn partial_words(chunk_start: f64, words: &[TimedWord]) -> Value {
+    words
+        .iter()
+        .map(|word| {
+            json!({
+                "word": word.text,
+                "start": round2(chunk_start + word.start),
+                "end": round2(chunk_start + word.end),
+            })
+        })
+        .collect()
+}
+
+/// Text of one chunk; times are in seconds from the start of the chunk.
+struct ChunkText {
+    text: String,
+    start: f64,
+    end: f64,
+    words: Vec<TimedWord>,
+}
+
+struct TimedWord {
+    text: String,
+    start: f64,
+    end: f64,
+}
+
+/// Runs one chunk through whisper.cpp. Returns `None` when the model only
+/// heard non-speech (`[BLANK_AUDIO]`, `(music)`). Words are timed only when
+/// `word_timestamps` is set.
+fn transcribe(
+    whisper: &mut WhisperState,
+    audio: &[f32],
+    language: &str,
+    initial_prompt: Option<&str>,
+    word_timestamps: bool,
+) -> anyhow::Result<Option<ChunkText>> {
+    let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+    params.set_language(Some(language));
+    params.set_translate(false);
+    params.set_temperature(0.0);
+    // Keep the previous chunk's text as context, like the MLX worker.
+    params.set_no_context(false);
+    params.set_no_speech_thold(NO_SPEECH_THRESHOLD);
+    params.set_print_special(false);
+    params.set_print_progress(false);
+    params.set_print_realtime(false);
+    params.set_print_timestamps(false);
+    params.set_token_timestamps(word_timestamps);
+    if let Some(prompt) = initial_prompt.filter(|prompt| !prompt.trim().is_empty()) {
+        params.set_initial_prompt(prompt);
+    }
+
+    whisper
+        .full(params, audio)
+        .context("whisper.cpp transcription failed")?;
+
+    let duration = audio.len() as f64 / SAMPLE_RATE as f64;
+    let mut parts = Vec::new();
+    let mut words = Vec::new();
+    let mut bounds: Option<(f64, f64)> = None;
+    for index in 0..whisper.full_n_segments()? {
+        let text = whisper.full_get_segment_text(index)?;
+        let text = text.trim();
+        if text.is_empty() || is_non_speech(text) {
+            continue;
+        }
+        // Segment times are in centiseconds.
+        let start = whisper.full_get_segment_t0(index)? as f64 / 100.0;
+        let end = (whisper.full_get_segment_t1(index)? as f64 / 100.0).min(duration);
+        bounds = Some(bounds.map_or((start, end), |(first, _)| (first, end)));
+        parts.push(text.to_string());
+        if word_timestamps {
+            words.extend(segment_words(whisper, index, duration)?);
+        }
+    }
+
+    Ok(bounds.map(|(start, end)| ChunkText {
+        text: parts.join(" "),
+        start,
+        end,
+        words,
+    }))
+}
+
+/// Joins the tokens of segment `index` into words. A token starting with a
+/// space opens a new word; the rest continue the current one. Token bytes
+/// are joined before decoding since a character can span two tokens.
+fn segment_words(
+    whisper: &WhisperState,
+    index: i32,
+    duration: f64,
+) -> anyhow::Result<Vec<TimedWord>> {
+    fn finish(words: &mut Vec<TimedWord>, bytes: &[u8], start: f64, end: f64) {
+        let text = String::from_utf8_lossy(bytes).trim().to_string();
+        if !text.is_empty() {
+            words.push(TimedWord { text, start, end });
+        }
+    }
+
+    let mut words = Vec::new();
+    let mut current: Option<(Vec<u8>, f64, f64)> = None;
+    for token in 0..whisper.full_n_tokens(index)? {
+        let bytes = whisper.full_get_token_bytes(index, token)?;
+        // Special tokens (`[_BEG_]`, `[_TT_150]`, `<|endoftext|>`) carry no text.
+        if bytes.starts_with(b"[_") || bytes.starts_with(b"<|") {
+            continue;
+        }
+        let data = whisper.full_get_token_data(index, token)?;
+        let start = data.t0 as f64 / 100.0;
+        let end = (data.t1 as f64 / 100.0).min(duration);
+
+        match current.as_mut() {
+            Some((text, _, word_end)) if !bytes.starts_with(b" ") => {
+                text.extend_from_slice(&bytes);
+                *word_end = end;
+            }
+            _ => {
+                if let Some((text, word_start, word_end)) = current.take() {
+                    finish(&mut words, &text, word_start, word_end);
+                }
+                current = Some((bytes, start, end));
+            }
+        }
+    }
+    if let Some((text, start, end)) = current {
+        finish(&mut words, &text, start, end);
+    }
+    Ok(words)
+}
+
+fn is_non_speech(text: &str) -> bool {
+    (text.starts_with('[') && text.ends_with(']')) || (text.starts_with('(') && text.ends_with(')'))
+}
+
+/// Runs `audio` through the voice detector. `None` when there is too little
+/// speech to transcribe; otherwise whether the speech follows a pause long
+/// enough to start a paragraph. `pause` carries the silence at the end of
+/// the previous chunk of this source.
+fn detect_speech(
+    detector: &mut SpeechDetector,
+    audio: &[f32],
+    pause: &mut f64,
+) -> anyhow::Result<Option<bool>> {
+    let pcm = audio
+        .iter()
+        .map(|sample| to_i16(*sample))
+        .collect::<Vec<_>>();
+    let mut speech_frames = Vec::new();
+    let mut frame_count = 0;
+    for (index, frame) in pcm.chunks(vad::FRAME_SAMPLES).enumerate() {
+        frame_count += 1;
+        let speech = if frame.len() == vad::FRAME_SAMPLES {
+            detector.is_speech(frame)?
+        } else {
+            let mut padded = frame.to_vec();
+            padded.resize(vad::FRAME_SAMPLES, 0);
+            detector.is_speech(&padded)?
+        };
+        if speech {
+            speech_frames.push(index);
+        }
+    }
+
+    let (Some(first), Some(last)) = (speech_frames.first(), speech_frames.last()) else {
+        *pause += frame_count as f64 * VAD_FRAME_SECONDS;
+        return Ok(None);
+    };
+    if (speech_frames.len() as f64) * VAD_FRAME_SECONDS < VAD_MIN_SPEECH_SECONDS {
+        *pause += frame_count as f64 * VAD_FRAME_SECONDS;
+        return Ok(None);
+    }
+
+    let paragraph = *pause + *first as f64 * VAD_FRAME_SECONDS >= PARAGRAPH_PAUSE_SECONDS;
+    *pause = (frame_count - 1 - last) as f64 * VAD_FRAME_SECONDS;
+    Ok(Some(paragraph))
+}
+
+/// Pauses transcription while desktop audio is music with no speech, so the
+/// model neither burns battery on it nor writes out hallucinated lyrics, and
+/// reports each change as a `music` event. Same heuristic as the Python
+/// worker: music only after `MUSIC_CONFIRM_CHUNKS` chunks in a row, speech
+/// again after the first chunk that is not.
+#[derive(Default)]
+struct MusicGate {
+    music_chunks: u32,
+    active: bool,
+}
+
+impl MusicGate {
+    fn update(&mut self, desktop: &[f32], at: f64) -> bool {
+        self.music_chunks = if is_music(desktop) {
+            self.music_chunks + 1
+        } else {
+            0
+        };
+        let active = self.music_chunks >= MUSIC_CONFIRM_CHUNKS;
+        if active != self.active {
+            self.active = active;
+            emit(json!({ "type": "music", "active": active, "at": round2(at) }));
+        }
+        active
+    }
+}
+
+/// Whether a desktop chunk sounds like music with no speech in it. Speech
+/// keeps dipping between words, even over a soundtrack; music holds its
+/// level. So: almost no 30 ms frame below half the mean level, and little
+/// spread in loudness.
+fn is_music(audio: &[f32]) -> bool {
+    let energies = audio
+        .chunks_exact(vad::FRAME_SAMPLES)
+        .map(rms)
+        .collect::<Vec<_>>();
+    if energies.len() < 8 || rms(audio) < MUSIC_MIN_RMS {
+        return false;
+    }
+
+    let count = energies.len() as f32;
+    let mean = energies.iter().sum::<f32>() / count;
+    let low_energy_ratio = energies
+        .iter()
+        .filter(|energy| **energy < 0.5 * mean)
+        .count() as f32
+        / count;
+    let levels_db = energies
+        .iter()
+        .map(|energy| 20.0 * (energy + 1e-6).log10())
+        .collect::<Vec<_>>();
+    let mean_db = levels_db.iter().sum::<f32>() / count;
+    let spread_db = (levels_db
+        .iter()
+        .map(|level| (level - mean_db).powi(2))
+        .sum::<f32>()
+        / count)
+        .sqrt();
+    low_energy_ratio <= MUSIC_MAX_LOW_ENERGY_RATIO && spread_db <= MUSIC_MAX_SPREAD_DB
+}
+
+/// One ffmpeg microphone capture (optionally through the denoise filter) or
+/// the ScreenCaptureKit helper, with its stderr collected in the background.
+struct Capture {
+    process: Child,
+    /// ffmpeg feeding the denoise filter when `process` is the filter. It
+    /// exits on its own once the filter's stdin closes.
+    upstream: Option<Child>,
+    stdout: ChildStdout,
+    errors: Receiver<String>,
+}
+
+impl Capture {
+    fn mic(
+        name: &str,
+        profile: &DeviceProfile,
+        denoise_helper: Option<&Path>,
+    ) -> anyhow::Result<Self> {
+        let sample_rate = if denoise_helper.is_some() {
+            48_000
+        } else {
+            16_000
+        };
+        let mut command = Command::new(resolve_ffmpeg());
+        command
+            .args([
+                "-hide_banner",
+                "-loglevel",
+                "warning",
+                "-thread_queue_size",
+                "512",
+            ])
+            .args(["-f", "avfoundation", "-i"])
+            .arg(format!(":{name}"));
+        let filters = mic_filters(profile);
+        if !filters.is_empty() {
+            command.arg("-af").arg(filters.join(","));
+        }
+        let mut ffmpeg = command
+            .args(["-ac", "1", "-ar"])
+            .arg(sample_rate.to_string())
+            .args(["-f", "s16le", "-"])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("failed starting ffmpeg for the microphone")?;
+
+        let (errors_tx, errors) = mpsc::channel();
+        forward_lines(ffmpeg.stderr.take(), errors_tx.clone());
+        let ffmpeg_stdout = ffmpeg
+            .stdout
+            .take()
+            .context("microphone ffmpeg stdout unavailable")?;
+
+        let Some(helper) = denoise_helper else {
+            return Ok(Self {
+                process: ffmpeg,
+                upstream: None,
+                stdout: ffmpeg_stdout,
+                errors,
+            });
+        };
+
+        let mut filter = match Command::new(helper)
+            .arg("--denoise-filter")
+            .stdin(Stdio::from(ffmpeg_stdout))
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+        {
+            Ok(filter) => filter,
+            Err(error) => {
+                let _ = ffmpeg.kill();
+                let _ = ffmpeg.wait();
+                return Err(error).context("failed starting the denoise filter");
+            }
+        };
+        forward_lines(filter.stderr.take(), errors_tx);
+        let stdout = filter
+            .stdout
+            .take()
+            .context("denoise filter stdout unavailable")?;
+
+        Ok(Self {
+            process: filter,
+            upstream: Some(ffmpeg),
+            stdout,
+            errors,
+        })
+    }
+
+    fn desktop(helper: &Path, resample_quality: &str) -> anyhow::Result<Self> {
+        let mut process = Command::new(helper)
+            .args(["--sck-audio-helper", "--resample-quality", resample_quality])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("failed starting the ScreenCaptureKit helper")?;
+
+        let (errors_tx, errors) = mpsc::channel();
+        forward_lines(process.stderr.take(), errors_tx);
+        let stdout = process
+            .stdout
+            .take()
+            .context("ScreenCaptureKit helper stdout unavailable")?;
+
+        Ok(Self {
+            process,
+            upstream: None,
+            stdout,
+            errors,
+        })
+    }
+
+    /// Reads up to `size` bytes, fewer only at end of stream, always a whole
+    /// number of samples. Empty once the process has stopped delivering.
+    fn read_block(&mut self, size: usize) -> anyhow::Result<Vec<u8>> {
+        let mut block = vec![0u8; size];
+        let mut filled = 0;
+        while filled < size {
+            match self.stdout.read(&mut block[filled..]) {
+                Ok(0) => break,
+                Ok(read) => filled += read,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+                Err(error) => return Err(error).context("failed reading captured audio"),
+            }
+        }
+        block.truncate(filled - filled % 2);
+        Ok(block)
+    }
+
+    fn is_running(&mut self) -> bool {
+        matches!(self.process.try_wait(), Ok(None))
+    }
+
+    fn drain_errors(&self) -> String {
+        self.errors.try_iter().collect::<Vec<_>>().join("\n")
+    }
+
+    /// Stops the process and returns whatever it wrote to stderr. The helper
+    /// gets `stop` on stdin and a grace period; ffmpeg is killed right away.
+    fn stop(mut self) -> String {
+        if let Some(mut stdin) = self.process.stdin.take() {
+            let _ = stdin.write_all(b"stop\n");
+            let deadline = Instant::now() + STOP_GRACE;
+            while matches!(self.process.try_wait(), Ok(None)) && Instant::now() < deadline {
+                thread::sleep(Duration::from_millis(50));
+            }
+        }
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+        if let Some(mut upstream) = self.upstream.take() {
+            let _ = upstream.kill();
+            let _ = upstream.wait();
+        }
+        self.drain_errors()
+    }
+}
+
+fn forward_lines(stderr: Option<impl Read + Send + 'static>, lines: mpsc::Sender<String>) {
+    let Some(stderr) = stderr else { return };
+    thread::spawn(move || {
+        for line in BufReader::new(stderr).lines() {
+            let Ok(line) = line else { break };
+            let line = line.trim();
+            if !line.is_empty() && lines.send(line.to_string()).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+/// 16 kHz mono WAV, written as the session goes; the sizes in the header
+/// are filled in by `finish`.
+struct WavWriter {
+    file: BufWriter<File>,
+    data_bytes: u32,
+}
+
+impl WavWriter {
+    fn create(path: &Path) -> anyhow::Result<Self> {
+        let mut file = BufWriter::new(
+            File::create(path).with_context(|| format!("failed creating {}", path.display()))?,
+        );
+        file.write_all(&wav_header(0))?;
+        Ok(Self {
+            file,
+            data_bytes: 0,
+        })
+    }
+
+    fn write(&mut self, samples: &[f32]) -> anyhow::Result<()> {
+        let pcm = samples
+            .iter()
+            .flat_map(|sample| to_i16(*sample).to_le_bytes())
+            .collect::<Vec<_>>();
+        self.file.write_all(&pcm)?;
+        self.data_bytes = self.data_bytes.saturating_add(pcm.len() as u32);
+        Ok(())
+    }
+
+    fn finish(mut self) -> anyhow::Result<()> {
+        self.file.seek(SeekFrom::Start(0))?;
+        self.file.write_all(&wav_header(self.data_bytes))?;
+        self.file.flush()?;
+        Ok(())
+    }
+}
+
+fn wav_header(data_bytes: u32) -> Vec<u8> {
+    let byte_rate = BYTES_PER_SECOND as u32;
+    let mut header = Vec::with_capacity(44);
+    header.extend_from_slice(b"RIFF");
+    header.extend_from_slice(&(36 + data_bytes).to_le_bytes());
+    header.extend_from_slice(b"WAVEfmt ");
+    header.extend_from_slice(&16u32.to_le_bytes());
+    header.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    header.extend_from_slice(&1u16.to_le_bytes()); // mono
+    header.extend_from_slice(&(SAMPLE_RATE as u32).to_le_bytes());
+    header.extend_from_slice(&byte_rate.to_le_bytes());
+    header.extend_from_slice(&2u16.to_le_bytes()); // block align
+    header.extend_from_slice(&16u16.to_le_bytes()); // bits per sample
+    header.extend_from_slice(b"data");
+    header.extend_from_slice(&data_bytes.to_le_bytes());
+    header
+}
+
+/// The saved microphone name, or the best-scoring input when it is missing
+/// or not connected. `none` records without a microphone.
+fn choose_mic(preferred: Option<&str>) -> Option<String> {
+    let preferred = preferred
+        .map(str::to_string)
+        .or_else(|| std::env::var("WHISPERBAR_MIC_DEVICE").ok())
+        .filter(|name| !name.trim().is_empty());
+    if preferred
+        .as_deref()
+        .is_some_and(|name| matches!(name.trim().to_lowercase().as_str(), "none" | "__none__"))
+    {
+        return None;
+    }
+
+    if let Some(name) = preferred.as_deref().and_then(resolve_mic) {
+        return Some(name);
+    }
+    let devices = audio::list_audio_devices().ok()?;
+    audio::choose_default_mic(&devices).and_then(|id| audio::capture_name(&devices, &id))
+}
+
+/// Matches a device name, or part of one, against the connected inputs.
+fn resolve_mic(requested: &str) -> Option<String> {
+    let lowered = requested.trim().to_lowercase();
+    if lowered.is_empty() {
+        return None;
+    }
+    let devices = audio::list_audio_devices().ok()?;
+    devices
+        .iter()
+        .find(|device| device.name.to_lowercase() == lowered)
+        .or_else(|| {
+            devices
+                .iter()
+                .find(|device| device.name.to_lowercase().contains(&lowered))
+        })
+        .map(|device| device.name.clone())
+}
+
+fn mic_filters(profile: &DeviceProfile) -> Vec<String> {
+    let mut filters = Vec::new();
+    if let Some(channel) = profile.channel.filter(|channel| *channel >= 1) {
+        // Pick one input channel instead of downmixing all of them.
+        filters.push(format!("pan=mono|c0=c{}", channel - 1));
+    }
+    if profile.noise_suppression {
+        filters.push("afftdn".to_string());
+    }
+    if profile.gain_db != 0.0 {
+        filters.push(format!("volume={}dB", profile.gain_db));
+    }
+    filters
+}
+
+fn resolve_ffmpeg() -> PathBuf {
+    let explicit = std::env::var("WHISPERBAR_FFMPEG_PATH")
+        .ok()
+        .filter(|path| !path.trim().is_empty())
+        .map(PathBuf::from);
+    let in_path = std::env::var_os("PATH").and_then(|paths| {
+        std::env::split_paths(&paths)
+            .map(|dir| dir.join("ffmpeg"))
+            .find(|candidate| candidate.is_file())
+    });
+
+    explicit
+        .into_iter()
+        .chain(in_path)
+        .chain(
+            ["/opt/homebrew/bin/ffmpeg", "/usr/local/bin/ffmpeg"]
+                .into_iter()
+                .map(PathBuf::from),
+        )
+        .find(|candidate| candidate.is_file())
+        .unwrap_or_else(|| PathBuf::from("ffmpeg"))
+}
+
+fn normalize_language(language: &str) -> &'static str {
+    let normalized = language.trim().to_lowercase().replace('_', "-");
+    if normalized == "pt" || normalized == "ptbr" || normalized.starts_with("pt-") {
+        "pt"
+    } else {
+        "en"
+    }
+}
+
+fn samples(pcm: &[u8]) -> Vec<f32> {
+    pcm.chunks_exact(2)
+        .map(|pair| f32::from(i16::from_le_bytes([pair[0], pair[1]])) / 32768.0)
+        .collect()
+}
+
+/// Sums the two streams; both are already levelled by AGC.
+fn mix(first: &[f32], second: &[f32]) -> Vec<f32> {
+    let mut mixed = vec![0.0; first.len().max(second.len())];
+    for (index, sample) in mixed.iter_mut().enumerate() {
+        *sample = (first.get(index).copied().unwrap_or_default()
+            + second.get(index).copied().unwrap_or_default())
+        .clamp(-1.0, 1.0);
+    }
+    mixed
+}
+
+fn to_i16(sample: f32) -> i16 {
+    (sample * 32767.0)
+        .round()
+        .clamp(f32::from(i16::MIN), f32::from(i16::MAX)) as i16
+}
+
+fn rms(audio: &[f32]) -> f32 {
+    if audio.is_empty() {
+        return 0.0;
+    }
+    (audio.iter().map(|sample| sample * sample).sum::<f32>() / audio.len() as f32).sqrt()
+}
+
+fn level(pcm: &[u8]) -> LevelReading {
+    let audio = samples(pcm);
+    LevelReading {
+        rms: rms(&audio),
+        peak: audio
+            .iter()
+            .fold(0.0, |peak, sample| peak.max(sample.abs())),
+    }
+}
+
+fn round2(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+/// A stage time for the `timings` of a partial.
+fn millis(elapsed: Duration) -> f64 {
+    (elapsed.as_secs_f64() * 10_000.0).round() / 10.0
+}
+
+fn emit_status(message: &str) {
+    emit(json!({ "type": "status", "message": message }));
+}
+
+fn emit(event: Value) {
+    let mut stdout = io::stdout().lock();
+    let _ = writeln!(stdout, "{event}");
+    let _ = stdout.flush();
+}
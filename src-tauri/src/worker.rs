@@ -1,70 +1,605 @@
 use std::{
     fs,
+    path::Path,
     process::Stdio,
     time::{Duration, Instant},
 };
 
 use anyhow::{anyhow, Context};
-use serde::Deserialize;
-use tauri::AppHandle;
+use chrono::{Local, Utc};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
 use tokio::{
-    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    io::{AsyncBufReadExt, BufReader},
     process::Command,
     time::timeout,
 };
+use tracing::Instrument;
 
 use crate::{
-    app_state::{emit_state, update_state, AppStatus, SharedState, WorkerProcess},
-    runtime_scripts, transcript_file, ui,
+    acronyms,
+    app_state::{
+        emit_state, update_session, AppStatus, MuteInterval, RunningEngine, SessionNote,
+        SessionState, SettingsState, SharedState, TranscriptSegment, WordTiming,
+    },
+    audio::{self, AudioLevel, AudioSource, LevelReading, AUDIO_LEVEL_EVENT},
+    casing,
+    dictation::{self, DictationOutput},
+    engine::{self, EngineEvent, EngineInput, SessionOptions},
+    errors::{self, ErrorKind},
+    glossary::{self, CalendarEvent, MeetingEndAction},
+    hallucinations, history, integrity,
+    links::{self, AutoLinkSink},
+    memory,
+    models::{self, ModelBackend},
+    normalize,
+    partials::{self, PartialCoalescer},
+    pipeline_timings::{self, WorkerTimings},
+    postprocess, preroll, recording_audio, recovery, replacements, runtime_scripts, text_cleanup,
+    titles,
+    transcript_file::{self, Title},
+    tray, ui, worker_log,
 };
 
+/// Returned by `start_recording` while the reviewed transcript has edits that
+/// were never saved. Call `discard_unsaved_transcript` (or save) to proceed.
+#[derive(Debug)]
+pub struct UnsavedTranscript;
+
+impl std::fmt::Display for UnsavedTranscript {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("unsaved transcript")
+    }
+}
+
+impl std::error::Error for UnsavedTranscript {}
+
+const NO_SPEECH_MESSAGE: &str =
+    "No speech was captured. Check microphone permission and audio input device.";
+
+/// Returned by `stop_recording` when the session produced no text. Already
+/// reported as a warning, so callers should not escalate it.
+#[derive(Debug)]
+pub struct NoSpeechCaptured;
+
+impl std::fmt::Display for NoSpeechCaptured {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(NO_SPEECH_MESSAGE)
+    }
+}
+
+impl std::error::Error for NoSpeechCaptured {}
+
+/// How a failed `start_recording` is reported: `None` for
+/// `UnsavedTranscript`, which is not a failure (the UI asks to save or
+/// discard first), otherwise the error kind.
+pub fn start_error_kind(error: &anyhow::Error) -> Option<ErrorKind> {
+    if error.is::<UnsavedTranscript>() {
+        None
+    } else if error.is::<integrity::ModelCorrupted>() {
+        Some(ErrorKind::ModelCorrupted)
+    } else {
+        Some(ErrorKind::Recording)
+    }
+}
+
+/// How a failed `stop_recording` is reported: `None` for `NoSpeechCaptured`,
+/// already reported as a warning.
+pub fn stop_error_kind(error: &anyhow::Error) -> Option<ErrorKind> {
+    (!error.is::<NoSpeechCaptured>()).then_some(ErrorKind::Recording)
+}
+
+/// What `stop_recording` saved.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingResult {
+    pub transcript_path: String,
+    /// The session WAV next to the transcript, when `keep_audio` is on.
+    pub audio_path: Option<String>,
+}
+
+/// One JSON line from `worker.py`'s stdout.
 #[derive(Debug, Deserialize)]
-struct WorkerEvent {
+pub struct WorkerEvent {
     #[serde(rename = "type")]
-    event_type: String,
-    text: Option<String>,
-    message: Option<String>,
+    pub event_type: String,
+    pub text: Option<String>,
+    pub message: Option<String>,
+    pub speaker: Option<u32>,
+    pub start: Option<f64>,
+    pub end: Option<f64>,
+    pub mic: Option<LevelReading>,
+    pub desktop: Option<LevelReading>,
+    pub source: Option<AudioSource>,
+    pub muted: Option<bool>,
+    /// Session time in seconds the event refers to.
+    pub at: Option<f64>,
+    /// Capture name of the microphone a `mic_failed` event is about.
+    pub device: Option<String>,
+    /// Set on a `partial` that follows a long pause.
+    pub paragraph: Option<bool>,
+    /// Set on a `music` event: whether desktop audio is now music only.
+    pub active: Option<bool>,
+    /// Per-word timing of a `partial`, with `--word-timestamps`.
+    pub words: Option<Vec<WordTiming>>,
+    /// Worker-side stage times of a `partial`.
+    pub timings: Option<WorkerTimings>,
+}
+
+/// Delay used by the tray "Record in 5 Seconds" action.
+pub const QUICK_RECORD_DELAY_SECS: u32 = 5;
+
+/// `quick_dictation` length when the caller gives none, and the most it allows.
+pub const QUICK_DICTATION_DEFAULT_SECONDS: u32 = 30;
+pub const QUICK_DICTATION_MAX_SECONDS: u32 = 300;
+
+/// Quiet after speech that ends a quick dictation. Speech is reported per
+/// worker chunk (2.8 s), so this has to cover a full chunk without speech.
+const QUICK_DICTATION_SILENCE: Duration = Duration::from_secs(4);
+
+const QUICK_DICTATION_POLL: Duration = Duration::from_millis(250);
+
+/// How often the session watchdog checks silence and recording length.
+const WATCHDOG_INTERVAL: Duration = Duration::from_secs(15);
+
+/// How long before `max_recording_minutes` the user is warned.
+const DURATION_WARNING_LEAD: Duration = Duration::from_secs(5 * 60);
+
+/// Starts recording after a visible countdown. Each tick is published through
+/// the state event; `cancel_countdown` (or a manual start) aborts it.
+pub async fn start_recording_after(
+    app: &AppHandle,
+    state: &SharedState,
+    seconds: u32,
+) -> anyhow::Result<()> {
+    state.paths.ensure_writable()?;
+    if state.transcript.lock().await.dirty {
+        return Err(UnsavedTranscript.into());
+    }
+
+    {
+        let session = state.session.lock().await;
+        if session.countdown.is_some() {
+            return Err(anyhow!("a recording countdown is already running"));
+        }
+        if !matches!(session.status, AppStatus::Ready | AppStatus::Idle) {
+            return Err(anyhow!("recording cannot start right now"));
+        }
+    }
+
+    let app_task = app.clone();
+    let state_task = state.clone();
+    let countdown = tauri::async_runtime::spawn(async move {
+        for remaining in (1..=seconds).rev() {
+            update_session(&app_task, &state_task, move |session| {
+                session.countdown_seconds = Some(remaining);
+                session.status_message = format!("Recording in {remaining}s");
+            })
+            .await;
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+
+        // Checked in the same step that ends the countdown: an install or a
+        // re-transcription that began meanwhile keeps the session from starting.
+        let ready = {
+            let mut session = state_task.session.lock().await;
+            session.countdown = None;
+            session.countdown_seconds = None;
+            matches!(session.status, AppStatus::Ready | AppStatus::Idle)
+        };
+        if !ready {
+            emit_state(&app_task, &state_task).await;
+            return;
+        }
+
+        let Err(error) = start_recording(&app_task, &state_task, DictationOutput::Off, None).await
+        else {
+            return;
+        };
+        if let Some(kind) = start_error_kind(&error) {
+            errors::report(&app_task, &state_task, kind, error.to_string(), |session| {
+                session.status = AppStatus::Error;
+                session.status_message = "Error".to_string();
+            })
+            .await;
+        }
+    });
+
+    state.session.lock().await.countdown = Some(countdown);
+    Ok(())
+}
+
+pub async fn cancel_countdown(app: &AppHandle, state: &SharedState) -> anyhow::Result<()> {
+    if !abort_countdown(state).await {
+        return Err(anyhow!("no recording countdown is running"));
+    }
+    emit_state(app, state).await;
+    Ok(())
+}
+
+/// Aborts a pending countdown. Returns `false` when none was running.
+async fn abort_countdown(state: &SharedState) -> bool {
+    let mut session = state.session.lock().await;
+    let Some(countdown) = session.countdown.take() else {
+        return false;
+    };
+
+    countdown.abort();
+    session.countdown_seconds = None;
+    if session.status == AppStatus::Ready {
+        session.status_message = "Ready".to_string();
+    }
+    true
 }
 
-pub async fn start_recording(app: &AppHandle, state: &SharedState) -> anyhow::Result<()> {
+pub async fn start_recording(
+    app: &AppHandle,
+    state: &SharedState,
+    dictation: DictationOutput,
+    calendar_event: Option<CalendarEvent>,
+) -> anyhow::Result<()> {
+    state.paths.ensure_writable()?;
     runtime_scripts::ensure_scripts(state).await?;
 
-    let (
-        venv_python,
-        worker_script,
-        selected_mic_device,
-    ) = {
-        let guard = state.0.lock().await;
+    if dictation == DictationOutput::Paste && !dictation::accessibility_trusted() {
+        return Err(anyhow!(dictation::PERMISSION_MESSAGE));
+    }
 
-        if guard.status == AppStatus::Recording {
+    if state.transcript.lock().await.dirty {
+        return Err(UnsavedTranscript.into());
+    }
+
+    // A manual start supersedes a pending quick-record countdown, and the
+    // microphone is needed for the session rather than a calibration.
+    abort_countdown(state).await;
+    calibration::cancel(state).await;
+
+    {
+        let status = state.status().await;
+
+        if status == AppStatus::Recording {
             return Err(anyhow!("recording is already active"));
         }
 
-        if !matches!(guard.status, AppStatus::Ready | AppStatus::Idle) {
+        if status == AppStatus::Installing {
+            return Err(anyhow!(
+                "a model is being installed; recording can start once it finishes"
+            ));
+        }
+
+        if status == AppStatus::Transcribing {
+            return Err(anyhow!(
+                "a recording is being transcribed again; recording can start once it finishes"
+            ));
+        }
+
+        if !matches!(status, AppStatus::Ready | AppStatus::Idle) {
             return Err(anyhow!(
                 "dependencies are not ready yet. wait for installation to finish"
             ));
         }
+    }
 
+    let (mut cleanup, partial_updates_per_second, keep_audio, selected_model_id) = {
+        let settings = state.settings.lock().await;
         (
-            guard.venv_python.clone(),
-            guard.worker_script.clone(),
-            guard.selected_mic_device.clone(),
+            SegmentCleanup::new(&settings),
+            settings.partial_updates_per_second,
+            settings.keep_audio,
+            settings.selected_model_id.clone(),
         )
     };
 
-    if !venv_python.exists() {
-        return Err(anyhow!(
-            "Python environment is missing. Retry dependency installation"
-        ));
+    // Checked again in `worker_command`; here so a warm worker loaded before
+    // the policy is not reused either.
+    state.managed.check_model(&selected_model_id)?;
+
+    // Refuse a model this Mac cannot hold instead of letting the worker run
+    // out of memory minutes into the session.
+    let memory_warning = match models::find_model(&selected_model_id) {
+        Some(model) => memory::preflight(&model)?,
+        None => None,
+    };
+
+    let meeting_end = match &calendar_event {
+        Some(event) => event.end_time()?,
+        None => None,
+    };
+
+    let audio_path = if keep_audio {
+        Some(recording_audio::session_path(&state.paths)?)
+    } else {
+        None
+    };
+    let send_preroll = preroll::is_active(state).await;
+
+    let (engine, mut events) = engine::start(
+        state,
+        SessionOptions {
+            // Names from the linked meeting bias recognition toward how they are spelled.
+            initial_prompt: calendar_event.as_ref().and_then(glossary::initial_prompt),
+            audio_path: audio_path.clone(),
+            preroll: send_preroll,
+        },
+    )
+    .await?;
+
+    let app_events = app.clone();
+    let state_events = state.clone();
+    let events_task = tauri::async_runtime::spawn(async move {
+        let mut partials = PartialCoalescer::new(partial_updates_per_second);
+
+        loop {
+            let deadline = partials.deadline();
+            tokio::select! {
+                event = events.recv() => match event {
+                    Some(EngineEvent::Event(event)) => {
+                        let span = if event.event_type == "partial" {
+                            tracing::info_span!("pipeline.handle_event")
+                        } else {
+                            tracing::Span::none()
+                        };
+                        handle_worker_event(&app_events, &state_events, &mut partials, event, send_preroll, &mut cleanup)
+                            .instrument(span)
+                            .await;
+                    }
+                    Some(EngineEvent::Log(line)) => {
+                        worker_log::append(&state_events.paths, &line).await;
+                        update_session(&app_events, &state_events, |session| {
+                            if session.status == AppStatus::Recording {
+                                session.status_message = format!("Recording ({line})");
+                            }
+                        })
+                        .await;
+                    }
+                    None => break,
+                },
+                _ = partials::wait_for_deadline(deadline) => {
+                    partials.flush(&app_events, &state_events).await;
+                }
+            }
+        }
+
+        partials.flush(&app_events, &state_events).await;
+    });
+
+    {
+        let mut transcript = state.transcript.lock().await;
+        transcript.segments.clear();
+        transcript.partial_metrics = Default::default();
+        transcript.mute_intervals.clear();
+        transcript.notes.clear();
+        transcript.edits = Default::default();
+    }
+    pipeline_timings::reset();
+
+    let started_at = Instant::now();
+    let autosave_path = recovery::spawn_autosave(state, started_at);
+    update_session(app, state, |session| {
+        session.worker = Some(RunningEngine {
+            engine,
+            events_task: Some(events_task),
+        });
+        session.status = AppStatus::Recording;
+        session.status_message = "Recording".to_string();
+        session.reset_error();
+        session.last_saved_path = None;
+        session.last_audio_path = None;
+        session.audio_path = audio_path;
+        session.autosave_path = Some(autosave_path);
+        session.dictation = dictation;
+        session.recording_started_at = Some(started_at);
+        session.recording_started_local = Some(Local::now());
+        session.last_speech_at = None;
+        session.muted_source = None;
+        session.mic_muted = false;
+        session.music_gated = false;
+        session.meeting_end = meeting_end;
+        session.failed_mics.clear();
+    })
+    .await;
+
+    spawn_session_watchdog(app, state, started_at);
+    spawn_elapsed_ticker(app, state, started_at);
+
+    ui::hide_tray_window(app);
+    // Dictation keeps focus on the app the text will be pasted into; the live
+    // window is still reachable through `reopen_live_window`.
+    if dictation == DictationOutput::Off {
+        let placement = state.settings.lock().await.floating_placement;
+        ui::ensure_floating_window(app, placement)?;
     }
 
-    let (venv_python, model_path, language) = {
-        let guard = state.0.lock().await;
+    emit_state(app, state).await;
+
+    if let Some(message) = memory_warning {
+        errors::report(app, state, ErrorKind::LowMemory, message, |_| {}).await;
+    }
+
+    Ok(())
+}
+
+/// Publishes the elapsed time of the session started at `started_at` once a
+/// second, through the state event and the menu bar title, until it ends.
+fn spawn_elapsed_ticker(app: &AppHandle, state: &SharedState, started_at: Instant) {
+    let app = app.clone();
+    let state = state.clone();
+
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(1));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+        loop {
+            interval.tick().await;
+
+            let recording = {
+                let session = state.session.lock().await;
+                session.status == AppStatus::Recording
+                    && session.recording_started_at == Some(started_at)
+            };
+            if !recording {
+                tray::set_recording_timer(&app, None);
+                return;
+            }
+
+            tray::set_recording_timer(&app, Some(started_at.elapsed().as_secs()));
+            emit_state(&app, &state).await;
+        }
+    });
+}
+
+/// Stops the session started at `started_at` once no speech has been reported
+/// for `auto_stop_silence_minutes`, once it reaches `max_recording_minutes`
+/// (with a warning `DURATION_WARNING_LEAD` ahead), or, per
+/// `meeting_end_action`, once its calendar event ended
+/// `meeting_end_grace_minutes` ago. Settings are re-read on every check, so
+/// changing them mid-recording applies right away. Exits when that session
+/// ends.
+fn spawn_session_watchdog(app: &AppHandle, state: &SharedState, started_at: Instant) {
+    let app = app.clone();
+    let state = state.clone();
+
+    tauri::async_runtime::spawn(async move {
+        let mut warned = false;
+        let mut prompted = false;
+
+        loop {
+            tokio::time::sleep(WATCHDOG_INTERVAL).await;
+
+            let (silence_minutes, max_minutes, meeting_action, grace_minutes) = {
+                let settings = state.settings.lock().await;
+                (
+                    settings.auto_stop_silence_minutes,
+                    settings.max_recording_minutes,
+                    settings.meeting_end_action,
+                    settings.meeting_end_grace_minutes,
+                )
+            };
+            let (silent_for, meeting_end) = {
+                let session = state.session.lock().await;
+                if session.status != AppStatus::Recording
+                    || session.recording_started_at != Some(started_at)
+                {
+                    return;
+                }
+                (
+                    session.last_speech_at.unwrap_or(started_at).elapsed(),
+                    session.meeting_end,
+                )
+            };
+            let meeting_over = meeting_end.is_some_and(|end| {
+                Utc::now() >= end + chrono::Duration::minutes(i64::from(grace_minutes))
+            });
+
+            let limit = Duration::from_secs(u64::from(max_minutes) * 60);
+            let reason = if max_minutes > 0 && started_at.elapsed() >= limit {
+                format!("Stopped at the {max_minutes} min recording limit")
+            } else if silence_minutes > 0
+                && silent_for >= Duration::from_secs(u64::from(silence_minutes) * 60)
+            {
+                format!("Stopped after {silence_minutes} min of silence")
+            } else if meeting_over && meeting_action == MeetingEndAction::Stop {
+                format!("Stopped {grace_minutes} min after the meeting ended")
+            } else {
+                if meeting_over && meeting_action == MeetingEndAction::Prompt && !prompted {
+                    prompted = true;
+                    errors::report(
+                        &app,
+                        &state,
+                        ErrorKind::MeetingEnded,
+                        "The linked meeting has ended. Stop recording?".to_string(),
+                        |_| {},
+                    )
+                    .await;
+                }
+                if max_minutes > 0
+                    && !warned
+                    && started_at.elapsed() + DURATION_WARNING_LEAD >= limit
+                {
+                    warned = true;
+                    let remaining = limit.saturating_sub(started_at.elapsed()).as_secs() / 60 + 1;
+                    errors::report(
+                        &app,
+                        &state,
+                        ErrorKind::DurationLimit,
+                        format!(
+                            "Recording will stop and save in about {remaining} min \
+                             ({max_minutes} min limit)."
+                        ),
+                        |_| {},
+                    )
+                    .await;
+                }
+                continue;
+            };
+
+            match stop_recording(&app, &state).await {
+                Ok(_) => {
+                    update_session(&app, &state, move |session| {
+                        if session.status == AppStatus::Ready {
+                            session.status_message = reason;
+                        }
+                    })
+                    .await;
+                }
+                // Already surfaced as a warning by `stop_recording`.
+                Err(error) if error.is::<NoSpeechCaptured>() => {}
+                Err(error) => {
+                    errors::report(
+                        &app,
+                        &state,
+                        ErrorKind::Recording,
+                        error.to_string(),
+                        |session| {
+                            session.status = AppStatus::Error;
+                            session.status_message = "Error".to_string();
+                        },
+                    )
+                    .await;
+                }
+            }
+            return;
+        }
+    });
+}
+
+/// The worker invocation shared by the app and `whisperbar record`: model,
+/// language, capture sources and microphone tuning from the saved settings,
+/// with every stdio stream piped. whisper.cpp models run in this executable
+/// (`--native-worker`), which takes the same arguments as the Python worker.
+pub async fn worker_command(state: &SharedState) -> anyhow::Result<Command> {
+    let (
+        backend,
+        model_path,
+        language,
+        selected_mic_device,
+        system_audio,
+        diarize,
+        separate_sources,
+        resample_quality,
+        noise_suppression,
+        voice_activity_detection,
+        skip_music,
+        word_timestamps,
+        device_profiles,
+    ) = {
+        let settings = state.settings.lock().await;
+        state.managed.check_model(&settings.selected_model_id)?;
         (
-            guard.venv_python.clone(),
-            guard.model_path.clone(),
-            guard.language.clone(),
+            models::backend(&settings.custom_models, &settings.selected_model_id)
+                .unwrap_or(ModelBackend::Mlx),
+            settings.model_path.clone(),
+            settings.language.clone(),
+            settings.selected_mic_device.clone(),
+            settings.system_audio_enabled,
+            settings.diarization_enabled && settings.diarization_available(),
+            settings.separate_sources,
+            settings.resample_quality,
+            settings.noise_suppression,
+            settings.voice_activity_detection,
+            settings.skip_music,
+            settings.word_timestamps,
+            serde_json::to_string(&settings.device_profiles)?,
         )
     };
 
@@ -73,101 +608,157 @@ pub async fn start_recording(app: &AppHandle, state: &SharedState) -> anyhow::Re
             "Selected model is not installed. Click Install Model first."
         ));
     }
+    // A truncated weights file otherwise fails deep inside the worker.
+    integrity::check_sizes(&model_path)?;
 
-    let mut command = Command::new(&venv_python);
+    let mut command = match backend {
+        ModelBackend::Mlx => {
+            let venv_python = state.install.lock().await.venv_python.clone();
+            if !venv_python.exists() {
+                return Err(anyhow!(
+                    "Python environment is missing. Retry dependency installation"
+                ));
+            }
+            let mut command = Command::new(&venv_python);
+            command.arg(&state.paths.worker_script);
+            command
+        }
+        ModelBackend::WhisperCpp => {
+            let exe_path =
+                std::env::current_exe().context("unable to locate the app executable")?;
+            let mut command = Command::new(exe_path);
+            command.arg("--native-worker");
+            command
+        }
+    };
     command
-        .arg(&worker_script)
         .arg("--language")
-        .arg(language)
+        .arg(&language)
         .arg("--model-path")
         .arg(&model_path)
         .stdin(Stdio::piped())
         .stdout(Stdio::piped())
         .stderr(Stdio::piped());
 
+    if diarize {
+        command.arg("--diarize");
+    }
+
+    if separate_sources && system_audio {
+        command.arg("--separate-sources");
+    }
+
+    command
+        .arg("--resample-quality")
+        .arg(resample_quality.as_arg());
+
+    // The filter runs as `--denoise-filter` of the helper executable below.
+    if noise_suppression {
+        command.arg("--denoise");
+    }
+
+    // Likewise `--vad-filter`.
+    if voice_activity_detection {
+        command.arg("--vad");
+    }
+
+    if !system_audio {
+        command.arg("--no-system-audio");
+    } else if skip_music {
+        command.arg("--skip-music");
+    }
+
+    if word_timestamps {
+        command.arg("--word-timestamps");
+    }
+
+    // The worker resolves the actual microphone (including "Auto"), so it
+    // picks the matching profile itself.
+    command.arg("--device-profiles").arg(device_profiles);
+
     if let Ok(exe_path) = std::env::current_exe() {
         command.arg("--sck-helper-path").arg(exe_path);
     }
 
-    if let Ok(audio_device) = std::env::var("WHISPERBAR_AUDIO_DEVICE") {
-        if !audio_device.trim().is_empty() {
-            command.arg("--audio-device").arg(audio_device);
-        }
-    }
-    if let Some(mic_device) = selected_mic_device {
-        if !mic_device.trim().is_empty() {
-            command.arg("--mic-device").arg(mic_device);
-        }
-    } else if let Ok(mic_device) = std::env::var("WHISPERBAR_MIC_DEVICE") {
-        if !mic_device.trim().is_empty() {
-            command.arg("--mic-device").arg(mic_device);
-        }
+    // Settings hold the CoreAudio UID; ffmpeg in the worker opens devices by
+    // name. A UID that is no longer connected falls back to the worker's pick.
+    let mic_device = match selected_mic_device {
+        Some(id) => audio::list_audio_devices()
+            .ok()
+            .and_then(|devices| audio::capture_name(&devices, &id)),
+        None => std::env::var("WHISPERBAR_MIC_DEVICE").ok(),
+    };
+    if let Some(mic_device) = mic_device.filter(|device| !device.trim().is_empty()) {
+        command.arg("--mic-device").arg(mic_device);
     }
-    let mut child = command
-        .spawn()
-        .with_context(|| format!("failed starting worker with {}", venv_python.display()))?;
 
+    Ok(command)
+}
+
+/// Runs the selected model over a kept recording (`--transcribe-file`) and
+/// returns its segments with the same cleanup as a live session. Lines the
+/// hallucination filter catches are dropped without a warning.
+pub async fn transcribe_recording(
+    state: &SharedState,
+    audio: &Path,
+) -> anyhow::Result<Vec<TranscriptSegment>> {
+    let mut cleanup = SegmentCleanup::new(&*state.settings.lock().await);
+
+    let mut command = worker_command(state).await?;
+    command
+        .arg("--transcribe-file")
+        .arg(audio)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true);
+    let mut child = command.spawn().context("failed starting worker")?;
     let stdout = child
         .stdout
         .take()
         .context("unable to capture worker stdout")?;
-    let stderr = child
-        .stderr
-        .take()
-        .context("unable to capture worker stderr")?;
-    let stdin = child.stdin.take();
-
-    let app_stdout = app.clone();
-    let state_stdout = state.clone();
-    let stdout_task = tauri::async_runtime::spawn(async move {
-        let mut lines = BufReader::new(stdout).lines();
-
-        while let Ok(Some(line)) = lines.next_line().await {
-            handle_worker_event(&app_stdout, &state_stdout, &line).await;
-        }
-    });
+    let mut lines = BufReader::new(stdout).lines();
 
-    let app_stderr = app.clone();
-    let state_stderr = state.clone();
-    let stderr_task = tauri::async_runtime::spawn(async move {
-        let mut lines = BufReader::new(stderr).lines();
-
-        while let Ok(Some(line)) = lines.next_line().await {
-            if !line.trim().is_empty() {
-                update_state(&app_stderr, &state_stderr, |inner| {
-                    if inner.status == AppStatus::Recording {
-                        inner.status_message = format!("Recording ({line})");
-                    }
-                })
-                .await;
+    let mut segments = Vec::new();
+    let mut failure = None;
+    while let Some(line) = lines.next_line().await? {
+        let Ok(event) = serde_json::from_str::<WorkerEvent>(&line) else {
+            continue;
+        };
+        match event.event_type.as_str() {
+            "partial" => {
+                let Some(text) = event.text else { continue };
+                let segment = TranscriptSegment {
+                    text: cleanup.text(&text),
+                    speaker: event.speaker,
+                    source: event.source,
+                    paragraph: event.paragraph.unwrap_or(false),
+                    start: event.start,
+                    end: event.end,
+                    words: event.words.unwrap_or_default(),
+                };
+                if !segment.text.is_empty() && cleanup.hallucination(&segment).is_none() {
+                    segments.push(segment);
+                }
             }
+            "error" => failure = event.message,
+            _ => {}
         }
-    });
-
-    update_state(app, state, |inner| {
-        inner.worker = Some(WorkerProcess {
-            child,
-            stdin,
-            stdout_task: Some(stdout_task),
-            stderr_task: Some(stderr_task),
-        });
-        inner.status = AppStatus::Recording;
-        inner.status_message = "Recording".to_string();
-        inner.error_message = None;
-        inner.last_saved_path = None;
-        inner.transcript.clear();
-    })
-    .await;
-
-    ui::hide_tray_window(app);
-    ui::ensure_floating_window(app)?;
-
-    emit_state(app, state).await;
+    }
 
-    Ok(())
+    let status = child.wait().await?;
+    if let Some(message) = failure {
+        return Err(anyhow!(message));
+    }
+    if !status.success() {
+        return Err(anyhow!("the worker stopped before finishing the recording"));
+    }
+    Ok(segments)
 }
 
-fn model_ready(model_path: &std::path::Path) -> bool {
+fn model_ready(model_path: &Path) -> bool {
+    if model_path.join(models::GGML_MODEL_FILE).is_file() {
+        return true;
+    }
     if !model_path.exists() || !model_path.join("config.json").exists() {
         return false;
     }
@@ -180,48 +771,216 @@ fn model_ready(model_path: &std::path::Path) -> bool {
         .any(|name| name.starts_with("weights.") || name.starts_with("model"))
 }
 
-async fn handle_worker_event(app: &AppHandle, state: &SharedState, line: &str) {
-    let Ok(event) = serde_json::from_str::<WorkerEvent>(line) else {
+/// Turns the worker's text into transcript text for one session.
+/// The text cleanup every transcribed segment goes through, shared by live
+/// sessions, re-transcription and `whisperbar record`.
+pub(crate) struct SegmentCleanup {
+    casing: casing::Restorer,
+    language: String,
+    remove_fillers: bool,
+    acronyms: acronyms::Expander,
+    replacements: replacements::Replacer,
+    /// `None` with `hallucination_filter` off.
+    hallucinations: Option<hallucinations::Filter>,
+    /// The session already showed its `hallucination` warning.
+    warned: bool,
+}
+
+impl SegmentCleanup {
+    pub(crate) fn new(settings: &SettingsState) -> Self {
+        Self {
+            casing: casing::Restorer::new(&settings.language),
+            language: settings.language.clone(),
+            remove_fillers: settings.remove_fillers,
+            acronyms: settings.acronym_expander(),
+            replacements: settings.replacer(),
+            hallucinations: settings
+                .hallucination_filter
+                .then(hallucinations::Filter::default),
+            warned: false,
+        }
+    }
+
+    pub(crate) fn text(&mut self, text: &str) -> String {
+        let text = self.casing.apply(text);
+        let text = normalize::apply(&text, &self.language);
+        let text = match self.remove_fillers {
+            true => text_cleanup::remove_fillers(&text, &self.language),
+            false => text,
+        };
+        self.replacements.apply(&self.acronyms.apply(&text))
+    }
+
+    pub(crate) fn hallucination(
+        &mut self,
+        segment: &TranscriptSegment,
+    ) -> Option<hallucinations::Reason> {
+        self.hallucinations.as_mut()?.check(segment)
+    }
+}
+
+/// Counts a dropped segment and, the first time in a session, says what was
+/// dropped and why.
+async fn suppress_segment(
+    app: &AppHandle,
+    state: &SharedState,
+    cleanup: &mut SegmentCleanup,
+    segment: &TranscriptSegment,
+    reason: hallucinations::Reason,
+) {
+    state
+        .transcript
+        .lock()
+        .await
+        .partial_metrics
+        .segments_suppressed += 1;
+    if std::mem::replace(&mut cleanup.warned, true) {
         return;
-    };
+    }
+    let message = format!(
+        "Dropped “{}”: it looked like {}. Turn off the hallucination filter to keep lines like it.",
+        segment.text.trim(),
+        reason.describe()
+    );
+    errors::report(app, state, ErrorKind::Hallucination, message, |_| {}).await;
+}
+
+async fn handle_worker_event(
+    app: &AppHandle,
+    state: &SharedState,
+    partials: &mut PartialCoalescer,
+    event: WorkerEvent,
+    send_preroll: bool,
+    cleanup: &mut SegmentCleanup,
+) {
+    if event.event_type == "partial" {
+        if let Some(text) = event.text {
+            let segment = TranscriptSegment {
+                text: cleanup.text(&text),
+                speaker: event.speaker,
+                source: event.source,
+                paragraph: event.paragraph.unwrap_or(false),
+                start: event.start,
+                end: event.end,
+                words: event.words.unwrap_or_default(),
+            };
+            match cleanup.hallucination(&segment) {
+                Some(reason) => suppress_segment(app, state, cleanup, &segment, reason).await,
+                None => partials.push(segment),
+            }
+        }
+        return;
+    }
+
+    // Meter readings bypass the transcript entirely; no need to flush first.
+    if event.event_type == "level" {
+        let _ = app.emit(
+            AUDIO_LEVEL_EVENT,
+            AudioLevel {
+                mic: event.mic,
+                desktop: event.desktop,
+            },
+        );
+        return;
+    }
+
+    // Keep ordering intact: anything queued lands before the next event.
+    partials.flush(app, state).await;
 
     match event.event_type.as_str() {
-        "status" => {
-            if let Some(message) = event.message {
-                update_state(app, state, move |inner| {
-                    if inner.status == AppStatus::Recording {
-                        inner.status_message = message;
+        "speech" => {
+            state.session.lock().await.last_speech_at = Some(Instant::now());
+        }
+        "loopback" => {
+            let message = event.message.unwrap_or_else(|| {
+                "Desktop audio contains your microphone; speech may be transcribed twice."
+                    .to_string()
+            });
+            errors::report(app, state, ErrorKind::Loopback, message, |_| {}).await;
+        }
+        "mic_muted" => {
+            let muted = event.muted.unwrap_or(false);
+            let at = event.at.unwrap_or(0.0);
+            {
+                let mut transcript = state.transcript.lock().await;
+                let open = transcript
+                    .mute_intervals
+                    .last()
+                    .is_some_and(|mute| mute.end.is_none());
+                if muted && !open {
+                    transcript.mute_intervals.push(MuteInterval {
+                        start: at,
+                        end: None,
+                    });
+                } else if !muted && open {
+                    if let Some(mute) = transcript.mute_intervals.last_mut() {
+                        mute.end = Some(at);
                     }
-                })
-                .await;
+                }
             }
+            update_session(app, state, |session| {
+                session.mic_muted = muted;
+            })
+            .await;
         }
-        "partial" => {
-            if let Some(text) = event.text {
-                if text.trim().is_empty() {
-                    return;
+        "music" => {
+            let gated = event.active.unwrap_or(false);
+            update_session(app, state, |session| {
+                session.music_gated = gated;
+            })
+            .await;
+        }
+        "mic_failed" => {
+            if let Some(device) = event.device {
+                fall_back_from_microphone(app, state, device).await;
+            }
+        }
+        "muted" => {
+            update_session(app, state, |session| {
+                session.muted_source = event.source;
+                if session.error_kind == Some(ErrorKind::Loopback) {
+                    errors::dismiss(session);
+                }
+            })
+            .await;
+        }
+        "status" => {
+            if let Some(message) = event.message {
+                // Capture just started in the worker: hand over the pre-roll.
+                if send_preroll && message.starts_with("Listening") {
+                    preroll::send_to_worker(state).await;
                 }
 
-                update_state(app, state, move |inner| {
-                    if !inner.transcript.is_empty() {
-                        inner.transcript.push('\n');
-                    }
-                    inner.transcript.push_str(text.trim());
-                    if inner.status == AppStatus::Recording {
-                        inner.status_message = "Recording".to_string();
+                update_session(app, state, move |session| {
+                    if session.status == AppStatus::Recording {
+                        session.status_message = message;
                     }
                 })
                 .await;
             }
         }
         "final" => {
+            // Partials already carry every segment; the final text only
+            // matters when none made it through.
             if let Some(text) = event.text {
-                update_state(app, state, move |inner| {
-                    if !text.trim().is_empty() && text.len() > inner.transcript.len() {
-                        inner.transcript = text;
+                let segment = TranscriptSegment {
+                    text: cleanup.text(text.trim()),
+                    speaker: None,
+                    source: None,
+                    paragraph: false,
+                    start: None,
+                    end: None,
+                    words: Vec::new(),
+                };
+                // Whatever the filter dropped from the partials would come
+                // back here otherwise.
+                if cleanup.hallucination(&segment).is_none() {
+                    let mut transcript = state.transcript.lock().await;
+                    if !segment.text.is_empty() && !transcript.has_text() {
+                        transcript.segments = vec![segment];
                     }
-                })
-                .await;
+                }
+                emit_state(app, state).await;
             }
         }
         "error" => {
@@ -229,13 +988,16 @@ async fn handle_worker_event(app: &AppHandle, state: &SharedState, line: &str) {
                 .message
                 .unwrap_or_else(|| "Worker reported an unknown error".to_string());
 
-            update_state(app, state, move |inner| {
-                inner.status = AppStatus::Error;
-                inner.status_message = "Recording error".to_string();
-                inner.error_message = Some(message);
-                inner.worker = None;
+            errors::report(app, state, ErrorKind::Worker, message, |session| {
+                session.status = AppStatus::Error;
+                session.status_message = "Recording error".to_string();
+                session.worker = None;
+                session.recording_started_at = None;
+                session.mic_muted = false;
+                session.music_gated = false;
             })
             .await;
+            preroll::sync(state).await;
 
             ui::close_floating_window(app);
             ui::show_tray_window(app);
@@ -244,39 +1006,208 @@ async fn handle_worker_event(app: &AppHandle, state: &SharedState, line: &str) {
     }
 }
 
-pub async fn stop_recording(app: &AppHandle, state: &SharedState) -> anyhow::Result<String> {
-    let mut worker = {
-        let mut guard = state.0.lock().await;
-        if guard.status != AppStatus::Recording {
-            return Err(anyhow!("recording is not active"));
+/// Moves the session off a microphone that failed to open or stopped
+/// delivering audio: back to the selected microphone when that is not the
+/// one that failed, otherwise to the best remaining device. Without one,
+/// the worker keeps going on desktop audio or gives up.
+async fn fall_back_from_microphone(app: &AppHandle, state: &SharedState, failed: String) {
+    let selected = state.settings.lock().await.selected_mic_device.clone();
+    let failed_mics = {
+        let mut session = state.session.lock().await;
+        if !session.failed_mics.contains(&failed) {
+            session.failed_mics.push(failed.clone());
+        }
+        session.failed_mics.clone()
+    };
+
+    let candidates: Vec<_> = audio::list_audio_devices()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|device| !failed_mics.contains(&device.name))
+        .collect();
+    let fallback = selected
+        .filter(|id| candidates.iter().any(|device| &device.id == id))
+        .or_else(|| audio::choose_default_mic(&candidates))
+        .and_then(|id| audio::capture_name(&candidates, &id));
+
+    let message = match &fallback {
+        Some(name) => format!("{failed} stopped working; switched to {name}"),
+        None => format!("{failed} stopped working and no other microphone is available"),
+    };
+    if let Some(name) = &fallback {
+        let _ = switch_microphone(state, name).await;
+    }
+    update_session(app, state, move |session| {
+        if session.status == AppStatus::Recording {
+            session.status_message = message;
+        }
+    })
+    .await;
+}
+
+/// Asks the worker to leave `source` out of the mix for the rest of the
+/// session. The worker confirms with a `muted` event; it refuses to drop the
+/// only source being captured.
+pub async fn mute_source(state: &SharedState, source: AudioSource) -> anyhow::Result<()> {
+    feed(state, EngineInput::Mute(source)).await
+}
+
+/// Points the running worker's microphone capture at `capture_name`. The
+/// worker ignores it when that microphone is already being captured.
+pub async fn switch_microphone(state: &SharedState, capture_name: &str) -> anyhow::Result<()> {
+    feed(state, EngineInput::Mic(capture_name.to_string())).await
+}
+
+async fn feed(state: &SharedState, input: EngineInput) -> anyhow::Result<()> {
+    let mut session = state.session.lock().await;
+    if session.status != AppStatus::Recording {
+        return Err(anyhow!("recording is not active"));
+    }
+    session
+        .worker
+        .as_mut()
+        .ok_or_else(|| anyhow!("missing worker process"))?
+        .engine
+        .feed(input)
+        .await
+}
+
+/// Adds a timestamped note to the session being recorded. It is saved with
+/// the transcript, before the first line spoken after it, and indexed for
+/// search.
+pub async fn append_note(app: &AppHandle, state: &SharedState, text: &str) -> anyhow::Result<()> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(anyhow!("note is empty"));
+    }
+
+    let at = {
+        let session = state.session.lock().await;
+        match (session.status, session.recording_started_at) {
+            (AppStatus::Recording, Some(started_at)) => started_at.elapsed().as_secs_f64(),
+            _ => return Err(anyhow!("recording is not active")),
         }
+    };
 
-        guard.status_message = "Stopping recording".to_string();
-        guard
+    state.transcript.lock().await.notes.push(SessionNote {
+        at,
+        // One line, so it stays a single quoted line in the Markdown.
+        text: text.split_whitespace().collect::<Vec<_>>().join(" "),
+        image: None,
+    });
+    emit_state(app, state).await;
+    Ok(())
+}
+
+/// Kills the worker and drops everything it captured: no file is written, no
+/// history entry is recorded and no "no transcript" warning is raised.
+pub async fn cancel_recording(app: &AppHandle, state: &SharedState) -> anyhow::Result<()> {
+    let (mut worker, audio_path, autosave_path) = {
+        let mut session = state.session.lock().await;
+        require_stoppable(&session)?;
+
+        session.dictation = DictationOutput::Off;
+        session.recording_started_at = None;
+        session.mic_muted = false;
+        session.music_gated = false;
+        let worker = session
             .worker
             .take()
-            .ok_or_else(|| anyhow!("missing worker process"))?
+            .ok_or_else(|| anyhow!("missing worker process"))?;
+        (
+            worker,
+            session.audio_path.take(),
+            session.autosave_path.take(),
+        )
     };
 
-    emit_state(app, state).await;
+    // Stop reading first so no late partial lands in the cleared transcript.
+    if let Some(task) = worker.events_task.take() {
+        task.abort();
+    }
+    // Stop rather than kill so the worker's cleanup stops its capture
+    // helpers; with its events no longer read, whatever it transcribes
+    // meanwhile is dropped.
+    let _ = worker.engine.stop(Duration::from_secs(5)).await;
 
-    if let Some(stdin) = worker.stdin.as_mut() {
-        stdin
-            .write_all(b"stop\n")
-            .await
-            .context("failed signaling worker to stop")?;
+    if let Some(audio_path) = &audio_path {
+        recording_audio::discard(audio_path).await;
+    }
+    if let Some(autosave_path) = &autosave_path {
+        recovery::remove(autosave_path).await;
     }
 
-    let start_wait = Instant::now();
-    if timeout(Duration::from_secs(15), worker.child.wait())
-        .await
-        .is_err()
     {
-        worker.child.kill().await.context("failed killing worker")?;
-        let _ = worker.child.wait().await;
+        let mut transcript = state.transcript.lock().await;
+        transcript.segments.clear();
+        transcript.partial_metrics = Default::default();
+        transcript.mute_intervals.clear();
+        transcript.notes.clear();
+        transcript.dirty = false;
+        transcript.edits = Default::default();
+    }
+
+    update_session(app, state, |session| {
+        session.status = AppStatus::Ready;
+        session.status_message = "Recording canceled".to_string();
+        session.reset_error();
+    })
+    .await;
+    preroll::sync(state).await;
+
+    ui::close_floating_window(app);
+    ui::show_tray_window(app);
+
+    Ok(())
+}
 
-        update_state(app, state, move |inner| {
-            inner.status_message = format!(
+/// Fails unless `Availability::can_stop`, saying why.
+fn require_stoppable(session: &SessionState) -> anyhow::Result<()> {
+    match session.status {
+        AppStatus::Recording if session.worker.is_none() => {
+            Err(anyhow!("the recording is already stopping"))
+        }
+        AppStatus::Recording => Ok(()),
+        AppStatus::Installing => Err(anyhow!("no recording to stop: a model is being installed")),
+        _ => Err(anyhow!("recording is not active")),
+    }
+}
+
+pub async fn stop_recording(
+    app: &AppHandle,
+    state: &SharedState,
+) -> anyhow::Result<RecordingResult> {
+    let (mut worker, dictation, audio_path, autosave_path) = {
+        let mut session = state.session.lock().await;
+        require_stoppable(&session)?;
+
+        session.status_message = "Stopping recording".to_string();
+        session.recording_started_at = None;
+        session.mic_muted = false;
+        session.music_gated = false;
+        let worker = session
+            .worker
+            .take()
+            .ok_or_else(|| anyhow!("missing worker process"))?;
+        (
+            worker,
+            std::mem::take(&mut session.dictation),
+            session.audio_path.take(),
+            session.autosave_path.take(),
+        )
+    };
+
+    emit_state(app, state).await;
+
+    let start_wait = Instant::now();
+    let forced = worker
+        .engine
+        .stop(Duration::from_secs(15))
+        .await
+        .context("failed stopping worker")?;
+    if forced {
+        update_session(app, state, move |session| {
+            session.status_message = format!(
                 "Worker forced to stop after {}s",
                 start_wait.elapsed().as_secs()
             );
@@ -284,51 +1215,209 @@ pub async fn stop_recording(app: &AppHandle, state: &SharedState) -> anyhow::Res
         .await;
     }
 
-    if let Some(stdout_task) = worker.stdout_task.take() {
-        let _ = timeout(Duration::from_secs(3), stdout_task).await;
-    }
-    if let Some(stderr_task) = worker.stderr_task.take() {
-        let _ = timeout(Duration::from_secs(3), stderr_task).await;
+    if let Some(events_task) = worker.events_task.take() {
+        let _ = timeout(Duration::from_secs(3), events_task).await;
     }
 
-    let transcript = {
-        let guard = state.0.lock().await;
-        guard.transcript.clone()
+    let (has_text, mut segments, mut mutes, mut notes) = {
+        let transcript = state.transcript.lock().await;
+        (
+            transcript.has_text(),
+            transcript.segments.clone(),
+            transcript.mute_intervals.clone(),
+            transcript.notes.clone(),
+        )
+    };
+    let (render_options, mut naming, title_llm, postprocess, link_dictation, trim_padding) = {
+        let settings = state.settings.lock().await;
+        (
+            settings.render_options(),
+            settings.file_naming(),
+            settings.title_llm.clone(),
+            settings.postprocess.clone(),
+            settings.auto_links(AutoLinkSink::Dictation),
+            settings.silence_trim_padding,
+        )
     };
 
-    if transcript.trim().is_empty() {
-        update_state(app, state, |inner| {
-            inner.status = AppStatus::Error;
-            inner.status_message = "No transcript captured".to_string();
-            inner.error_message = Some(
-                "No speech was captured. Check microphone permission and audio input device."
-                    .to_string(),
-            );
-            inner.worker = None;
-        })
+    if !has_text {
+        if let Some(audio_path) = &audio_path {
+            recording_audio::discard(audio_path).await;
+        }
+        if let Some(autosave_path) = &autosave_path {
+            recovery::remove(autosave_path).await;
+        }
+        errors::report(
+            app,
+            state,
+            ErrorKind::NoSpeech,
+            NO_SPEECH_MESSAGE.to_string(),
+            |session| {
+                session.status = AppStatus::Error;
+                session.status_message = "No transcript captured".to_string();
+                session.worker = None;
+            },
+        )
         .await;
+        preroll::sync(state).await;
         ui::close_floating_window(app);
         ui::show_tray_window(app);
-        return Err(anyhow!(
-            "No speech was captured. Check microphone permission and audio input device."
-        ));
+        return Err(NoSpeechCaptured.into());
+    }
+
+    if let (Some(audio_path), Some(padding)) = (&audio_path, trim_padding) {
+        // An untrimmed recording is still worth keeping.
+        if recording_audio::trim_silence(audio_path, padding, &mut segments, &mut mutes, &mut notes)
+            .await
+            .is_ok()
+        {
+            let mut transcript = state.transcript.lock().await;
+            transcript.segments = segments.clone();
+            transcript.mute_intervals = mutes.clone();
+            transcript.notes = notes.clone();
+        }
+    }
+
+    if let Some(llm) = title_llm {
+        update_session(app, state, |session| {
+            session.status_message = "Writing a title".to_string();
+        })
+        .await;
+        // Offline or slow: save under the time instead of failing the stop.
+        naming.title = match titles::generate(&llm, &segments).await {
+            Ok(title) => Title::Generated(title),
+            Err(_) => Title::Unavailable,
+        };
     }
 
-    let file_path = transcript_file::save_markdown(&transcript).await?;
+    let file_path =
+        transcript_file::save_markdown(&segments, &mutes, &notes, render_options, &naming).await?;
     let file_path_str = file_path.display().to_string();
+    let saved_audio = match &audio_path {
+        Some(audio_path) => recording_audio::keep_alongside(audio_path, &file_path)
+            .await
+            .ok(),
+        None => None,
+    };
+    let audio_path_str = saved_audio.as_ref().map(|path| path.display().to_string());
+    history::record_session(
+        app,
+        state,
+        &file_path,
+        saved_audio.as_deref(),
+        &segments,
+        &notes,
+    )
+    .await;
+    // Saved for good; a crash from here on has nothing left to recover.
+    if let Some(autosave_path) = &autosave_path {
+        recovery::remove(autosave_path).await;
+    }
 
-    update_state(app, state, move |inner| {
-        inner.status = AppStatus::Ready;
-        inner.status_message = "Ready".to_string();
-        inner.last_saved_path = Some(file_path_str.clone());
-        inner.error_message = None;
-        inner.install_progress = Some(1.0);
-        inner.worker = None;
+    state.install.lock().await.progress = Some(1.0);
+    let result = RecordingResult {
+        transcript_path: file_path_str,
+        audio_path: audio_path_str,
+    };
+    let saved = result.clone();
+    update_session(app, state, move |session| {
+        session.status = AppStatus::Ready;
+        session.status_message = "Ready".to_string();
+        session.last_saved_path = Some(saved.transcript_path);
+        session.last_audio_path = saved.audio_path;
+        session.reset_error();
+        session.worker = None;
     })
     .await;
+    if let Some(config) = postprocess {
+        postprocess::spawn(app, state, config, file_path.clone(), segments.clone());
+    }
+    preroll::sync(state).await;
 
     ui::close_floating_window(app);
-    ui::show_tray_window(app);
 
-    Ok(file_path.display().to_string())
+    let delivered = match dictation {
+        DictationOutput::Off => {
+            ui::show_tray_window(app);
+            return Ok(result);
+        }
+        DictationOutput::Paste => {
+            ui::hide_tray_window(app);
+            // Let focus settle back on the previously frontmost app.
+            tokio::time::sleep(Duration::from_millis(150)).await;
+            dictation::paste_text(&dictation_text(&segments, link_dictation)).await
+        }
+        DictationOutput::Copy => {
+            dictation::copy_text(&dictation_text(&segments, link_dictation)).await
+        }
+    };
+    if let Err(error) = delivered {
+        errors::report(app, state, ErrorKind::Dictation, error.to_string(), |_| {}).await;
+        ui::show_tray_window(app);
+    }
+
+    Ok(result)
+}
+
+/// The transcript as a dictation delivers it: plain lines, with spoken
+/// addresses written out when the `dictation` link sink is on.
+fn dictation_text(segments: &[TranscriptSegment], link: bool) -> String {
+    let text = transcript_file::render_plain(segments);
+    if link {
+        links::written(&text)
+    } else {
+        text
+    }
+}
+
+/// One call for Shortcuts, Raycast and similar launchers: records without the
+/// live window, stops once speech is followed by `QUICK_DICTATION_SILENCE` or
+/// after `max_seconds` (default `QUICK_DICTATION_DEFAULT_SECONDS`), saves the
+/// session like any other, copies the text to the clipboard and returns it.
+pub async fn quick_dictation(
+    app: &AppHandle,
+    state: &SharedState,
+    max_seconds: Option<u32>,
+) -> anyhow::Result<String> {
+    let limit = Duration::from_secs(u64::from(
+        max_seconds
+            .unwrap_or(QUICK_DICTATION_DEFAULT_SECONDS)
+            .clamp(1, QUICK_DICTATION_MAX_SECONDS),
+    ));
+
+    start_recording(app, state, DictationOutput::Copy, None).await?;
+    let started_at = state
+        .session
+        .lock()
+        .await
+        .recording_started_at
+        .ok_or_else(|| anyhow!("recording is not active"))?;
+
+    loop {
+        tokio::time::sleep(QUICK_DICTATION_POLL).await;
+        let last_speech_at = {
+            let session = state.session.lock().await;
+            if session.status != AppStatus::Recording
+                || session.recording_started_at != Some(started_at)
+            {
+                return Err(anyhow!("quick dictation was stopped before it finished"));
+            }
+            session.last_speech_at
+        };
+        if started_at.elapsed() >= limit
+            || last_speech_at.is_some_and(|at| at.elapsed() >= QUICK_DICTATION_SILENCE)
+        {
+            break;
+        }
+    }
+
+    stop_recording(app, state).await?;
+
+    let segments = state.transcript.lock().await.segments.clone();
+    let link = state
+        .settings
+        .lock()
+        .await
+        .auto_links(AutoLinkSink::Dictation);
+    Ok(dictation_text(&segments, link))
 }
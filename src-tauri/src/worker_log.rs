@@ -0,0 +1,63 @@
+use std::path::{Path, PathBuf};
+
+use chrono::{Local, NaiveDate};
+use tokio::io::AsyncWriteExt;
+
+use crate::app_state::AppPaths;
+
+/// Days a worker log is kept before nightly maintenance removes it.
+pub const RETENTION_DAYS: i64 = 14;
+
+/// `<app data>/logs`, holding one `worker-<YYYY-MM-DD>.log` per day.
+pub fn logs_dir(app_data_dir: &Path) -> PathBuf {
+    app_data_dir.join("logs")
+}
+
+/// Appends one line of worker stderr to today's log, so a failed session can
+/// be looked into afterwards. Skipped while read-only; a line that cannot be
+/// written is dropped.
+pub async fn append(paths: &AppPaths, line: &str) {
+    if paths.read_only {
+        return;
+    }
+    let now = Local::now();
+    let entry = format!("{} {line}\n", now.format("%H:%M:%S"));
+    let _ = write(&logs_dir(&paths.app_data_dir), now.date_naive(), &entry).await;
+}
+
+async fn write(dir: &Path, date: NaiveDate, entry: &str) -> std::io::Result<()> {
+    tokio::fs::create_dir_all(dir).await?;
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join(format!("worker-{}.log", date.format("%Y-%m-%d"))))
+        .await?;
+    file.write_all(entry.as_bytes()).await
+}
+
+/// Deletes the worker logs dated more than `RETENTION_DAYS` before `today`.
+/// Returns how many were removed.
+pub async fn prune(app_data_dir: &Path, today: NaiveDate) -> anyhow::Result<usize> {
+    let mut entries = match tokio::fs::read_dir(logs_dir(app_data_dir)).await {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(error) => return Err(error.into()),
+    };
+
+    let mut removed = 0;
+    while let Some(entry) = entries.next_entry().await? {
+        let Some(date) = log_date(&entry.file_name().to_string_lossy()) else {
+            continue;
+        };
+        if (today - date).num_days() > RETENTION_DAYS {
+            tokio::fs::remove_file(entry.path()).await?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+fn log_date(file_name: &str) -> Option<NaiveDate> {
+    let date = file_name.strip_prefix("worker-")?.strip_suffix(".log")?;
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()
+}
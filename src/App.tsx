@@ -1,52 +1,329 @@
-import { useCallback, useEffect, useMemo, useState } from "react";
+import { useCallback, useEffect, useLayoutEffect, useMemo, useRef, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 import { getCurrentWindow } from "@tauri-apps/api/window";
 
-type AppStatus = "Idle" | "Installing" | "Ready" | "Recording" | "Error";
+type AppStatus = "Idle" | "Installing" | "Ready" | "Recording" | "Transcribing" | "Error";
 type Language = "en" | "pt-BR";
+type ErrorKind = "install" | "recording" | "worker" | "noSpeech" | "dictation" | "durationLimit" | "loopback" | "meetingEnded" | "lowMemory" | "modelCorrupted" | "lowDiskSpace" | "hallucination" | "postprocess" | "moment";
+type ErrorSeverity = "Warning" | "Fatal";
+type AutoLinkSink = "transcript" | "dictation";
 
 interface AppSnapshot {
+  seq: number;
   status: AppStatus;
   statusMessage: string;
   language: Language;
   selectedModelId: string;
   selectedModelInstalled: boolean;
   selectedMicDevice: string | null;
+  systemAudioEnabled: boolean;
+  mutedSource: AudioSource | null;
+  micMuted: boolean;
+  musicGated: boolean;
   transcript: string;
+  sessionNotes: SessionNote[];
   lastSavedPath: string | null;
+  lastAudioPath: string | null;
   installProgress: number | null;
+  installStatus: InstallStatus | null;
   errorMessage: string | null;
+  errorKind: ErrorKind | null;
+  errorSeverity: ErrorSeverity | null;
+  suppressedErrors: ErrorKind[];
+  autoLinkSinks: AutoLinkSink[];
+  downloadNetwork: DownloadNetwork;
+  deviceProfiles: Record<string, DeviceProfile>;
+  acronymProfiles: Record<string, Acronym[]>;
+  acronymProfile: string | null;
+  replacements: string;
+  prerollSeconds: number;
+  prerollActive: boolean;
+  keepAudio: boolean;
+  silenceTrimPadding: number | null;
+  hallucinationFilter: boolean;
+  removeFillers: boolean;
+  resampleQuality: ResampleQuality;
+  noiseSuppression: boolean;
+  voiceActivityDetection: boolean;
+  skipMusic: boolean;
+  wordTimestamps: boolean;
+  autoStopSilenceMinutes: number;
+  warmWorkerMinutes: number;
+  preloadModel: boolean;
+  maxRecordingMinutes: number;
+  meetingEndAction: MeetingEndAction;
+  meetingEndGraceMinutes: number;
+  floatingPlacement: FloatingPlacement | null;
+  recordButton: boolean;
+  maintenanceHour: number;
+  retentionDays: number;
+  partialUpdatesPerSecond: number;
+  diarizationEnabled: boolean;
+  diarizationAvailable: boolean;
+  separateSources: boolean;
+  includeTimestamps: boolean;
+  nameTemplate: string;
+  titleLlm: LlmConfig | null;
+  postprocess: PostprocessConfig | null;
+  spellCheckEnabled: boolean;
+  transcriptDirty: boolean;
+  transcriptCanUndo: boolean;
+  transcriptCanRedo: boolean;
+  recordingCountdown: number | null;
+  recordingElapsedSeconds: number | null;
+  readOnly: boolean;
+  usageReportEndpoint: string | null;
+  lockedSettings: string[];
+  allowedModels: string[] | null;
+  localOnly: boolean;
+  outputDir: string | null;
+  canStart: boolean;
+  canStop: boolean;
+  canInstall: boolean;
+}
+
+interface SessionNote {
+  at: number;
+  text: string;
+  /** Screenshot from `capture_moment`, relative to ~/Documents/WhisperBar. */
+  image?: string;
+}
+
+interface InstallPhase {
+  id: "venv" | "packages" | "model";
+  label: string;
+  status: "pending" | "active" | "done" | "skipped";
+  progress: number;
+  file: string | null;
+  bytesDone: number | null;
+  bytesTotal: number | null;
+  bytesResumed: number | null;
+  bytesPerSecond: number | null;
+  etaSeconds: number | null;
+}
+
+interface InstallStatus {
+  phases: InstallPhase[];
+}
+
+interface TranscriptSegment {
+  text: string;
+  speaker: number | null;
+  source: AudioSource | null;
+  start: number | null;
+  end: number | null;
+  words?: WordTiming[];
+}
+
+interface WordTiming {
+  word: string;
+  start: number;
+  end: number;
+}
+
+interface SpellingIssue {
+  segment: number;
+  word: string;
+  offset: number;
+  suggestions: string[];
+}
+
+interface HistoryEntry {
+  id: string;
+  path: string;
+  createdAt: string;
+  modelId: string;
+  language: string;
+  micDevice: string | null;
+  diarizationEnabled: boolean;
+  durationSeconds: number | null;
+  wordCount: number;
+  rating: SessionRating | null;
+  audioPath: string | null;
+}
+
+interface RecoverableSession {
+  id: string;
+  startedAt: string;
+  savedAt: string;
+  wordCount: number;
+  preview: string;
+  hasAudio: boolean;
+}
+
+interface ViewerTranscript {
+  path: string;
+  text: string;
+}
+
+interface SearchHit {
+  path: string;
+  createdAt: string;
+  snippet: Array<{ text: string; highlighted: boolean }>;
+}
+
+interface SessionRating {
+  stars: number;
+  note: string | null;
+  ratedAt: string;
+}
+
+interface StorageBreakdown {
+  appDataDir: string;
+  totalBytes: number;
+  categories: Array<{ id: string; label: string; bytes: number }>;
+}
+
+interface MaintenanceReport {
+  finishedAt: string;
+  durationMs: number;
+  searchIndexBytesReclaimed: number;
+  models: Array<{ id: string; complete: boolean; intact: boolean }>;
+  sessionsRemoved: number;
+  logsRemoved: number;
+  errors: string[];
+}
+
+interface Diagnostics {
+  appVersion: string;
+  appDataDir: string;
+  selectedModelId: string;
+  selectedModelInstalled: boolean;
+  historySessions: number;
+  readOnly: boolean;
+  lastMaintenance: MaintenanceReport | null;
+}
+
+interface DeviceProfile {
+  gainDb: number;
+  noiseSuppression: boolean;
+  channel: number | null;
+  silenceThreshold: number | null;
+}
+
+interface Acronym {
+  spoken: string;
+  written: string;
+}
+
+const DEFAULT_DEVICE_PROFILE: DeviceProfile = {
+  gainDb: 0,
+  noiseSuppression: false,
+  channel: null,
+  silenceThreshold: null
+};
+
+interface CalibrationProgress {
+  phase: "quiet" | "speak" | "done" | "failed";
+  remainingSeconds: number;
+  level: LevelReading | null;
+  result: { deviceName: string; noiseFloorDb: number; speechDb: number; profile: DeviceProfile } | null;
+  message: string | null;
 }
 
 interface ModelOption {
   id: string;
   name: string;
-  sizeLabel: string;
+  sizeBytes: number;
+  memoryBytes: number | null;
+  backend: "mlx" | "whisperCpp";
+  custom: boolean;
 }
 
 interface AudioDeviceOption {
   id: string;
   name: string;
   isMicrophoneLike: boolean;
+  inputChannels: number;
 }
 
 const INITIAL_STATE: AppSnapshot = {
+  seq: 0,
   status: "Idle",
   statusMessage: "Idle",
   language: "en",
   selectedModelId: "large-v3-turbo",
   selectedModelInstalled: false,
   selectedMicDevice: null,
+  systemAudioEnabled: true,
+  mutedSource: null,
+  micMuted: false,
+  musicGated: false,
   transcript: "",
+  sessionNotes: [],
   lastSavedPath: null,
+  lastAudioPath: null,
   installProgress: null,
-  errorMessage: null
+  installStatus: null,
+  errorMessage: null,
+  errorKind: null,
+  errorSeverity: null,
+  suppressedErrors: [],
+  autoLinkSinks: [],
+  downloadNetwork: { proxy: null, hasCredentials: false, hfEndpoint: null },
+  deviceProfiles: {},
+  acronymProfiles: {},
+  acronymProfile: null,
+  replacements: "",
+  prerollSeconds: 0,
+  prerollActive: false,
+  keepAudio: false,
+  silenceTrimPadding: null,
+  hallucinationFilter: true,
+  removeFillers: false,
+  resampleQuality: "high",
+  noiseSuppression: false,
+  voiceActivityDetection: false,
+  skipMusic: false,
+  wordTimestamps: false,
+  autoStopSilenceMinutes: 0,
+  warmWorkerMinutes: 10,
+  preloadModel: false,
+  maxRecordingMinutes: 180,
+  meetingEndAction: "off",
+  meetingEndGraceMinutes: 5,
+  floatingPlacement: null,
+  recordButton: false,
+  maintenanceHour: 3,
+  retentionDays: 0,
+  partialUpdatesPerSecond: 4,
+  diarizationEnabled: false,
+  diarizationAvailable: true,
+  separateSources: false,
+  includeTimestamps: false,
+  nameTemplate: "Transcript-{date}-{time}",
+  titleLlm: null,
+  postprocess: null,
+  spellCheckEnabled: false,
+  transcriptDirty: false,
+  transcriptCanUndo: false,
+  transcriptCanRedo: false,
+  recordingCountdown: null,
+  recordingElapsedSeconds: null,
+  readOnly: false,
+  usageReportEndpoint: null,
+  lockedSettings: [],
+  allowedModels: null,
+  localOnly: false,
+  outputDir: null,
+  canStart: false,
+  canStop: false,
+  canInstall: false
 };
 
+const LIVE_SCROLL_KEY = "whisperbar.liveScroll";
+
+const MIB = 1024 * 1024;
+const GIB = 1024 * MIB;
+
 const FALLBACK_MODELS: ModelOption[] = [
-  { id: "large-v3-turbo", name: "Large v3 Turbo", sizeLabel: "0.81 GB" },
-  { id: "large-v3", name: "Large v3", sizeLabel: "3.10 GB" }
+  { id: "large-v3-turbo", name: "Large v3 Turbo", sizeBytes: 0.81 * GIB, memoryBytes: 2500 * MIB, backend: "mlx", custom: false },
+  { id: "large-v3", name: "Large v3", sizeBytes: 3.10 * GIB, memoryBytes: 6000 * MIB, backend: "mlx", custom: false },
+  { id: "medium", name: "Medium", sizeBytes: 1.53 * GIB, memoryBytes: 3000 * MIB, backend: "mlx", custom: false },
+  { id: "small", name: "Small", sizeBytes: 0.48 * GIB, memoryBytes: 1200 * MIB, backend: "mlx", custom: false },
+  { id: "base", name: "Base", sizeBytes: 0.15 * GIB, memoryBytes: 700 * MIB, backend: "mlx", custom: false },
+  { id: "tiny", name: "Tiny", sizeBytes: 0.08 * GIB, memoryBytes: 500 * MIB, backend: "mlx", custom: false }
 ];
 
 export function App() {
@@ -54,7 +331,6 @@ export function App() {
   const [windowLabel, setWindowLabel] = useState("tray");
   const [modelOptions, setModelOptions] = useState<ModelOption[]>(FALLBACK_MODELS);
   const [audioDevices, setAudioDevices] = useState<AudioDeviceOption[]>([]);
-  const [recordingSeconds, setRecordingSeconds] = useState(0);
 
   const refreshAudioDevices = useCallback(async () => {
     try {
@@ -69,41 +345,86 @@ export function App() {
     const current = getCurrentWindow();
     setWindowLabel(current.label);
 
-    void invoke<AppSnapshot>("get_app_state").then(setState).catch(() => undefined);
+    // Snapshots are numbered. Subscribe before fetching, drop anything older than
+    // what is shown, and replay from the backend buffer when a number is skipped.
+    let lastSeq = 0;
+    let catchingUp = false;
+    const apply = (snapshot: AppSnapshot) => {
+      if (snapshot.seq > lastSeq) {
+        lastSeq = snapshot.seq;
+        setState(snapshot);
+      }
+    };
+    const catchUp = async () => {
+      catchingUp = true;
+      try {
+        const { snapshots } = await invoke<StateCatchUp>("get_app_state_since", { seq: lastSeq });
+        snapshots.forEach(apply);
+      } catch {
+        // The next event retries.
+      } finally {
+        catchingUp = false;
+      }
+    };
+
+    const unlistenPromise = listen<AppSnapshot>("whisperbar://state", (event) => {
+      if (lastSeq > 0 && event.payload.seq > lastSeq + 1 && !catchingUp) {
+        void catchUp().then(() => apply(event.payload));
+        return;
+      }
+      apply(event.payload);
+    });
+
+    void unlistenPromise
+      .then(() => invoke<AppSnapshot>("get_app_state"))
+      .then(apply)
+      .catch(() => undefined);
     void invoke<ModelOption[]>("get_model_options").then(setModelOptions).catch(() => undefined);
     void refreshAudioDevices();
 
-    const unlistenPromise = listen<AppSnapshot>("whisperbar://state", (event) => setState(event.payload));
+    // Pushed by the backend when a device is plugged in or removed.
+    const unlistenDevicesPromise = listen<AudioDeviceOption[]>("whisperbar://devices-changed", (event) => {
+      setAudioDevices(event.payload);
+    });
+
     return () => {
       void unlistenPromise.then((unlisten) => unlisten());
+      void unlistenDevicesPromise.then((unlisten) => unlisten());
     };
   }, [refreshAudioDevices]);
 
-  useEffect(() => {
-    if (state.status !== "Recording") {
-      setRecordingSeconds(0);
-      return;
-    }
+  // Ticked by the backend every second, so every window shows the same clock.
+  const recordingSeconds = state.recordingElapsedSeconds ?? 0;
 
-    const startedAt = Date.now();
-    setRecordingSeconds(0);
-    const timer = window.setInterval(() => {
-      setRecordingSeconds(Math.max(0, Math.floor((Date.now() - startedAt) / 1000)));
-    }, 1000);
+  if (windowLabel === "history") {
+    return <HistoryWindow />;
+  }
 
-    return () => {
-      window.clearInterval(timer);
-    };
-  }, [state.status]);
+  if (windowLabel.startsWith("viewer-")) {
+    return <ViewerWindow />;
+  }
 
   if (windowLabel === "floating") {
-    return <FloatingTranscript transcript={state.transcript} status={state.status} recordingSeconds={recordingSeconds} />;
+    return (
+      <FloatingTranscript
+        transcript={state.transcript}
+        status={state.status}
+        recordingSeconds={recordingSeconds}
+        loopbackWarning={state.errorKind === "loopback" ? state.errorMessage : null}
+        mutedSource={state.mutedSource}
+        micMuted={state.micMuted}
+        musicGated={state.musicGated}
+        meetingEnded={state.errorKind === "meetingEnded"}
+        noteCount={state.sessionNotes.filter((note) => !note.image).length}
+      />
+    );
   }
 
   return (
     <TrayPanel
       state={state}
       modelOptions={modelOptions}
+      onModelOptions={setModelOptions}
       audioDevices={audioDevices}
       recordingSeconds={recordingSeconds}
     />
@@ -113,19 +434,35 @@ export function App() {
 function TrayPanel({
   state,
   modelOptions,
+  onModelOptions,
   audioDevices,
   recordingSeconds
 }: {
   state: AppSnapshot;
   modelOptions: ModelOption[];
+  onModelOptions: (options: ModelOption[]) => void;
   audioDevices: AudioDeviceOption[];
   recordingSeconds: number;
 }) {
   const [actionError, setActionError] = useState<string | null>(null);
+  const [dictation, setDictation] = useState(false);
+  const [accessibilityTrusted, setAccessibilityTrusted] = useState(true);
+
+  useEffect(() => {
+    if (!dictation) {
+      return;
+    }
+    void invoke<boolean>("get_accessibility_permission").then(setAccessibilityTrusted).catch(() => undefined);
+  }, [dictation]);
 
   const currentError = state.errorMessage ?? actionError;
-  const canStart = state.status === "Ready" && state.selectedModelInstalled;
-  const canStop = state.status === "Recording";
+  const canRetryInstall =
+    state.errorKind === "install" ||
+    state.errorKind === "modelCorrupted" ||
+    state.errorKind === "lowDiskSpace" ||
+    (currentError ?? "").toLowerCase().includes("install");
+  const { canStart, canStop } = state;
+  const recording = state.status === "Recording";
   const recordingClock = useMemo(() => formatDuration(recordingSeconds), [recordingSeconds]);
   const statusDetail = useMemo(() => getStatusDetail(state), [state]);
 
@@ -134,6 +471,11 @@ function TrayPanel({
     [modelOptions, state.selectedModelId]
   );
   const micDevices = useMemo(() => audioDevices.filter((device) => device.isMicrophoneLike), [audioDevices]);
+  const selectedMic = useMemo(
+    () => audioDevices.find((device) => device.id === state.selectedMicDevice) ?? null,
+    [audioDevices, state.selectedMicDevice]
+  );
+  const selectedMicName = selectedMic?.name ?? null;
   const installProgressPercent =
     state.status !== "Installing" || state.installProgress === null
       ? null
@@ -150,9 +492,24 @@ function TrayPanel({
 
   return (
     <main className="tray-shell">
-      <PanelHeader title="WhisperBar" status={state.status} recordingClock={canStop ? recordingClock : null} />
+      <PanelHeader title="WhisperBar" status={state.status} recordingClock={recording ? recordingClock : null} />
+
+      <SessionCard
+        detail={statusDetail}
+        progressPercent={installProgressPercent}
+        installPhases={state.status === "Installing" ? state.installStatus?.phases ?? null : null}
+      />
+
+      {state.readOnly ? (
+        <section className="block card warning-box">
+          <p>
+            Read-only mode: this user can&apos;t write to WhisperBar&apos;s data folder. History and search work;
+            recording, model installs and deleting sessions are off, and settings changes are not saved.
+          </p>
+        </section>
+      ) : null}
 
-      <SessionCard detail={statusDetail} progressPercent={installProgressPercent} />
+      <RecoveryPrompt idle={state.status === "Ready" || state.status === "Idle"} />
 
       <section className="control-grid">
         <SelectCard
@@ -173,18 +530,37 @@ function TrayPanel({
           value={state.selectedModelId}
           disabled={state.status === "Recording" || state.status === "Installing"}
           onChange={(value) => void runCommand("set_model", { modelId: value })}
-          options={modelOptions.map((model) => ({ value: model.id, label: `${model.name} (${model.sizeLabel})` }))}
-          footer={selectedModel ? `${selectedModel.name} selected` : state.selectedModelId}
+          options={modelOptions
+            .filter((model) => state.allowedModels === null || state.allowedModels.includes(model.id))
+            .map((model) => ({ value: model.id, label: `${model.name} (${formatBytes(model.sizeBytes)})` }))}
+          footer={
+            (selectedModel ? `${selectedModel.name} selected` : state.selectedModelId) +
+            (state.lockedSettings.includes("selectedModelId") ? " · models limited by your organization" : "")
+          }
         />
         {!state.selectedModelInstalled ? (
           <button
             className="btn btn-muted"
-            disabled={state.status === "Installing" || state.status === "Recording"}
+            disabled={!state.canInstall}
             onClick={() => void runCommand("install_selected_model")}
           >
             Install Model
           </button>
         ) : null}
+        <CustomModelForm
+          disabled={
+            state.status === "Recording" ||
+            state.status === "Installing" ||
+            state.lockedSettings.includes("selectedModelId")
+          }
+          onAdded={onModelOptions}
+          onError={setActionError}
+        />
+        <DownloadNetworkForm
+          network={state.downloadNetwork}
+          disabled={state.status === "Installing"}
+          onSave={(network) => void runCommand("set_download_network", { ...network })}
+        />
       </section>
 
       <section className="block card">
@@ -198,37 +574,571 @@ function TrayPanel({
               micDevice: value || null
             })
           }
-          options={[{ value: "", label: "Auto" }, ...micDevices.map((device) => ({ value: device.id, label: device.name }))]}
+          options={[
+            { value: "", label: "Auto" },
+            ...micDevices.map((device) => ({ value: device.id, label: device.name })),
+            // Kept while unplugged; the session uses the default mic until it is back.
+            ...(state.selectedMicDevice && !selectedMic
+              ? [{ value: state.selectedMicDevice, label: "Selected mic (disconnected)" }]
+              : [])
+          ]}
+          compact
+        />
+
+        {selectedMicName ? (
+          <DeviceProfileEditor
+            deviceName={selectedMicName}
+            channelCount={selectedMic?.inputChannels ?? 2}
+            profile={state.deviceProfiles[selectedMicName] ?? null}
+            disabled={state.status === "Recording"}
+            onChange={(profile) => void runCommand("set_device_profile", { deviceName: selectedMicName, profile })}
+          />
+        ) : (
+          <p className="subtle">Select a specific microphone to tune its gain, noise suppression and channel.</p>
+        )}
+
+        <SelectCard
+          id="preroll"
+          label="Pre-roll (keep mic audio from before Start)"
+          value={String(state.prerollSeconds)}
+          disabled={state.status === "Recording"}
+          onChange={(value) => void runCommand("set_preroll_seconds", { seconds: Number(value) })}
+          options={[
+            { value: "0", label: "Off" },
+            { value: "10", label: "Last 10 seconds" },
+            { value: "30", label: "Last 30 seconds" },
+            { value: "60", label: "Last 60 seconds" }
+          ]}
+          footer={state.prerollSeconds > 0 ? (state.prerollActive ? "Buffering in memory" : "Not buffering (no microphone found)") : undefined}
+          compact
+        />
+
+        <SelectCard
+          id="auto-stop-silence"
+          label="Stop automatically after silence"
+          value={String(state.autoStopSilenceMinutes)}
+          disabled={false}
+          onChange={(value) => void runCommand("set_auto_stop_silence", { minutes: Number(value) })}
+          options={[
+            { value: "0", label: "Never" },
+            { value: "5", label: "After 5 minutes" },
+            { value: "10", label: "After 10 minutes" },
+            { value: "30", label: "After 30 minutes" }
+          ]}
+          compact
+        />
+
+        <SelectCard
+          id="warm-worker"
+          label="Keep model loaded between recordings"
+          value={String(state.warmWorkerMinutes)}
+          disabled={false}
+          onChange={(value) => void runCommand("set_warm_worker_minutes", { minutes: Number(value) })}
+          options={[
+            { value: "0", label: "Off" },
+            { value: "10", label: "For 10 minutes" },
+            { value: "30", label: "For 30 minutes" },
+            { value: "120", label: "For 2 hours" }
+          ]}
+          compact
+        />
+
+        <ToggleRow
+          id="preload-model"
+          label="Load the model when the app starts"
+          checked={state.preloadModel}
+          disabled={state.warmWorkerMinutes === 0}
+          onChange={(enabled) => void runCommand("set_preload_model", { enabled })}
+        />
+
+        <SelectCard
+          id="max-recording"
+          label="Recording length limit"
+          value={String(state.maxRecordingMinutes)}
+          disabled={false}
+          onChange={(value) => void runCommand("set_max_recording_minutes", { minutes: Number(value) })}
+          options={[
+            { value: "60", label: "1 hour" },
+            { value: "180", label: "3 hours" },
+            { value: "360", label: "6 hours" },
+            { value: "0", label: "No limit" }
+          ]}
+          compact
+        />
+
+        <SelectCard
+          id="retention"
+          label="Delete saved transcripts"
+          value={String(state.retentionDays)}
+          disabled={false}
+          onChange={(value) => void runCommand("set_retention_days", { days: Number(value) })}
+          options={[
+            { value: "0", label: "Never" },
+            { value: "30", label: "After 30 days" },
+            { value: "90", label: "After 90 days" },
+            { value: "365", label: "After 1 year" }
+          ]}
+          compact
+        />
+
+        {state.usageReportEndpoint !== null ? (
+          <p className="subtle">
+            Your organization collects noisy usage counts (sessions, minutes, models; never transcript content) at{" "}
+            {state.usageReportEndpoint}.
+          </p>
+        ) : null}
+
+        <SelectCard
+          id="maintenance-hour"
+          label="Nightly maintenance"
+          value={String(state.maintenanceHour)}
+          disabled={false}
+          onChange={(value) => void runCommand("set_maintenance_hour", { hour: Number(value) })}
+          options={Array.from({ length: 24 }, (_, hour) => ({
+            value: String(hour),
+            label: `After ${String(hour).padStart(2, "0")}:00`
+          }))}
+          compact
+        />
+
+        <SelectCard
+          id="meeting-end"
+          label="When a linked meeting ends"
+          value={state.meetingEndAction}
+          disabled={false}
+          onChange={(value) =>
+            void runCommand("set_meeting_end_action", { action: value, graceMinutes: state.meetingEndGraceMinutes })
+          }
+          options={[
+            { value: "off", label: "Keep recording" },
+            { value: "prompt", label: "Ask to stop" },
+            { value: "stop", label: "Stop and save" }
+          ]}
+          compact
+        />
+
+        {state.meetingEndAction !== "off" ? (
+          <SelectCard
+            id="meeting-end-grace"
+            label="After the scheduled end"
+            value={String(state.meetingEndGraceMinutes)}
+            disabled={false}
+            onChange={(value) =>
+              void runCommand("set_meeting_end_action", { action: state.meetingEndAction, graceMinutes: Number(value) })
+            }
+            options={[
+              { value: "0", label: "Right away" },
+              { value: "5", label: "5 minutes" },
+              { value: "10", label: "10 minutes" },
+              { value: "15", label: "15 minutes" }
+            ]}
+            compact
+          />
+        ) : null}
+
+        <SelectCard
+          id="floating-corner"
+          label="Pin live window to"
+          value={state.floatingPlacement?.corner ?? ""}
+          disabled={false}
+          onChange={(value) =>
+            void runCommand("set_floating_window_position", {
+              corner: value || null,
+              display: state.floatingPlacement?.display ?? null
+            })
+          }
+          options={[
+            { value: "", label: "Don't pin" },
+            { value: "topLeft", label: "Top left" },
+            { value: "topRight", label: "Top right" },
+            { value: "bottomLeft", label: "Bottom left" },
+            { value: "bottomRight", label: "Bottom right" }
+          ]}
           compact
         />
 
-        <label htmlFor="system-device">Desktop Audio Input</label>
-        <p className="subtle">ScreenCaptureKit</p>
+        <ToggleRow
+          id="record-button"
+          label="Separate one-click record button in the menu bar"
+          checked={state.recordButton}
+          disabled={false}
+          onChange={(enabled) => void runCommand("set_record_button", { enabled })}
+        />
+
+        <ToggleRow
+          id="system-device"
+          label="Capture desktop audio (ScreenCaptureKit)"
+          checked={state.systemAudioEnabled}
+          disabled={state.status === "Recording"}
+          onChange={(enabled) => void runCommand("set_system_audio_enabled", { enabled })}
+        />
+
+        {state.systemAudioEnabled ? (
+          <SelectCard
+            id="resample-quality"
+            label="Desktop audio resampling"
+            value={state.resampleQuality}
+            disabled={state.status === "Recording"}
+            onChange={(value) => void runCommand("set_resample_quality", { quality: value })}
+            options={[
+              { value: "high", label: "High quality (sinc)" },
+              { value: "fast", label: "Fast (linear)" }
+            ]}
+            compact
+          />
+        ) : null}
+
+        <ToggleRow
+          id="noise-suppression"
+          label="Neural noise suppression on the microphone (RNNoise)"
+          checked={state.noiseSuppression}
+          disabled={state.status === "Recording"}
+          onChange={(enabled) => void runCommand("set_noise_suppression", { enabled })}
+        />
+
+        <ToggleRow
+          id="voice-activity-detection"
+          label="Only transcribe detected speech (voice activity detection)"
+          checked={state.voiceActivityDetection}
+          disabled={state.status === "Recording"}
+          onChange={(enabled) => void runCommand("set_voice_activity_detection", { enabled })}
+        />
+
+        <ToggleRow
+          id="skip-music"
+          label="Pause transcription while system audio is only music"
+          checked={state.skipMusic}
+          disabled={state.status === "Recording" || !state.systemAudioEnabled}
+          onChange={(enabled) => void runCommand("set_skip_music", { enabled })}
+        />
+
+        <ToggleRow
+          id="word-timestamps"
+          label="Highlight words live as they are spoken"
+          checked={state.wordTimestamps}
+          disabled={state.status === "Recording"}
+          onChange={(enabled) => void runCommand("set_word_timestamps", { enabled })}
+        />
+
+        <AcronymEditor profiles={state.acronymProfiles} active={state.acronymProfile} onCommand={runCommand} />
+
+        <ReplacementEditor saved={state.replacements} />
+
+        <ToggleRow
+          id="diarization"
+          label="Label speakers (Speaker 1 / Speaker 2)"
+          checked={state.diarizationEnabled}
+          disabled={state.status === "Recording" || !state.diarizationAvailable}
+          onChange={(enabled) => void runCommand("set_diarization", { enabled })}
+        />
+        {state.diarizationAvailable ? null : (
+          <p className="subtle">Speaker labels are not available with whisper.cpp models.</p>
+        )}
+
+        <ToggleRow
+          id="separate-sources"
+          label="Split transcript into Me (mic) / Others (desktop)"
+          checked={state.separateSources}
+          disabled={state.status === "Recording" || !state.systemAudioEnabled}
+          onChange={(enabled) => void runCommand("set_separate_sources", { enabled })}
+        />
+
+        <ToggleRow
+          id="timestamps"
+          label="Include [00:01:23] timestamps in saved transcript"
+          checked={state.includeTimestamps}
+          disabled={false}
+          onChange={(enabled) => void runCommand("set_include_timestamps", { enabled })}
+        />
+
+        <NameTemplateForm
+          template={state.nameTemplate}
+          onSave={(template) => void runCommand("set_name_template", { template })}
+        />
+
+        <LlmForm
+          llm={state.titleLlm}
+          offLabel="No generated titles"
+          saveLabel="Save Titles"
+          locked={state.lockedSettings.includes("titleLlm")}
+          onSave={(payload) => void runCommand("set_title_llm", { ...payload })}
+        />
+
+        <PostprocessForm
+          postprocess={state.postprocess}
+          locked={state.lockedSettings.includes("postprocess")}
+          onSave={(payload) => void runCommand("set_postprocess", { ...payload })}
+        />
+
+        {state.localOnly || state.lockedSettings.includes("outputDir") ? (
+          <p className="subtle">
+            Managed by your organization:
+            {state.localOnly ? " transcripts are only sent to LLM servers on this Mac." : ""}
+            {state.lockedSettings.includes("outputDir") ? ` Transcripts are saved to ${state.outputDir}.` : ""}
+          </p>
+        ) : null}
+
+        <ToggleRow
+          id="spell-check"
+          label="Flag misspelled words when reviewing a transcript"
+          checked={state.spellCheckEnabled}
+          disabled={false}
+          onChange={(enabled) => void runCommand("set_spell_check_enabled", { enabled })}
+        />
+
+        <ToggleRow
+          id="link-transcript"
+          label="Link spoken URLs and emails in saved transcript"
+          checked={state.autoLinkSinks.includes("transcript")}
+          disabled={false}
+          onChange={(enabled) => void runCommand("set_auto_link", { sink: "transcript", enabled })}
+        />
+
+        <ToggleRow
+          id="keep-audio"
+          label="Keep a WAV recording next to each transcript"
+          checked={state.keepAudio}
+          disabled={state.status === "Recording"}
+          onChange={(enabled) => void runCommand("set_keep_audio", { enabled })}
+        />
+
+        {state.keepAudio ? (
+          <SelectCard
+            id="silence-trim"
+            label="Trim silence from the start and end of the WAV"
+            value={state.silenceTrimPadding === null ? "off" : String(state.silenceTrimPadding)}
+            disabled={state.status === "Recording"}
+            onChange={(value) =>
+              void runCommand("set_silence_trim", {
+                paddingSeconds: value === "off" ? null : Number(value)
+              })
+            }
+            options={[
+              { value: "off", label: "Keep the whole recording" },
+              { value: "2", label: "Keep 2 seconds around the speech" },
+              { value: "5", label: "Keep 5 seconds around the speech" },
+              { value: "15", label: "Keep 15 seconds around the speech" }
+            ]}
+            compact
+          />
+        ) : null}
+
+        <ToggleRow
+          id="hallucination-filter"
+          label="Drop likely hallucinations (“Thanks for watching”, looping lines)"
+          checked={state.hallucinationFilter}
+          disabled={state.status === "Recording"}
+          onChange={(enabled) => void runCommand("set_hallucination_filter", { enabled })}
+        />
+
+        <ToggleRow
+          id="remove-fillers"
+          label="Remove filler words, repeats and stutters (“um”, “uh”, “I I”)"
+          checked={state.removeFillers}
+          disabled={state.status === "Recording"}
+          onChange={(enabled) => void runCommand("set_remove_fillers", { enabled })}
+        />
+
+        <ToggleRow
+          id="dictation"
+          label="Dictation: paste transcript at cursor when stopped"
+          checked={dictation}
+          disabled={state.status === "Recording"}
+          onChange={setDictation}
+        />
+        {dictation ? (
+          <ToggleRow
+            id="link-dictation"
+            label="Write out spoken URLs and emails when pasting"
+            checked={state.autoLinkSinks.includes("dictation")}
+            disabled={false}
+            onChange={(enabled) => void runCommand("set_auto_link", { sink: "dictation", enabled })}
+          />
+        ) : null}
+        {dictation && !accessibilityTrusted ? (
+          <p className="subtle">Grant Accessibility permission in System Settings &gt; Privacy &amp; Security.</p>
+        ) : null}
       </section>
 
-      <RecordControlButton canStart={canStart} canStop={canStop} onStart={() => void runCommand("start_recording")} onStop={() => void runCommand("stop_recording")} />
+      {/* Stays open after everything was deleted so the deletion can be undone. */}
+      {state.status !== "Recording" && (state.transcript.trim().length > 0 || state.transcriptCanUndo) ? (
+        <TranscriptReview
+          transcript={state.transcript}
+          dirty={state.transcriptDirty}
+          canUndo={state.transcriptCanUndo}
+          canRedo={state.transcriptCanRedo}
+          spellCheck={state.spellCheckEnabled}
+          onUpdate={(index, text) => void runCommand("update_transcript_segment", { index, text })}
+          onUndo={() => void runCommand("undo_transcript_edit")}
+          onSplit={(index, offset) => void runCommand("split_segment", { index, offset })}
+          onMergeNext={(index) => void runCommand("merge_segments", { indices: [index, index + 1] })}
+          onRedo={() => void runCommand("redo_transcript_edit")}
+          onSave={() => void runCommand("save_transcript")}
+          onDiscard={() => void runCommand("discard_unsaved_transcript")}
+        />
+      ) : null}
+
+      <RecordControlButton
+        recording={recording}
+        canStart={canStart}
+        canStop={canStop}
+        onStart={() => void runCommand("start_recording", { dictation })}
+        onStop={() => void runCommand("stop_recording")}
+        onCancel={() => void runCommand("cancel_recording")}
+      />
+
+      {state.recordingCountdown !== null ? (
+        <button className="btn btn-muted" onClick={() => void runCommand("cancel_recording_countdown")}>
+          Cancel Countdown ({state.recordingCountdown}s)
+        </button>
+      ) : canStart ? (
+        <button className="btn btn-muted" onClick={() => void runCommand("start_recording_countdown")}>
+          Record in 5 Seconds
+        </button>
+      ) : null}
 
       {currentError ? (
-        <section className="block card error-box">
+        <section className={`block card ${state.errorSeverity === "Warning" ? "warning-box" : "error-box"}`}>
           <p>{currentError}</p>
+          {state.errorKind === "loopback" && state.status === "Recording" ? <LoopbackActions onError={setActionError} /> : null}
           <button
             className="btn btn-muted"
-            onClick={() => void runCommand((currentError ?? "").toLowerCase().includes("install") ? "retry_bootstrap" : "clear_error")}
+            onClick={() => void runCommand(canRetryInstall ? "retry_bootstrap" : "clear_error")}
           >
-            {(currentError ?? "").toLowerCase().includes("install") ? "Retry Install" : "Dismiss Error"}
+            {state.errorKind === "modelCorrupted" ? "Reinstall Model" : canRetryInstall ? "Retry Install" : "Dismiss Error"}
           </button>
+          {state.errorSeverity === "Warning" && state.errorKind ? (
+            <button className="btn btn-muted" onClick={() => void runCommand("suppress_error_kind", { kind: state.errorKind })}>
+              Don&apos;t Show Again
+            </button>
+          ) : null}
         </section>
       ) : null}
 
+      {state.suppressedErrors.length > 0 ? (
+        <button className="btn btn-muted" onClick={() => void runCommand("reset_suppressed_errors")}>
+          Show Hidden Warnings Again ({state.suppressedErrors.length})
+        </button>
+      ) : null}
+
       {state.lastSavedPath ? <p className="saved-path">Saved: {state.lastSavedPath}</p> : null}
+      {state.lastAudioPath ? <p className="saved-path">Audio: {state.lastAudioPath}</p> : null}
+
+      <button className="btn btn-muted" onClick={() => void runCommand("show_history_window")}>
+        Transcript History
+      </button>
+
+      <StorageCard onOpen={() => void runCommand("open_app_data_dir")} />
     </main>
   );
 }
 
-function FloatingTranscript({ transcript, status, recordingSeconds }: { transcript: string; status: AppStatus; recordingSeconds: number }) {
+function StorageCard({ onOpen }: { onOpen: () => void }) {
+  const [breakdown, setBreakdown] = useState<StorageBreakdown | null>(null);
+  const [maintenance, setMaintenance] = useState<MaintenanceReport | null>(null);
+  const [loading, setLoading] = useState(false);
+
+  const refresh = useCallback(async () => {
+    setLoading(true);
+    try {
+      setBreakdown(await invoke<StorageBreakdown>("get_storage_breakdown"));
+      setMaintenance((await invoke<Diagnostics>("get_diagnostics")).lastMaintenance);
+    } catch {
+      setBreakdown(null);
+    } finally {
+      setLoading(false);
+    }
+  }, []);
+
+  return (
+    <section className="block card">
+      <div className="status-row">
+        <p className="status-title">Storage</p>
+        {breakdown ? <p className="status-percent">{formatBytes(breakdown.totalBytes)}</p> : null}
+      </div>
+      {breakdown ? (
+        <ul className="storage-list">
+          {breakdown.categories
+            .filter((category) => category.bytes > 0)
+            .map((category) => (
+              <li key={category.id} className="status-row">
+                <span>{category.label}</span>
+                <span className="subtle">{formatBytes(category.bytes)}</span>
+              </li>
+            ))}
+        </ul>
+      ) : null}
+      {maintenance ? (
+        <p className="status-message">
+          Last maintenance {new Date(maintenance.finishedAt).toLocaleString()}
+          {maintenance.errors.length > 0 ? ` (${maintenance.errors.length} step(s) failed)` : ""}
+        </p>
+      ) : null}
+      <button className="btn btn-muted" disabled={loading} onClick={() => void refresh()}>
+        {loading ? "Measuring..." : breakdown ? "Refresh Storage Usage" : "Show Storage Usage"}
+      </button>
+      <button className="btn btn-muted" onClick={onOpen}>
+        Open App Data Folder
+      </button>
+    </section>
+  );
+}
+
+function FloatingTranscript({
+  transcript,
+  status,
+  recordingSeconds,
+  loopbackWarning,
+  mutedSource,
+  micMuted,
+  musicGated,
+  meetingEnded,
+  noteCount
+}: {
+  transcript: string;
+  status: AppStatus;
+  recordingSeconds: number;
+  loopbackWarning: string | null;
+  mutedSource: AudioSource | null;
+  micMuted: boolean;
+  musicGated: boolean;
+  meetingEnded: boolean;
+  noteCount: number;
+}) {
   const preview = transcript.trim().length > 0 ? transcript : "Listening... transcript will appear here.";
   const canStop = status === "Recording";
   const [actionError, setActionError] = useState<string | null>(null);
+  const [chapterNotice, setChapterNotice] = useState<string | null>(null);
+  const bodyRef = useRef<HTMLElement | null>(null);
+  const followRef = useRef(true);
+
+  // Restore where the user was reading if the window was hidden and reopened.
+  useLayoutEffect(() => {
+    const body = bodyRef.current;
+    const saved = window.sessionStorage.getItem(LIVE_SCROLL_KEY);
+    if (!body || saved === null) {
+      return;
+    }
+    const { top, follow } = JSON.parse(saved) as { top: number; follow: boolean };
+    followRef.current = follow;
+    body.scrollTop = follow ? body.scrollHeight : top;
+  }, []);
+
+  useEffect(() => {
+    const body = bodyRef.current;
+    if (body && followRef.current) {
+      body.scrollTop = body.scrollHeight;
+    }
+  }, [transcript]);
+
+  const rememberScroll = () => {
+    const body = bodyRef.current;
+    if (!body) {
+      return;
+    }
+    followRef.current = body.scrollHeight - body.scrollTop - body.clientHeight < 24;
+    window.sessionStorage.setItem(LIVE_SCROLL_KEY, JSON.stringify({ top: body.scrollTop, follow: followRef.current }));
+  };
 
   const stopFromFloating = async () => {
     setActionError(null);
@@ -239,29 +1149,1036 @@ function FloatingTranscript({ transcript, status, recordingSeconds }: { transcri
     }
   };
 
+  const exportChapters = async () => {
+    setActionError(null);
+    try {
+      const paths = await invoke<string[]>("export_completed_chapters");
+      const folder = paths[0].slice(0, paths[0].lastIndexOf("/"));
+      setChapterNotice(`${paths.length} chapter${paths.length === 1 ? "" : "s"} saved to ${folder}`);
+    } catch (error) {
+      setChapterNotice(null);
+      setActionError(error instanceof Error ? error.message : String(error));
+    }
+  };
+
+  const captureMoment = async () => {
+    setActionError(null);
+    try {
+      const path = await invoke<string>("capture_moment");
+      setChapterNotice(`Screenshot saved to ${path}`);
+    } catch (error) {
+      setActionError(error instanceof Error ? error.message : String(error));
+    }
+  };
+
   return (
     <main className="floating-shell">
       <PanelHeader title="Live Transcript" status={status} recordingClock={canStop ? formatDuration(recordingSeconds) : null} />
-      <section className="transcript-body">{preview}</section>
+      {canStop ? <LevelMeter /> : null}
+      {canStop ? <LiveWords /> : null}
+      {canStop && micMuted ? <p className="floating-alert">Mic appears muted</p> : null}
+      {canStop && musicGated ? <p className="floating-alert">Music only: transcription paused</p> : null}
+      {canStop && meetingEnded ? <p className="floating-alert">The linked meeting has ended. Stop recording?</p> : null}
+      {canStop && loopbackWarning ? (
+        <section className="warning-box loopback-banner">
+          <p>{loopbackWarning}</p>
+          <LoopbackActions onError={setActionError} />
+        </section>
+      ) : null}
+      {canStop && mutedSource ? (
+        <p className="subtle">{mutedSource === "mic" ? "Microphone" : "Desktop audio"} left out of this session.</p>
+      ) : null}
+      <section className="transcript-body" ref={bodyRef} onScroll={rememberScroll}>
+        {preview}
+      </section>
+      {canStop ? <SessionNoteInput noteCount={noteCount} onError={setActionError} /> : null}
       {canStop ? (
         <div className="floating-actions">
+          <button className="btn btn-muted" title="Capture Screen (⌥⇧⌘S)" onClick={() => void captureMoment()}>
+            Capture Screen
+          </button>
+          <button className="btn btn-muted" onClick={() => void exportChapters()}>
+            Export Finished Chapters
+          </button>
           <button className="btn btn-stop floating-stop" onClick={stopFromFloating}>
             Stop Recording
           </button>
         </div>
       ) : null}
+      {canStop && chapterNotice ? <p className="subtle">{chapterNotice}</p> : null}
       {actionError ? <p className="subtle">{actionError}</p> : null}
     </main>
   );
 }
 
-function PanelHeader({
-  title,
-  status,
-  recordingClock
-}: {
-  title: string;
-  status: AppStatus;
+type AudioSource = "mic" | "desktop";
+
+type MeetingEndAction = "off" | "prompt" | "stop";
+
+type ResampleQuality = "high" | "fast";
+
+function SessionNoteInput({ noteCount, onError }: { noteCount: number; onError: (message: string) => void }) {
+  const [text, setText] = useState("");
+
+  const addNote = async () => {
+    if (!text.trim()) {
+      return;
+    }
+    try {
+      await invoke("append_session_note", { text });
+      setText("");
+    } catch (error) {
+      onError(error instanceof Error ? error.message : String(error));
+    }
+  };
+
+  return (
+    <div className="row actions">
+      <input
+        placeholder={noteCount > 0 ? `Add a note (${noteCount} so far)` : "Add a note"}
+        value={text}
+        onChange={(event) => setText(event.target.value)}
+        onKeyDown={(event) => {
+          if (event.key === "Enter") {
+            void addNote();
+          }
+        }}
+      />
+      <button className="btn btn-muted" disabled={!text.trim()} onClick={() => void addNote()}>
+        Add Note
+      </button>
+    </div>
+  );
+}
+
+function CustomModelForm({
+  disabled,
+  onAdded,
+  onError
+}: {
+  disabled: boolean;
+  onAdded: (options: ModelOption[]) => void;
+  onError: (message: string) => void;
+}) {
+  const [path, setPath] = useState("");
+
+  const addModel = async () => {
+    try {
+      onAdded(await invoke<ModelOption[]>("add_custom_model", { path }));
+      setPath("");
+    } catch (error) {
+      onError(error instanceof Error ? error.message : String(error));
+    }
+  };
+
+  return (
+    <div className="row actions">
+      <input
+        placeholder="Local model folder (MLX or GGML)"
+        value={path}
+        disabled={disabled}
+        onChange={(event) => setPath(event.target.value)}
+      />
+      <button className="btn btn-muted" disabled={disabled || !path.trim()} onClick={() => void addModel()}>
+        Add Model
+      </button>
+    </div>
+  );
+}
+
+function NameTemplateForm({ template, onSave }: { template: string; onSave: (template: string) => void }) {
+  const [draft, setDraft] = useState(template);
+
+  useEffect(() => {
+    setDraft(template);
+  }, [template]);
+
+  return (
+    <div className="row actions">
+      <input
+        placeholder="File name: {date}, {time}, {title}, {model}, {language}"
+        title="Tokens: {date}, {time}, {title}, {model}, {language}. Leave empty for the default."
+        value={draft}
+        onChange={(event) => setDraft(event.target.value)}
+      />
+      <button className="btn btn-muted" disabled={draft.trim() === template} onClick={() => onSave(draft.trim())}>
+        Save Name
+      </button>
+    </div>
+  );
+}
+
+type LlmApi = "ollama" | "openAi";
+
+interface LlmConfig {
+  api: LlmApi;
+  endpoint: string;
+  model: string;
+  hasApiKey: boolean;
+}
+
+interface LlmPayload {
+  api: LlmApi | null;
+  endpoint: string;
+  model: string;
+  apiKey: string | null;
+}
+
+function LlmForm({
+  llm,
+  offLabel,
+  saveLabel,
+  extraChanged = false,
+  locked = false,
+  onSave
+}: {
+  llm: LlmConfig | null;
+  offLabel: string;
+  saveLabel: string;
+  extraChanged?: boolean;
+  // Turned off by a configuration profile.
+  locked?: boolean;
+  onSave: (payload: LlmPayload) => void;
+}) {
+  const [api, setApi] = useState<LlmApi | "">(llm?.api ?? "");
+  const [endpoint, setEndpoint] = useState(llm?.endpoint ?? "");
+  const [model, setModel] = useState(llm?.model ?? "");
+  const [apiKey, setApiKey] = useState("");
+
+  useEffect(() => {
+    setApi(llm?.api ?? "");
+    setEndpoint(llm?.endpoint ?? "");
+    setModel(llm?.model ?? "");
+    setApiKey("");
+  }, [llm?.api, llm?.endpoint, llm?.model, llm?.hasApiKey]);
+
+  const changed =
+    extraChanged ||
+    api !== (llm?.api ?? "") ||
+    endpoint.trim() !== (llm?.endpoint ?? "") ||
+    model.trim() !== (llm?.model ?? "") ||
+    apiKey !== "";
+
+  return (
+    <div className="row actions">
+      <select
+        value={api}
+        disabled={locked}
+        onChange={(event) => {
+          const next = event.target.value as LlmApi | "";
+          setApi(next);
+          if (next === "ollama" && !endpoint.trim()) {
+            setEndpoint("http://localhost:11434");
+          }
+        }}
+      >
+        <option value="">{locked ? `${offLabel} (turned off by your organization)` : offLabel}</option>
+        <option value="ollama">Ollama</option>
+        <option value="openAi">OpenAI-compatible</option>
+      </select>
+      {api ? (
+        <>
+          <input placeholder="Endpoint" value={endpoint} onChange={(event) => setEndpoint(event.target.value)} />
+          <input placeholder="Model" value={model} onChange={(event) => setModel(event.target.value)} />
+          {api === "openAi" ? (
+            <input
+              type="password"
+              placeholder={llm?.hasApiKey ? "API key (saved)" : "API key"}
+              value={apiKey}
+              onChange={(event) => setApiKey(event.target.value)}
+            />
+          ) : null}
+        </>
+      ) : null}
+      <button
+        className="btn btn-muted"
+        disabled={locked || !changed}
+        onClick={() =>
+          onSave({
+            api: api || null,
+            endpoint: endpoint.trim(),
+            model: model.trim(),
+            apiKey: api === "ollama" ? "" : apiKey || null
+          })
+        }
+      >
+        {saveLabel}
+      </button>
+    </div>
+  );
+}
+
+interface PostprocessConfig {
+  llm: LlmConfig;
+  steps: string;
+}
+
+function PostprocessForm({
+  postprocess,
+  locked,
+  onSave
+}: {
+  postprocess: PostprocessConfig | null;
+  locked: boolean;
+  onSave: (payload: LlmPayload & { steps: string | null }) => void;
+}) {
+  const [steps, setSteps] = useState(postprocess?.steps ?? "");
+
+  useEffect(() => {
+    setSteps(postprocess?.steps ?? "");
+  }, [postprocess?.steps]);
+
+  const stepsChanged = postprocess !== null && steps !== postprocess.steps;
+
+  return (
+    <>
+      <LlmForm
+        llm={postprocess?.llm ?? null}
+        offLabel="No summary or action items"
+        saveLabel="Save Post-processing"
+        extraChanged={stepsChanged}
+        locked={locked}
+        onSave={(payload) => onSave({ ...payload, steps: stepsChanged ? steps : null })}
+      />
+      {postprocess ? (
+        <textarea
+          className="postprocess-steps"
+          rows={8}
+          spellCheck={false}
+          placeholder={"## Summary\nSummarize this transcript.\n\n{transcript}"}
+          value={steps}
+          onChange={(event) => setSteps(event.target.value)}
+        />
+      ) : null}
+    </>
+  );
+}
+
+interface DownloadNetwork {
+  /** Without the proxy's `user:password@`, which stays in the settings file. */
+  proxy: string | null;
+  hasCredentials: boolean;
+  hfEndpoint: string | null;
+}
+
+function DownloadNetworkForm({
+  network,
+  disabled,
+  onSave
+}: {
+  network: DownloadNetwork;
+  disabled: boolean;
+  onSave: (network: { proxy: string | null; hfEndpoint: string | null }) => void;
+}) {
+  const [proxy, setProxy] = useState(network.proxy ?? "");
+  const [hfEndpoint, setHfEndpoint] = useState(network.hfEndpoint ?? "");
+
+  useEffect(() => {
+    setProxy(network.proxy ?? "");
+    setHfEndpoint(network.hfEndpoint ?? "");
+  }, [network.proxy, network.hfEndpoint]);
+
+  const changed = proxy.trim() !== (network.proxy ?? "") || hfEndpoint.trim() !== (network.hfEndpoint ?? "");
+
+  return (
+    <div className="row actions">
+      <input
+        placeholder="Download proxy (http://proxy:8080)"
+        value={proxy}
+        disabled={disabled}
+        onChange={(event) => setProxy(event.target.value)}
+      />
+      <input
+        placeholder="Hugging Face mirror (https://hf-mirror.com)"
+        value={hfEndpoint}
+        disabled={disabled}
+        onChange={(event) => setHfEndpoint(event.target.value)}
+      />
+      <button
+        className="btn btn-muted"
+        disabled={disabled || !changed}
+        onClick={() => onSave({ proxy: proxy.trim() || null, hfEndpoint: hfEndpoint.trim() || null })}
+      >
+        Save Network
+      </button>
+      {network.hasCredentials ? <p className="subtle">The proxy's saved credentials are kept unless you change it.</p> : null}
+    </div>
+  );
+}
+
+function LoopbackActions({ onError }: { onError: (message: string) => void }) {
+  const mute = async (source: AudioSource) => {
+    try {
+      await invoke("mute_session_source", { source });
+    } catch (error) {
+      onError(error instanceof Error ? error.message : String(error));
+    }
+  };
+
+  return (
+    <div className="row actions">
+      <button className="btn btn-muted" onClick={() => void mute("mic")}>
+        Drop Mic This Session
+      </button>
+      <button className="btn btn-muted" onClick={() => void mute("desktop")}>
+        Drop Desktop This Session
+      </button>
+    </div>
+  );
+}
+
+type ScreenCorner = "topLeft" | "topRight" | "bottomLeft" | "bottomRight";
+
+interface FloatingPlacement {
+  corner: ScreenCorner;
+  display: number | null;
+}
+
+interface StateCatchUp {
+  snapshots: AppSnapshot[];
+  reset: boolean;
+}
+
+interface LevelReading {
+  rms: number;
+  peak: number;
+}
+
+interface AudioLevel {
+  mic: LevelReading | null;
+  desktop: LevelReading | null;
+}
+
+// Maps a linear level onto a -60..0 dBFS bar.
+function levelPercent(value: number): number {
+  if (value <= 0) {
+    return 0;
+  }
+  const db = 20 * Math.log10(value);
+  return Math.max(0, Math.min(100, ((db + 60) / 60) * 100));
+}
+
+function LevelMeter() {
+  const [level, setLevel] = useState<AudioLevel | null>(null);
+
+  useEffect(() => {
+    const unlistenPromise = listen<AudioLevel>("whisperbar://audio-level", (event) => setLevel(event.payload));
+    return () => {
+      void unlistenPromise.then((unlisten) => unlisten());
+    };
+  }, []);
+
+  const rows: Array<[string, LevelReading | null | undefined]> = [
+    ["Mic", level?.mic],
+    ["Desktop", level?.desktop]
+  ];
+
+  return (
+    <section className="level-meter">
+      {rows.map(([label, reading]) => (
+        <div className="level-row" key={label}>
+          <span className="level-label">{label}</span>
+          <div className="level-track">
+            <div className="level-fill" style={{ width: `${reading ? levelPercent(reading.rms) : 0}%` }} />
+            {reading ? <div className="level-peak" style={{ left: `${levelPercent(reading.peak)}%` }} /> : null}
+          </div>
+        </div>
+      ))}
+    </section>
+  );
+}
+
+// Replays the newest timed line at its spoken pace, lighting up each word as
+// it is reached. Lines arrive a chunk late, so the pace is taken from the
+// word offsets rather than the recording clock.
+function LiveWords() {
+  const [line, setLine] = useState<{ words: WordTiming[]; receivedAt: number } | null>(null);
+  const [elapsed, setElapsed] = useState(0);
+
+  useEffect(() => {
+    const unlistenPromise = listen<WordTiming[]>("whisperbar://live-words", (event) => {
+      setLine({ words: event.payload, receivedAt: performance.now() });
+      setElapsed(0);
+    });
+    return () => {
+      void unlistenPromise.then((unlisten) => unlisten());
+    };
+  }, []);
+
+  useEffect(() => {
+    if (!line) {
+      return;
+    }
+    const timer = window.setInterval(() => setElapsed((performance.now() - line.receivedAt) / 1000), 80);
+    return () => window.clearInterval(timer);
+  }, [line]);
+
+  if (!line || line.words.length === 0) {
+    return null;
+  }
+
+  const origin = line.words[0].start;
+  return (
+    <p className="live-words">
+      {line.words.map((word, index) => (
+        <span key={index} className={word.start - origin <= elapsed ? "live-word spoken" : "live-word"}>
+          {word.word}{" "}
+        </span>
+      ))}
+    </p>
+  );
+}
+
+function TranscriptReview({
+  transcript,
+  dirty,
+  canUndo,
+  canRedo,
+  spellCheck,
+  onUpdate,
+  onUndo,
+  onRedo,
+  onSplit,
+  onMergeNext,
+  onSave,
+  onDiscard
+}: {
+  transcript: string;
+  dirty: boolean;
+  canUndo: boolean;
+  canRedo: boolean;
+  spellCheck: boolean;
+  onUpdate: (index: number, text: string) => void;
+  onUndo: () => void;
+  onRedo: () => void;
+  onSplit: (index: number, offset: number) => void;
+  onMergeNext: (index: number) => void;
+  onSave: () => void;
+  onDiscard: () => void;
+}) {
+  const [segments, setSegments] = useState<TranscriptSegment[]>([]);
+  // Last caret position in a segment, for `Split at Cursor`.
+  const [cursor, setCursor] = useState<{ index: number; offset: number } | null>(null);
+
+  // The snapshot only carries rendered text; refetch segments when it changes.
+  useEffect(() => {
+    setCursor(null);
+    void invoke<TranscriptSegment[]>("get_transcript_segments").then(setSegments).catch(() => undefined);
+  }, [transcript]);
+
+  const [issues, setIssues] = useState<SpellingIssue[]>([]);
+  const [spellError, setSpellError] = useState<string | null>(null);
+
+  useEffect(() => {
+    if (!spellCheck) {
+      setIssues([]);
+      setSpellError(null);
+      return;
+    }
+    void invoke<SpellingIssue[]>("check_transcript_spelling")
+      .then((found) => {
+        setIssues(found);
+        setSpellError(null);
+      })
+      .catch((error) => setSpellError(String(error)));
+  }, [transcript, spellCheck]);
+
+  const applySuggestion = (issue: SpellingIssue, suggestion: string) => {
+    const text = segments[issue.segment]?.text;
+    if (text === undefined || text.slice(issue.offset, issue.offset + issue.word.length) !== issue.word) {
+      return;
+    }
+    onUpdate(issue.segment, text.slice(0, issue.offset) + suggestion + text.slice(issue.offset + issue.word.length));
+  };
+
+  return (
+    <section className="block card segment-editor">
+      <p className="status-title">Review Transcript</p>
+      {canUndo || canRedo ? (
+        <div className="row actions">
+          <button className="btn btn-muted" disabled={!canUndo} onClick={onUndo}>
+            Undo
+          </button>
+          <button className="btn btn-muted" disabled={!canRedo} onClick={onRedo}>
+            Redo
+          </button>
+        </div>
+      ) : null}
+      <FindReplace />
+      {spellError ? <p className="subtle">{spellError}</p> : null}
+      {segments.map((segment, index) => (
+        <div key={`${index}-${segment.text}`}>
+          <textarea
+            defaultValue={segment.text}
+            rows={2}
+            onSelect={(event) => setCursor({ index, offset: event.currentTarget.selectionStart })}
+            onBlur={(event) => {
+              if (event.target.value !== segment.text) {
+                onUpdate(index, event.target.value);
+              }
+            }}
+          />
+          <div className="row segment-actions">
+            <button
+              className="btn btn-muted"
+              disabled={cursor?.index !== index}
+              onClick={() => cursor && onSplit(index, cursor.offset)}
+            >
+              Split at Cursor
+            </button>
+            {index < segments.length - 1 ? (
+              <button className="btn btn-muted" onClick={() => onMergeNext(index)}>
+                Merge with Next
+              </button>
+            ) : null}
+          </div>
+          {issues
+            .filter((issue) => issue.segment === index)
+            .map((issue) => (
+              <div key={`${issue.offset}-${issue.word}`} className="spelling-issue">
+                <span className="spelling-word">{issue.word}</span>
+                {issue.suggestions.length > 0 ? (
+                  issue.suggestions.map((suggestion) => (
+                    <button key={suggestion} className="btn btn-muted" onClick={() => applySuggestion(issue, suggestion)}>
+                      {suggestion}
+                    </button>
+                  ))
+                ) : (
+                  <span className="subtle">no suggestions</span>
+                )}
+              </div>
+            ))}
+        </div>
+      ))}
+      {dirty ? (
+        <>
+          <p className="subtle">Unsaved edits. Save or discard them before starting a new recording.</p>
+          <div className="row actions">
+            <button className="btn btn-muted" onClick={onSave}>
+              Save Edits
+            </button>
+            <button className="btn btn-muted" onClick={onDiscard}>
+              Discard
+            </button>
+          </div>
+        </>
+      ) : null}
+    </section>
+  );
+}
+
+function FindReplace() {
+  const [pattern, setPattern] = useState("");
+  const [replacement, setReplacement] = useState("");
+  const [regex, setRegex] = useState(false);
+  const [message, setMessage] = useState<string | null>(null);
+
+  const replaceAll = async () => {
+    try {
+      const count = await invoke<number>("replace_in_transcript", { pattern, replacement, regex });
+      setMessage(count === 1 ? "Replaced 1 match" : `Replaced ${count} matches`);
+    } catch (error) {
+      setMessage(String(error));
+    }
+  };
+
+  return (
+    <div className="find-replace">
+      <input placeholder="Find" value={pattern} onChange={(event) => setPattern(event.target.value)} />
+      <input placeholder="Replace with" value={replacement} onChange={(event) => setReplacement(event.target.value)} />
+      <ToggleRow id="find-regex" label="Regular expression" checked={regex} disabled={false} onChange={setRegex} />
+      <button className="btn btn-muted" disabled={!pattern} onClick={() => void replaceAll()}>
+        Replace All
+      </button>
+      {message ? <p className="subtle">{message}</p> : null}
+    </div>
+  );
+}
+
+function HistoryWindow() {
+  const [entries, setEntries] = useState<HistoryEntry[]>([]);
+  const [message, setMessage] = useState<string | null>(null);
+  const [query, setQuery] = useState("");
+  const [hits, setHits] = useState<SearchHit[] | null>(null);
+  const [bundlePath, setBundlePath] = useState("");
+  const [retranscribing, setRetranscribing] = useState<string | null>(null);
+
+  useEffect(() => {
+    if (!query.trim()) {
+      setHits(null);
+      return;
+    }
+
+    const timer = window.setTimeout(() => {
+      void invoke<SearchHit[]>("search_transcripts", { query })
+        .then(setHits)
+        .catch((error) => setMessage(error instanceof Error ? error.message : String(error)));
+    }, 200);
+    return () => window.clearTimeout(timer);
+  }, [query]);
+
+  useEffect(() => {
+    void invoke<HistoryEntry[]>("get_transcript_history").then(setEntries).catch(() => undefined);
+
+    const unlistenPromise = listen<HistoryEntry[]>("whisperbar://history", (event) => setEntries(event.payload));
+    return () => {
+      void unlistenPromise.then((unlisten) => unlisten());
+    };
+  }, []);
+
+  const runAction = useCallback(async (command: string, id: string) => {
+    setMessage(null);
+    try {
+      const result = await invoke<string | null>(command, { id });
+      if (typeof result === "string") {
+        setMessage(`Exported to ${result}`);
+      }
+    } catch (error) {
+      setMessage(error instanceof Error ? error.message : String(error));
+    }
+  }, []);
+
+  const retranscribe = useCallback(async (id: string) => {
+    setRetranscribing(id);
+    setMessage("Transcribing the recording again...");
+    try {
+      const path = await invoke<string>("retranscribe_history_entry", { id });
+      setMessage(`Saved to ${path}`);
+    } catch (error) {
+      setMessage(error instanceof Error ? error.message : String(error));
+    } finally {
+      setRetranscribing(null);
+    }
+  }, []);
+
+  const shareBundle = useCallback(async (id: string, includeAudio: boolean) => {
+    setMessage(null);
+    try {
+      const path = await invoke<string>("export_session_bundle", { id, includeAudio });
+      setMessage(`Exported to ${path}`);
+    } catch (error) {
+      setMessage(error instanceof Error ? error.message : String(error));
+    }
+  }, []);
+
+  const importBundle = useCallback(async () => {
+    setMessage(null);
+    try {
+      const path = await invoke<string>("import_session_bundle", { path: bundlePath });
+      setBundlePath("");
+      setMessage(`Imported to ${path}`);
+    } catch (error) {
+      setMessage(error instanceof Error ? error.message : String(error));
+    }
+  }, [bundlePath]);
+
+  const view = useCallback((pathOrSessionId: string) => {
+    void invoke("open_transcript_viewer", { pathOrSessionId }).catch((error) =>
+      setMessage(error instanceof Error ? error.message : String(error))
+    );
+  }, []);
+
+  return (
+    <main className="tray-shell">
+      <header className="panel-header">
+        <div className="title-wrap">
+          <h1>Transcript History</h1>
+        </div>
+      </header>
+
+      <input
+        className="search-input"
+        type="search"
+        placeholder="Search transcripts"
+        value={query}
+        onChange={(event) => setQuery(event.target.value)}
+      />
+
+      <form
+        className="row bundle-import"
+        onSubmit={(event) => {
+          event.preventDefault();
+          void importBundle();
+        }}
+      >
+        <input
+          type="text"
+          placeholder="Path to a .whisperbar bundle"
+          value={bundlePath}
+          onChange={(event) => setBundlePath(event.target.value)}
+        />
+        <button className="btn btn-muted" type="submit" disabled={!bundlePath.trim()}>
+          Import
+        </button>
+      </form>
+
+      {hits !== null ? (
+        <section className="block history-list">
+          {hits.length === 0 ? <p className="subtle">No matches.</p> : null}
+          {hits.map((hit) => (
+            <article
+              key={hit.path}
+              className="card history-entry clickable"
+              onClick={() => view(hit.path)}
+            >
+              <p className="status-message">{new Date(hit.createdAt).toLocaleString()}</p>
+              <p className="search-snippet">
+                {hit.snippet.map((part, index) => (part.highlighted ? <mark key={index}>{part.text}</mark> : <span key={index}>{part.text}</span>))}
+              </p>
+              <p className="saved-path">{hit.path}</p>
+            </article>
+          ))}
+        </section>
+      ) : null}
+
+      {hits === null && entries.length === 0 ? <p className="subtle">No saved transcripts yet.</p> : null}
+
+      <section className={hits === null ? "block history-list" : "hidden"}>
+        {entries.map((entry) => (
+          <article key={entry.id} className="card history-entry">
+            <div className="status-row">
+              <p className="status-message">{new Date(entry.createdAt).toLocaleString()}</p>
+              <p className="subtle">
+                {entry.durationSeconds !== null ? `${formatDuration(Math.round(entry.durationSeconds))} · ` : ""}
+                {entry.wordCount} words · {entry.modelId} · {entry.language}
+              </p>
+            </div>
+            <p className="saved-path">{entry.path}</p>
+            <RatingRow
+              rating={entry.rating}
+              onRate={(stars, note) =>
+                void invoke("rate_session", { id: entry.id, stars, note }).catch((error) =>
+                  setMessage(error instanceof Error ? error.message : String(error))
+                )
+              }
+            />
+            <div className="row actions">
+              <button className="btn btn-muted" onClick={() => view(entry.id)}>
+                View
+              </button>
+              <button className="btn btn-muted" onClick={() => void runAction("open_history_entry", entry.id)}>
+                Open in Editor
+              </button>
+              <button className="btn btn-muted" onClick={() => void runAction("export_history_entry", entry.id)}>
+                Export
+              </button>
+              <button className="btn btn-muted" onClick={() => void shareBundle(entry.id, false)}>
+                Share Bundle
+              </button>
+              {entry.audioPath !== null ? (
+                <button className="btn btn-muted" onClick={() => void shareBundle(entry.id, true)}>
+                  Share with Audio
+                </button>
+              ) : null}
+              {entry.audioPath !== null ? (
+                <button
+                  className="btn btn-muted"
+                  disabled={retranscribing !== null}
+                  onClick={() => void retranscribe(entry.id)}
+                >
+                  {retranscribing === entry.id ? "Transcribing..." : "Re-transcribe"}
+                </button>
+              ) : null}
+              <button className="btn btn-muted" onClick={() => void runAction("delete_history_entry", entry.id)}>
+                Delete
+              </button>
+            </div>
+          </article>
+        ))}
+      </section>
+
+      {message ? <p className="saved-path">{message}</p> : null}
+    </main>
+  );
+}
+
+/** Splits `line` around case-insensitive matches of `query`. */
+function highlightMatches(line: string, query: string): Array<{ text: string; highlighted: boolean }> {
+  if (!query) {
+    return [{ text: line, highlighted: false }];
+  }
+  const parts: Array<{ text: string; highlighted: boolean }> = [];
+  const lower = line.toLowerCase();
+  const needle = query.toLowerCase();
+  let last = 0;
+  for (let index = lower.indexOf(needle); index !== -1; index = lower.indexOf(needle, index + needle.length)) {
+    parts.push({ text: line.slice(last, index), highlighted: false });
+    parts.push({ text: line.slice(index, index + needle.length), highlighted: true });
+    last = index + needle.length;
+  }
+  parts.push({ text: line.slice(last), highlighted: false });
+  return parts;
+}
+
+function ViewerWindow() {
+  const [transcript, setTranscript] = useState<ViewerTranscript | null>(null);
+  const [query, setQuery] = useState("");
+  const [message, setMessage] = useState<string | null>(null);
+
+  const load = useCallback(() => {
+    void invoke<ViewerTranscript>("get_viewer_transcript")
+      .then(setTranscript)
+      .catch((error) => setMessage(error instanceof Error ? error.message : String(error)));
+  }, []);
+
+  useEffect(load, [load]);
+
+  const runAction = async (command: string) => {
+    setMessage(null);
+    try {
+      const result = await invoke<string | null>(command);
+      setMessage(typeof result === "string" ? `Exported to ${result}` : "Copied to clipboard");
+    } catch (error) {
+      setMessage(error instanceof Error ? error.message : String(error));
+    }
+  };
+
+  const needle = query.trim();
+  const lines = transcript?.text.split("\n") ?? [];
+  const matches = needle
+    ? lines.reduce((count, line) => count + highlightMatches(line, needle).filter((part) => part.highlighted).length, 0)
+    : 0;
+
+  return (
+    <main className="tray-shell">
+      <header className="panel-header">
+        <div className="title-wrap">
+          <h1>Transcript</h1>
+        </div>
+      </header>
+
+      <input
+        className="search-input"
+        type="search"
+        placeholder="Search this transcript"
+        value={query}
+        onChange={(event) => setQuery(event.target.value)}
+      />
+      {needle ? <p className="subtle">{matches === 1 ? "1 match" : `${matches} matches`}</p> : null}
+
+      <div className="row actions">
+        <button className="btn btn-muted" disabled={!transcript} onClick={() => void runAction("copy_viewer_transcript")}>
+          Copy
+        </button>
+        <button className="btn btn-muted" disabled={!transcript} onClick={() => void runAction("export_viewer_transcript")}>
+          Export
+        </button>
+        <button className="btn btn-muted" onClick={load}>
+          Reload
+        </button>
+      </div>
+
+      {message ? <p className="saved-path">{message}</p> : null}
+
+      {transcript ? (
+        <section className="block card viewer-text">
+          {lines.map((line, index) => (
+            <p key={index} className={needle && !line.toLowerCase().includes(needle.toLowerCase()) ? "viewer-dim" : ""}>
+              {highlightMatches(line, needle).map((part, partIndex) =>
+                part.highlighted ? <mark key={partIndex}>{part.text}</mark> : <span key={partIndex}>{part.text}</span>
+              )}
+            </p>
+          ))}
+        </section>
+      ) : null}
+
+      {transcript ? <p className="saved-path">{transcript.path}</p> : null}
+    </main>
+  );
+}
+
+/** Offers the transcript of a session that never reached `stop_recording`. */
+function RecoveryPrompt({ idle }: { idle: boolean }) {
+  const [session, setSession] = useState<RecoverableSession | null>(null);
+  const [message, setMessage] = useState<string | null>(null);
+
+  const refresh = useCallback(() => {
+    void invoke<RecoverableSession | null>("get_recoverable_session").then(setSession).catch(() => undefined);
+  }, []);
+
+  useEffect(refresh, [refresh]);
+
+  if (!session) {
+    return null;
+  }
+
+  const recover = async (restore: boolean) => {
+    setMessage(null);
+    try {
+      await invoke<string | null>("recover_session", { id: session.id, restore });
+      refresh();
+    } catch (error) {
+      setMessage(error instanceof Error ? error.message : String(error));
+    }
+  };
+
+  const started = new Date(session.startedAt);
+  return (
+    <section className="block card warning-box">
+      <p className="status-title">
+        Restore unsaved transcript from {started.toLocaleTimeString([], { hour: "2-digit", minute: "2-digit" })}?
+      </p>
+      <p className="subtle">
+        {started.toLocaleDateString()} · {session.wordCount} words{session.hasAudio ? " · audio kept" : ""}
+      </p>
+      <p className="search-snippet">{session.preview}</p>
+      <div className="row actions">
+        <button className="btn btn-muted" disabled={!idle} onClick={() => void recover(true)}>
+          Restore
+        </button>
+        <button className="btn btn-muted" onClick={() => void recover(false)}>
+          Discard
+        </button>
+      </div>
+      {message ? <p className="subtle">{message}</p> : null}
+    </section>
+  );
+}
+
+function RatingRow({
+  rating,
+  onRate
+}: {
+  rating: SessionRating | null;
+  onRate: (stars: number, note: string | null) => void;
+}) {
+  const [note, setNote] = useState(rating?.note ?? "");
+  const stars = rating?.stars ?? 0;
+
+  return (
+    <div className="rating-row">
+      {[1, 2, 3, 4, 5].map((value) => (
+        <button
+          key={value}
+          className={`star ${value <= stars ? "star-on" : ""}`}
+          aria-label={`${value} star${value > 1 ? "s" : ""}`}
+          onClick={() => onRate(value, note || null)}
+        >
+          ★
+        </button>
+      ))}
+      <input
+        className="rating-note"
+        placeholder="Note (optional)"
+        value={note}
+        onChange={(event) => setNote(event.target.value)}
+        onBlur={() => {
+          if (stars > 0 && note !== (rating?.note ?? "")) {
+            onRate(stars, note || null);
+          }
+        }}
+      />
+    </div>
+  );
+}
+
+function PanelHeader({
+  title,
+  status,
+  recordingClock
+}: {
+  title: string;
+  status: AppStatus;
   recordingClock: string | null;
 }) {
   return (
@@ -277,7 +2194,15 @@ function PanelHeader({
   );
 }
 
-function SessionCard({ detail, progressPercent }: { detail: string; progressPercent: number | null }) {
+function SessionCard({
+  detail,
+  progressPercent,
+  installPhases
+}: {
+  detail: string;
+  progressPercent: number | null;
+  installPhases: InstallPhase[] | null;
+}) {
   return (
     <section className="block card">
       <div className="status-row">
@@ -290,10 +2215,76 @@ function SessionCard({ detail, progressPercent }: { detail: string; progressPerc
           <div className="progress-value" style={{ width: `${Math.max(8, progressPercent)}%` }} />
         </div>
       ) : null}
+      {installPhases ? (
+        <ul className="install-phases">
+          {installPhases.map((phase) => (
+            <li key={phase.id} className={`install-phase phase-${phase.status}`}>
+              <div className="status-row">
+                <span>{phase.label}</span>
+                <span className="status-percent">{formatPhaseState(phase)}</span>
+              </div>
+              {phase.status === "active" ? (
+                <>
+                  <div className="progress-track" role="progressbar" aria-valuenow={Math.round(phase.progress * 100)}>
+                    <div className="progress-value" style={{ width: `${Math.round(phase.progress * 100)}%` }} />
+                  </div>
+                  <p className="phase-detail">{formatPhaseDetail(phase)}</p>
+                </>
+              ) : null}
+            </li>
+          ))}
+        </ul>
+      ) : null}
     </section>
   );
 }
 
+function formatPhaseState(phase: InstallPhase): string {
+  switch (phase.status) {
+    case "pending":
+      return "Waiting";
+    case "skipped":
+      return "Already installed";
+    case "done":
+      return "Done";
+    case "active":
+      return `${Math.round(phase.progress * 100)}%`;
+  }
+}
+
+function formatPhaseDetail(phase: InstallPhase): string {
+  const parts: string[] = [];
+  if (phase.file) {
+    parts.push(phase.file);
+  }
+  if (phase.bytesDone !== null && phase.bytesTotal !== null) {
+    parts.push(`${formatBytes(phase.bytesDone)} of ${formatBytes(phase.bytesTotal)}`);
+  }
+  if (phase.bytesResumed !== null) {
+    parts.push(`resumed from ${formatBytes(phase.bytesResumed)}`);
+  }
+  if (phase.bytesPerSecond !== null) {
+    parts.push(`${formatBytes(phase.bytesPerSecond)}/s`);
+  }
+  if (phase.etaSeconds !== null) {
+    parts.push(`${formatDuration(Math.round(phase.etaSeconds))} left`);
+  }
+  return parts.join(" · ");
+}
+
+function formatBytes(bytes: number): string {
+  const units = ["B", "KB", "MB", "GB"];
+  let value = bytes;
+  let unit = 0;
+  while (value >= 1024 && unit < units.length - 1) {
+    value /= 1024;
+    unit += 1;
+  }
+  const digits = unit === 0 ? 0 : unit === units.length - 1 ? 2 : 1;
+  const number = value.toLocaleString(undefined, { minimumFractionDigits: digits, maximumFractionDigits: digits });
+  return `${number} ${units[unit]}`;
+}
+
 function SelectCard({
   id,
   label,
@@ -328,23 +2319,308 @@ function SelectCard({
   );
 }
 
+function DeviceProfileEditor({
+  deviceName,
+  channelCount,
+  profile,
+  disabled,
+  onChange
+}: {
+  deviceName: string;
+  channelCount: number;
+  profile: DeviceProfile | null;
+  disabled: boolean;
+  onChange: (profile: DeviceProfile | null) => void;
+}) {
+  const current = profile ?? DEFAULT_DEVICE_PROFILE;
+
+  return (
+    <div className="block device-profile">
+      <label htmlFor="mic-gain">
+        Gain for {deviceName}: {current.gainDb > 0 ? "+" : ""}
+        {current.gainDb} dB
+      </label>
+      <input
+        id="mic-gain"
+        type="range"
+        min={-12}
+        max={12}
+        step={1}
+        value={current.gainDb}
+        disabled={disabled}
+        onChange={(event) => onChange({ ...current, gainDb: Number(event.target.value) })}
+      />
+      <ToggleRow
+        id="mic-noise"
+        label="Noise suppression"
+        checked={current.noiseSuppression}
+        disabled={disabled}
+        onChange={(noiseSuppression) => onChange({ ...current, noiseSuppression })}
+      />
+      <SelectCard
+        id="mic-channel"
+        label="Input Channel"
+        value={current.channel === null ? "" : String(current.channel)}
+        disabled={disabled}
+        onChange={(value) => onChange({ ...current, channel: value ? Number(value) : null })}
+        options={[
+          { value: "", label: "Mix all channels" },
+          ...Array.from({ length: Math.max(channelCount, 1) }, (_, index) => ({
+            value: String(index + 1),
+            label: `Channel ${index + 1}`
+          }))
+        ]}
+        compact
+      />
+      <CalibrationWizard disabled={disabled} />
+      {profile ? (
+        <button className="btn btn-muted" disabled={disabled} onClick={() => onChange(null)}>
+          Reset to Defaults
+        </button>
+      ) : null}
+    </div>
+  );
+}
+
+/** The `spoken => written` lines `save_acronym_profile` reads and `export_acronym_profile` writes. */
+function acronymText(entries: Acronym[]): string {
+  return entries.map((entry) => `${entry.spoken} => ${entry.written}`).join("\n");
+}
+
+function AcronymEditor({
+  profiles,
+  active,
+  onCommand
+}: {
+  profiles: Record<string, Acronym[]>;
+  active: string | null;
+  onCommand: (command: string, payload?: Record<string, unknown>) => Promise<void>;
+}) {
+  const names = Object.keys(profiles);
+  const [name, setName] = useState(active ?? names[0] ?? "Default");
+  const [text, setText] = useState(() => acronymText(profiles[name] ?? []));
+  const [message, setMessage] = useState<string | null>(null);
+
+  // Typing the name of an existing profile loads it for editing.
+  const edit = (next: string) => {
+    setName(next);
+    if (profiles[next]) {
+      setText(acronymText(profiles[next]));
+    }
+    setMessage(null);
+  };
+
+  const remove = async () => {
+    await onCommand("save_acronym_profile", { name, text: null });
+    setName("");
+    setText("");
+  };
+
+  const save = async () => {
+    try {
+      const count = await invoke<number>("save_acronym_profile", { name, text });
+      setMessage(count === 1 ? "Saved 1 acronym" : `Saved ${count} acronyms`);
+    } catch (error) {
+      setMessage(error instanceof Error ? error.message : String(error));
+    }
+  };
+
+  const exportProfile = async () => {
+    try {
+      setMessage(`Exported to ${await invoke<string>("export_acronym_profile", { name })}`);
+    } catch (error) {
+      setMessage(error instanceof Error ? error.message : String(error));
+    }
+  };
+
+  return (
+    <div className="block acronyms">
+      <SelectCard
+        id="acronym-profile"
+        label="Acronyms"
+        value={active ?? ""}
+        disabled={false}
+        onChange={(value) => void onCommand("set_acronym_profile", { name: value || null })}
+        options={[{ value: "", label: "Off" }, ...names.map((profile) => ({ value: profile, label: profile }))]}
+        compact
+      />
+      <input placeholder="Profile name" value={name} onChange={(event) => edit(event.target.value)} />
+      <textarea
+        rows={4}
+        placeholder={"sequel => SQL\nK eight S => K8s"}
+        value={text}
+        onChange={(event) => setText(event.target.value)}
+      />
+      <p className="subtle">One spoken =&gt; written entry per line; paste an exported profile to import it.</p>
+      <div className="row actions">
+        <button className="btn btn-muted" disabled={!name.trim()} onClick={() => void save()}>
+          Save
+        </button>
+        {profiles[name] ? (
+          <>
+            <button className="btn btn-muted" onClick={() => void exportProfile()}>
+              Export
+            </button>
+            <button className="btn btn-muted" onClick={() => void remove()}>
+              Delete
+            </button>
+          </>
+        ) : null}
+      </div>
+      {message ? <p className="subtle">{message}</p> : null}
+    </div>
+  );
+}
+
+function ReplacementEditor({ saved }: { saved: string }) {
+  const [text, setText] = useState(saved);
+  const [message, setMessage] = useState<string | null>(null);
+
+  useEffect(() => setText(saved), [saved]);
+
+  const save = async () => {
+    try {
+      const count = await invoke<number>("set_replacements", { text });
+      setMessage(count === 1 ? "Saved 1 replacement" : `Saved ${count} replacements`);
+    } catch (error) {
+      setMessage(error instanceof Error ? error.message : String(error));
+    }
+  };
+
+  return (
+    <div className="block replacements">
+      <label htmlFor="replacements">Replacements</label>
+      <textarea
+        id="replacements"
+        rows={4}
+        placeholder={"whisper bar => WhisperBar\n/(\\d+) percent/ => $1%"}
+        value={text}
+        onChange={(event) => setText(event.target.value)}
+      />
+      <p className="subtle">One find =&gt; replace entry per line, applied in order; wrap a regex in slashes.</p>
+      <div className="row actions">
+        <button className="btn btn-muted" disabled={text === saved} onClick={() => void save()}>
+          Save
+        </button>
+      </div>
+      {message ? <p className="subtle">{message}</p> : null}
+    </div>
+  );
+}
+
+function CalibrationWizard({ disabled }: { disabled: boolean }) {
+  const [progress, setProgress] = useState<CalibrationProgress | null>(null);
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    const unlistenPromise = listen<CalibrationProgress>("whisperbar://calibration", (event) =>
+      setProgress(event.payload)
+    );
+    return () => {
+      void unlistenPromise.then((unlisten) => unlisten());
+    };
+  }, []);
+
+  const running = progress?.phase === "quiet" || progress?.phase === "speak";
+
+  const start = async () => {
+    setError(null);
+    setProgress(null);
+    try {
+      await invoke("start_calibration");
+    } catch (err) {
+      setError(String(err));
+    }
+  };
+
+  const cancel = async () => {
+    setProgress(null);
+    await invoke("cancel_calibration").catch(() => undefined);
+  };
+
+  return (
+    <div className="calibration">
+      {running && progress ? (
+        <>
+          <p>
+            {progress.phase === "quiet"
+              ? "Stay quiet so the room noise can be measured"
+              : "Now talk normally, as you would in a meeting"}{" "}
+            ({progress.remainingSeconds}s)
+          </p>
+          <div className="level-track">
+            <div className="level-fill" style={{ width: `${progress.level ? levelPercent(progress.level.rms) : 0}%` }} />
+          </div>
+          <button className="btn btn-muted" onClick={() => void cancel()}>
+            Cancel
+          </button>
+        </>
+      ) : (
+        <button className="btn btn-muted" disabled={disabled} onClick={() => void start()}>
+          Calibrate Levels
+        </button>
+      )}
+      {progress?.phase === "done" && progress.result ? (
+        <p className="subtle">
+          Noise {progress.result.noiseFloorDb.toFixed(0)} dBFS, speech {progress.result.speechDb.toFixed(0)} dBFS. Saved{" "}
+          {progress.result.profile.gainDb > 0 ? "+" : ""}
+          {progress.result.profile.gainDb} dB gain
+          {progress.result.profile.noiseSuppression ? " with noise suppression" : ""}.
+        </p>
+      ) : null}
+      {progress?.phase === "failed" ? <p className="subtle">{progress.message}</p> : null}
+      {error ? <p className="subtle">{error}</p> : null}
+    </div>
+  );
+}
+
+function ToggleRow({
+  id,
+  label,
+  checked,
+  disabled,
+  onChange
+}: {
+  id: string;
+  label: string;
+  checked: boolean;
+  disabled: boolean;
+  onChange: (checked: boolean) => void;
+}) {
+  return (
+    <div className="toggle-row">
+      <input id={id} type="checkbox" checked={checked} disabled={disabled} onChange={(event) => onChange(event.target.checked)} />
+      <label htmlFor={id}>{label}</label>
+    </div>
+  );
+}
+
 function RecordControlButton({
+  recording,
   canStart,
   canStop,
   onStart,
-  onStop
+  onStop,
+  onCancel
 }: {
+  recording: boolean;
   canStart: boolean;
   canStop: boolean;
   onStart: () => void;
   onStop: () => void;
+  onCancel: () => void;
 }) {
   return (
     <div className="row actions">
-      {canStop ? (
-        <button className="btn btn-stop primary-action" onClick={onStop}>
-          Stop Recording
-        </button>
+      {recording ? (
+        <>
+          <button className="btn btn-stop primary-action" disabled={!canStop} onClick={onStop}>
+            Stop Recording
+          </button>
+          <button className="btn btn-muted" disabled={!canStop} onClick={onCancel}>
+            Cancel
+          </button>
+        </>
       ) : (
         <button className="btn btn-start primary-action" disabled={!canStart} onClick={onStart}>
           Start Recording
@@ -384,6 +2660,10 @@ function getStatusDetail(state: AppSnapshot): string {
     return message;
   }
 
+  if (state.status === "Transcribing") {
+    return "Transcribing a kept recording again.";
+  }
+
   return message || "An error occurred.";
 }
 